@@ -0,0 +1,239 @@
+//! Benchmarks for generating and parsing snowflakes.
+//!
+//! Run with `cargo bench`. Every scenario prints the median time per ID over several samples.
+//!
+//! [`SnowflakeLayout::DEFAULT`] only has room for 4096 IDs per second, so each generation
+//! sample produces one second's worth of IDs, starting right after the clock ticks over.
+//! Without that, every generator would spend almost all of its time waiting for the next
+//! second and the numbers would only measure the clock.
+
+use std::hint::black_box;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rusty_snowflake::{
+    AtomicSnowflakeGenerator, Snowflake, SnowflakeGenerator, SnowflakeLayout, ThreadLocalGenerator,
+};
+
+/// The number of samples taken of each scenario
+const SAMPLES: usize = 5;
+
+/// The number of IDs generated per sample, one second's worth of sequence numbers
+const IDS_PER_SAMPLE: usize = SnowflakeLayout::DEFAULT.max_sequence() as usize + 1;
+
+/// The thread counts the contention scenarios are run with
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+fn main() {
+    single_thread();
+    contention();
+    batch();
+    parse();
+    format();
+}
+
+fn single_thread() {
+    report("single thread/SnowflakeGenerator", IDS_PER_SAMPLE, || {
+        let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+        time_from_next_second(|| {
+            for _ in 0..IDS_PER_SAMPLE {
+                black_box(generator.next());
+            }
+        })
+    });
+
+    report(
+        "single thread/AtomicSnowflakeGenerator",
+        IDS_PER_SAMPLE,
+        || {
+            let generator = AtomicSnowflakeGenerator::try_new(1).unwrap();
+            time_from_next_second(|| {
+                for _ in 0..IDS_PER_SAMPLE {
+                    black_box(generator.next());
+                }
+            })
+        },
+    );
+}
+
+fn contention() {
+    for threads in THREADS {
+        report(
+            &format!("contention/Mutex<SnowflakeGenerator>/{} threads", threads),
+            IDS_PER_SAMPLE,
+            || {
+                let generator = Mutex::new(SnowflakeGenerator::try_new(1).unwrap());
+                on_threads(threads, || {
+                    black_box(*generator.lock().unwrap().next());
+                })
+            },
+        );
+
+        report(
+            &format!("contention/AtomicSnowflakeGenerator/{} threads", threads),
+            IDS_PER_SAMPLE,
+            || {
+                let generator = AtomicSnowflakeGenerator::try_new(1).unwrap();
+                on_threads(threads, || {
+                    black_box(generator.next());
+                })
+            },
+        );
+
+        report(
+            &format!("contention/ThreadLocalGenerator/{} threads", threads),
+            IDS_PER_SAMPLE,
+            || {
+                let generator = ThreadLocalGenerator::new(1, 6).unwrap();
+                on_threads(threads, || {
+                    black_box(generator.next());
+                })
+            },
+        );
+    }
+}
+
+fn batch() {
+    report("batch/next_batch", IDS_PER_SAMPLE, || {
+        let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+        time_from_next_second(|| {
+            black_box(generator.next_batch(IDS_PER_SAMPLE));
+        })
+    });
+
+    report("batch/fill", IDS_PER_SAMPLE, || {
+        let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+        let mut ids = vec![0; IDS_PER_SAMPLE];
+        time_from_next_second(|| {
+            generator.fill(&mut ids);
+            black_box(&ids);
+        })
+    });
+}
+
+fn parse() {
+    const IDS: usize = 1_000_000;
+
+    let ids: Vec<u64> = (0..IDS as u64)
+        .map(|n| n.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 1)
+        .collect();
+    let strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+
+    report("parse/parse", IDS, || {
+        time(|| {
+            for &id in &ids {
+                black_box(Snowflake::parse(black_box(id)));
+            }
+        })
+    });
+
+    report("parse/parse_many", IDS, || {
+        time(|| {
+            black_box(Snowflake::parse_many(black_box(&ids)));
+        })
+    });
+
+    report("parse/parse_str", IDS, || {
+        time(|| {
+            for string in &strings {
+                black_box(Snowflake::parse_str(black_box(string)).unwrap());
+            }
+        })
+    });
+}
+
+fn format() {
+    const IDS: usize = 1_000_000;
+
+    let snowflakes: Vec<Snowflake> = (0..IDS as u64)
+        .map(|n| Snowflake::parse(n.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 1))
+        .collect();
+
+    report("format/to_string", IDS, || {
+        time(|| {
+            for snowflake in &snowflakes {
+                black_box(black_box(snowflake).to_string());
+            }
+        })
+    });
+
+    report("format/write_to", IDS, || {
+        let mut line = String::with_capacity(32);
+        time(|| {
+            for &snowflake in &snowflakes {
+                line.clear();
+                black_box(snowflake).write_to(&mut line).unwrap();
+                black_box(&line);
+            }
+        })
+    });
+
+    report("format/encode_to_slice", IDS, || {
+        let mut buffer = [0; rusty_snowflake::keys::MAX_ID_LENGTH];
+        time(|| {
+            for &snowflake in &snowflakes {
+                black_box(black_box(snowflake).encode_to_slice(&mut buffer).unwrap());
+            }
+        })
+    });
+}
+
+/// Run `sample` [`SAMPLES`] times and print the median time per ID
+fn report(name: &str, ids: usize, mut sample: impl FnMut() -> Duration) {
+    let mut times: Vec<Duration> = (0..SAMPLES).map(|_| sample()).collect();
+    times.sort();
+
+    let median = times[SAMPLES / 2];
+    println!(
+        "{:<56} {:>10.1} ns/id",
+        name,
+        median.as_nanos() as f64 / ids as f64
+    );
+}
+
+/// Time `f`, starting just after the system clock ticks over to a new second
+fn time_from_next_second(f: impl FnOnce()) -> Duration {
+    wait_for_next_second();
+    time(f)
+}
+
+/// Time `f` right away
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+/// Time generating [`IDS_PER_SAMPLE`] IDs with `next`, split evenly over `threads` threads
+fn on_threads(threads: usize, next: impl Fn() + Sync) -> Duration {
+    let barrier = Barrier::new(threads + 1);
+    let mut start = None;
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                // Warm up before the clock ticks over, so `ThreadLocalGenerator` claims its
+                // thread ID outside of the measured second
+                next();
+                barrier.wait();
+                for _ in 0..IDS_PER_SAMPLE / threads {
+                    next();
+                }
+            });
+        }
+
+        wait_for_next_second();
+        start = Some(Instant::now());
+        barrier.wait();
+    });
+
+    start.unwrap().elapsed()
+}
+
+fn wait_for_next_second() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    thread::sleep(Duration::from_nanos(
+        1_000_000_000 - now.subsec_nanos() as u64,
+    ));
+}
@@ -0,0 +1,91 @@
+"""Python bindings for rusty-snowflake over its C ABI.
+
+Build the shared library with the ``ffi`` feature and point ``RUSTY_SNOWFLAKE_LIB`` at it::
+
+    cargo rustc --release --features ffi --crate-type cdylib
+    export RUSTY_SNOWFLAKE_LIB=target/release/librusty_snowflake.so
+
+Every conversion runs in the Rust library, so IDs decode exactly as they do in Rust.
+"""
+
+import ctypes
+import os
+from dataclasses import dataclass
+
+
+class _Snowflake(ctypes.Structure):
+    _fields_ = [
+        ("worker_id", ctypes.c_uint64),
+        ("sequence", ctypes.c_uint64),
+        ("timestamp", ctypes.c_uint64),
+        ("extra", ctypes.c_uint64),
+    ]
+
+
+def _load(path=None):
+    lib = ctypes.CDLL(path or os.environ.get("RUSTY_SNOWFLAKE_LIB", "librusty_snowflake.so"))
+
+    lib.snowflake_generator_new.argtypes = [ctypes.c_uint64]
+    lib.snowflake_generator_new.restype = ctypes.c_void_p
+    lib.snowflake_generator_free.argtypes = [ctypes.c_void_p]
+    lib.snowflake_generator_free.restype = None
+    lib.snowflake_next.argtypes = [ctypes.c_void_p]
+    lib.snowflake_next.restype = ctypes.c_uint64
+    lib.snowflake_parse.argtypes = [ctypes.c_uint64]
+    lib.snowflake_parse.restype = _Snowflake
+    lib.snowflake_to_id.argtypes = [_Snowflake]
+    lib.snowflake_to_id.restype = ctypes.c_uint64
+
+    return lib
+
+
+_lib = _load()
+
+
+@dataclass(frozen=True, order=True)
+class Snowflake:
+    """The parts of a snowflake ID."""
+
+    timestamp: int
+    worker_id: int
+    sequence: int
+    extra: int = 0
+
+    @classmethod
+    def parse(cls, id):
+        """Parse a snowflake ID into its parts."""
+        parts = _lib.snowflake_parse(id)
+        return cls(parts.timestamp, parts.worker_id, parts.sequence, parts.extra)
+
+    def to_id(self):
+        """Convert the parts into a snowflake ID."""
+        return _lib.snowflake_to_id(
+            _Snowflake(self.worker_id, self.sequence, self.timestamp, self.extra)
+        )
+
+
+class SnowflakeGenerator:
+    """Generates snowflake IDs with a fixed worker ID."""
+
+    def __init__(self, worker_id):
+        self._generator = _lib.snowflake_generator_new(worker_id)
+        if not self._generator:
+            raise ValueError(f"worker ID {worker_id} exceeds the maximum of 1023")
+
+    def next(self):
+        """Generate the next snowflake ID."""
+        id = _lib.snowflake_next(self._generator)
+        if id == 0:
+            raise RuntimeError("failed to generate snowflake")
+        return id
+
+    def __iter__(self):
+        return self
+
+    def __next__(self):
+        return self.next()
+
+    def __del__(self):
+        if getattr(self, "_generator", None):
+            _lib.snowflake_generator_free(self._generator)
+            self._generator = None
@@ -0,0 +1,44 @@
+/* C bindings for rusty-snowflake, built with the `ffi` feature.
+ *
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/rusty_snowflake.h`. */
+
+#ifndef RUSTY_SNOWFLAKE_H
+#define RUSTY_SNOWFLAKE_H
+
+#include <stdint.h>
+
+/* A snowflake generator, created by snowflake_generator_new. */
+typedef struct SnowflakeGenerator SnowflakeGenerator;
+
+/* The parts of a snowflake ID. */
+typedef struct Snowflake {
+  uint64_t worker_id;
+  uint64_t sequence;
+  uint64_t timestamp;
+  uint64_t extra;
+} Snowflake;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Create a generator, or return NULL if the worker ID doesn't fit in 10 bits. */
+SnowflakeGenerator *snowflake_generator_new(uint64_t worker_id);
+
+/* Free a generator. Passing NULL does nothing. */
+void snowflake_generator_free(SnowflakeGenerator *generator);
+
+/* Generate the next snowflake ID, or return 0 if the generator failed. */
+uint64_t snowflake_next(SnowflakeGenerator *generator);
+
+/* Parse a snowflake ID into its parts. */
+Snowflake snowflake_parse(uint64_t id);
+
+/* Convert a snowflake's parts into a snowflake ID. */
+uint64_t snowflake_to_id(Snowflake snowflake);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSTY_SNOWFLAKE_H */
@@ -0,0 +1,129 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{Snowflake, SnowflakeLayout};
+
+impl Snowflake {
+    /// Get how long ago the snowflake was created
+    ///
+    /// # Returns
+    /// The time since the snowflake was created, or zero if it was created in the future
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert!(snowflake.age() < Duration::from_secs(2));
+    /// ```
+    pub fn age(self) -> Duration {
+        self.age_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get how long ago the snowflake was created, using the epoch and tick of a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the snowflake was parsed with
+    ///
+    /// # Returns
+    /// The time since the snowflake was created, or zero if it was created in the future
+    pub fn age_with_layout(self, layout: &SnowflakeLayout) -> Duration {
+        SystemTime::now()
+            .duration_since(self.created_at_with_layout(layout))
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Get how long ago the snowflake was created, for a human to read
+    ///
+    /// The age is shown in its two largest units, like `3h 12m ago` or `2d 5h ago`, and
+    /// anything under a second is `just now`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    /// let age = snowflake.age_humanized();
+    ///
+    /// assert!(age == "just now" || age == "1s ago");
+    /// ```
+    pub fn age_humanized(self) -> String {
+        self.age_humanized_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get how long ago the snowflake was created for a human to read, using the epoch and
+    /// tick of a custom layout
+    ///
+    /// See [`Snowflake::age_humanized`].
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the snowflake was parsed with
+    pub fn age_humanized_with_layout(self, layout: &SnowflakeLayout) -> String {
+        humanize(self.age_with_layout(layout))
+    }
+}
+
+/// Format an age in its two largest units
+fn humanize(age: Duration) -> String {
+    const UNITS: [(u64, &str); 4] = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+
+    let seconds = age.as_secs();
+    let Some(largest) = UNITS.iter().position(|&(unit, _)| seconds >= unit) else {
+        return "just now".to_string();
+    };
+
+    let (unit, suffix) = UNITS[largest];
+    let mut humanized = format!("{}{}", seconds / unit, suffix);
+    if let Some(&(next_unit, next_suffix)) = UNITS.get(largest + 1) {
+        let rest = seconds % unit / next_unit;
+        if rest > 0 {
+            humanized += &format!(" {}{}", rest, next_suffix);
+        }
+    }
+
+    humanized + " ago"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize() {
+        assert_eq!(humanize(Duration::from_millis(999)), "just now");
+        assert_eq!(humanize(Duration::from_secs(42)), "42s ago");
+        assert_eq!(humanize(Duration::from_secs(5 * 60 + 3)), "5m 3s ago");
+        assert_eq!(
+            humanize(Duration::from_secs(3 * 3_600 + 12 * 60 + 9)),
+            "3h 12m ago"
+        );
+        assert_eq!(humanize(Duration::from_secs(3_600 + 30)), "1h ago");
+        assert_eq!(
+            humanize(Duration::from_secs(400 * 86_400 + 7_200)),
+            "400d 2h ago"
+        );
+    }
+
+    #[test]
+    fn test_age() {
+        let snowflake = Snowflake::builder()
+            .timestamp(1_700_000_000)
+            .build()
+            .unwrap();
+        let age = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .unwrap();
+
+        assert!(snowflake.age().abs_diff(age) < Duration::from_secs(2));
+        assert!(snowflake.age_humanized().ends_with("ago"));
+
+        // Snowflakes from the future are no age at all
+        let future = Snowflake::builder()
+            .timestamp(u64::MAX >> 23)
+            .build()
+            .unwrap();
+        assert_eq!(future.age(), Duration::ZERO);
+        assert_eq!(future.age_humanized(), "just now");
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::generator::generation_failed;
+use crate::{GeneratorStats, Snowflake, SnowflakeError, SnowflakeGenerator};
+
+#[derive(Debug, Clone)]
+/// A snowflake generator for async code that never blocks the executor.
+///
+/// This is a cheap, cloneable handle to a shared [`SnowflakeGenerator`], so it can be handed to
+/// as many tasks as needed. Wherever the generator would sleep the thread, which is when the
+/// sequence is exhausted for the current tick, when the clock went backwards under
+/// `ClockBackwardsPolicy::Wait` and when the rate limit is reached under
+/// `RateLimitPolicy::Wait`, [`AsyncSnowflakeGenerator::next_async`] awaits a timer instead.
+/// The generator's `OverflowStrategy` still decides how long it waits, though not how.
+///
+/// The timer doesn't depend on any particular runtime, so this works with tokio, async-std or
+/// any other executor.
+pub struct AsyncSnowflakeGenerator {
+    inner: Arc<Mutex<SnowflakeGenerator>>,
+}
+
+impl AsyncSnowflakeGenerator {
+    /// Create a new async snowflake generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    ///
+    /// # Returns
+    /// A new `AsyncSnowflakeGenerator`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT) or the system clock is set
+    /// before the UNIX epoch
+    pub fn try_new(worker_id: u64) -> Result<AsyncSnowflakeGenerator, SnowflakeError> {
+        Ok(AsyncSnowflakeGenerator::from(SnowflakeGenerator::try_new(
+            worker_id,
+        )?))
+    }
+
+    /// Get the last snowflake generated by any handle to the generator
+    pub fn last_snowflake(&self) -> Snowflake {
+        self.lock().last_snowflake
+    }
+
+    /// Get statistics about the snowflakes generated by every handle to the generator
+    pub fn stats(&self) -> GeneratorStats {
+        self.lock().stats()
+    }
+
+    /// Generates the next snowflake ID without blocking the thread.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use rusty_snowflake::AsyncSnowflakeGenerator;
+    ///
+    /// let generator = AsyncSnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// let handle = generator.clone();
+    /// tokio::spawn(async move {
+    ///     println!("Generated snowflake ID: {}", handle.next_async().await);
+    /// });
+    ///
+    /// println!("Generated snowflake ID: {}", generator.next_async().await);
+    /// ```
+    ///
+    /// # Returns
+    /// The generated snowflake
+    ///
+    /// # Panics
+    /// Panics if [`AsyncSnowflakeGenerator::try_next_async`] returns an error, in the same cases
+    /// as [`SnowflakeGenerator::next`].
+    pub async fn next_async(&self) -> Snowflake {
+        match self.try_next_async().await {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Generates the next snowflake ID, waiting without blocking wherever the generator would
+    /// wait
+    ///
+    /// # Returns
+    /// The generated snowflake, or any error [`SnowflakeGenerator::try_next`] returns
+    pub async fn try_next_async(&self) -> Result<Snowflake, SnowflakeError> {
+        loop {
+            let wait = {
+                let mut generator = self.lock();
+                match generator.until_ready() {
+                    Some(wait) => wait,
+                    None => return generator.try_next(),
+                }
+            };
+
+            // The generator would block, so wait on a timer and check again
+            Sleep::new(wait).await;
+        }
+    }
+
+    /// Lock the shared generator
+    fn lock(&self) -> std::sync::MutexGuard<'_, SnowflakeGenerator> {
+        // The generator is only a `Copy` value, so a panic can't leave it half updated
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<SnowflakeGenerator> for AsyncSnowflakeGenerator {
+    fn from(generator: SnowflakeGenerator) -> AsyncSnowflakeGenerator {
+        AsyncSnowflakeGenerator {
+            inner: Arc::new(Mutex::new(generator)),
+        }
+    }
+}
+
+/// Snowflakes generated ahead of time on a background thread, for async code to take one at a
+/// time.
+///
+/// The thread fills a buffer of up to `capacity` snowflakes and waits whenever it's full, so
+/// it never runs further ahead of the consumer than that. Taking a snowflake only locks the
+/// buffer for a moment, instead of the generator, and doesn't wait unless the buffer is empty.
+///
+/// [`SnowflakeStream::poll_next`] has the signature of `futures::Stream::poll_next`, so the
+/// stream can be wrapped in a `Stream` implementation where one is needed. Like
+/// [`AsyncSnowflakeGenerator`], it works with any executor. The background thread stops when
+/// the stream is dropped.
+///
+/// # Example
+/// ```rust,ignore
+/// use rusty_snowflake::{SnowflakeGenerator, SnowflakeStream};
+///
+/// let mut stream = SnowflakeStream::new(SnowflakeGenerator::try_new(420).unwrap(), 256);
+///
+/// while let Some(snowflake) = stream.next().await {
+///     println!("Generated snowflake ID: {}", snowflake);
+/// }
+/// ```
+pub struct SnowflakeStream {
+    shared: Arc<StreamShared>,
+}
+
+/// The state shared by a [`SnowflakeStream`] and its background thread.
+struct StreamShared {
+    buffer: Mutex<StreamBuffer>,
+    /// Signalled when the buffer has room or the stream is dropped.
+    space: Condvar,
+}
+
+struct StreamBuffer {
+    snowflakes: VecDeque<Snowflake>,
+    capacity: usize,
+    /// Set when the stream is dropped, or when the generator failed and the thread stopped.
+    closed: bool,
+    /// The task waiting for a snowflake.
+    waker: Option<Waker>,
+}
+
+impl SnowflakeStream {
+    /// Start generating snowflakes on a background thread
+    ///
+    /// # Arguments
+    /// * `generator` - The generator to take snowflakes from
+    /// * `capacity` - The most snowflakes generated ahead of time, at least one
+    pub fn new(generator: SnowflakeGenerator, capacity: usize) -> SnowflakeStream {
+        let capacity = capacity.max(1);
+        let shared = Arc::new(StreamShared {
+            buffer: Mutex::new(StreamBuffer {
+                snowflakes: VecDeque::with_capacity(capacity),
+                capacity,
+                closed: false,
+                waker: None,
+            }),
+            space: Condvar::new(),
+        });
+
+        let producer = Arc::clone(&shared);
+        std::thread::spawn(move || producer.produce(generator));
+
+        SnowflakeStream { shared }
+    }
+
+    /// Take the next snowflake if one is ready, or register the task to be woken when one is
+    ///
+    /// # Returns
+    /// The next snowflake, or `None` once the generator has failed
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Snowflake>> {
+        let mut buffer = self.shared.lock();
+
+        match buffer.snowflakes.pop_front() {
+            Some(snowflake) => {
+                drop(buffer);
+                self.shared.space.notify_one();
+                Poll::Ready(Some(snowflake))
+            }
+            None if buffer.closed => Poll::Ready(None),
+            None => {
+                buffer.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Take the next snowflake, waiting for the background thread if none is ready
+    ///
+    /// # Returns
+    /// The next snowflake, or `None` once the generator has failed
+    pub async fn next(&mut self) -> Option<Snowflake> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl Drop for SnowflakeStream {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.space.notify_one();
+    }
+}
+
+impl std::fmt::Debug for SnowflakeStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let buffer = self.shared.lock();
+        f.debug_struct("SnowflakeStream")
+            .field("buffered", &buffer.snowflakes.len())
+            .field("capacity", &buffer.capacity)
+            .field("closed", &buffer.closed)
+            .finish()
+    }
+}
+
+impl StreamShared {
+    /// Generate snowflakes into the buffer until the stream is dropped or the generator fails
+    fn produce(&self, mut generator: SnowflakeGenerator) {
+        loop {
+            {
+                let mut buffer = self.lock();
+                while buffer.snowflakes.len() >= buffer.capacity && !buffer.closed {
+                    buffer = self
+                        .space
+                        .wait(buffer)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                if buffer.closed {
+                    return;
+                }
+            }
+
+            // Generate without holding the lock, since it can wait for the next tick
+            let result = generator.try_next();
+
+            let mut buffer = self.lock();
+            match result {
+                Ok(snowflake) => buffer.snowflakes.push_back(snowflake),
+                Err(_) => buffer.closed = true,
+            }
+            if let Some(waker) = buffer.waker.take() {
+                waker.wake();
+            }
+            if buffer.closed {
+                return;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StreamBuffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A future that completes once a deadline has passed.
+///
+/// The first time it's polled before the deadline, a timer thread is started that wakes the
+/// task when the deadline passes.
+struct Sleep {
+    deadline: Instant,
+    waker: Option<Arc<Mutex<Waker>>>,
+}
+
+impl Sleep {
+    fn new(duration: Duration) -> Sleep {
+        Sleep {
+            deadline: Instant::now() + duration,
+            waker: None,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        match &self.waker {
+            Some(waker) => {
+                // The task may have moved since it was last polled
+                waker
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone_from(cx.waker());
+            }
+            None => {
+                let waker = Arc::new(Mutex::new(cx.waker().clone()));
+                let deadline = self.deadline;
+                let timer = Arc::clone(&waker);
+
+                std::thread::spawn(move || {
+                    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    timer
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .wake_by_ref();
+                });
+
+                self.waker = Some(waker);
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    use super::*;
+    use crate::generator::current_timestamp;
+    use crate::{ClockBackwardsPolicy, GeneratorOptions, SnowflakeLayout};
+
+    /// A waker that unparks the thread running `block_on`
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Run a future to completion on the current thread
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    /// Poll a future once, checking the poll doesn't block the thread, then run it to completion
+    fn poll_without_blocking<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut future = std::pin::pin!(future);
+        let start = Instant::now();
+        let poll = future.as_mut().poll(&mut Context::from_waker(&waker));
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        match poll {
+            Poll::Ready(output) => output,
+            Poll::Pending => block_on(future),
+        }
+    }
+
+    #[test]
+    fn test_next_async() {
+        let generator = AsyncSnowflakeGenerator::try_new(420).unwrap();
+        let mut last = generator.last_snowflake();
+
+        for _ in 0..10 {
+            let snowflake = block_on(generator.next_async());
+            assert!(snowflake > last);
+            assert_eq!(snowflake, generator.last_snowflake());
+            last = snowflake;
+        }
+    }
+
+    #[test]
+    fn test_next_async_when_sequence_overflows() {
+        let time = current_timestamp();
+        let generator = AsyncSnowflakeGenerator::from(SnowflakeGenerator::start_at(&Snowflake {
+            worker_id: 1,
+            sequence: SnowflakeLayout::DEFAULT.max_sequence(),
+            timestamp: time,
+            extra: 0,
+        }));
+
+        let next = block_on(generator.next_async());
+
+        assert_eq!(next.sequence, 0);
+        assert!(next.timestamp > time);
+    }
+
+    #[test]
+    fn test_next_async_with_layout_does_not_block() {
+        let layout = SnowflakeLayout::builder()
+            .sequence_bits(2)
+            .tick_millis(500)
+            .build()
+            .unwrap();
+        let mut generator = SnowflakeGenerator::with_layout(1, layout).unwrap();
+        while generator.last_snowflake.sequence < layout.max_sequence() {
+            generator.next();
+        }
+        let last = generator.last_snowflake;
+        let generator = AsyncSnowflakeGenerator::from(generator);
+
+        // Polling waits for the next tick on a timer instead of sleeping the thread
+        let next = poll_without_blocking(generator.next_async());
+        assert!(next.timestamp > last.timestamp);
+        assert_eq!(next.sequence, 0);
+    }
+
+    #[test]
+    fn test_next_async_with_rate_limit_wait() {
+        let options = GeneratorOptions::new().max_ids_per_second(4).unwrap();
+        let generator = AsyncSnowflakeGenerator::from(
+            SnowflakeGenerator::try_new(1)
+                .unwrap()
+                .with_options(options),
+        );
+        for _ in 0..4 {
+            block_on(generator.next_async());
+        }
+
+        let start = Instant::now();
+        poll_without_blocking(generator.next_async());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_next_async_with_clock_backwards_wait() {
+        let ahead = current_timestamp() + 1;
+        let generator = AsyncSnowflakeGenerator::from(
+            SnowflakeGenerator::start_at(&Snowflake {
+                worker_id: 1,
+                sequence: 0,
+                timestamp: ahead,
+                extra: 0,
+            })
+            .with_options(
+                GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Wait),
+            ),
+        );
+
+        let next = poll_without_blocking(generator.next_async());
+        assert!(next.timestamp >= ahead);
+    }
+
+    #[test]
+    fn test_next_async_with_borrow_time() {
+        let time = current_timestamp();
+        let generator = AsyncSnowflakeGenerator::from(
+            SnowflakeGenerator::start_at(&Snowflake {
+                worker_id: 1,
+                sequence: SnowflakeLayout::DEFAULT.max_sequence(),
+                timestamp: time,
+                extra: 0,
+            })
+            .with_options(
+                GeneratorOptions::new()
+                    .overflow_strategy(crate::OverflowStrategy::BorrowTime { max_ticks: 5 }),
+            ),
+        );
+
+        // Borrowing a tick from the future doesn't wait at all
+        let next = poll_without_blocking(generator.next_async());
+        assert_eq!((next.timestamp, next.sequence), (time + 1, 0));
+    }
+
+    #[test]
+    fn test_try_next_async_error() {
+        let generator = AsyncSnowflakeGenerator::from(
+            SnowflakeGenerator::start_at(&Snowflake {
+                worker_id: 1,
+                sequence: 0,
+                timestamp: current_timestamp() + 60,
+                extra: 0,
+            })
+            .with_options(
+                crate::GeneratorOptions::new()
+                    .clock_backwards_policy(crate::ClockBackwardsPolicy::Error),
+            ),
+        );
+
+        assert!(matches!(
+            block_on(generator.try_next_async()),
+            Err(SnowflakeError::ClockMovedBackwards { .. })
+        ));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let generator = AsyncSnowflakeGenerator::try_new(1).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = generator.clone();
+                std::thread::spawn(move || block_on(generator.next_async()))
+            })
+            .collect();
+
+        let mut ids: Vec<u64> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap().to_id())
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 4);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = SnowflakeStream::new(SnowflakeGenerator::try_new(3).unwrap(), 16);
+
+        let snowflakes = (0..100)
+            .map(|_| block_on(stream.next()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(snowflakes
+            .iter()
+            .all(|snowflake| snowflake.worker_id() == 3));
+    }
+
+    #[test]
+    fn test_stream_backpressure() {
+        let stream = SnowflakeStream::new(SnowflakeGenerator::try_new(3).unwrap(), 4);
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(stream.shared.lock().snowflakes.len(), 4);
+    }
+
+    #[test]
+    fn test_stream_ends_when_generator_fails() {
+        let generator = SnowflakeGenerator::start_at(&Snowflake {
+            worker_id: 1,
+            sequence: 0,
+            timestamp: current_timestamp() + 60,
+            extra: 0,
+        })
+        .with_options(
+            crate::GeneratorOptions::new()
+                .clock_backwards_policy(crate::ClockBackwardsPolicy::Error),
+        );
+        let mut stream = SnowflakeStream::new(generator, 4);
+
+        assert_eq!(block_on(stream.next()), None);
+    }
+
+    #[test]
+    fn test_sleep() {
+        let start = Instant::now();
+        block_on(Sleep::new(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
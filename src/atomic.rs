@@ -0,0 +1,290 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::generator::generation_failed;
+use crate::{Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+#[derive(Debug)]
+/// A lock-free snowflake generator that can be shared between threads.
+///
+/// The timestamp and sequence of the last generated snowflake are packed into a single
+/// `AtomicU64` and advanced with a compare-and-swap loop, so threads never wait on a lock.
+/// IDs are packed with [`SnowflakeLayout::DEFAULT`].
+pub struct AtomicSnowflakeGenerator {
+    /// The worker ID of every snowflake generated.
+    worker_id: u64,
+    /// The timestamp and sequence of the last snowflake, as `timestamp << sequence_bits | sequence`.
+    state: AtomicU64,
+}
+
+impl AtomicSnowflakeGenerator {
+    /// Create a new atomic snowflake generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    ///
+    /// # Returns
+    /// A new `AtomicSnowflakeGenerator`
+    ///
+    /// A system clock set before the UNIX epoch gives a timestamp of zero, and worker IDs that
+    /// don't fit in [`SnowflakeLayout::DEFAULT`] are silently truncated when converted to an ID.
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `AtomicSnowflakeGenerator::try_new` instead"
+    )]
+    pub fn new(worker_id: u64) -> AtomicSnowflakeGenerator {
+        #[allow(deprecated)]
+        AtomicSnowflakeGenerator::start_at(&Snowflake::new(worker_id))
+    }
+
+    /// Create a new atomic snowflake generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    ///
+    /// # Returns
+    /// A new `AtomicSnowflakeGenerator`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`] or the system clock is set before the UNIX epoch
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::AtomicSnowflakeGenerator;
+    ///
+    /// let generator = AtomicSnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// assert_eq!(generator.last_snowflake().worker_id(), 420);
+    /// ```
+    pub fn try_new(worker_id: u64) -> Result<AtomicSnowflakeGenerator, SnowflakeError> {
+        Ok(AtomicSnowflakeGenerator::start_at(&Snowflake::try_new(
+            worker_id,
+        )?))
+    }
+
+    /// Start at the given snowflake
+    ///
+    /// # Arguments
+    /// * `snowflake` - The snowflake to start at
+    ///
+    /// # Returns
+    /// An `AtomicSnowflakeGenerator`
+    pub fn start_at(snowflake: &Snowflake) -> AtomicSnowflakeGenerator {
+        AtomicSnowflakeGenerator {
+            worker_id: snowflake.worker_id,
+            state: AtomicU64::new(pack(snowflake.timestamp, snowflake.sequence)),
+        }
+    }
+
+    /// Get the last snowflake generated by the generator
+    pub fn last_snowflake(&self) -> Snowflake {
+        let (timestamp, sequence) = unpack(self.state.load(Ordering::Acquire));
+
+        Snowflake {
+            worker_id: self.worker_id,
+            sequence,
+            timestamp,
+            extra: 0,
+        }
+    }
+
+    /// Generates the next snowflake ID.
+    ///
+    /// Unlike [`SnowflakeGenerator::next`] this only needs a shared reference, so one generator
+    /// can be shared between threads (for example in an `Arc`) without a mutex.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use rusty_snowflake::AtomicSnowflakeGenerator;
+    ///
+    /// let generator = Arc::new(AtomicSnowflakeGenerator::try_new(420).unwrap());
+    ///
+    /// let handles: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let generator = Arc::clone(&generator);
+    ///         std::thread::spawn(move || generator.next())
+    ///     })
+    ///     .collect();
+    ///
+    /// for handle in handles {
+    ///     println!("Generated snowflake ID: {}", handle.join().unwrap());
+    /// }
+    /// ```
+    ///
+    /// # Returns
+    /// The generated snowflake
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`AtomicSnowflakeGenerator::try_next`] returns an error, which can only happen
+    /// when the system clock is set before the UNIX epoch (`SnowflakeError::ClockBeforeEpoch`),
+    /// or once the timestamp runs out at the [`SnowflakeLayout::exhaustion_date`] of
+    /// [`SnowflakeLayout::DEFAULT`] (`SnowflakeError::TimestampOverflow`).
+    pub fn next(&self) -> Snowflake {
+        match self.try_next() {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// # Returns
+    /// The generated snowflake, or `SnowflakeError::ClockBeforeEpoch` if the system clock is
+    /// set before the UNIX epoch and `SnowflakeError::TimestampOverflow` once the timestamp no
+    /// longer fits in [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::AtomicSnowflakeGenerator;
+    ///
+    /// let generator = AtomicSnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// assert_eq!(generator.try_next().unwrap().worker_id(), 420);
+    /// ```
+    pub fn try_next(&self) -> Result<Snowflake, SnowflakeError> {
+        let mut current = self.state.load(Ordering::Acquire);
+
+        loop {
+            let (last_timestamp, last_sequence) = unpack(current);
+            let timestamp = SnowflakeGenerator::try_get_timestamp()?;
+
+            // The state only has room for the layout's timestamp bits
+            let max = SnowflakeLayout::DEFAULT.max_timestamp();
+            if timestamp > max {
+                return Err(SnowflakeError::TimestampOverflow { timestamp, max });
+            }
+
+            let (timestamp, sequence) = if timestamp > last_timestamp {
+                (timestamp, 0) // Reset sequence because timestamp changed
+            } else if last_sequence < SnowflakeLayout::DEFAULT.max_sequence() {
+                (last_timestamp, last_sequence + 1) // Increment sequence
+            } else {
+                // Sequence is exhausted for this second, wait for the clock to move on
+                SnowflakeGenerator::try_wait_next_timestamp(last_timestamp)?;
+                current = self.state.load(Ordering::Acquire);
+                continue;
+            };
+
+            match self.state.compare_exchange_weak(
+                current,
+                pack(timestamp, sequence),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    return Ok(Snowflake {
+                        worker_id: self.worker_id,
+                        sequence,
+                        timestamp,
+                        extra: 0,
+                    })
+                }
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+/// Pack a timestamp and sequence into the generator state
+fn pack(timestamp: u64, sequence: u64) -> u64 {
+    let layout = SnowflakeLayout::DEFAULT;
+    ((timestamp & layout.max_timestamp()) << layout.sequence_bits())
+        | (sequence & layout.max_sequence())
+}
+
+/// Unpack the generator state into a timestamp and sequence
+fn unpack(state: u64) -> (u64, u64) {
+    let layout = SnowflakeLayout::DEFAULT;
+    (
+        state >> layout.sequence_bits(),
+        state & layout.max_sequence(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::generator::current_timestamp;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_new() {
+        let generator = AtomicSnowflakeGenerator::new(420);
+        assert_eq!(generator.last_snowflake(), Snowflake::new(420));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let generator = AtomicSnowflakeGenerator::try_new(420).unwrap();
+        assert_eq!(generator.last_snowflake(), Snowflake::try_new(420).unwrap());
+        assert!(AtomicSnowflakeGenerator::try_new(1024).is_err());
+    }
+
+    #[test]
+    fn test_start_at() {
+        let snowflake = Snowflake {
+            worker_id: 1,
+            sequence: 5,
+            timestamp: 100,
+            extra: 0,
+        };
+        let generator = AtomicSnowflakeGenerator::start_at(&snowflake);
+        assert_eq!(generator.last_snowflake(), snowflake);
+    }
+
+    #[test]
+    fn test_next() {
+        let generator = AtomicSnowflakeGenerator::try_new(420).unwrap();
+        let mut last = generator.last_snowflake();
+
+        for _ in 0..10 {
+            let snowflake = generator.next();
+            assert!(snowflake > last);
+            assert_eq!(snowflake, generator.last_snowflake());
+            last = snowflake;
+        }
+    }
+
+    #[test]
+    fn test_next_when_sequence_overflows() {
+        let time = current_timestamp();
+        let generator = AtomicSnowflakeGenerator::start_at(&Snowflake {
+            worker_id: 1,
+            sequence: SnowflakeLayout::DEFAULT.max_sequence(),
+            timestamp: time,
+            extra: 0,
+        });
+
+        let next = generator.next();
+
+        assert_eq!(next.sequence, 0);
+        assert!(next.timestamp > time);
+    }
+
+    #[test]
+    fn test_next_concurrent_unique() {
+        let generator = Arc::new(AtomicSnowflakeGenerator::try_new(1).unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = Arc::clone(&generator);
+                std::thread::spawn(move || {
+                    (0..1000)
+                        .map(|_| generator.next().to_id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids = HashSet::new();
+        for handle in handles {
+            for id in handle.join().unwrap() {
+                assert!(ids.insert(id), "Duplicate snowflake ID generated");
+            }
+        }
+
+        assert_eq!(ids.len(), 4000);
+    }
+}
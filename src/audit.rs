@@ -0,0 +1,563 @@
+//! Recording the ranges of IDs a generator hands out, to check after an incident that no range
+//! was ever issued twice.
+//!
+//! An [`AuditedGenerator`] wraps a [`SnowflakeGenerator`] and reports every tick it generated
+//! in as an [`IdRange`] to an [`AuditSink`]: a file with [`FileAuditSink`], a channel with a
+//! `std::sync::mpsc::Sender<IdRange>` or any `FnMut(IdRange)` callback. One range covers the
+//! consecutive sequence numbers of one tick, so the log grows with the number of ticks rather
+//! than the number of IDs. [`read_audit_log`] and [`find_overlaps`] check the logs of every
+//! replica afterwards.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::mpsc;
+//! use rusty_snowflake::audit::{find_overlaps, AuditedGenerator};
+//! use rusty_snowflake::SnowflakeGenerator;
+//!
+//! let (sender, receiver) = mpsc::channel();
+//! let mut generator = AuditedGenerator::new(SnowflakeGenerator::try_new(1).unwrap(), sender);
+//!
+//! generator.try_next_batch(100).unwrap();
+//! drop(generator);
+//!
+//! let ranges: Vec<_> = receiver.iter().collect();
+//! assert_eq!(ranges.iter().map(|range| range.len()).sum::<u64>(), 100);
+//! assert!(find_overlaps(&ranges).is_empty());
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+use crate::coordination::ManagedGenerator;
+use crate::generator::generation_failed;
+use crate::{Clock, GeneratorState, Snowflake, SnowflakeError, SnowflakeGenerator, SystemClock};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// The consecutive sequence numbers a worker issued in one tick.
+///
+/// Displays as `worker_id timestamp first_sequence last_sequence`, the line format of
+/// [`FileAuditSink`].
+pub struct IdRange {
+    /// The worker ID of the snowflakes
+    pub worker_id: u64,
+    /// The timestamp of the snowflakes
+    pub timestamp: u64,
+    /// The sequence number of the first snowflake in the range
+    pub first_sequence: u64,
+    /// The sequence number of the last snowflake in the range
+    pub last_sequence: u64,
+}
+
+impl IdRange {
+    /// Get the number of snowflakes in the range
+    pub fn len(&self) -> u64 {
+        self.last_sequence - self.first_sequence + 1
+    }
+
+    /// Check whether the range is empty, which it never is
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Check whether two ranges share a snowflake
+    ///
+    /// # Arguments
+    /// * `other` - The range to compare with
+    pub fn overlaps(&self, other: &IdRange) -> bool {
+        self.worker_id == other.worker_id
+            && self.timestamp == other.timestamp
+            && self.first_sequence <= other.last_sequence
+            && other.first_sequence <= self.last_sequence
+    }
+}
+
+impl std::fmt::Display for IdRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.worker_id, self.timestamp, self.first_sequence, self.last_sequence
+        )
+    }
+}
+
+/// A destination for the ranges an [`AuditedGenerator`] issues.
+///
+/// Implemented for `FnMut(IdRange)` callbacks, `std::sync::mpsc::Sender<IdRange>` channels and
+/// [`FileAuditSink`].
+pub trait AuditSink {
+    /// Record a range once the generator has moved past it
+    fn record(&mut self, range: IdRange);
+
+    /// Make sure every range recorded so far is stored
+    ///
+    /// # Returns
+    /// The first error storing a range hit since the last flush
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(IdRange)> AuditSink for F {
+    fn record(&mut self, range: IdRange) {
+        self(range)
+    }
+}
+
+impl AuditSink for Sender<IdRange> {
+    /// Send the range, dropping it if the receiver is gone
+    fn record(&mut self, range: IdRange) {
+        let _ = self.send(range);
+    }
+}
+
+#[derive(Debug)]
+/// Appends ranges to a file, one [`IdRange`] per line.
+///
+/// Writes are buffered, so call [`AuditedGenerator::flush`] to know they reached the file.
+pub struct FileAuditSink {
+    writer: BufWriter<File>,
+    /// The first error writing a range since the last flush.
+    error: Option<io::Error>,
+}
+
+impl FileAuditSink {
+    /// Open a file to append ranges to, creating it if it doesn't exist
+    ///
+    /// # Arguments
+    /// * `path` - The path of the audit log
+    ///
+    /// # Returns
+    /// The sink, or the error opening the file
+    pub fn open(path: impl AsRef<Path>) -> io::Result<FileAuditSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(FileAuditSink {
+            writer: BufWriter::new(file),
+            error: None,
+        })
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&mut self, range: IdRange) {
+        if let Err(err) = writeln!(self.writer, "{}", range) {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()
+    }
+}
+
+/// A [`SnowflakeGenerator`] that reports the ranges of IDs it issues to an [`AuditSink`].
+///
+/// The range of the current tick is reported once the generator moves on to another tick, and
+/// when it's [flushed](AuditedGenerator::flush) or dropped.
+///
+/// # Example
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+/// use rusty_snowflake::audit::{AuditedGenerator, IdRange};
+/// use rusty_snowflake::{MockClock, SnowflakeGenerator};
+///
+/// let ranges = Arc::new(Mutex::new(Vec::new()));
+/// let log = Arc::clone(&ranges);
+///
+/// let clock = MockClock::new(1_000_000);
+/// let generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+/// let mut generator = AuditedGenerator::new(generator, move |range: IdRange| {
+///     log.lock().unwrap().push(range)
+/// });
+///
+/// generator.try_next().unwrap();
+/// generator.try_next().unwrap();
+/// clock.advance(1_000);
+/// generator.try_next().unwrap();
+///
+/// assert_eq!(ranges.lock().unwrap().len(), 1);
+/// generator.flush().unwrap();
+/// assert_eq!(ranges.lock().unwrap()[1].timestamp, 1_001);
+/// ```
+pub struct AuditedGenerator<S: AuditSink, C: Clock = SystemClock> {
+    generator: SnowflakeGenerator<C>,
+    sink: S,
+    /// The range of the current tick, not reported yet.
+    open: Option<IdRange>,
+}
+
+impl<S: AuditSink, C: Clock> AuditedGenerator<S, C> {
+    /// Start auditing a generator
+    ///
+    /// # Arguments
+    /// * `generator` - The generator to audit
+    /// * `sink` - Where to report the ranges of IDs generated
+    ///
+    /// # Returns
+    /// A new `AuditedGenerator`
+    pub fn new(generator: SnowflakeGenerator<C>, sink: S) -> AuditedGenerator<S, C> {
+        AuditedGenerator {
+            generator,
+            sink,
+            open: None,
+        }
+    }
+
+    /// Get the generator being audited
+    pub fn generator(&self) -> &SnowflakeGenerator<C> {
+        &self.generator
+    }
+
+    /// Get the sink the ranges are reported to
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// # Panics
+    /// Panics in the same cases as [`SnowflakeGenerator::next`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Snowflake {
+        match self.try_next() {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// # Returns
+    /// The generated snowflake, or the error [`SnowflakeGenerator::try_next`] returned
+    pub fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        let snowflake = self.generator.try_next()?;
+        self.issue(snowflake);
+        Ok(snowflake)
+    }
+
+    /// Generates the next `n` snowflake IDs
+    ///
+    /// # Returns
+    /// The generated snowflakes, or the error [`SnowflakeGenerator::try_next_batch`] returned
+    pub fn try_next_batch(&mut self, n: usize) -> Result<Vec<Snowflake>, SnowflakeError> {
+        let snowflakes = self.generator.try_next_batch(n)?;
+        for snowflake in &snowflakes {
+            self.issue(*snowflake);
+        }
+        Ok(snowflakes)
+    }
+
+    /// Fills `ids` with the next snowflake IDs
+    ///
+    /// # Returns
+    /// The error [`SnowflakeGenerator::try_fill`] returned, after which the IDs it did write
+    /// are still reported
+    pub fn try_fill(&mut self, ids: &mut [u64]) -> Result<(), SnowflakeError> {
+        let mut filled = 0;
+        let result = self.generator.try_fill_counted(ids, &mut filled);
+        let layout = *self.generator.layout();
+
+        for id in &ids[..filled] {
+            self.issue(Snowflake::parse_with_layout(*id, &layout));
+        }
+        result
+    }
+
+    /// Report the range of the current tick and flush the sink
+    ///
+    /// # Returns
+    /// The error [`AuditSink::flush`] returned
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(range) = self.open.take() {
+            self.sink.record(range);
+        }
+        self.sink.flush()
+    }
+
+    /// Add a snowflake to the current range, reporting the range first if the snowflake
+    /// doesn't continue it
+    fn issue(&mut self, snowflake: Snowflake) {
+        if let Some(range) = &mut self.open {
+            if range.timestamp == snowflake.timestamp
+                && range.last_sequence.checked_add(1) == Some(snowflake.sequence)
+            {
+                range.last_sequence = snowflake.sequence;
+                return;
+            }
+        }
+
+        let next = IdRange {
+            worker_id: snowflake.worker_id,
+            timestamp: snowflake.timestamp,
+            first_sequence: snowflake.sequence,
+            last_sequence: snowflake.sequence,
+        };
+        if let Some(range) = self.open.replace(next) {
+            self.sink.record(range);
+        }
+    }
+}
+
+impl<S: AuditSink, C: Clock> Drop for AuditedGenerator<S, C> {
+    /// Report the range of the current tick, ignoring errors storing it
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<S: AuditSink, C: Clock> ManagedGenerator for AuditedGenerator<S, C> {
+    fn worker_id(&self) -> u64 {
+        self.generator.worker_id()
+    }
+
+    fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        AuditedGenerator::try_next(self)
+    }
+
+    fn snapshot(&self) -> GeneratorState {
+        self.generator.snapshot()
+    }
+
+    fn restore(&mut self, state: GeneratorState) -> Result<(), SnowflakeError> {
+        self.generator.restore(state)
+    }
+
+    /// Report the range of the current tick and flush the sink
+    fn flush(&mut self) -> io::Result<()> {
+        AuditedGenerator::flush(self)
+    }
+}
+
+impl<S: AuditSink + std::fmt::Debug, C: Clock + std::fmt::Debug> std::fmt::Debug
+    for AuditedGenerator<S, C>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AuditedGenerator")
+            .field("generator", &self.generator)
+            .field("sink", &self.sink)
+            .field("open", &self.open)
+            .finish()
+    }
+}
+
+/// Read the ranges written by a [`FileAuditSink`]
+///
+/// # Arguments
+/// * `reader` - The audit log, such as a `BufReader` over the file
+///
+/// # Returns
+/// The ranges in the order they were written, or an `InvalidData` error for a line that isn't
+/// a range
+pub fn read_audit_log(reader: impl BufRead) -> io::Result<Vec<IdRange>> {
+    reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+            let fields = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<u64>, _>>();
+
+            match fields.as_deref() {
+                Ok(&[worker_id, timestamp, first_sequence, last_sequence])
+                    if first_sequence <= last_sequence =>
+                {
+                    Ok(IdRange {
+                        worker_id,
+                        timestamp,
+                        first_sequence,
+                        last_sequence,
+                    })
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid audit log line {:?}", line),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Find every pair of ranges that share a snowflake, which means a duplicate ID was issued
+///
+/// # Arguments
+/// * `ranges` - The ranges from the audit logs of every generator sharing the ID space
+///
+/// # Returns
+/// The overlapping pairs, empty if every ID was issued once
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::audit::{find_overlaps, IdRange};
+///
+/// let range = |first_sequence, last_sequence| IdRange {
+///     worker_id: 1,
+///     timestamp: 1_700_000_000,
+///     first_sequence,
+///     last_sequence,
+/// };
+///
+/// assert!(find_overlaps(&[range(0, 9), range(10, 19)]).is_empty());
+/// assert_eq!(
+///     find_overlaps(&[range(0, 9), range(5, 5)]),
+///     [(range(0, 9), range(5, 5))]
+/// );
+/// ```
+pub fn find_overlaps(ranges: &[IdRange]) -> Vec<(IdRange, IdRange)> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_by_key(|range| (range.worker_id, range.timestamp, range.first_sequence));
+
+    let mut overlaps = Vec::new();
+    for (index, range) in sorted.iter().enumerate() {
+        // Ranges after this one start at or after it, so stop at the first that doesn't overlap
+        for other in sorted[index + 1..]
+            .iter()
+            .take_while(|other| range.overlaps(other))
+        {
+            overlaps.push((*range, *other));
+        }
+    }
+
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::{GeneratorOptions, MockClock, OverflowStrategy, SnowflakeLayout};
+
+    #[test]
+    fn test_ranges_per_tick() {
+        let layout = SnowflakeLayout::builder().sequence_bits(2).build().unwrap();
+        let clock = MockClock::new(1_000_000);
+        let generator = SnowflakeGenerator::with_layout_and_clock(3, layout, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().overflow_strategy(OverflowStrategy::Error));
+        let (sender, receiver) = mpsc::channel();
+        let mut generator = AuditedGenerator::new(generator, sender);
+
+        // The generator starts at sequence 0 of the current second, so this tick has 3 left
+        let mut ids = [0; 3];
+        generator.try_fill(&mut ids).unwrap();
+        assert!(generator.try_next().is_err());
+
+        clock.advance(1_000);
+        generator.next();
+        assert!(generator.try_fill(&mut [0; 5]).is_err());
+        drop(generator);
+
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            [
+                IdRange {
+                    worker_id: 3,
+                    timestamp: 1_000,
+                    first_sequence: 1,
+                    last_sequence: 3,
+                },
+                IdRange {
+                    worker_id: 3,
+                    timestamp: 1_001,
+                    first_sequence: 0,
+                    last_sequence: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_sink() {
+        let path =
+            std::env::temp_dir().join(format!("rusty-snowflake-audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        for worker_id in [1, 1] {
+            let generator =
+                SnowflakeGenerator::with_clock(worker_id, MockClock::new(1_000_000)).unwrap();
+            let mut generator =
+                AuditedGenerator::new(generator, FileAuditSink::open(&path).unwrap());
+            generator.try_next_batch(10).unwrap();
+            generator.flush().unwrap();
+        }
+
+        let ranges = read_audit_log(BufReader::new(File::open(&path).unwrap())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Two generators with the same worker ID in the same second issue the same IDs
+        assert_eq!(ranges.iter().map(IdRange::len).sum::<u64>(), 20);
+        assert!(!find_overlaps(&ranges).is_empty());
+    }
+
+    #[test]
+    fn test_leased_shutdown_flushes() {
+        use crate::coordination::{
+            LeasedGenerator, MemoryWorkerRegistry, WorkerLease, WorkerRegistry,
+        };
+
+        let registry = MemoryWorkerRegistry::new();
+        let (sender, receiver) = mpsc::channel();
+        let generator = SnowflakeGenerator::with_clock(0, MockClock::new(1_000_000)).unwrap();
+        let mut generator = LeasedGenerator::new(
+            registry.lease().unwrap(),
+            AuditedGenerator::new(generator, sender),
+        )
+        .unwrap();
+
+        generator.next();
+        generator.next();
+        assert!(receiver.try_recv().is_err());
+
+        generator.shutdown().unwrap();
+        assert_eq!(receiver.try_recv().unwrap().len(), 2);
+        assert_eq!(registry.lease().unwrap().worker_id(), 0);
+    }
+
+    #[test]
+    fn test_read_audit_log() {
+        let log = "1 2 3 4\n\n5 6 7 7\n";
+        assert_eq!(read_audit_log(log.as_bytes()).unwrap().len(), 2);
+
+        for invalid in ["1 2 3", "1 2 3 4 5", "1 2 x 4", "1 2 4 3"] {
+            let err = read_audit_log(invalid.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_find_overlaps() {
+        let range = |worker_id, timestamp, first_sequence, last_sequence| IdRange {
+            worker_id,
+            timestamp,
+            first_sequence,
+            last_sequence,
+        };
+
+        let disjoint = [
+            range(1, 5, 0, 9),
+            range(2, 5, 0, 9),
+            range(1, 6, 0, 9),
+            range(1, 5, 10, 10),
+        ];
+        assert!(find_overlaps(&disjoint).is_empty());
+
+        let overlapping = [range(1, 5, 0, 9), range(1, 5, 9, 12), range(1, 5, 2, 3)];
+        assert_eq!(
+            find_overlaps(&overlapping),
+            [
+                (range(1, 5, 0, 9), range(1, 5, 2, 3)),
+                (range(1, 5, 0, 9), range(1, 5, 9, 12)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Generate and inspect snowflake IDs from the command line.
+
+use std::net::TcpListener;
+use std::process::ExitCode;
+
+use rusty_snowflake::server::IdServer;
+use rusty_snowflake::{Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+const USAGE: &str = "\
+Usage:
+    rusty-snowflake generate [--worker <ID>] [--count <N>] [--layout <LAYOUT>]
+    rusty-snowflake decode <ID> [--layout <LAYOUT>]
+    rusty-snowflake encode --timestamp <TIME> [--worker <ID>] [--sequence <N>] [--layout <LAYOUT>]
+    rusty-snowflake serve [--port <PORT>] [--worker <ID>] [--layout <LAYOUT>]
+    rusty-snowflake --help
+
+<TIME> is an ISO-8601 UTC time such as 2024-01-31T12:00:00Z, or milliseconds since the UNIX epoch.
+<LAYOUT> is one of default, twitter, discord, instagram or sonyflake.";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if is_help(args) {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let (command, args) = args.split_first().ok_or("missing command")?;
+    let mut args = Args::parse(args)?;
+    let layout = match args.take("--layout") {
+        Some(name) => parse_layout(&name)?,
+        None => SnowflakeLayout::DEFAULT,
+    };
+
+    match command.as_str() {
+        "generate" => {
+            let worker_id = args.take_u64("--worker")?.unwrap_or(0);
+            let count = args.take_u64("--count")?.unwrap_or(1);
+            args.finish()?;
+
+            let mut generator = SnowflakeGenerator::with_layout(worker_id, layout)
+                .map_err(|err| err.to_string())?;
+            for _ in 0..count {
+                let snowflake = generator.try_next().map_err(|err| err.to_string())?;
+                println!("{}", snowflake.to_id_with_layout(&layout));
+            }
+        }
+        "decode" => {
+            let id = args.positional()?;
+            args.finish()?;
+
+            let id = id
+                .parse::<u64>()
+                .map_err(SnowflakeError::from)
+                .map_err(|err| err.to_string())?;
+            let snowflake = Snowflake::parse_with_layout(id, &layout);
+            let millis = layout.millis_from_timestamp(snowflake.timestamp());
+
+            println!("timestamp: {}", format_iso8601(millis));
+            println!("worker:    {}", snowflake.worker_id());
+            println!("sequence:  {}", snowflake.sequence());
+        }
+        "encode" => {
+            let time = args.take("--timestamp").ok_or("missing --timestamp")?;
+            let worker_id = args.take_u64("--worker")?.unwrap_or(0);
+            let sequence = args.take_u64("--sequence")?.unwrap_or(0);
+            args.finish()?;
+
+            let millis = parse_time(&time)?;
+            if millis < layout.epoch() {
+                return Err(SnowflakeError::ClockBeforeEpoch.to_string());
+            }
+
+            let snowflake = Snowflake::builder()
+                .timestamp(layout.timestamp_from_millis(millis))
+                .worker_id(worker_id)
+                .sequence(sequence)
+                .layout(layout)
+                .build()
+                .map_err(|err| err.to_string())?;
+            println!("{}", snowflake.to_id_with_layout(&layout));
+        }
+        "serve" => {
+            let port = args.take_u64("--port")?.unwrap_or(8080);
+            let worker_id = args.take_u64("--worker")?.unwrap_or(0);
+            args.finish()?;
+
+            let port = u16::try_from(port).map_err(|_| format!("invalid port {}", port))?;
+            let generator = SnowflakeGenerator::with_layout(worker_id, layout)
+                .map_err(|err| err.to_string())?;
+            let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|err| err.to_string())?;
+
+            eprintln!("serving IDs for worker {} on port {}", worker_id, port);
+            IdServer::new(generator).serve(listener);
+        }
+        command => return Err(format!("unknown command {:?}", command)),
+    }
+
+    Ok(())
+}
+
+/// Check whether the arguments ask for the usage, with `help`, `--help` or `-h`
+fn is_help(args: &[String]) -> bool {
+    args.first().is_some_and(|command| command == "help")
+        || args.iter().any(|arg| arg == "--help" || arg == "-h")
+}
+
+/// The arguments after the command, split into `--flag value` options and positionals
+struct Args {
+    options: Vec<(String, String)>,
+    positionals: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Args, String> {
+        let mut options = Vec::new();
+        let mut positionals = Vec::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                options.push((arg.clone(), value.clone()));
+            } else {
+                positionals.push(arg.clone());
+            }
+        }
+
+        Ok(Args {
+            options,
+            positionals,
+        })
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        let index = self.options.iter().position(|(option, _)| option == name)?;
+        Some(self.options.remove(index).1)
+    }
+
+    fn take_u64(&mut self, name: &str) -> Result<Option<u64>, String> {
+        self.take(name)
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(|err| format!("invalid value for {}: {}", name, err))
+            })
+            .transpose()
+    }
+
+    fn positional(&mut self) -> Result<String, String> {
+        if self.positionals.is_empty() {
+            return Err("missing ID".to_string());
+        }
+
+        Ok(self.positionals.remove(0))
+    }
+
+    /// Reject any arguments that weren't used
+    fn finish(self) -> Result<(), String> {
+        match (self.options.first(), self.positionals.first()) {
+            (Some((option, _)), _) => Err(format!("unexpected option {}", option)),
+            (None, Some(arg)) => Err(format!("unexpected argument {:?}", arg)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+fn parse_layout(name: &str) -> Result<SnowflakeLayout, String> {
+    match name {
+        "default" => Ok(SnowflakeLayout::DEFAULT),
+        "twitter" => Ok(SnowflakeLayout::TWITTER),
+        "discord" => Ok(SnowflakeLayout::DISCORD),
+        "instagram" => Ok(SnowflakeLayout::INSTAGRAM),
+        "sonyflake" => Ok(SnowflakeLayout::SONYFLAKE),
+        name => Err(format!("unknown layout {:?}", name)),
+    }
+}
+
+/// Parse milliseconds since the UNIX epoch, or a UTC time like `2024-01-31T12:00:00.123Z`
+fn parse_time(time: &str) -> Result<u64, String> {
+    if let Ok(millis) = time.parse::<u64>() {
+        return Ok(millis);
+    }
+
+    let invalid = || format!("invalid time {:?}", time);
+    let time = time.strip_suffix('Z').ok_or_else(invalid)?;
+    let (date, clock) = time.split_once('T').ok_or_else(invalid)?;
+    let (clock, fraction) = clock.split_once('.').unwrap_or((clock, "0"));
+
+    let number = |part: &str| part.parse::<u64>().map_err(|_| invalid());
+    let date = date.split('-').map(number).collect::<Result<Vec<_>, _>>()?;
+    let clock = clock
+        .split(':')
+        .map(number)
+        .collect::<Result<Vec<_>, _>>()?;
+    let fraction = format!("{:0<3}", fraction);
+    let millis = number(fraction.get(..3).ok_or_else(invalid)?)?;
+
+    match (date.as_slice(), clock.as_slice()) {
+        (&[year, month @ 1..=12, day], &[hour @ 0..=23, minute @ 0..=59, second @ 0..=59])
+            if year >= 1970 && (1..=days_in_month(year, month)).contains(&day) =>
+        {
+            days_from_civil(year, month, day)
+                .and_then(|days| days.checked_mul(86_400))
+                .and_then(|seconds| seconds.checked_add((hour * 60 + minute) * 60 + second))
+                .and_then(|seconds| seconds.checked_mul(1_000))
+                .and_then(|millis_before| millis_before.checked_add(millis))
+                .ok_or_else(invalid)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Get the number of days in a month, counting leap years in the Gregorian calendar
+fn days_in_month(year: u64, month: u64) -> u64 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Format milliseconds since the UNIX epoch as a UTC time like `2024-01-31T12:00:00.123Z`
+fn format_iso8601(millis: u64) -> String {
+    let seconds = millis / 1_000;
+    let (year, month, day) = civil_from_days(seconds / 86_400);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        millis % 1_000
+    )
+}
+
+/// Count the days from 1970-01-01 to a date, with Howard Hinnant's algorithm
+///
+/// Returns `None` if the count doesn't fit in a `u64`.
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era.checked_mul(146_097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719_468)
+}
+
+/// Get the date that is a number of days after 1970-01-01, with Howard Hinnant's algorithm
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("0"), Ok(0));
+        assert_eq!(parse_time("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(
+            parse_time("2016-04-30T11:18:25.796Z"),
+            Ok(1_462_015_105_796)
+        );
+        assert_eq!(parse_time("2000-03-01T00:00:00.5Z"), Ok(951_868_800_500));
+        assert!(parse_time("2016-13-01T00:00:00Z").is_err());
+        assert!(parse_time("2016-04-30 11:18:25").is_err());
+        assert!(parse_time("2016-04-30T11:18:25.ééZ").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_overflow() {
+        assert!(parse_time("99999999999999-01-01T00:00:00Z").is_err());
+        assert!(parse_time("18446744073709551615-12-31T23:59:59Z").is_err());
+        // The last time that fits in u64 milliseconds
+        assert_eq!(parse_time(&format_iso8601(u64::MAX)), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_parse_time_day_of_month() {
+        assert!(parse_time("2024-02-30T12:00:00Z").is_err());
+        assert!(parse_time("2023-02-29T12:00:00Z").is_err());
+        assert!(parse_time("1900-02-29T12:00:00Z").is_err());
+        assert!(parse_time("2024-04-31T12:00:00Z").is_err());
+        assert!(parse_time("2024-01-00T12:00:00Z").is_err());
+        assert!(parse_time("2024-02-29T12:00:00Z").is_ok());
+        assert!(parse_time("2000-02-29T12:00:00Z").is_ok());
+        assert!(parse_time("2024-12-31T12:00:00Z").is_ok());
+    }
+
+    #[test]
+    fn test_help() {
+        assert!(is_help(&["--help".to_string()]));
+        assert!(is_help(&["help".to_string()]));
+        assert!(is_help(&["decode", "-h"].map(String::from)));
+        assert!(!is_help(&["decode", "123"].map(String::from)));
+        assert_eq!(run(&["--help".to_string()]), Ok(()));
+    }
+
+    #[test]
+    fn test_format_iso8601() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_iso8601(1_462_015_105_796),
+            "2016-04-30T11:18:25.796Z"
+        );
+        assert_eq!(format_iso8601(951_782_400_000), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for millis in [0, 86_399_999, 951_782_400_000, 4_102_444_800_000] {
+            assert_eq!(parse_time(&format_iso8601(millis)), Ok(millis));
+        }
+    }
+
+    #[test]
+    fn test_args() {
+        let args = ["123", "--layout", "discord"].map(String::from);
+        let mut args = Args::parse(&args).unwrap();
+
+        assert_eq!(args.take("--layout"), Some("discord".to_string()));
+        assert_eq!(args.positional(), Ok("123".to_string()));
+        assert!(args.finish().is_ok());
+
+        let args = ["--count"].map(String::from);
+        assert!(Args::parse(&args).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use core::time::Duration;
+
+use crate::{Snowflake, SnowflakeLayout};
+
+/// The range [`Snowflake::bucket_range`] returns for a bucket without any IDs.
+const EMPTY_RANGE: (u64, u64) = (1, 0);
+
+impl Snowflake {
+    /// Get the time bucket the snowflake was created in, for routing IDs to partitions
+    ///
+    /// Buckets are counted from the UNIX epoch rather than the layout's epoch, so daily
+    /// buckets start at midnight UTC whatever the layout. Granularities shorter than a
+    /// microsecond are treated as one microsecond.
+    ///
+    /// # Arguments
+    /// * `granularity` - The length of each bucket, like a day or an hour
+    ///
+    /// # Returns
+    /// The number of whole buckets between the UNIX epoch and the snowflake's creation time
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    ///
+    /// // 2023-11-14 22:13:20 UTC
+    /// let snowflake = Snowflake::builder().timestamp(1_700_000_000).build().unwrap();
+    /// let day = snowflake.bucket(DAY);
+    ///
+    /// assert_eq!(day, 19_675);
+    ///
+    /// let (start, end) = Snowflake::bucket_range(day, DAY);
+    /// assert!(start <= snowflake.to_id() && snowflake.to_id() <= end);
+    /// ```
+    pub fn bucket(self, granularity: Duration) -> u64 {
+        self.bucket_with_layout(granularity, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the time bucket the snowflake was created in, using the epoch and tick of a custom
+    /// layout
+    ///
+    /// See [`Snowflake::bucket`].
+    ///
+    /// # Arguments
+    /// * `granularity` - The length of each bucket
+    /// * `layout` - The layout the snowflake was parsed with
+    pub fn bucket_with_layout(self, granularity: Duration, layout: &SnowflakeLayout) -> u64 {
+        let micros = layout.micros_from_timestamp(self.timestamp) as u128;
+        (micros / granularity_micros(granularity)) as u64
+    }
+
+    /// Get the inclusive range of IDs created in a time bucket, for partition bounds and
+    /// pruning queries like `WHERE id BETWEEN ? AND ?`
+    ///
+    /// A bucket that no timestamp tick starts in, because it's before the epoch, after the
+    /// last timestamp or between two ticks of a layout with a longer tick, holds no IDs and
+    /// gives `(1, 0)`.
+    ///
+    /// # Arguments
+    /// * `bucket` - The bucket, as returned by [`Snowflake::bucket`]
+    /// * `granularity` - The length of each bucket
+    ///
+    /// # Returns
+    /// The smallest and largest IDs in the bucket
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// const HOUR: Duration = Duration::from_secs(60 * 60);
+    ///
+    /// let (start, end) = Snowflake::bucket_range(472_222, HOUR);
+    ///
+    /// assert_eq!(Snowflake::parse(start).timestamp(), 1_699_999_200);
+    /// assert_eq!(Snowflake::parse(end).timestamp(), 1_700_002_799);
+    /// assert_eq!(Snowflake::bucket_range(472_223, HOUR).0, end + 1);
+    /// ```
+    pub fn bucket_range(bucket: u64, granularity: Duration) -> (u64, u64) {
+        Snowflake::bucket_range_with_layout(bucket, granularity, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the inclusive range of IDs created in a time bucket, using a custom layout
+    ///
+    /// See [`Snowflake::bucket_range`].
+    ///
+    /// # Arguments
+    /// * `bucket` - The bucket, as returned by [`Snowflake::bucket_with_layout`]
+    /// * `granularity` - The length of each bucket
+    /// * `layout` - The layout of the IDs in the bucket
+    ///
+    /// # Returns
+    /// The smallest and largest IDs in the bucket, or `(1, 0)` if it holds no IDs
+    pub fn bucket_range_with_layout(
+        bucket: u64,
+        granularity: Duration,
+        layout: &SnowflakeLayout,
+    ) -> (u64, u64) {
+        let granularity = granularity_micros(granularity);
+        let epoch = layout.epoch() as u128 * 1_000;
+        let tick = layout.tick_micros() as u128;
+
+        let start = (bucket as u128).saturating_mul(granularity);
+        let end = start.saturating_add(granularity - 1);
+        if end < epoch {
+            return EMPTY_RANGE;
+        }
+
+        // The first tick that starts at or after the bucket, and the last that starts in it
+        let first = start.saturating_sub(epoch).div_ceil(tick);
+        let last = ((end - epoch) / tick).min(layout.max_timestamp() as u128);
+        if first > last {
+            return EMPTY_RANGE;
+        }
+
+        match (
+            Snowflake::min_for_timestamp_with_layout(first as u64, layout),
+            Snowflake::max_for_timestamp_with_layout(last as u64, layout),
+        ) {
+            (Ok(min), Ok(max)) => (min.to_id_with_layout(layout), max.to_id_with_layout(layout)),
+            _ => EMPTY_RANGE,
+        }
+    }
+}
+
+/// The length of a bucket in microseconds, at least one
+fn granularity_micros(granularity: Duration) -> u128 {
+    granularity.as_micros().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn test_buckets_cover_every_id() {
+        let layout = SnowflakeLayout::DISCORD;
+
+        for day in [16_436, 19_675, 19_676] {
+            let (start, end) = Snowflake::bucket_range_with_layout(day, DAY, &layout);
+            let (next, _) = Snowflake::bucket_range_with_layout(day + 1, DAY, &layout);
+
+            assert_eq!(next, end + 1);
+            for id in [start, end] {
+                let snowflake = Snowflake::parse_with_layout(id, &layout);
+                assert_eq!(snowflake.bucket_with_layout(DAY, &layout), day);
+            }
+        }
+
+        // The first day with IDs is the one the Discord epoch falls in
+        assert_eq!(
+            Snowflake::bucket_range_with_layout(16_435, DAY, &layout),
+            EMPTY_RANGE
+        );
+        assert_eq!(
+            Snowflake::bucket_range_with_layout(16_436, DAY, &layout).0,
+            0
+        );
+    }
+
+    #[test]
+    fn test_buckets_with_extra_bits() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .build()
+            .unwrap();
+        let snowflake = Snowflake {
+            worker_id: 0,
+            sequence: 0,
+            timestamp: 20,
+            extra: 3,
+        };
+        let bucket = snowflake.bucket_with_layout(HOUR, &layout);
+        let (start, end) = Snowflake::bucket_range_with_layout(bucket, HOUR, &layout);
+        let (next, _) = Snowflake::bucket_range_with_layout(bucket + 1, HOUR, &layout);
+
+        // The last tick of the bucket with the largest extra value
+        let last = Snowflake {
+            timestamp: Snowflake::parse_with_layout(end, &layout).timestamp,
+            ..snowflake
+        };
+        for id in [
+            snowflake.to_id_with_layout(&layout),
+            last.to_id_with_layout(&layout),
+        ] {
+            assert!(start <= id && id <= end);
+        }
+        assert_eq!(next, end + 1);
+    }
+
+    #[test]
+    fn test_empty_buckets() {
+        // Ticks of a second leave most millisecond buckets empty
+        let millisecond = Duration::from_millis(1);
+        assert_eq!(Snowflake::bucket_range(1_000, millisecond).0, 1 << 22);
+        assert_eq!(Snowflake::bucket_range(1_001, millisecond), EMPTY_RANGE);
+
+        // Past the last timestamp of the layout
+        assert_eq!(Snowflake::bucket_range(u64::MAX, HOUR), EMPTY_RANGE);
+        assert_eq!(
+            Snowflake::bucket_range(0, Duration::ZERO),
+            (0, Snowflake::max_for_timestamp(0).unwrap().to_id())
+        );
+    }
+
+    #[test]
+    fn test_bucket() {
+        let snowflake = Snowflake::builder()
+            .timestamp(1_700_000_000)
+            .build()
+            .unwrap();
+
+        assert_eq!(snowflake.bucket(HOUR), 472_222);
+        assert_eq!(snowflake.bucket(Duration::ZERO), 1_700_000_000_000_000);
+        assert_eq!(snowflake.bucket(Duration::MAX), 0);
+    }
+}
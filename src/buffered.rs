@@ -0,0 +1,369 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::Thread;
+use std::time::Duration;
+
+use crate::generator::generation_failed;
+use crate::{Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+/// How long the background thread waits for room in a full buffer before checking again, in
+/// case a wake-up was missed.
+const REFILL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How many times a caller checks an empty buffer before it starts sleeping between checks.
+const SPINS: u32 = 64;
+
+/// Snowflakes generated ahead of time on a background thread, for latency-sensitive callers.
+///
+/// The background thread keeps a ring buffer of up to `capacity` IDs topped up, so taking one
+/// is a single compare-and-swap on the buffer and never waits on the generator itself. When
+/// the generator runs out of sequence numbers and waits for the next tick, callers keep taking
+/// IDs that were generated before, which moves the wait off the caller's tail latency as long
+/// as the buffer is big enough to cover it.
+///
+/// Callers can share the generator between threads by reference. The background thread stops
+/// when the generator is dropped.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{BufferedGenerator, SnowflakeGenerator};
+///
+/// let generator = BufferedGenerator::new(SnowflakeGenerator::try_new(420).unwrap(), 1024);
+///
+/// let first = generator.next();
+/// let second = generator.next();
+///
+/// assert!(second > first);
+/// assert_eq!(first.worker_id(), 420);
+/// ```
+pub struct BufferedGenerator {
+    shared: Arc<Shared>,
+    /// The background thread, to wake when IDs are taken from a full buffer.
+    producer: Thread,
+}
+
+/// The state shared by a [`BufferedGenerator`] and its background thread.
+struct Shared {
+    /// The ring buffer, with a power of two number of slots.
+    slots: Box<[Slot]>,
+    /// The position of the next ID to take.
+    head: AtomicUsize,
+    /// The position of the next ID to add, only written by the background thread.
+    tail: AtomicUsize,
+    layout: SnowflakeLayout,
+    /// Set when the generator is dropped, or when the background thread stopped.
+    closed: AtomicBool,
+    /// The error that stopped the background thread.
+    error: Mutex<Option<SnowflakeError>>,
+}
+
+/// One slot of the ring buffer.
+struct Slot {
+    /// Equal to the position of the slot when it's free to write and one more than it when it
+    /// holds an ID, so readers and the writer can tell which lap of the ring it's on.
+    stamp: AtomicUsize,
+    id: AtomicU64,
+}
+
+impl BufferedGenerator {
+    /// Start generating snowflakes on a background thread
+    ///
+    /// # Arguments
+    /// * `generator` - The generator to take snowflakes from
+    /// * `capacity` - The most snowflakes generated ahead of time, rounded up to a power of two
+    ///   of at least two
+    pub fn new(generator: SnowflakeGenerator, capacity: usize) -> BufferedGenerator {
+        let capacity = capacity.max(2).next_power_of_two();
+        let shared = Arc::new(Shared {
+            slots: (0..capacity)
+                .map(|position| Slot {
+                    stamp: AtomicUsize::new(position),
+                    id: AtomicU64::new(0),
+                })
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            layout: *generator.layout(),
+            closed: AtomicBool::new(false),
+            error: Mutex::new(None),
+        });
+
+        let producer = Arc::clone(&shared);
+        let handle = std::thread::spawn(move || producer.produce(generator));
+
+        BufferedGenerator {
+            shared,
+            producer: handle.thread().clone(),
+        }
+    }
+
+    /// Take the next snowflake if one is ready, without waiting
+    ///
+    /// # Returns
+    /// The next snowflake, or `None` if the buffer is empty
+    pub fn pop(&self) -> Option<Snowflake> {
+        let (id, remaining) = self.shared.pop()?;
+
+        // Wake the background thread once half of a full buffer was taken
+        if remaining == self.capacity() / 2 {
+            self.producer.unpark();
+        }
+
+        Some(Snowflake::parse_with_layout(id, &self.shared.layout))
+    }
+
+    /// Take the next snowflake, waiting for the background thread if none is ready
+    ///
+    /// # Panics
+    /// Panics if the generator failed, see [`SnowflakeGenerator::try_next`]. Use
+    /// [`BufferedGenerator::try_next`] to handle that instead.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&self) -> Snowflake {
+        match self.try_next() {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Take the next snowflake, waiting for the background thread if none is ready
+    ///
+    /// # Returns
+    /// The next snowflake, or the generator's error once it has failed and every snowflake
+    /// generated before has been taken
+    pub fn try_next(&self) -> Result<Snowflake, SnowflakeError> {
+        let mut spins = 0;
+
+        loop {
+            if let Some(snowflake) = self.pop() {
+                return Ok(snowflake);
+            }
+
+            if self.shared.closed.load(Ordering::Acquire) {
+                // The thread may have added its last IDs just before stopping
+                if let Some(snowflake) = self.pop() {
+                    return Ok(snowflake);
+                }
+
+                // The background thread only stops early on an error, which it stores first
+                let error = self
+                    .shared
+                    .error
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+                if let Some(err) = error {
+                    return Err(err);
+                }
+            }
+
+            if spins < SPINS {
+                spins += 1;
+                std::hint::spin_loop();
+            } else {
+                std::thread::sleep(Duration::from_micros(50));
+            }
+        }
+    }
+
+    /// Get the number of snowflakes ready to take
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Check whether no snowflakes are ready to take
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the most snowflakes generated ahead of time
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.shared.layout
+    }
+}
+
+impl Drop for BufferedGenerator {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.producer.unpark();
+    }
+}
+
+impl std::fmt::Debug for BufferedGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("BufferedGenerator")
+            .field("buffered", &self.len())
+            .field("capacity", &self.capacity())
+            .field("layout", &self.shared.layout)
+            .finish()
+    }
+}
+
+impl Shared {
+    /// Generate snowflakes into the buffer until the generator is dropped or fails
+    fn produce(&self, mut generator: SnowflakeGenerator) {
+        while !self.closed.load(Ordering::Acquire) {
+            let tail = self.tail.load(Ordering::Relaxed);
+            let slot = &self.slots[tail & self.mask()];
+
+            // The slot still holds an ID from the last lap, so the buffer is full
+            if slot.stamp.load(Ordering::Acquire) != tail {
+                std::thread::park_timeout(REFILL_INTERVAL);
+                continue;
+            }
+
+            match generator.try_next() {
+                Ok(snowflake) => {
+                    slot.id
+                        .store(snowflake.to_id_with_layout(&self.layout), Ordering::Relaxed);
+                    slot.stamp.store(tail + 1, Ordering::Release);
+                    self.tail.store(tail + 1, Ordering::Release);
+                }
+                Err(err) => {
+                    *self.error.lock().unwrap_or_else(PoisonError::into_inner) = Some(err);
+                    self.closed.store(true, Ordering::Release);
+                }
+            }
+        }
+    }
+
+    /// Take the oldest ID from the buffer
+    ///
+    /// # Returns
+    /// The ID and the number of IDs left after it, or `None` if the buffer is empty
+    fn pop(&self) -> Option<(u64, usize)> {
+        let mut head = self.head.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[head & self.mask()];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if stamp == head + 1 {
+                match self.head.compare_exchange_weak(
+                    head,
+                    head + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let id = slot.id.load(Ordering::Relaxed);
+                        // Free the slot for the next lap
+                        slot.stamp.store(head + self.slots.len(), Ordering::Release);
+
+                        let tail = self.tail.load(Ordering::Acquire);
+                        return Some((id, tail.saturating_sub(head + 1)));
+                    }
+                    Err(actual) => head = actual,
+                }
+            } else if stamp == head {
+                // Nothing has been written to the slot on this lap yet
+                return None;
+            } else {
+                // Another caller took the slot first
+                head = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.saturating_sub(head)
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{ClockBackwardsPolicy, GeneratorOptions};
+
+    /// Wait until the background thread has filled the buffer
+    fn wait_until_full(generator: &BufferedGenerator) {
+        while generator.len() < generator.capacity() {
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn test_next() {
+        let generator = BufferedGenerator::new(SnowflakeGenerator::try_new(1).unwrap(), 100);
+        assert_eq!(generator.capacity(), 128);
+
+        let snowflakes = (0..1_000).map(|_| generator.next()).collect::<Vec<_>>();
+
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(snowflakes
+            .iter()
+            .all(|snowflake| snowflake.worker_id() == 1));
+    }
+
+    #[test]
+    fn test_pop_from_full_buffer() {
+        let generator = BufferedGenerator::new(SnowflakeGenerator::try_new(1).unwrap(), 8);
+        wait_until_full(&generator);
+
+        assert_eq!(generator.len(), 8);
+        let first = generator.pop().unwrap();
+        assert!(generator.pop().unwrap() > first);
+
+        // Taking half of a full buffer wakes the background thread to top it up
+        for _ in 0..4 {
+            generator.pop().unwrap();
+        }
+        wait_until_full(&generator);
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let generator = BufferedGenerator::new(SnowflakeGenerator::try_new(1).unwrap(), 64);
+
+        let ids = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let ids = (0..2_000)
+                            .map(|_| generator.next().to_id())
+                            .collect::<Vec<_>>();
+                        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+                        ids
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<HashSet<_>>()
+        });
+
+        assert_eq!(ids.len(), 8_000);
+    }
+
+    #[test]
+    fn test_generator_error() {
+        let generator = SnowflakeGenerator::start_at(&Snowflake {
+            worker_id: 1,
+            sequence: 0,
+            timestamp: SnowflakeGenerator::try_get_timestamp().unwrap() + 60,
+            extra: 0,
+        })
+        .with_options(GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error));
+        let generator = BufferedGenerator::new(generator, 4);
+
+        assert!(matches!(
+            generator.try_next(),
+            Err(SnowflakeError::ClockMovedBackwards { .. })
+        ));
+        assert!(generator.is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Working with large numbers of IDs at once, such as generating datasets for load tests or
+//! merging the sorted IDs of several shards.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{Clock, Snowflake, SnowflakeError, SnowflakeLayout, SystemClock};
+
+/// Generate `count_per_worker` IDs for each worker ID on its own thread, merged into one sorted
+/// list
+///
+/// See [`generate_parallel_with_layout`].
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::bulk;
+///
+/// let ids = bulk::generate_parallel(&[1, 2, 3], 10_000).unwrap();
+///
+/// assert_eq!(ids.len(), 30_000);
+/// assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+/// ```
+pub fn generate_parallel(
+    worker_ids: &[u64],
+    count_per_worker: usize,
+) -> Result<Vec<u64>, SnowflakeError> {
+    generate_parallel_with_layout(worker_ids, count_per_worker, &SnowflakeLayout::DEFAULT)
+}
+
+/// Generate `count_per_worker` IDs for each worker ID on its own thread, merged into one sorted
+/// list, using a custom layout
+///
+/// Each worker uses every sequence number of a tick before moving on to the next, like a
+/// worker running flat out, and its last IDs fall in the current tick. The IDs don't wait for
+/// the clock, so this is as fast as the threads can write them, but they can repeat IDs a real
+/// generator with the same worker ID issued recently.
+///
+/// Duplicate worker IDs are only generated for once.
+///
+/// # Arguments
+/// * `worker_ids` - The worker IDs to generate for
+/// * `count_per_worker` - The number of IDs to generate for each worker ID
+/// * `layout` - The bit layout of the IDs
+///
+/// # Returns
+/// The IDs in ascending order, or an error if a worker ID doesn't fit in the layout or the
+/// timestamps would go past the end of the layout's timestamp
+pub fn generate_parallel_with_layout(
+    worker_ids: &[u64],
+    count_per_worker: usize,
+    layout: &SnowflakeLayout,
+) -> Result<Vec<u64>, SnowflakeError> {
+    let mut worker_ids = worker_ids.to_vec();
+    worker_ids.sort_unstable();
+    worker_ids.dedup();
+
+    let max = layout.max_worker_id();
+    if let Some(&worker_id) = worker_ids.iter().find(|&&worker_id| worker_id > max) {
+        return Err(SnowflakeError::WorkerIdOverflow { worker_id, max });
+    }
+
+    // Fill whole ticks, ending in the current one
+    let per_tick = layout.max_sequence().saturating_add(1);
+    let ticks = (count_per_worker as u64).div_ceil(per_tick);
+    let now = layout.timestamp_from_micros(SystemClock.now_micros());
+    let start = now.saturating_sub(ticks.saturating_sub(1));
+
+    let max = layout.max_timestamp();
+    let last = start.saturating_add(ticks.saturating_sub(1));
+    if last > max {
+        return Err(SnowflakeError::TimestampOverflow {
+            timestamp: last,
+            max,
+        });
+    }
+
+    let runs = std::thread::scope(|scope| {
+        let handles = worker_ids
+            .iter()
+            .map(|&worker_id| {
+                scope.spawn(move || {
+                    (0..count_per_worker as u64)
+                        .map(|index| {
+                            Snowflake {
+                                worker_id,
+                                sequence: index % per_tick,
+                                timestamp: start + index / per_tick,
+                                extra: 0,
+                            }
+                            .to_id_with_layout(layout)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    Ok(merge(runs))
+}
+
+/// Merge sorted runs of IDs into one sorted list
+fn merge(runs: Vec<Vec<u64>>) -> Vec<u64> {
+    let mut merged = Vec::with_capacity(runs.iter().map(Vec::len).sum());
+    merged.extend(merge_sorted(runs));
+    merged
+}
+
+/// Merge streams of IDs that are each in ascending order into one stream in ascending order
+///
+/// Snowflake IDs sort by time, so this puts the IDs of several workers or shards back into
+/// global time order, such as when reassembling the order of events from the logs of each
+/// shard. The streams are read lazily, one ID ahead each.
+///
+/// Equal IDs from different streams are all kept, the one from the earlier stream first.
+///
+/// # Arguments
+/// * `streams` - The streams to merge, each in ascending order
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::bulk;
+///
+/// let shards = vec![vec![1, 4, 9], vec![2, 3, 10], vec![]];
+///
+/// assert_eq!(
+///     bulk::merge_sorted(shards).collect::<Vec<_>>(),
+///     [1, 2, 3, 4, 9, 10]
+/// );
+/// ```
+pub fn merge_sorted<I: IntoIterator<Item = u64>>(
+    streams: impl IntoIterator<Item = I>,
+) -> MergeSorted<I::IntoIter> {
+    let mut streams = streams
+        .into_iter()
+        .map(IntoIterator::into_iter)
+        .collect::<Vec<_>>();
+
+    let heads = streams
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(stream, ids)| Some(Reverse((ids.next()?, stream))))
+        .collect();
+
+    MergeSorted { streams, heads }
+}
+
+#[derive(Debug, Clone)]
+/// The IDs of several ascending streams in ascending order, returned by [`merge_sorted`].
+pub struct MergeSorted<I: Iterator<Item = u64>> {
+    streams: Vec<I>,
+    /// The next ID of every stream that has one left, with the index of its stream.
+    heads: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for MergeSorted<I> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let Reverse((id, stream)) = self.heads.pop()?;
+        if let Some(next) = self.streams[stream].next() {
+            self.heads.push(Reverse((next, stream)));
+        }
+        Some(id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.streams.iter().map(Iterator::size_hint).fold(
+            (self.heads.len(), Some(self.heads.len())),
+            |(low, high), (stream_low, stream_high)| {
+                (
+                    low.saturating_add(stream_low),
+                    high.zip(stream_high)
+                        .and_then(|(high, stream_high)| high.checked_add(stream_high)),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SnowflakeGenerator;
+
+    #[test]
+    fn test_generate_parallel() {
+        let ids = generate_parallel(&[7, 3, 7], 5_000).unwrap();
+
+        assert_eq!(ids.len(), 10_000);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let snowflakes = ids.iter().map(|&id| Snowflake::parse(id));
+        assert_eq!(
+            snowflakes.clone().filter(|s| s.worker_id() == 3).count(),
+            5_000
+        );
+
+        // A full tick of 4096 IDs per worker, then a partial tick ending now
+        let first = Snowflake::parse(ids[0]);
+        let last = Snowflake::parse(ids[ids.len() - 1]);
+        assert_eq!(last.timestamp() - first.timestamp(), 1);
+        assert_eq!(last.sequence(), 5_000 - 4_096 - 1);
+        assert!(last.timestamp() <= SnowflakeGenerator::try_get_timestamp().unwrap());
+    }
+
+    #[test]
+    fn test_generate_parallel_with_layout() {
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let ids = generate_parallel_with_layout(&[1, 2], 1_000, &layout).unwrap();
+
+        assert_eq!(ids.len(), 2_000);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(generate_parallel_with_layout(&[1 << 16], 1, &layout).is_err());
+
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(0)
+            .worker_id_bits(0)
+            .sequence_bits(64)
+            .epoch(u64::MAX)
+            .build()
+            .unwrap();
+        assert_eq!(
+            generate_parallel_with_layout(&[0], 3, &layout),
+            Ok(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let evens = (0..10).map(|n| n * 2);
+        let odds = (0..10).map(|n| n * 2 + 1);
+
+        let merged = merge_sorted([evens.collect::<Vec<_>>(), odds.collect()]);
+        assert_eq!(merged.size_hint(), (20, Some(20)));
+        assert_eq!(merged.collect::<Vec<_>>(), (0..20).collect::<Vec<_>>());
+
+        // Lazy streams are only read as far as needed
+        let mut merged = merge_sorted([(0..).step_by(3), (10..).step_by(2)]);
+        assert_eq!(merged.size_hint().1, None);
+        assert_eq!(
+            merged.by_ref().take(6).collect::<Vec<_>>(),
+            [0, 3, 6, 9, 10, 12]
+        );
+
+        assert_eq!(merge_sorted(Vec::<Vec<u64>>::new()).next(), None);
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(
+            merge(vec![vec![1, 4, 9], vec![], vec![2, 3, 10]]),
+            [1, 2, 3, 4, 9, 10]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! A client for a central ID issuer such as `server::IdServer`.
+//!
+//! [`RemoteGenerator`] fetches IDs from the issuer in blocks and hands them out locally, so only
+//! one in every block of IDs costs a round trip, like the segment buffers of Leaf or
+//! uid-generator.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::{Clock, SnowflakeError, SnowflakeGenerator, SystemClock};
+
+#[derive(Debug)]
+/// Hands out IDs fetched in blocks from a central issuer speaking the protocol of the `server`
+/// module.
+///
+/// When the issuer can't be reached the generator falls back to a local [`SnowflakeGenerator`],
+/// if one was given, and tries the issuer again after the retry interval. The fallback
+/// generator needs a worker ID of its own that the issuer never uses, so its IDs can't collide
+/// with the issuer's.
+///
+/// # Example
+/// ```rust,no_run
+/// use rusty_snowflake::client::RemoteGenerator;
+/// use rusty_snowflake::SnowflakeGenerator;
+///
+/// // Worker ID 1023 is reserved for this client's fallback
+/// let mut generator = RemoteGenerator::new("ids.internal:8080")
+///     .block_size(500)
+///     .fallback(SnowflakeGenerator::try_new(1023).unwrap());
+///
+/// println!("Generated ID: {}", generator.try_next().unwrap());
+/// ```
+pub struct RemoteGenerator<C: Clock = SystemClock> {
+    /// The `host:port` of the issuer.
+    address: String,
+    /// How many IDs to fetch at once.
+    block_size: u64,
+    /// How long connecting to and reading from the issuer may take.
+    timeout: Duration,
+    /// How long to wait after a failed fetch before trying the issuer again.
+    retry_interval: Duration,
+    /// The generator used while the issuer is unreachable.
+    fallback: Option<SnowflakeGenerator<C>>,
+    /// When the issuer may be tried again after a failed fetch.
+    retry_at: Option<Instant>,
+    /// The fetched IDs that haven't been handed out yet.
+    block: VecDeque<u64>,
+}
+
+impl RemoteGenerator {
+    /// Create a client for the issuer at `address` that fetches 1000 IDs at a time, with a one
+    /// second timeout and no fallback
+    ///
+    /// # Arguments
+    /// * `address` - The `host:port` of the issuer
+    pub fn new(address: impl Into<String>) -> RemoteGenerator {
+        RemoteGenerator {
+            address: address.into(),
+            block_size: 1000,
+            timeout: Duration::from_secs(1),
+            retry_interval: Duration::from_secs(1),
+            fallback: None,
+            retry_at: None,
+            block: VecDeque::new(),
+        }
+    }
+}
+
+impl<C: Clock> RemoteGenerator<C> {
+    /// Set how many IDs to fetch at once
+    ///
+    /// This has to be no more than the issuer's `max_count`.
+    pub fn block_size(mut self, block_size: u64) -> RemoteGenerator<C> {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Set how long connecting to and reading from the issuer may take
+    pub fn timeout(mut self, timeout: Duration) -> RemoteGenerator<C> {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how long to use the fallback after a failed fetch before trying the issuer again
+    pub fn retry_interval(mut self, retry_interval: Duration) -> RemoteGenerator<C> {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Use `generator` while the issuer can't be reached
+    ///
+    /// Its IDs are encoded with its own layout, which should match the issuer's.
+    pub fn fallback<F: Clock>(self, generator: SnowflakeGenerator<F>) -> RemoteGenerator<F> {
+        RemoteGenerator {
+            address: self.address,
+            block_size: self.block_size,
+            timeout: self.timeout,
+            retry_interval: self.retry_interval,
+            fallback: Some(generator),
+            retry_at: self.retry_at,
+            block: self.block,
+        }
+    }
+
+    /// Get the number of fetched IDs left before the next fetch
+    pub fn remaining(&self) -> usize {
+        self.block.len()
+    }
+
+    /// Get the next ID, fetching a new block from the issuer if the current one ran out
+    ///
+    /// # Returns
+    /// The ID, or an error if the issuer couldn't be reached and there is no fallback, or the
+    /// fallback failed
+    pub fn try_next(&mut self) -> Result<u64, RemoteError> {
+        if let Some(id) = self.block.pop_front() {
+            return Ok(id);
+        }
+
+        let retrying = self
+            .retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at);
+        if !retrying || self.fallback.is_none() {
+            match self.fetch() {
+                Ok(block) => {
+                    self.retry_at = None;
+                    self.block = block;
+                    if let Some(id) = self.block.pop_front() {
+                        return Ok(id);
+                    }
+                }
+                Err(err) => {
+                    self.retry_at = Some(Instant::now() + self.retry_interval);
+                    if self.fallback.is_none() {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+
+        match &mut self.fallback {
+            Some(generator) => {
+                let layout = *generator.layout();
+                let snowflake = generator.try_next().map_err(RemoteError::Fallback)?;
+                Ok(snowflake.to_id_with_layout(&layout))
+            }
+            None => Err(RemoteError::Protocol("empty block".to_string())),
+        }
+    }
+
+    /// Fetch a block of IDs from the issuer
+    fn fetch(&self) -> Result<VecDeque<u64>, RemoteError> {
+        let address = self
+            .address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address didn't resolve"))?;
+
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        write!(
+            stream,
+            "GET /next?count={} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            self.block_size, self.address
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| RemoteError::Protocol(response.clone()))?;
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| RemoteError::Protocol(head.to_string()))?;
+
+        if status != 200 {
+            return Err(RemoteError::Status {
+                status,
+                message: body.trim_end().to_string(),
+            });
+        }
+
+        body.lines()
+            .map(|line| {
+                line.parse::<u64>()
+                    .map_err(|_| RemoteError::Protocol(line.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+/// An error returned when a [`RemoteGenerator`] can't hand out an ID.
+///
+/// New variants may be added in minor releases, so matches on it need a wildcard arm.
+pub enum RemoteError {
+    /// The connection to the issuer failed.
+    Io(io::Error),
+    /// The issuer replied with an error status.
+    Status {
+        /// The HTTP status code
+        status: u16,
+        /// The body of the reply
+        message: String,
+    },
+    /// The issuer replied with something the client didn't expect.
+    Protocol(String),
+    /// The issuer couldn't be reached and the fallback generator failed.
+    Fallback(SnowflakeError),
+}
+
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RemoteError::Io(err) => write!(f, "connection to the issuer failed: {}", err),
+            RemoteError::Status { status, message } => {
+                write!(f, "issuer replied with status {}: {}", status, message)
+            }
+            RemoteError::Protocol(message) => {
+                write!(f, "unexpected reply from the issuer: {}", message)
+            }
+            RemoteError::Fallback(err) => write!(f, "fallback generator failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RemoteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RemoteError::Io(err) => Some(err),
+            RemoteError::Fallback(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RemoteError {
+    fn from(err: io::Error) -> RemoteError {
+        RemoteError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::{MockClock, Snowflake};
+
+    /// Answer each connection with the next of `responses`, returning the request lines
+    fn fake_issuer(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                requests.push(request.trim_end().to_string());
+
+                // Read the rest of the request, or closing with it unread resets the connection
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+
+            requests
+        });
+
+        (address, server)
+    }
+
+    /// An issuer that accepts connections but never replies
+    ///
+    /// Keeping the listener alive stops another test from binding the same port and answering
+    /// in its place.
+    fn silent_issuer() -> (String, TcpListener) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        (listener.local_addr().unwrap().to_string(), listener)
+    }
+
+    #[test]
+    fn test_fetches_in_blocks() {
+        let (address, server) = fake_issuer(vec![
+            "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n1\n2\n",
+            "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n3\n4\n",
+        ]);
+        let mut generator = RemoteGenerator::new(address).block_size(2);
+
+        let ids = (0..4)
+            .map(|_| generator.try_next().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, [1, 2, 3, 4]);
+        assert_eq!(generator.remaining(), 0);
+        assert_eq!(
+            server.join().unwrap(),
+            ["GET /next?count=2 HTTP/1.1", "GET /next?count=2 HTTP/1.1"]
+        );
+    }
+
+    #[test]
+    fn test_error_status() {
+        let (address, _server) = fake_issuer(vec![
+            "HTTP/1.1 503 Service Unavailable\r\n\r\nclock moved backwards\n",
+        ]);
+
+        assert!(matches!(
+            RemoteGenerator::new(address).try_next(),
+            Err(RemoteError::Status { status: 503, message }) if message == "clock moved backwards"
+        ));
+    }
+
+    #[test]
+    fn test_unreachable_without_fallback() {
+        let (address, _listener) = silent_issuer();
+        let mut generator = RemoteGenerator::new(address).timeout(Duration::from_millis(50));
+
+        assert!(matches!(generator.try_next(), Err(RemoteError::Io(_))));
+    }
+
+    #[test]
+    fn test_fallback() {
+        let fallback = SnowflakeGenerator::with_clock(1023, MockClock::new(1_000_000)).unwrap();
+        let (address, _listener) = silent_issuer();
+        let mut generator = RemoteGenerator::new(address)
+            .timeout(Duration::from_millis(50))
+            .retry_interval(Duration::from_secs(60))
+            .fallback(fallback);
+
+        let first = Snowflake::parse(generator.try_next().unwrap());
+        let second = Snowflake::parse(generator.try_next().unwrap());
+
+        assert_eq!(first.worker_id(), 1023);
+        assert!(second > first);
+        assert!(generator.retry_at.is_some());
+    }
+}
@@ -0,0 +1,197 @@
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+/// A source of the current time for a snowflake generator.
+///
+/// Implement this to control where generators get their timestamps from, for example to make
+/// tests deterministic.
+pub trait Clock {
+    /// Get the current time in milliseconds since the UNIX epoch (1970-01-01 00:00:00 UTC)
+    fn now_millis(&self) -> u64;
+
+    /// Get the current time in microseconds since the UNIX epoch
+    ///
+    /// Generators read this, so clocks that can tell the time more precisely than a
+    /// millisecond should override it for layouts with shorter ticks. Defaults to
+    /// [`Clock::now_millis`] in microseconds.
+    fn now_micros(&self) -> u64 {
+        self.now_millis().saturating_mul(1_000)
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// A clock that reads the system time.
+///
+/// If the system clock is set before the UNIX epoch this reports the epoch itself, which
+/// generators treat as the clock moving backwards.
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    fn now_micros(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_micros() as u64)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+/// A clock that only changes when told to, for deterministic tests.
+///
+/// Clones share the same time, so a test can keep a handle to the clock after giving it to a
+/// generator.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{Clock, MockClock};
+///
+/// let clock = MockClock::new(1_000);
+/// let handle = clock.clone();
+///
+/// handle.advance(500);
+///
+/// assert_eq!(clock.now_millis(), 1_500);
+/// ```
+pub struct MockClock {
+    /// The current time in microseconds since the UNIX epoch.
+    micros: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "std")]
+impl MockClock {
+    /// Create a new mock clock
+    ///
+    /// # Arguments
+    /// * `millis` - The starting time in milliseconds since the UNIX epoch
+    pub fn new(millis: u64) -> MockClock {
+        MockClock {
+            micros: Arc::new(AtomicU64::new(millis.saturating_mul(1_000))),
+        }
+    }
+
+    /// Set the current time
+    ///
+    /// # Arguments
+    /// * `millis` - The new time in milliseconds since the UNIX epoch
+    pub fn set(&self, millis: u64) {
+        self.set_micros(millis.saturating_mul(1_000));
+    }
+
+    /// Set the current time to the microsecond, for layouts with ticks shorter than a
+    /// millisecond
+    ///
+    /// # Arguments
+    /// * `micros` - The new time in microseconds since the UNIX epoch
+    pub fn set_micros(&self, micros: u64) {
+        self.micros.store(micros, Ordering::SeqCst);
+    }
+
+    /// Move the current time forward
+    ///
+    /// # Arguments
+    /// * `millis` - The number of milliseconds to move forward by
+    pub fn advance(&self, millis: u64) {
+        self.advance_micros(millis.saturating_mul(1_000));
+    }
+
+    /// Move the current time forward to the microsecond
+    ///
+    /// # Arguments
+    /// * `micros` - The number of microseconds to move forward by
+    pub fn advance_micros(&self, micros: u64) {
+        let _ = self
+            .micros
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                Some(now.saturating_add(micros))
+            });
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.now_micros() / 1_000
+    }
+
+    fn now_micros(&self) -> u64 {
+        self.micros.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_clock() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let millis = SystemClock.now_millis();
+
+        assert!(millis >= now);
+        assert!(millis - now < 1_000);
+    }
+
+    #[test]
+    fn test_system_clock_micros() {
+        let millis = SystemClock.now_millis();
+        let micros = SystemClock.now_micros();
+
+        assert!(micros / 1_000 >= millis);
+        assert!(micros / 1_000 - millis < 1_000);
+    }
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::new(100);
+        assert_eq!(clock.now_millis(), 100);
+
+        clock.advance(50);
+        assert_eq!(clock.now_millis(), 150);
+
+        clock.set(10);
+        assert_eq!(clock.now_millis(), 10);
+        assert_eq!(clock.now_micros(), 10_000);
+    }
+
+    #[test]
+    fn test_mock_clock_micros() {
+        let clock = MockClock::new(100);
+
+        clock.advance_micros(250);
+        assert_eq!(clock.now_micros(), 100_250);
+        assert_eq!(clock.now_millis(), 100);
+
+        clock.set_micros(7);
+        assert_eq!(clock.now_millis(), 0);
+
+        clock.set_micros(u64::MAX);
+        clock.advance(1);
+        assert_eq!(clock.now_micros(), u64::MAX);
+    }
+
+    #[test]
+    fn test_mock_clock_clones_share_time() {
+        let clock = MockClock::new(0);
+        let handle = clock.clone();
+
+        handle.advance(1_000);
+
+        assert_eq!(clock.now_millis(), 1_000);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{LayoutError, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+/// The environment variable holding the worker ID.
+const WORKER_ID_VAR: &str = "SNOWFLAKE_WORKER_ID";
+/// The environment variable holding the layout's epoch in milliseconds since the UNIX epoch.
+const EPOCH_VAR: &str = "SNOWFLAKE_EPOCH";
+/// The environment variable holding the length of a timestamp tick in milliseconds.
+const TICK_MILLIS_VAR: &str = "SNOWFLAKE_TICK_MILLIS";
+/// The environment variables holding the width of each field in bits.
+const TIMESTAMP_BITS_VAR: &str = "SNOWFLAKE_TIMESTAMP_BITS";
+const WORKER_ID_BITS_VAR: &str = "SNOWFLAKE_WORKER_ID_BITS";
+const SEQUENCE_BITS_VAR: &str = "SNOWFLAKE_SEQUENCE_BITS";
+const DATACENTER_ID_BITS_VAR: &str = "SNOWFLAKE_DATACENTER_ID_BITS";
+const EXTRA_BITS_VAR: &str = "SNOWFLAKE_EXTRA_BITS";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// The worker ID and layout of a generator, read from the environment so deployments can
+/// configure it without code changes.
+///
+/// [`GeneratorConfig::from_env`] reads these variables:
+///
+/// | Variable | Meaning | Default |
+/// | -------- | ------- | ------- |
+/// | `SNOWFLAKE_WORKER_ID` | The worker ID | Required |
+/// | `SNOWFLAKE_EPOCH` | The epoch in milliseconds since the UNIX epoch | `0` |
+/// | `SNOWFLAKE_TICK_MILLIS` | The length of a timestamp tick in milliseconds | `1000` |
+/// | `SNOWFLAKE_TIMESTAMP_BITS` | The number of timestamp bits | `41` |
+/// | `SNOWFLAKE_WORKER_ID_BITS` | The number of worker ID bits | `10` |
+/// | `SNOWFLAKE_SEQUENCE_BITS` | The number of sequence bits | `12` |
+/// | `SNOWFLAKE_DATACENTER_ID_BITS` | How many worker ID bits hold the datacenter ID | `5` |
+/// | `SNOWFLAKE_EXTRA_BITS` | The number of caller-defined extra bits | `0` |
+///
+/// The defaults are those of [`SnowflakeLayout::DEFAULT`].
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::GeneratorConfig;
+///
+/// std::env::set_var("SNOWFLAKE_WORKER_ID", "42");
+/// std::env::set_var("SNOWFLAKE_EPOCH", "1700000000000");
+/// std::env::set_var("SNOWFLAKE_TICK_MILLIS", "1");
+///
+/// let config = GeneratorConfig::from_env().unwrap();
+/// assert_eq!(config.layout().epoch(), 1_700_000_000_000);
+///
+/// let mut generator = config.build().unwrap();
+/// assert_eq!(generator.next().worker_id(), 42);
+/// ```
+pub struct GeneratorConfig {
+    worker_id: u64,
+    layout: SnowflakeLayout,
+}
+
+impl GeneratorConfig {
+    /// Create a config for the given worker ID and layout
+    ///
+    /// # Returns
+    /// A new `GeneratorConfig`, or an error if the worker ID doesn't fit in the layout
+    pub fn new(worker_id: u64, layout: SnowflakeLayout) -> Result<GeneratorConfig, ConfigError> {
+        let max = layout.max_worker_id();
+        if worker_id > max {
+            return Err(SnowflakeError::WorkerIdOverflow { worker_id, max }.into());
+        }
+
+        Ok(GeneratorConfig { worker_id, layout })
+    }
+
+    /// Read the config from the `SNOWFLAKE_*` environment variables
+    ///
+    /// See [`GeneratorConfig`] for the variables read.
+    ///
+    /// # Returns
+    /// The `GeneratorConfig`, or an error if `SNOWFLAKE_WORKER_ID` isn't set, a variable isn't
+    /// a number, the layout is invalid or the worker ID doesn't fit in it
+    pub fn from_env() -> Result<GeneratorConfig, ConfigError> {
+        GeneratorConfig::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Read the config from variables looked up by name
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<GeneratorConfig, ConfigError> {
+        let number = |name: &'static str| -> Result<Option<u64>, ConfigError> {
+            match var(name) {
+                Some(value) => match value.trim().parse() {
+                    Ok(number) => Ok(Some(number)),
+                    Err(_) => Err(ConfigError::Invalid { name, value }),
+                },
+                None => Ok(None),
+            }
+        };
+        let bits = |name: &'static str| -> Result<Option<u8>, ConfigError> {
+            match number(name)? {
+                Some(bits) => u8::try_from(bits)
+                    .map(Some)
+                    .map_err(|_| ConfigError::Invalid {
+                        name,
+                        value: bits.to_string(),
+                    }),
+                None => Ok(None),
+            }
+        };
+
+        let worker_id = number(WORKER_ID_VAR)?.ok_or(ConfigError::Missing {
+            name: WORKER_ID_VAR,
+        })?;
+
+        let default = SnowflakeLayout::DEFAULT;
+        let layout = SnowflakeLayout::builder()
+            .epoch(number(EPOCH_VAR)?.unwrap_or(default.epoch()))
+            .tick_micros(
+                number(TICK_MILLIS_VAR)?
+                    .map_or(default.tick_micros(), |millis| millis.saturating_mul(1_000)),
+            )
+            .timestamp_bits(bits(TIMESTAMP_BITS_VAR)?.unwrap_or(default.timestamp_bits()))
+            .worker_id_bits(bits(WORKER_ID_BITS_VAR)?.unwrap_or(default.worker_id_bits()))
+            .sequence_bits(bits(SEQUENCE_BITS_VAR)?.unwrap_or(default.sequence_bits()))
+            .datacenter_id_bits(
+                bits(DATACENTER_ID_BITS_VAR)?.unwrap_or(default.datacenter_id_bits()),
+            )
+            .extra_bits(bits(EXTRA_BITS_VAR)?.unwrap_or(default.extra_bits()))
+            .build()?;
+
+        GeneratorConfig::new(worker_id, layout)
+    }
+
+    /// Get the worker ID of the snowflakes generated
+    pub fn worker_id(&self) -> u64 {
+        self.worker_id
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Create a generator with the config
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if the system clock is set before the UNIX epoch
+    pub fn build(&self) -> Result<SnowflakeGenerator, SnowflakeError> {
+        SnowflakeGenerator::with_layout(self.worker_id, self.layout)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// An error returned when a [`GeneratorConfig`] can't be read.
+pub enum ConfigError {
+    /// A required variable isn't set.
+    Missing {
+        /// The name of the variable
+        name: &'static str,
+    },
+    /// A variable isn't a number, or is too large for its setting.
+    Invalid {
+        /// The name of the variable
+        name: &'static str,
+        /// The value of the variable
+        value: String,
+    },
+    /// The layout the variables describe is invalid.
+    Layout(LayoutError),
+    /// The worker ID doesn't fit in the layout.
+    Generation(SnowflakeError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::Missing { name } => write!(f, "{} is not set", name),
+            ConfigError::Invalid { name, value } => {
+                write!(f, "{} has an invalid value {:?}", name, value)
+            }
+            ConfigError::Layout(err) => write!(f, "invalid layout: {}", err),
+            ConfigError::Generation(err) => write!(f, "invalid config: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Layout(err) => Some(err),
+            ConfigError::Generation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LayoutError> for ConfigError {
+    fn from(err: LayoutError) -> ConfigError {
+        ConfigError::Layout(err)
+    }
+}
+
+impl From<SnowflakeError> for ConfigError {
+    fn from(err: SnowflakeError) -> ConfigError {
+        ConfigError::Generation(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Read a config from the given variables instead of the environment
+    fn from_vars(vars: &[(&str, &str)]) -> Result<GeneratorConfig, ConfigError> {
+        let vars = vars
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        GeneratorConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = from_vars(&[("SNOWFLAKE_WORKER_ID", " 7 ")]).unwrap();
+
+        assert_eq!(config.worker_id(), 7);
+        assert_eq!(config.layout(), &SnowflakeLayout::DEFAULT);
+    }
+
+    #[test]
+    fn test_layout() {
+        let config = from_vars(&[
+            ("SNOWFLAKE_WORKER_ID", "300"),
+            ("SNOWFLAKE_EPOCH", "1420070400000"),
+            ("SNOWFLAKE_TICK_MILLIS", "1"),
+            ("SNOWFLAKE_TIMESTAMP_BITS", "40"),
+            ("SNOWFLAKE_DATACENTER_ID_BITS", "0"),
+            ("SNOWFLAKE_EXTRA_BITS", "2"),
+        ])
+        .unwrap();
+
+        let layout = config.layout();
+        assert_eq!(layout.epoch(), 1_420_070_400_000);
+        assert_eq!(layout.tick_millis(), 1);
+        assert_eq!(layout.timestamp_bits(), 40);
+        assert_eq!(layout.datacenter_id_bits(), 0);
+        assert_eq!(layout.extra_bits(), 2);
+        assert_eq!(config.build().unwrap().next().worker_id(), 300);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            from_vars(&[]),
+            Err(ConfigError::Missing {
+                name: "SNOWFLAKE_WORKER_ID"
+            })
+        );
+        assert_eq!(
+            from_vars(&[("SNOWFLAKE_WORKER_ID", "1"), ("SNOWFLAKE_EPOCH", "soon")]),
+            Err(ConfigError::Invalid {
+                name: "SNOWFLAKE_EPOCH",
+                value: "soon".to_string()
+            })
+        );
+        assert_eq!(
+            from_vars(&[
+                ("SNOWFLAKE_WORKER_ID", "1"),
+                ("SNOWFLAKE_SEQUENCE_BITS", "300")
+            ]),
+            Err(ConfigError::Invalid {
+                name: "SNOWFLAKE_SEQUENCE_BITS",
+                value: "300".to_string()
+            })
+        );
+        assert_eq!(
+            from_vars(&[
+                ("SNOWFLAKE_WORKER_ID", "1"),
+                ("SNOWFLAKE_TIMESTAMP_BITS", "50")
+            ]),
+            Err(ConfigError::Layout(LayoutError::TooManyBits { total: 72 }))
+        );
+        assert_eq!(
+            from_vars(&[("SNOWFLAKE_WORKER_ID", "1024")]),
+            Err(ConfigError::Generation(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            }))
+        );
+        assert_eq!(
+            ConfigError::Missing {
+                name: "SNOWFLAKE_WORKER_ID"
+            }
+            .to_string(),
+            "SNOWFLAKE_WORKER_ID is not set"
+        );
+    }
+}
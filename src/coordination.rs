@@ -0,0 +1,302 @@
+//! Coordinating worker IDs between the replicas of a service.
+//!
+//! Each replica leases a worker ID from a shared store before it starts generating snowflakes,
+//! so no two replicas ever use the same worker ID at the same time. Processes on the same host
+//! can instead share a single worker ID with a [`SharedFileGenerator`].
+
+use crate::SnowflakeError;
+
+mod backoff;
+mod file;
+mod leased;
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+pub use self::backoff::Backoff;
+pub use self::file::SharedFileGenerator;
+pub use self::leased::{LeasedGenerator, ManagedGenerator};
+pub use self::memory::{MemoryWorkerLease, MemoryWorkerRegistry};
+#[cfg(feature = "redis")]
+pub use self::redis::{RedisWorkerLease, RedisWorkerRegistry};
+
+/// A shared store that hands out worker IDs.
+///
+/// Implement this to lease worker IDs from your own infrastructure, such as etcd or ZooKeeper,
+/// and write code that doesn't care which store it runs against.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::coordination::{MemoryWorkerRegistry, WorkerLease, WorkerRegistry};
+/// use rusty_snowflake::SnowflakeGenerator;
+///
+/// fn generator<R: WorkerRegistry>(registry: &R) -> (R::Lease, SnowflakeGenerator) {
+///     let lease = registry.lease().unwrap();
+///     let generator = SnowflakeGenerator::try_new(lease.worker_id()).unwrap();
+///
+///     (lease, generator)
+/// }
+///
+/// let registry = MemoryWorkerRegistry::new();
+/// let (_first_lease, mut first) = generator(&registry);
+/// let (_second_lease, mut second) = generator(&registry);
+///
+/// assert_ne!(first.next().worker_id(), second.next().worker_id());
+/// ```
+pub trait WorkerRegistry {
+    /// The lease handed out by this registry
+    type Lease: WorkerLease;
+
+    /// Lease a worker ID that no other holder is using
+    fn lease(&self) -> Result<Self::Lease, CoordinationError>;
+
+    /// Lease a worker ID, retrying while the store is unavailable or every worker ID is taken
+    ///
+    /// Use this when a replica starts, so it waits out a store that's restarting or leases
+    /// that a rolling deploy hasn't released yet instead of crash-looping. Errors that aren't
+    /// [retryable](CoordinationError::is_retryable) are returned straight away.
+    ///
+    /// # Arguments
+    /// * `backoff` - How long to wait between attempts, and when to give up
+    ///
+    /// # Returns
+    /// The lease, or the error of the last attempt
+    fn lease_with_backoff(&self, backoff: &Backoff) -> Result<Self::Lease, CoordinationError> {
+        backoff.retry(|| self.lease())
+    }
+}
+
+/// A worker ID held from a [`WorkerRegistry`].
+///
+/// Implementations release the worker ID when the lease is dropped.
+pub trait WorkerLease {
+    /// Get the leased worker ID
+    fn worker_id(&self) -> u64;
+
+    /// Extend the lease so it doesn't expire
+    ///
+    /// Returns `CoordinationError::LeaseLost` if another holder may now be using the worker ID.
+    fn renew(&mut self) -> Result<(), CoordinationError>;
+
+    /// Release the lease so another holder can use the worker ID
+    fn release(self) -> Result<(), CoordinationError>
+    where
+        Self: Sized;
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+/// An error returned when a worker ID can't be leased, renewed or released.
+///
+/// See [`CoordinationError::is_retryable`] for which errors are worth retrying.
+pub enum CoordinationError {
+    /// The connection to the store failed.
+    Io(std::io::Error),
+    /// The store is temporarily unable to serve requests, such as while it loads its data or
+    /// fails over.
+    Unavailable(String),
+    /// The store replied with an error.
+    Store(String),
+    /// The store replied with something the client didn't expect.
+    Protocol(String),
+    /// The store didn't respond within the timeout.
+    Timeout,
+    /// Every worker ID the layout can hold is already leased.
+    NoFreeWorkerId,
+    /// The lease expired, or was taken over by another replica, before it was renewed.
+    LeaseLost {
+        /// The worker ID that was leased
+        worker_id: u64,
+    },
+    /// A generator was paired with a lease on another worker ID than its own.
+    WorkerIdMismatch {
+        /// The worker ID that was leased
+        leased: u64,
+        /// The worker ID of the generator
+        generator: u64,
+    },
+    /// A snowflake couldn't be generated.
+    Generation(SnowflakeError),
+}
+
+impl std::fmt::Display for CoordinationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CoordinationError::Io(err) => write!(f, "connection to the store failed: {}", err),
+            CoordinationError::Unavailable(message) => {
+                write!(f, "store is temporarily unavailable: {}", message)
+            }
+            CoordinationError::Store(message) => {
+                write!(f, "store replied with an error: {}", message)
+            }
+            CoordinationError::Protocol(message) => {
+                write!(f, "unexpected reply from the store: {}", message)
+            }
+            CoordinationError::Timeout => write!(f, "store didn't respond in time"),
+            CoordinationError::NoFreeWorkerId => write!(f, "every worker ID is already leased"),
+            CoordinationError::LeaseLost { worker_id } => {
+                write!(f, "lease on worker ID {} was lost", worker_id)
+            }
+            CoordinationError::WorkerIdMismatch { leased, generator } => write!(
+                f,
+                "lease is for worker ID {}, but the generator uses {}",
+                leased, generator
+            ),
+            CoordinationError::Generation(err) => write!(f, "generation failed: {}", err),
+        }
+    }
+}
+
+impl CoordinationError {
+    /// Check whether the same request may succeed if it's tried again later
+    ///
+    /// Connection failures like a refused or reset connection, a timeout, an unavailable store
+    /// and running out of free worker IDs, which happens while leases from a previous deploy
+    /// expire, are retryable. A lost lease, an error or unexpected reply from the store, a
+    /// generator paired with the wrong lease and a failed generation are not.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::coordination::CoordinationError;
+    ///
+    /// assert!(CoordinationError::NoFreeWorkerId.is_retryable());
+    /// assert!(!CoordinationError::LeaseLost { worker_id: 1 }.is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        use std::io::ErrorKind;
+
+        match self {
+            CoordinationError::Io(err) => matches!(
+                err.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::UnexpectedEof
+            ),
+            CoordinationError::Unavailable(_)
+            | CoordinationError::Timeout
+            | CoordinationError::NoFreeWorkerId => true,
+            CoordinationError::Store(_)
+            | CoordinationError::Protocol(_)
+            | CoordinationError::LeaseLost { .. }
+            | CoordinationError::WorkerIdMismatch { .. }
+            | CoordinationError::Generation(_) => false,
+        }
+    }
+}
+
+impl std::error::Error for CoordinationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CoordinationError::Io(err) => Some(err),
+            CoordinationError::Generation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CoordinationError {
+    fn from(err: std::io::Error) -> CoordinationError {
+        match err.kind() {
+            // Sockets report a timed out read as either, depending on the platform
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                CoordinationError::Timeout
+            }
+            _ => CoordinationError::Io(err),
+        }
+    }
+}
+
+impl From<SnowflakeError> for CoordinationError {
+    fn from(err: SnowflakeError) -> CoordinationError {
+        CoordinationError::Generation(err)
+    }
+}
+
+/// Create a random token that identifies this lease holder, so other replicas can't guess it
+/// and renew or release the lease in its place
+#[cfg_attr(not(feature = "redis"), allow(dead_code))]
+fn lease_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    // `RandomState` is seeded from the operating system's random number generator
+    let mut random = std::collections::hash_map::RandomState::new().build_hasher();
+    random.write_u128(nanos);
+
+    format!("{}-{:016x}", std::process::id(), random.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            CoordinationError::NoFreeWorkerId.to_string(),
+            "every worker ID is already leased"
+        );
+        assert_eq!(
+            CoordinationError::LeaseLost { worker_id: 7 }.to_string(),
+            "lease on worker ID 7 was lost"
+        );
+        assert_eq!(
+            CoordinationError::Unavailable("LOADING".to_string()).to_string(),
+            "store is temporarily unavailable: LOADING"
+        );
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        use std::io::{Error, ErrorKind};
+
+        assert!(CoordinationError::from(Error::from(ErrorKind::ConnectionRefused)).is_retryable());
+        assert!(!CoordinationError::from(Error::from(ErrorKind::PermissionDenied)).is_retryable());
+        assert!(CoordinationError::Unavailable("MASTERDOWN".to_string()).is_retryable());
+        assert!(!CoordinationError::Store("ERR".to_string()).is_retryable());
+        assert!(!CoordinationError::Protocol("?".to_string()).is_retryable());
+        assert!(!CoordinationError::from(SnowflakeError::ClockBeforeEpoch).is_retryable());
+    }
+
+    #[test]
+    fn test_lease_with_backoff() {
+        let registry = MemoryWorkerRegistry::with_layout(
+            &crate::SnowflakeLayout::builder()
+                .worker_id_bits(1)
+                .build()
+                .unwrap(),
+        );
+        let _first = registry.lease().unwrap();
+        let lease = registry.lease().unwrap();
+
+        // The last worker ID is freed while the second replica backs off
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            lease.release()
+        });
+        let backoff = Backoff::new()
+            .initial_delay(std::time::Duration::from_millis(5))
+            .max_elapsed(std::time::Duration::from_secs(5));
+
+        assert_eq!(
+            registry.lease_with_backoff(&backoff).unwrap().worker_id(),
+            1
+        );
+        releaser.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_lease_token() {
+        assert!(lease_token().starts_with(&format!("{}-", std::process::id())));
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+use super::CoordinationError;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// An exponential backoff policy for retrying a coordination store that's briefly unavailable,
+/// such as while it restarts or fails over when the service starts.
+///
+/// Only errors that [`CoordinationError::is_retryable`] are retried. The delay starts at
+/// `initial_delay` and is multiplied by `multiplier` after every failed attempt, up to
+/// `max_delay`, and retrying stops once the next attempt would start after `max_elapsed`.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rusty_snowflake::coordination::{Backoff, MemoryWorkerRegistry, WorkerRegistry};
+///
+/// let backoff = Backoff::new()
+///     .initial_delay(Duration::from_millis(50))
+///     .max_elapsed(Duration::from_secs(30));
+///
+/// let registry = MemoryWorkerRegistry::new();
+/// let lease = registry.lease_with_backoff(&backoff).unwrap();
+/// ```
+pub struct Backoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: u32,
+    max_elapsed: Duration,
+    jitter: bool,
+}
+
+impl Backoff {
+    /// Create a policy that waits 100 milliseconds after the first failure, doubles the delay
+    /// up to 10 seconds and gives up after a minute, with jitter
+    pub fn new() -> Backoff {
+        Backoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2,
+            max_elapsed: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+
+    /// Set how long to wait after the first failed attempt
+    pub fn initial_delay(mut self, delay: Duration) -> Backoff {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the longest to wait between two attempts
+    pub fn max_delay(mut self, delay: Duration) -> Backoff {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set how much longer each delay is than the one before
+    ///
+    /// A multiplier of `1` waits `initial_delay` between every attempt.
+    pub fn multiplier(mut self, multiplier: u32) -> Backoff {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set how long to keep retrying before returning the last error
+    ///
+    /// A duration of zero makes a single attempt.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Backoff {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+
+    /// Set whether to wait a random time between half the delay and the full delay, so
+    /// replicas started together don't all retry at the same moment
+    pub fn jitter(mut self, jitter: bool) -> Backoff {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Get the delay before an attempt, without jitter
+    ///
+    /// # Arguments
+    /// * `attempt` - The number of attempts that already failed, starting at one
+    ///
+    /// # Returns
+    /// `initial_delay * multiplier ^ (attempt - 1)`, capped at `max_delay`
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::coordination::Backoff;
+    ///
+    /// let backoff = Backoff::new().max_delay(Duration::from_millis(500));
+    ///
+    /// assert_eq!(backoff.delay(1), Duration::from_millis(100));
+    /// assert_eq!(backoff.delay(3), Duration::from_millis(400));
+    /// assert_eq!(backoff.delay(4), Duration::from_millis(500));
+    /// ```
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay)
+    }
+
+    /// Run an operation against the store until it succeeds, fails with an error that isn't
+    /// retryable or runs out of time
+    ///
+    /// # Arguments
+    /// * `operation` - The operation, such as leasing or renewing a worker ID
+    ///
+    /// # Returns
+    /// The result of the last attempt
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::coordination::{Backoff, CoordinationError};
+    ///
+    /// let backoff = Backoff::new().initial_delay(Duration::ZERO);
+    /// let mut attempts = 0;
+    ///
+    /// let result = backoff.retry(|| {
+    ///     attempts += 1;
+    ///     match attempts {
+    ///         1 | 2 => Err(CoordinationError::Unavailable("LOADING".to_string())),
+    ///         _ => Ok(attempts),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(result.unwrap(), 3);
+    /// ```
+    pub fn retry<T>(
+        &self,
+        mut operation: impl FnMut() -> Result<T, CoordinationError>,
+    ) -> Result<T, CoordinationError> {
+        let start = Instant::now();
+        let mut random = RandomState::new().build_hasher().finish();
+        let mut attempt = 0;
+
+        loop {
+            let err = match operation() {
+                Err(err) if err.is_retryable() => err,
+                result => return result,
+            };
+            attempt += 1;
+
+            let mut delay = self.delay(attempt);
+            if self.jitter {
+                // Xorshift, which is random enough to spread retries out
+                random ^= random << 13;
+                random ^= random >> 7;
+                random ^= random << 17;
+                let fraction = (random >> 11) as f64 / (1u64 << 53) as f64;
+                let half = delay / 2;
+                delay = half
+                    + Duration::try_from_secs_f64(half.as_secs_f64() * fraction)
+                        .unwrap_or_default();
+            }
+
+            if start.elapsed().saturating_add(delay) > self.max_elapsed {
+                return Err(err);
+            }
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let backoff = Backoff::new().jitter(false);
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay(8), Duration::from_secs(10));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(10));
+
+        let constant = backoff.multiplier(1).max_delay(Duration::MAX);
+        assert_eq!(constant.delay(1_000), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_retry_stops_on_terminal_errors() {
+        let backoff = Backoff::new().initial_delay(Duration::ZERO);
+        let mut attempts = 0;
+
+        let result: Result<(), _> = backoff.retry(|| {
+            attempts += 1;
+            match attempts {
+                1 => Err(CoordinationError::NoFreeWorkerId),
+                _ => Err(CoordinationError::LeaseLost { worker_id: 1 }),
+            }
+        });
+
+        assert!(matches!(
+            result,
+            Err(CoordinationError::LeaseLost { worker_id: 1 })
+        ));
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let backoff = Backoff::new()
+            .initial_delay(Duration::from_millis(1))
+            .max_elapsed(Duration::from_millis(20));
+        let mut attempts = 0;
+
+        let result: Result<(), _> = backoff.retry(|| {
+            attempts += 1;
+            Err(CoordinationError::Unavailable("TRYAGAIN".to_string()))
+        });
+
+        assert!(matches!(result, Err(CoordinationError::Unavailable(_))));
+        assert!((2..=6).contains(&attempts), "{} attempts", attempts);
+
+        // No time to retry at all
+        let mut attempts = 0;
+        let _ = Backoff::new().max_elapsed(Duration::ZERO).retry(|| {
+            attempts += 1;
+            Err::<(), _>(CoordinationError::NoFreeWorkerId)
+        });
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use super::CoordinationError;
+use crate::{Clock, GeneratorState, Snowflake, SnowflakeError, SnowflakeLayout, SystemClock};
+
+#[derive(Debug)]
+/// Lets the processes on one host share a worker ID by leasing blocks of sequence numbers
+/// through a locked state file.
+///
+/// The file holds the [`GeneratorState`] of the last sequence number leased by any process.
+/// Each generator locks the file, leases the next block of sequence numbers under the current
+/// tick, and hands the block out without touching the file again until it runs out. The
+/// workers of a pre-forking server can open the same file and share one worker ID instead of
+/// needing one each.
+///
+/// Every process sharing the file must use the same worker ID and layout.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::coordination::SharedFileGenerator;
+///
+/// let path = std::env::temp_dir().join("rusty-snowflake-doctest.state");
+///
+/// // In each worker process
+/// let mut generator = SharedFileGenerator::open(&path, 420).unwrap().block_size(64);
+///
+/// let snowflake = generator.try_next().unwrap();
+/// assert_eq!(snowflake.worker_id(), 420);
+/// ```
+pub struct SharedFileGenerator<C: Clock = SystemClock> {
+    /// The state file shared by every process.
+    file: File,
+    worker_id: u64,
+    layout: SnowflakeLayout,
+    clock: C,
+    /// How many sequence numbers to lease at once.
+    block_size: u64,
+    /// The last snowflake handed out from the current block.
+    last_snowflake: Option<Snowflake>,
+    /// The last sequence number of the current block.
+    block_end: u64,
+}
+
+impl SharedFileGenerator {
+    /// Open or create the state file at `path` and generate snowflakes with
+    /// [`SnowflakeLayout::DEFAULT`], leasing 16 sequence numbers at a time
+    ///
+    /// # Arguments
+    /// * `path` - The state file shared by every process
+    /// * `worker_id` - The worker ID shared by every process
+    ///
+    /// # Returns
+    /// A new `SharedFileGenerator`, or an error if the file couldn't be opened or the worker ID
+    /// doesn't fit in the layout
+    pub fn open(
+        path: impl AsRef<Path>,
+        worker_id: u64,
+    ) -> Result<SharedFileGenerator, CoordinationError> {
+        SharedFileGenerator::open_with_layout_and_clock(
+            path,
+            worker_id,
+            SnowflakeLayout::DEFAULT,
+            SystemClock,
+        )
+    }
+}
+
+impl<C: Clock> SharedFileGenerator<C> {
+    /// Open or create the state file at `path` and generate snowflakes with a custom layout and
+    /// clock
+    ///
+    /// # Arguments
+    /// * `path` - The state file shared by every process
+    /// * `worker_id` - The worker ID shared by every process
+    /// * `layout` - The bit layout of the snowflakes generated
+    /// * `clock` - The clock to read timestamps from
+    ///
+    /// # Returns
+    /// A new `SharedFileGenerator`, or an error if the file couldn't be opened or the worker ID
+    /// doesn't fit in the layout
+    pub fn open_with_layout_and_clock(
+        path: impl AsRef<Path>,
+        worker_id: u64,
+        layout: SnowflakeLayout,
+        clock: C,
+    ) -> Result<SharedFileGenerator<C>, CoordinationError> {
+        let max = layout.max_worker_id();
+        if worker_id > max {
+            return Err(SnowflakeError::WorkerIdOverflow { worker_id, max }.into());
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        Ok(SharedFileGenerator {
+            file,
+            worker_id,
+            layout,
+            clock,
+            block_size: 16,
+            last_snowflake: None,
+            block_end: 0,
+        })
+    }
+
+    /// Set how many sequence numbers to lease at once
+    ///
+    /// Bigger blocks lock the file less often, but leave fewer sequence numbers in each tick
+    /// for the other processes.
+    pub fn block_size(mut self, block_size: u64) -> SharedFileGenerator<C> {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Generates the next snowflake ID, leasing a new block of sequence numbers if the current
+    /// one ran out
+    ///
+    /// When every sequence number of the current tick has been leased this waits for the next
+    /// tick. If the clock went backwards, the block is leased under the last timestamp in the
+    /// file.
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if the state file couldn't be read or written or
+    /// the timestamp no longer fits in the layout
+    pub fn try_next(&mut self) -> Result<Snowflake, CoordinationError> {
+        let snowflake = match self.last_snowflake {
+            Some(last) if last.sequence < self.block_end => Snowflake {
+                sequence: last.sequence + 1,
+                ..last
+            },
+            _ => self.lease()?,
+        };
+
+        self.last_snowflake = Some(snowflake);
+        Ok(snowflake)
+    }
+
+    /// Lease the next block of sequence numbers, waiting for the next tick if the current one
+    /// has none left
+    ///
+    /// # Returns
+    /// The first snowflake of the block
+    fn lease(&mut self) -> Result<Snowflake, CoordinationError> {
+        loop {
+            self.file.lock()?;
+            let leased = self.lease_locked();
+            self.file.unlock()?;
+
+            match leased? {
+                Ok(snowflake) => return Ok(snowflake),
+                Err(exhausted) => {
+                    let next_tick = self
+                        .layout
+                        .micros_from_timestamp(exhausted.saturating_add(1));
+                    let wait = next_tick.saturating_sub(self.clock.now_micros()).max(1);
+                    std::thread::sleep(Duration::from_micros(wait));
+                }
+            }
+        }
+    }
+
+    /// Lease the next block of sequence numbers while holding the lock on the state file
+    ///
+    /// # Returns
+    /// The first snowflake of the block, or the timestamp whose sequence numbers ran out
+    fn lease_locked(&mut self) -> Result<Result<Snowflake, u64>, CoordinationError> {
+        let mut bytes = [0; 16];
+        self.file.seek(SeekFrom::Start(0))?;
+        let last = match self.file.read_exact(&mut bytes) {
+            Ok(()) => Some(GeneratorState::from_bytes(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        let now = self.layout.timestamp_from_micros(self.clock.now_micros());
+        let (timestamp, sequence) = match last {
+            // Borrow the last timestamp if the clock went backwards
+            Some(last) if now <= last.timestamp() => {
+                if last.sequence() >= self.layout.max_sequence() {
+                    // No later timestamp fits in the layout, so waiting for one would never end
+                    let max = self.layout.max_timestamp();
+                    if last.timestamp() >= max {
+                        return Err(SnowflakeError::TimestampOverflow {
+                            timestamp: last.timestamp().saturating_add(1),
+                            max,
+                        }
+                        .into());
+                    }
+                    return Ok(Err(last.timestamp()));
+                }
+                (last.timestamp(), last.sequence() + 1)
+            }
+            _ => (now, 0),
+        };
+
+        let max = self.layout.max_timestamp();
+        if timestamp > max {
+            return Err(SnowflakeError::TimestampOverflow { timestamp, max }.into());
+        }
+
+        let available = self.layout.max_sequence() - sequence;
+        self.block_end = sequence + available.min(self.block_size - 1);
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file
+            .write_all(&GeneratorState::new(timestamp, self.block_end).to_bytes())?;
+
+        Ok(Ok(Snowflake {
+            worker_id: self.worker_id,
+            sequence,
+            timestamp,
+            extra: 0,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::MockClock;
+
+    /// A state file path unique to this test
+    fn state_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rusty-snowflake-{}-{}.state",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_generators_share_sequence() {
+        let path = state_path("share");
+        let clock = MockClock::new(1_000_000);
+        let open = || {
+            SharedFileGenerator::open_with_layout_and_clock(
+                &path,
+                7,
+                SnowflakeLayout::DEFAULT,
+                clock.clone(),
+            )
+            .unwrap()
+            .block_size(2)
+        };
+        let (mut first, mut second) = (open(), open());
+
+        let sequences = [
+            first.try_next().unwrap(),
+            second.try_next().unwrap(),
+            first.try_next().unwrap(),
+            second.try_next().unwrap(),
+            first.try_next().unwrap(),
+        ]
+        .map(|snowflake| snowflake.sequence());
+        assert_eq!(sequences, [0, 2, 1, 3, 4]);
+
+        clock.advance(1_000);
+        let snowflake = second.try_next().unwrap();
+        assert_eq!((snowflake.timestamp(), snowflake.sequence()), (1_001, 0));
+        assert_eq!(snowflake.worker_id(), 7);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_threads_get_unique_ids() {
+        let path = state_path("threads");
+        let layout = SnowflakeLayout::builder()
+            .tick_millis(1)
+            .epoch(1_700_000_000_000)
+            .build()
+            .unwrap();
+
+        let ids = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut generator = SharedFileGenerator::open_with_layout_and_clock(
+                            &path,
+                            1,
+                            layout,
+                            SystemClock,
+                        )
+                        .unwrap()
+                        .block_size(64);
+
+                        (0..2_000)
+                            .map(|_| generator.try_next().unwrap().to_id_with_layout(&layout))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_exhausted_state_file() {
+        let path = state_path("exhausted");
+        let layout = SnowflakeLayout::DEFAULT;
+        std::fs::write(
+            &path,
+            GeneratorState::new(layout.max_timestamp(), layout.max_sequence()).to_bytes(),
+        )
+        .unwrap();
+
+        // Waiting for a timestamp after the last one would never end
+        let mut generator =
+            SharedFileGenerator::open_with_layout_and_clock(&path, 1, layout, MockClock::new(0))
+                .unwrap();
+        assert!(matches!(
+            generator.try_next(),
+            Err(CoordinationError::Generation(
+                SnowflakeError::TimestampOverflow { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_worker_id_overflow() {
+        assert!(matches!(
+            SharedFileGenerator::open(state_path("overflow"), 1024),
+            Err(CoordinationError::Generation(
+                SnowflakeError::WorkerIdOverflow { .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,505 @@
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::{CoordinationError, WorkerLease};
+use crate::generator::generation_failed;
+use crate::{Clock, GeneratorState, Snowflake, SnowflakeError, SnowflakeGenerator};
+
+/// A generator that a [`LeasedGenerator`] can hold.
+///
+/// Implemented for [`SnowflakeGenerator`] and, with the `audit` feature,
+/// [`AuditedGenerator`](crate::audit::AuditedGenerator).
+pub trait ManagedGenerator {
+    /// Get the worker ID of the snowflakes generated
+    fn worker_id(&self) -> u64;
+
+    /// Generate the next snowflake ID
+    fn try_next(&mut self) -> Result<Snowflake, SnowflakeError>;
+
+    /// Get the position of the generator, to persist when shutting down
+    fn snapshot(&self) -> GeneratorState;
+
+    /// Move the generator past a position persisted before a restart
+    fn restore(&mut self, state: GeneratorState) -> Result<(), SnowflakeError>;
+
+    /// Store anything the generator buffered, such as audited ranges, when shutting down
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<C: Clock> ManagedGenerator for SnowflakeGenerator<C> {
+    fn worker_id(&self) -> u64 {
+        self.last_snowflake.worker_id
+    }
+
+    fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        SnowflakeGenerator::try_next(self)
+    }
+
+    fn snapshot(&self) -> GeneratorState {
+        SnowflakeGenerator::snapshot(self)
+    }
+
+    fn restore(&mut self, state: GeneratorState) -> Result<(), SnowflakeError> {
+        SnowflakeGenerator::restore(self, state)
+    }
+}
+
+/// A generator that holds the lease on its worker ID, and hands it back when the service shuts
+/// down.
+///
+/// [`LeasedGenerator::shutdown`], which a service calls when it receives `SIGTERM`, flushes the
+/// generator, persists its position to the [state file](LeasedGenerator::state_file) if there
+/// is one and then releases the lease, so the worker ID returns to the pool straight away
+/// instead of when the lease expires. Dropping the generator does the same, ignoring errors.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::coordination::{LeasedGenerator, MemoryWorkerRegistry, WorkerRegistry};
+///
+/// let registry = MemoryWorkerRegistry::new();
+/// let path = std::env::temp_dir().join("rusty-snowflake-leased-doctest.state");
+///
+/// let mut generator = LeasedGenerator::from_lease(registry.lease().unwrap())
+///     .unwrap()
+///     .state_file(&path)
+///     .unwrap();
+/// let last = generator.next();
+///
+/// generator.shutdown().unwrap();
+///
+/// // The worker ID is free again, and the restarted generator carries on after `last`
+/// let mut restarted = LeasedGenerator::from_lease(registry.lease().unwrap())
+///     .unwrap()
+///     .state_file(&path)
+///     .unwrap();
+/// assert_eq!(restarted.worker_id(), last.worker_id());
+/// assert!(restarted.next() > last);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct LeasedGenerator<L: WorkerLease, G: ManagedGenerator = SnowflakeGenerator> {
+    generator: G,
+    /// Taken when the lease is released.
+    lease: Option<L>,
+    worker_id: u64,
+    /// Where to persist the generator's position when shutting down.
+    state_path: Option<PathBuf>,
+}
+
+impl<L: WorkerLease> LeasedGenerator<L> {
+    /// Start generating snowflakes with a leased worker ID and
+    /// [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT)
+    ///
+    /// # Arguments
+    /// * `lease` - The lease on the worker ID
+    ///
+    /// # Returns
+    /// A new `LeasedGenerator`, or an error if the generator can't be created
+    pub fn from_lease(lease: L) -> Result<LeasedGenerator<L>, CoordinationError> {
+        let generator = SnowflakeGenerator::try_new(lease.worker_id())?;
+        LeasedGenerator::new(lease, generator)
+    }
+}
+
+impl<L: WorkerLease, G: ManagedGenerator> LeasedGenerator<L, G> {
+    /// Hold the lease on a generator's worker ID
+    ///
+    /// # Arguments
+    /// * `lease` - The lease on the worker ID
+    /// * `generator` - A generator using the leased worker ID
+    ///
+    /// # Returns
+    /// A new `LeasedGenerator`, or `CoordinationError::WorkerIdMismatch` if the generator
+    /// uses another worker ID than the lease is for
+    pub fn new(lease: L, generator: G) -> Result<LeasedGenerator<L, G>, CoordinationError> {
+        let worker_id = lease.worker_id();
+        if generator.worker_id() != worker_id {
+            return Err(CoordinationError::WorkerIdMismatch {
+                leased: worker_id,
+                generator: generator.worker_id(),
+            });
+        }
+
+        Ok(LeasedGenerator {
+            generator,
+            lease: Some(lease),
+            worker_id,
+            state_path: None,
+        })
+    }
+
+    /// Persist the generator's position to a file when shutting down, restoring it from the
+    /// file now if it exists
+    ///
+    /// # Arguments
+    /// * `path` - The path of the state file
+    ///
+    /// # Returns
+    /// The generator, or an error if the file couldn't be read or holds a position the
+    /// generator's layout can't reach
+    pub fn state_file(
+        mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<LeasedGenerator<L, G>, CoordinationError> {
+        let path = path.as_ref();
+
+        if let Some(state) = read_state(path)? {
+            self.generator.restore(state)?;
+        }
+        self.state_path = Some(path.to_path_buf());
+
+        Ok(self)
+    }
+
+    /// Get the leased worker ID
+    pub fn worker_id(&self) -> u64 {
+        self.worker_id
+    }
+
+    /// Get the generator
+    pub fn generator(&self) -> &G {
+        &self.generator
+    }
+
+    /// Extend the lease so it doesn't expire
+    ///
+    /// # Returns
+    /// The error [`WorkerLease::renew`] returned
+    pub fn renew(&mut self) -> Result<(), CoordinationError> {
+        match &mut self.lease {
+            Some(lease) => lease.renew(),
+            None => Err(CoordinationError::LeaseLost {
+                worker_id: self.worker_id,
+            }),
+        }
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// # Panics
+    /// Panics in the same cases as [`SnowflakeGenerator::next`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Snowflake {
+        match self.try_next() {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// # Returns
+    /// The generated snowflake, or the error the generator returned
+    pub fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        self.generator.try_next()
+    }
+
+    /// Flush the generator, persist its position and release the lease
+    ///
+    /// Every step is attempted even if an earlier one fails, so the worker ID is released
+    /// even when the state file can't be written.
+    ///
+    /// # Returns
+    /// The first error hit
+    pub fn shutdown(mut self) -> Result<(), CoordinationError> {
+        self.stop()
+    }
+
+    /// Flush the generator, persist its position and release the lease on a background
+    /// thread, without blocking the executor
+    ///
+    /// Like [`AsyncSnowflakeGenerator`](crate::AsyncSnowflakeGenerator), this works with any
+    /// executor.
+    ///
+    /// # Returns
+    /// The first error hit, as for [`LeasedGenerator::shutdown`]
+    #[cfg(feature = "async")]
+    pub async fn shutdown_async(self) -> Result<(), CoordinationError>
+    where
+        L: Send + 'static,
+        G: Send + 'static,
+    {
+        blocking::spawn(move || self.shutdown())
+            .await
+            .unwrap_or_else(|| Err(io::Error::other("shutdown thread panicked").into()))
+    }
+
+    /// Run the shutdown steps, unless they already ran
+    fn stop(&mut self) -> Result<(), CoordinationError> {
+        let Some(lease) = self.lease.take() else {
+            return Ok(());
+        };
+
+        let flushed = self.generator.flush().map_err(CoordinationError::from);
+        let persisted = match &self.state_path {
+            Some(path) => write_state(path, self.generator.snapshot()),
+            None => Ok(()),
+        };
+        let released = lease.release();
+
+        flushed.and(persisted).and(released)
+    }
+}
+
+impl<L: WorkerLease, G: ManagedGenerator> Drop for LeasedGenerator<L, G> {
+    /// Shut down, ignoring errors
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+impl<L: WorkerLease + std::fmt::Debug, G: ManagedGenerator + std::fmt::Debug> std::fmt::Debug
+    for LeasedGenerator<L, G>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("LeasedGenerator")
+            .field("generator", &self.generator)
+            .field("lease", &self.lease)
+            .field("worker_id", &self.worker_id)
+            .field("state_path", &self.state_path)
+            .finish()
+    }
+}
+
+/// Read a persisted position, if the state file exists
+fn read_state(path: &Path) -> Result<Option<GeneratorState>, CoordinationError> {
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut bytes = [0; 16];
+    file.read_exact(&mut bytes)?;
+    Ok(Some(GeneratorState::from_bytes(bytes)))
+}
+
+/// Persist a position, replacing the state file only once the new one is written in full
+fn write_state(path: &Path, state: GeneratorState) -> Result<(), CoordinationError> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    let mut file = std::fs::File::create(&temporary)?;
+    file.write_all(&state.to_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+mod blocking {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+    use std::task::{Context, Poll, Waker};
+
+    /// A future that completes once a function running on a background thread returns, with
+    /// `None` if it panicked.
+    pub(super) struct Blocking<T> {
+        shared: Arc<Mutex<Shared<T>>>,
+    }
+
+    struct Shared<T> {
+        result: Option<T>,
+        finished: bool,
+        waker: Option<Waker>,
+    }
+
+    /// Marks the function finished and wakes the task, even if the function panicked.
+    struct Finish<T>(Arc<Mutex<Shared<T>>>);
+
+    impl<T> Drop for Finish<T> {
+        fn drop(&mut self) {
+            let mut shared = lock(&self.0);
+            shared.finished = true;
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Run a function on a new thread
+    pub(super) fn spawn<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Blocking<T> {
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            finished: false,
+            waker: None,
+        }));
+
+        let finish = Finish(Arc::clone(&shared));
+        std::thread::spawn(move || {
+            let finish = finish;
+            let result = f();
+            lock(&finish.0).result = Some(result);
+        });
+
+        Blocking { shared }
+    }
+
+    impl<T> Future for Blocking<T> {
+        type Output = Option<T>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+            let mut shared = lock(&self.shared);
+            if shared.finished {
+                return Poll::Ready(shared.result.take());
+            }
+
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn lock<T>(shared: &Mutex<Shared<T>>) -> MutexGuard<'_, Shared<T>> {
+        shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordination::{MemoryWorkerRegistry, WorkerRegistry};
+    use crate::{MockClock, SnowflakeLayout};
+
+    fn state_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "rusty-snowflake-leased-{}-{}.state",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_shutdown_releases_lease() {
+        let registry = MemoryWorkerRegistry::with_layout(&SnowflakeLayout::SINGLE_PROCESS);
+        let generator = LeasedGenerator::from_lease(registry.lease().unwrap()).unwrap();
+        assert!(matches!(
+            registry.lease(),
+            Err(CoordinationError::NoFreeWorkerId)
+        ));
+
+        generator.shutdown().unwrap();
+        assert_eq!(registry.lease().unwrap().worker_id(), 0);
+    }
+
+    #[test]
+    fn test_drop_releases_lease() {
+        let registry = MemoryWorkerRegistry::with_layout(&SnowflakeLayout::SINGLE_PROCESS);
+        let generator = LeasedGenerator::from_lease(registry.lease().unwrap()).unwrap();
+
+        drop(generator);
+        assert!(registry.lease().is_ok());
+    }
+
+    #[test]
+    fn test_worker_id_mismatch() {
+        let registry = MemoryWorkerRegistry::new();
+        let lease = registry.lease().unwrap();
+
+        let result = LeasedGenerator::new(lease, SnowflakeGenerator::try_new(7).unwrap());
+        assert!(matches!(
+            result,
+            Err(CoordinationError::WorkerIdMismatch {
+                leased: 0,
+                generator: 7
+            })
+        ));
+        // The lease was dropped along with the error
+        assert_eq!(registry.lease().unwrap().worker_id(), 0);
+    }
+
+    #[test]
+    fn test_state_file() {
+        let path = state_path("state");
+        let _ = std::fs::remove_file(&path);
+        let registry = MemoryWorkerRegistry::new();
+        let clock = MockClock::new(1_000_000);
+
+        let generator = SnowflakeGenerator::with_clock(0, clock.clone()).unwrap();
+        let mut generator = LeasedGenerator::new(registry.lease().unwrap(), generator)
+            .unwrap()
+            .state_file(&path)
+            .unwrap();
+        generator.next();
+        let last = generator.next();
+        generator.shutdown().unwrap();
+
+        // The clock went backwards while the service restarted
+        clock.set(999_000);
+        let generator = SnowflakeGenerator::with_clock(0, clock.clone()).unwrap();
+        let mut restarted = LeasedGenerator::new(registry.lease().unwrap(), generator)
+            .unwrap()
+            .state_file(&path)
+            .unwrap();
+        assert!(restarted.next() > last);
+
+        drop(restarted);
+        assert_eq!(
+            read_state(&path).unwrap(),
+            Some(GeneratorState::new(last.timestamp(), last.sequence() + 1))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_releases_lease_when_persisting_fails() {
+        let registry = MemoryWorkerRegistry::with_layout(&SnowflakeLayout::SINGLE_PROCESS);
+        let path = std::env::temp_dir()
+            .join("rusty-snowflake-missing-directory")
+            .join("generator.state");
+
+        let generator = LeasedGenerator::from_lease(registry.lease().unwrap())
+            .unwrap()
+            .state_file(&path)
+            .unwrap();
+
+        assert!(matches!(
+            generator.shutdown(),
+            Err(CoordinationError::Io(_))
+        ));
+        assert!(registry.lease().is_ok());
+    }
+
+    #[test]
+    fn test_renew() {
+        let registry = MemoryWorkerRegistry::new();
+        let mut generator = LeasedGenerator::from_lease(registry.lease().unwrap()).unwrap();
+
+        assert!(generator.renew().is_ok());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_shutdown_async() {
+        use std::future::Future;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+        use std::thread::Thread;
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let registry = MemoryWorkerRegistry::with_layout(&SnowflakeLayout::SINGLE_PROCESS);
+        let generator = LeasedGenerator::from_lease(registry.lease().unwrap()).unwrap();
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(generator.shutdown_async());
+        let result = loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(result) => break result,
+                Poll::Pending => std::thread::park(),
+            }
+        };
+
+        assert!(result.is_ok());
+        assert!(registry.lease().is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{CoordinationError, WorkerLease, WorkerRegistry};
+use crate::SnowflakeLayout;
+
+#[derive(Debug, Clone)]
+/// Leases worker IDs within a single process.
+///
+/// Clones share the same leases, so generators on different threads can each lease their own
+/// worker ID. Also useful as a stand-in for a real store in tests.
+pub struct MemoryWorkerRegistry {
+    /// The worker IDs that are currently leased.
+    leased: Arc<Mutex<BTreeSet<u64>>>,
+    /// The largest worker ID to lease.
+    max_worker_id: u64,
+}
+
+impl MemoryWorkerRegistry {
+    /// Create a new registry that leases worker IDs for [`SnowflakeLayout::DEFAULT`]
+    pub fn new() -> MemoryWorkerRegistry {
+        MemoryWorkerRegistry::with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Create a new registry that leases worker IDs for a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the leased worker IDs have to fit in
+    pub fn with_layout(layout: &SnowflakeLayout) -> MemoryWorkerRegistry {
+        MemoryWorkerRegistry {
+            leased: Arc::new(Mutex::new(BTreeSet::new())),
+            max_worker_id: layout.max_worker_id(),
+        }
+    }
+}
+
+impl Default for MemoryWorkerRegistry {
+    fn default() -> MemoryWorkerRegistry {
+        MemoryWorkerRegistry::new()
+    }
+}
+
+impl WorkerRegistry for MemoryWorkerRegistry {
+    type Lease = MemoryWorkerLease;
+
+    fn lease(&self) -> Result<MemoryWorkerLease, CoordinationError> {
+        let mut leased = lock(&self.leased);
+
+        let worker_id = (0..=self.max_worker_id)
+            .find(|worker_id| !leased.contains(worker_id))
+            .ok_or(CoordinationError::NoFreeWorkerId)?;
+        leased.insert(worker_id);
+
+        Ok(MemoryWorkerLease {
+            leased: Arc::clone(&self.leased),
+            worker_id,
+            released: false,
+        })
+    }
+}
+
+#[derive(Debug)]
+/// A worker ID leased from a [`MemoryWorkerRegistry`].
+///
+/// The lease never expires, and is released when this is dropped.
+pub struct MemoryWorkerLease {
+    leased: Arc<Mutex<BTreeSet<u64>>>,
+    worker_id: u64,
+    released: bool,
+}
+
+impl WorkerLease for MemoryWorkerLease {
+    fn worker_id(&self) -> u64 {
+        self.worker_id
+    }
+
+    fn renew(&mut self) -> Result<(), CoordinationError> {
+        Ok(())
+    }
+
+    fn release(mut self) -> Result<(), CoordinationError> {
+        self.released = true;
+        lock(&self.leased).remove(&self.worker_id);
+        Ok(())
+    }
+}
+
+impl Drop for MemoryWorkerLease {
+    fn drop(&mut self) {
+        if !self.released {
+            lock(&self.leased).remove(&self.worker_id);
+        }
+    }
+}
+
+/// Lock the leased worker IDs, which stay consistent even if a holder panicked
+fn lock(leased: &Mutex<BTreeSet<u64>>) -> MutexGuard<'_, BTreeSet<u64>> {
+    leased
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease() {
+        let registry = MemoryWorkerRegistry::new();
+
+        let mut first = registry.lease().unwrap();
+        let second = registry.clone().lease().unwrap();
+
+        assert_eq!(first.worker_id(), 0);
+        assert_eq!(second.worker_id(), 1);
+        assert!(first.renew().is_ok());
+    }
+
+    #[test]
+    fn test_release() {
+        let registry = MemoryWorkerRegistry::new();
+
+        let first = registry.lease().unwrap();
+        let second = registry.lease().unwrap();
+        first.release().unwrap();
+        drop(second);
+
+        assert_eq!(registry.lease().unwrap().worker_id(), 0);
+    }
+
+    #[test]
+    fn test_no_free_worker_id() {
+        let layout = SnowflakeLayout::builder()
+            .worker_id_bits(1)
+            .build()
+            .unwrap();
+        let registry = MemoryWorkerRegistry::with_layout(&layout);
+
+        let _first = registry.lease().unwrap();
+        let _second = registry.lease().unwrap();
+
+        assert!(matches!(
+            registry.lease(),
+            Err(CoordinationError::NoFreeWorkerId)
+        ));
+    }
+}
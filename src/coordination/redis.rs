@@ -0,0 +1,485 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use super::{lease_token, CoordinationError, WorkerLease, WorkerRegistry};
+use crate::SnowflakeLayout;
+
+/// Extends a lease only if this holder still owns it.
+const RENEW_SCRIPT: &str = "if redis.call('get', KEYS[1]) == ARGV[1] then \
+    return redis.call('pexpire', KEYS[1], ARGV[2]) else return 0 end";
+
+/// Deletes a lease only if this holder still owns it.
+const RELEASE_SCRIPT: &str = "if redis.call('get', KEYS[1]) == ARGV[1] then \
+    return redis.call('del', KEYS[1]) else return 0 end";
+
+/// The prefixes of error replies Redis sends while it can't serve requests for a while.
+const UNAVAILABLE_ERRORS: [&str; 5] = ["LOADING", "BUSY", "TRYAGAIN", "MASTERDOWN", "CLUSTERDOWN"];
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// Leases worker IDs from a Redis server.
+///
+/// Each worker ID is a key holding a random token with a TTL, claimed with `SET NX PX`. A
+/// replica that crashes stops renewing its lease, and the worker ID becomes free again once the
+/// TTL runs out. A server that stops responding fails leasing and renewing with
+/// `CoordinationError::Timeout` instead of blocking.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use rusty_snowflake::coordination::RedisWorkerRegistry;
+/// use rusty_snowflake::SnowflakeGenerator;
+///
+/// let registry = RedisWorkerRegistry::new("127.0.0.1:6379").ttl(Duration::from_secs(30));
+/// let mut lease = registry.lease().unwrap();
+///
+/// let mut generator = SnowflakeGenerator::try_new(lease.worker_id()).unwrap();
+/// println!("Generated snowflake: {}", generator.next());
+///
+/// // Renew well within the TTL, for example every 10 seconds
+/// lease.renew().unwrap();
+/// ```
+pub struct RedisWorkerRegistry {
+    /// The `host:port` of the Redis server.
+    address: String,
+    /// The prefix of the keys that hold the leases.
+    key_prefix: String,
+    /// How long a lease lasts without being renewed.
+    ttl: Duration,
+    /// How long connecting to, writing to and reading from the server may take.
+    timeout: Duration,
+    /// The largest worker ID to lease.
+    max_worker_id: u64,
+}
+
+impl RedisWorkerRegistry {
+    /// Create a new registry that leases worker IDs for [`SnowflakeLayout::DEFAULT`], in keys
+    /// prefixed with `snowflake:worker:` that expire after 30 seconds, with a one second timeout
+    ///
+    /// # Arguments
+    /// * `address` - The `host:port` of the Redis server
+    pub fn new(address: impl Into<String>) -> RedisWorkerRegistry {
+        RedisWorkerRegistry {
+            address: address.into(),
+            key_prefix: "snowflake:worker:".to_string(),
+            ttl: Duration::from_secs(30),
+            timeout: Duration::from_secs(1),
+            max_worker_id: SnowflakeLayout::DEFAULT.max_worker_id(),
+        }
+    }
+
+    /// Set the prefix of the keys that hold the leases
+    ///
+    /// Services that share a Redis server but not a worker ID space need different prefixes.
+    pub fn key_prefix(mut self, key_prefix: impl Into<String>) -> RedisWorkerRegistry {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Set how long a lease lasts without being renewed
+    pub fn ttl(mut self, ttl: Duration) -> RedisWorkerRegistry {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set how long connecting to, writing to and reading from the server may take
+    ///
+    /// The timeout also applies when the lease is renewed and released, so keep it well within
+    /// the TTL.
+    pub fn timeout(mut self, timeout: Duration) -> RedisWorkerRegistry {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the layout the leased worker IDs have to fit in
+    pub fn layout(mut self, layout: &SnowflakeLayout) -> RedisWorkerRegistry {
+        self.max_worker_id = layout.max_worker_id();
+        self
+    }
+
+    /// Lease the lowest free worker ID
+    ///
+    /// # Returns
+    /// The lease, `CoordinationError::NoFreeWorkerId` if every worker ID is leased, or
+    /// `CoordinationError::Timeout` if the server didn't respond within the timeout
+    pub fn lease(&self) -> Result<RedisWorkerLease, CoordinationError> {
+        let mut connection = Connection::open(&self.address, self.timeout)?;
+        let token = lease_token();
+        let ttl = self.ttl.as_millis().max(1).to_string();
+
+        for worker_id in 0..=self.max_worker_id {
+            let key = format!("{}{}", self.key_prefix, worker_id);
+
+            match connection.command(&["SET", &key, &token, "NX", "PX", &ttl])? {
+                Reply::Simple(_) => {
+                    return Ok(RedisWorkerLease {
+                        connection,
+                        key,
+                        token,
+                        ttl: self.ttl,
+                        worker_id,
+                        released: false,
+                    })
+                }
+                Reply::Bulk(None) => continue,
+                reply => return Err(reply.unexpected()),
+            }
+        }
+
+        Err(CoordinationError::NoFreeWorkerId)
+    }
+}
+
+#[derive(Debug)]
+/// A worker ID leased from a [`RedisWorkerRegistry`].
+///
+/// The lease is released when this is dropped.
+pub struct RedisWorkerLease {
+    connection: Connection,
+    key: String,
+    token: String,
+    ttl: Duration,
+    worker_id: u64,
+    released: bool,
+}
+
+impl RedisWorkerLease {
+    /// Get the leased worker ID
+    pub fn worker_id(&self) -> u64 {
+        self.worker_id
+    }
+
+    /// Extend the lease by the registry's TTL
+    ///
+    /// # Returns
+    /// `CoordinationError::LeaseLost` if the lease already expired, in which case another
+    /// replica may be using the worker ID and generating snowflakes with it has to stop
+    pub fn renew(&mut self) -> Result<(), CoordinationError> {
+        let ttl = self.ttl.as_millis().max(1).to_string();
+
+        self.run_script(RENEW_SCRIPT, &ttl)
+    }
+
+    /// Release the lease so another replica can use the worker ID
+    pub fn release(mut self) -> Result<(), CoordinationError> {
+        self.released = true;
+        self.run_script(RELEASE_SCRIPT, "")
+    }
+
+    /// Run a script that touches the key only if it still holds this lease's token
+    fn run_script(&mut self, script: &str, argument: &str) -> Result<(), CoordinationError> {
+        let reply =
+            self.connection
+                .command(&["EVAL", script, "1", &self.key, &self.token, argument])?;
+
+        match reply {
+            Reply::Integer(1) => Ok(()),
+            Reply::Integer(0) => Err(CoordinationError::LeaseLost {
+                worker_id: self.worker_id,
+            }),
+            reply => Err(reply.unexpected()),
+        }
+    }
+}
+
+impl WorkerRegistry for RedisWorkerRegistry {
+    type Lease = RedisWorkerLease;
+
+    fn lease(&self) -> Result<RedisWorkerLease, CoordinationError> {
+        RedisWorkerRegistry::lease(self)
+    }
+}
+
+impl WorkerLease for RedisWorkerLease {
+    fn worker_id(&self) -> u64 {
+        RedisWorkerLease::worker_id(self)
+    }
+
+    fn renew(&mut self) -> Result<(), CoordinationError> {
+        RedisWorkerLease::renew(self)
+    }
+
+    fn release(self) -> Result<(), CoordinationError> {
+        RedisWorkerLease::release(self)
+    }
+}
+
+impl Drop for RedisWorkerLease {
+    fn drop(&mut self) {
+        if !self.released {
+            self.released = true;
+            let _ = self.run_script(RELEASE_SCRIPT, "");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// A reply from a Redis server.
+enum Reply {
+    Simple(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+impl Reply {
+    fn unexpected(&self) -> CoordinationError {
+        CoordinationError::Protocol(format!("{:?}", self))
+    }
+}
+
+#[derive(Debug)]
+/// A connection to a Redis server speaking RESP2.
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    /// Connect to the server, failing any connect, write or read that takes longer than
+    /// `timeout`
+    fn open(address: &str, timeout: Duration) -> Result<Connection, CoordinationError> {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address didn't resolve"))?;
+
+        let writer = TcpStream::connect_timeout(&address, timeout)?;
+        writer.set_read_timeout(Some(timeout))?;
+        writer.set_write_timeout(Some(timeout))?;
+
+        Ok(Connection {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Send a command and read its reply
+    fn command(&mut self, arguments: &[&str]) -> Result<Reply, CoordinationError> {
+        self.send(arguments)?;
+        self.read_reply()
+    }
+
+    /// Send a command as an array of bulk strings
+    fn send(&mut self, arguments: &[&str]) -> Result<(), CoordinationError> {
+        let mut request = format!("*{}\r\n", arguments.len());
+        for argument in arguments {
+            request.push_str(&format!("${}\r\n{}\r\n", argument.len(), argument));
+        }
+
+        self.writer.write_all(request.as_bytes())?;
+        Ok(())
+    }
+
+    fn read_reply(&mut self) -> Result<Reply, CoordinationError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(CoordinationError::Protocol("connection closed".to_string()));
+        }
+
+        let line = line.trim_end_matches("\r\n");
+        let (kind, rest) = line.split_at(line.len().min(1));
+        let length = || {
+            rest.parse::<i64>()
+                .map_err(|_| CoordinationError::Protocol(line.to_string()))
+        };
+
+        match kind {
+            "+" => Ok(Reply::Simple(rest.to_string())),
+            "-" if UNAVAILABLE_ERRORS.contains(&rest.split(' ').next().unwrap_or_default()) => {
+                Err(CoordinationError::Unavailable(rest.to_string()))
+            }
+            "-" => Err(CoordinationError::Store(rest.to_string())),
+            ":" => Ok(Reply::Integer(length()?)),
+            "$" => match usize::try_from(length()?) {
+                Ok(length) => {
+                    let mut bulk = vec![0; length + 2];
+                    self.reader.read_exact(&mut bulk)?;
+                    bulk.truncate(length);
+                    Ok(Reply::Bulk(Some(bulk)))
+                }
+                Err(_) => Ok(Reply::Bulk(None)),
+            },
+            "*" => match usize::try_from(length()?) {
+                Ok(length) => Ok(Reply::Array(Some(
+                    (0..length)
+                        .map(|_| self.read_reply())
+                        .collect::<Result<_, _>>()?,
+                ))),
+                Err(_) => Ok(Reply::Array(None)),
+            },
+            _ => Err(CoordinationError::Protocol(line.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// Serve one connection with just enough of Redis to exercise the registry, ignoring TTLs
+    fn fake_redis(
+        mut keys: HashMap<String, String>,
+    ) -> (String, std::thread::JoinHandle<HashMap<String, String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut connection = Connection {
+                reader: BufReader::new(stream.try_clone().unwrap()),
+                writer: stream,
+            };
+
+            while let Ok(Reply::Array(Some(arguments))) = connection.read_reply() {
+                let arguments = arguments
+                    .into_iter()
+                    .map(|argument| match argument {
+                        Reply::Bulk(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                        argument => panic!("unexpected argument {:?}", argument),
+                    })
+                    .collect::<Vec<_>>();
+
+                let reply = match arguments[0].as_str() {
+                    _ if arguments[1].starts_with("loading:") => {
+                        "-LOADING Redis is loading the dataset in memory\r\n".to_string()
+                    }
+                    "SET" if keys.contains_key(&arguments[1]) => "$-1\r\n".to_string(),
+                    "SET" => {
+                        keys.insert(arguments[1].clone(), arguments[2].clone());
+                        "+OK\r\n".to_string()
+                    }
+                    "EVAL" if keys.get(&arguments[3]) != Some(&arguments[4]) => {
+                        ":0\r\n".to_string()
+                    }
+                    "EVAL" if arguments[1] == RELEASE_SCRIPT => {
+                        keys.remove(&arguments[3]);
+                        ":1\r\n".to_string()
+                    }
+                    "EVAL" => ":1\r\n".to_string(),
+                    command => format!("-ERR unknown command '{}'\r\n", command),
+                };
+
+                connection.writer.write_all(reply.as_bytes()).unwrap();
+            }
+
+            keys
+        });
+
+        (address, server)
+    }
+
+    #[test]
+    fn test_lease_skips_taken_worker_ids() {
+        let taken = HashMap::from([
+            ("snowflake:worker:0".to_string(), "other".to_string()),
+            ("snowflake:worker:1".to_string(), "other".to_string()),
+        ]);
+        let (address, server) = fake_redis(taken);
+
+        let mut lease = RedisWorkerRegistry::new(address).lease().unwrap();
+        assert_eq!(lease.worker_id(), 2);
+        lease.renew().unwrap();
+        drop(lease);
+
+        let keys = server.join().unwrap();
+        assert!(!keys.contains_key("snowflake:worker:2"));
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_no_free_worker_id() {
+        let layout = SnowflakeLayout::builder()
+            .worker_id_bits(1)
+            .build()
+            .unwrap();
+        let taken = HashMap::from([
+            ("ids:0".to_string(), "other".to_string()),
+            ("ids:1".to_string(), "other".to_string()),
+        ]);
+        let (address, _server) = fake_redis(taken);
+
+        let registry = RedisWorkerRegistry::new(address)
+            .key_prefix("ids:")
+            .layout(&layout);
+
+        assert!(matches!(
+            registry.lease(),
+            Err(CoordinationError::NoFreeWorkerId)
+        ));
+    }
+
+    #[test]
+    fn test_unavailable() {
+        let (address, _server) = fake_redis(HashMap::new());
+        let err = RedisWorkerRegistry::new(address)
+            .key_prefix("loading:")
+            .lease()
+            .unwrap_err();
+
+        assert!(err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "store is temporarily unavailable: LOADING Redis is loading the dataset in memory"
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        // Accept the connection but never reply
+        let server = std::thread::spawn(move || listener.accept().unwrap());
+
+        let start = std::time::Instant::now();
+        let err = RedisWorkerRegistry::new(address)
+            .timeout(Duration::from_millis(50))
+            .lease()
+            .unwrap_err();
+
+        assert!(matches!(err, CoordinationError::Timeout));
+        assert!(err.is_retryable());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        drop(server.join().unwrap());
+    }
+
+    #[test]
+    fn test_lease_lost() {
+        let (address, server) = fake_redis(HashMap::new());
+
+        let mut lease = RedisWorkerRegistry::new(address).lease().unwrap();
+        lease.token = "stolen".to_string();
+
+        assert!(matches!(
+            lease.renew(),
+            Err(CoordinationError::LeaseLost { worker_id: 0 })
+        ));
+        assert!(matches!(
+            lease.release(),
+            Err(CoordinationError::LeaseLost { worker_id: 0 })
+        ));
+        assert!(server.join().unwrap().contains_key("snowflake:worker:0"));
+    }
+
+    /// Run with `REDIS_ADDR=127.0.0.1:6379 cargo test --features redis -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_redis_server() {
+        let address = std::env::var("REDIS_ADDR").unwrap_or_else(|_| "127.0.0.1:6379".to_string());
+        let registry = RedisWorkerRegistry::new(address)
+            .key_prefix(format!("rusty-snowflake-test:{}:", lease_token()))
+            .ttl(Duration::from_secs(5));
+
+        let mut first = registry.lease().unwrap();
+        let second = registry.lease().unwrap();
+        assert_eq!(first.worker_id(), 0);
+        assert_eq!(second.worker_id(), 1);
+
+        first.renew().unwrap();
+        first.release().unwrap();
+        assert_eq!(registry.lease().unwrap().worker_id(), 0);
+    }
+}
@@ -0,0 +1,229 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{SnowflakeLayout, TimeUnit};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// One field of a [`LayoutDescription`].
+pub struct FieldDescription {
+    name: &'static str,
+    offset: u32,
+    bits: u8,
+}
+
+impl FieldDescription {
+    /// The name of the field: `timestamp`, `extra`, `datacenter_id`, `machine_id`, `worker_id`
+    /// or `sequence`
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The position of the field's lowest bit in an ID
+    pub const fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// The number of bits in the field
+    pub const fn bits(&self) -> u8 {
+        self.bits
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+/// A machine-readable description of a [`SnowflakeLayout`], for tools and clients in other
+/// languages to decode IDs without repeating the layout by hand.
+///
+/// The fields are listed from the highest bits of an ID to the lowest. A layout with a
+/// datacenter ID lists it and the machine ID instead of the worker ID they make up. Fields
+/// without any bits are left out.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::SnowflakeLayout;
+///
+/// let description = SnowflakeLayout::DISCORD.describe();
+///
+/// assert_eq!(description.fields()[0].name(), "timestamp");
+/// assert_eq!(description.fields()[0].offset(), 22);
+/// assert_eq!(
+///     description.to_json(),
+///     r#"{"epoch_millis":1420070400000,"tick_micros":1000,"unit":"milliseconds","fields":[{"name":"timestamp","offset":22,"bits":42},{"name":"datacenter_id","offset":17,"bits":5},{"name":"machine_id","offset":12,"bits":5},{"name":"sequence","offset":0,"bits":12}]}"#
+/// );
+/// ```
+pub struct LayoutDescription {
+    epoch_millis: u64,
+    tick_micros: u64,
+    unit: Option<TimeUnit>,
+    fields: Vec<FieldDescription>,
+}
+
+impl LayoutDescription {
+    /// The epoch timestamps are counted from, in milliseconds since the UNIX epoch
+    pub const fn epoch_millis(&self) -> u64 {
+        self.epoch_millis
+    }
+
+    /// The number of microseconds in one timestamp tick
+    pub const fn tick_micros(&self) -> u64 {
+        self.tick_micros
+    }
+
+    /// The unit the timestamp is counted in, if the tick is exactly one [`TimeUnit`] long
+    pub const fn unit(&self) -> Option<TimeUnit> {
+        self.unit
+    }
+
+    /// The fields of an ID, from the highest bits to the lowest
+    pub fn fields(&self) -> &[FieldDescription] {
+        &self.fields
+    }
+
+    /// Write the description as a JSON object
+    ///
+    /// The object has the keys `epoch_millis`, `tick_micros`, `unit` (`"seconds"`,
+    /// `"centiseconds"`, `"milliseconds"`, `"microseconds"` or `null`) and `fields`, a list
+    /// of objects with the keys `name`, `offset` and `bits`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let unit = match self.unit {
+            Some(TimeUnit::Seconds) => "\"seconds\"",
+            Some(TimeUnit::Centiseconds) => "\"centiseconds\"",
+            Some(TimeUnit::Milliseconds) => "\"milliseconds\"",
+            Some(TimeUnit::Microseconds) => "\"microseconds\"",
+            None => "null",
+        };
+
+        // Writing to a `String` can't fail
+        let _ = write!(
+            json,
+            "{{\"epoch_millis\":{},\"tick_micros\":{},\"unit\":{},\"fields\":[",
+            self.epoch_millis, self.tick_micros, unit
+        );
+        for (index, field) in self.fields.iter().enumerate() {
+            let _ = write!(
+                json,
+                "{}{{\"name\":\"{}\",\"offset\":{},\"bits\":{}}}",
+                if index == 0 { "" } else { "," },
+                field.name,
+                field.offset,
+                field.bits
+            );
+        }
+        json.push_str("]}");
+
+        json
+    }
+}
+
+impl SnowflakeLayout {
+    /// Describe the layout for tools and clients in other languages
+    ///
+    /// See [`LayoutDescription`].
+    pub fn describe(&self) -> LayoutDescription {
+        let field = |name, offset, bits| FieldDescription { name, offset, bits };
+
+        let worker_id = if self.datacenter_id_bits() == 0 {
+            Vec::from([field(
+                "worker_id",
+                self.worker_id_shift(),
+                self.worker_id_bits(),
+            )])
+        } else {
+            let machine_id_shift = self.worker_id_shift();
+            Vec::from([
+                field(
+                    "datacenter_id",
+                    machine_id_shift + self.machine_id_bits() as u32,
+                    self.datacenter_id_bits(),
+                ),
+                field("machine_id", machine_id_shift, self.machine_id_bits()),
+            ])
+        };
+        let sequence = field("sequence", self.sequence_shift(), self.sequence_bits());
+
+        let mut fields = Vec::from([
+            field("timestamp", self.timestamp_shift(), self.timestamp_bits()),
+            field("extra", self.extra_shift(), self.extra_bits()),
+        ]);
+        if self.worker_id_shift() > self.sequence_shift() {
+            fields.extend(worker_id);
+            fields.push(sequence);
+        } else {
+            fields.push(sequence);
+            fields.extend(worker_id);
+        }
+        fields.retain(|field| field.bits > 0);
+
+        LayoutDescription {
+            epoch_millis: self.epoch(),
+            tick_micros: self.tick_micros(),
+            unit: self.time_unit(),
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_sequence_first() {
+        let description = SnowflakeLayout::SONYFLAKE.describe();
+
+        assert_eq!(
+            description
+                .fields()
+                .iter()
+                .map(|field| (field.name(), field.offset(), field.bits()))
+                .collect::<Vec<_>>(),
+            [
+                ("timestamp", 24, 39),
+                ("sequence", 16, 8),
+                ("worker_id", 0, 16)
+            ]
+        );
+        assert_eq!(description.unit(), Some(TimeUnit::Centiseconds));
+    }
+
+    #[test]
+    fn test_describe_extra() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .datacenter_id_bits(0)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            layout
+                .describe()
+                .fields()
+                .iter()
+                .map(|field| (field.name(), field.offset(), field.bits()))
+                .collect::<Vec<_>>(),
+            [
+                ("timestamp", 24, 39),
+                ("extra", 22, 2),
+                ("worker_id", 12, 10),
+                ("sequence", 0, 12)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let layout = SnowflakeLayout::builder()
+            .tick_millis(5)
+            .datacenter_id_bits(0)
+            .sequence_bits(0)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            layout.describe().to_json(),
+            r#"{"epoch_millis":0,"tick_micros":5000,"unit":null,"fields":[{"name":"timestamp","offset":10,"bits":41},{"name":"worker_id","offset":0,"bits":10}]}"#
+        );
+    }
+}
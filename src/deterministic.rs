@@ -0,0 +1,200 @@
+use crate::{
+    GeneratorOptions, MockClock, OverflowStrategy, Snowflake, SnowflakeError, SnowflakeGenerator,
+    SnowflakeLayout,
+};
+
+/// A generator that produces the same stream of snowflakes every time it's run with the same
+/// arguments.
+///
+/// It reads timestamps from a [`MockClock`] that starts at a fixed time and only moves when the
+/// tick function says so. Before each snowflake the tick function is called with the index of
+/// the snowflake, starting from zero, and returns how many milliseconds the clock moves
+/// forward. If the sequence runs out within a tick, the clock jumps to the next tick instead of
+/// waiting for it.
+///
+/// Use it for golden-file tests, or to simulate IDs from the past.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::DeterministicGenerator;
+///
+/// let ids = |seed| {
+///     DeterministicGenerator::new(1, seed, |index| index % 3)
+///         .unwrap()
+///         .take(1_000)
+///         .map(|snowflake| snowflake.to_id())
+///         .collect::<Vec<_>>()
+/// };
+///
+/// assert_eq!(ids(1_600_000_000_000), ids(1_600_000_000_000));
+/// assert_ne!(ids(1_600_000_000_000), ids(1_700_000_000_000));
+/// ```
+pub struct DeterministicGenerator<F: FnMut(u64) -> u64> {
+    generator: SnowflakeGenerator<MockClock>,
+    /// A handle to the generator's clock.
+    clock: MockClock,
+    /// Returns how far the clock moves before each snowflake.
+    tick: F,
+    /// The index of the next snowflake.
+    index: u64,
+}
+
+impl<F: FnMut(u64) -> u64> DeterministicGenerator<F> {
+    /// Create a new deterministic generator that generates snowflakes with
+    /// [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    /// * `start_millis` - The time the clock starts at, in milliseconds since the UNIX epoch
+    /// * `tick` - Given the index of the next snowflake, returns how many milliseconds the clock
+    ///   moves forward before it's generated
+    ///
+    /// # Returns
+    /// A new `DeterministicGenerator`, or an error if the worker ID doesn't fit in the layout
+    pub fn new(
+        worker_id: u64,
+        start_millis: u64,
+        tick: F,
+    ) -> Result<DeterministicGenerator<F>, SnowflakeError> {
+        DeterministicGenerator::with_layout(worker_id, SnowflakeLayout::DEFAULT, start_millis, tick)
+    }
+
+    /// Create a new deterministic generator that generates snowflakes with a custom bit layout
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    /// * `layout` - The bit layout of the snowflakes generated
+    /// * `start_millis` - The time the clock starts at, in milliseconds since the UNIX epoch
+    /// * `tick` - Given the index of the next snowflake, returns how many milliseconds the clock
+    ///   moves forward before it's generated
+    ///
+    /// # Returns
+    /// A new `DeterministicGenerator`, or an error if the worker ID doesn't fit in the layout
+    pub fn with_layout(
+        worker_id: u64,
+        layout: SnowflakeLayout,
+        start_millis: u64,
+        tick: F,
+    ) -> Result<DeterministicGenerator<F>, SnowflakeError> {
+        let clock = MockClock::new(start_millis);
+        let generator =
+            SnowflakeGenerator::with_layout_and_clock(worker_id, layout, clock.clone())?
+                .with_options(GeneratorOptions::new().overflow_strategy(OverflowStrategy::Error));
+
+        Ok(DeterministicGenerator {
+            generator,
+            clock,
+            tick,
+            index: 0,
+        })
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        self.generator.layout()
+    }
+
+    /// Get the clock the snowflakes are generated from
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Generates the next snowflake ID.
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if the timestamp no longer fits in the layout
+    pub fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        self.clock.advance((self.tick)(self.index));
+        self.index += 1;
+
+        match self.generator.try_next() {
+            Err(SnowflakeError::SequenceExhausted { timestamp }) => {
+                self.clock.set_micros(
+                    self.layout()
+                        .micros_from_timestamp(timestamp.saturating_add(1)),
+                );
+                self.generator.try_next()
+            }
+            result => result,
+        }
+    }
+}
+
+impl<F: FnMut(u64) -> u64> Iterator for DeterministicGenerator<F> {
+    type Item = Snowflake;
+
+    /// Generates the next snowflake ID, or `None` once the timestamp no longer fits in the
+    /// layout
+    fn next(&mut self) -> Option<Snowflake> {
+        self.try_next().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Clock;
+
+    #[test]
+    fn test_reproducible() {
+        let layout = SnowflakeLayout::builder().tick_millis(1).build().unwrap();
+        let run = || {
+            DeterministicGenerator::with_layout(3, layout, 1_000, |index| index % 2)
+                .unwrap()
+                .take(5)
+                .map(|snowflake| (snowflake.timestamp(), snowflake.sequence()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            run(),
+            [(1_000, 1), (1_001, 0), (1_001, 1), (1_002, 0), (1_002, 1)]
+        );
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_sequence_exhaustion_skips_to_next_tick() {
+        let layout = SnowflakeLayout::builder().sequence_bits(2).build().unwrap();
+        let mut generator = DeterministicGenerator::with_layout(0, layout, 5_000, |_| 0).unwrap();
+
+        let snowflakes = generator.by_ref().take(5).collect::<Vec<_>>();
+
+        assert_eq!(snowflakes[2].timestamp(), 5);
+        assert_eq!(snowflakes[2].sequence(), 3);
+        assert_eq!(snowflakes[3].timestamp(), 6);
+        assert_eq!(snowflakes[3].sequence(), 0);
+        assert_eq!(generator.clock().now_millis(), 6_000);
+    }
+
+    #[test]
+    fn test_sequence_exhaustion_with_sub_millisecond_ticks() {
+        let layout = SnowflakeLayout::builder()
+            .tick_micros(100)
+            .sequence_bits(2)
+            .build()
+            .unwrap();
+        let generator = DeterministicGenerator::with_layout(0, layout, 5_000, |_| 0).unwrap();
+
+        let snowflakes = generator.take(12).collect::<Vec<_>>();
+
+        assert_eq!(snowflakes.len(), 12);
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(snowflakes[3].timestamp(), snowflakes[2].timestamp() + 1);
+        assert_eq!(snowflakes[3].sequence(), 0);
+        assert_eq!(snowflakes[7].timestamp(), snowflakes[2].timestamp() + 2);
+    }
+
+    #[test]
+    fn test_stops_at_timestamp_exhaustion() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(4)
+            .epoch(0)
+            .tick_millis(1)
+            .build()
+            .unwrap();
+        let generator = DeterministicGenerator::with_layout(0, layout, 0, |_| 1).unwrap();
+
+        assert_eq!(generator.count(), 15);
+    }
+}
@@ -0,0 +1,155 @@
+//! Parsing and generating Discord IDs.
+//!
+//! Discord IDs use [`SnowflakeLayout::DISCORD`], and split its 10 bit worker ID field into a
+//! 5 bit internal worker ID followed by a 5 bit internal process ID. The functions here read
+//! and write those two sub-fields.
+//!
+//! # Example
+//! ```rust
+//! use rusty_snowflake::discord;
+//!
+//! let snowflake = discord::parse(175928847299117063);
+//!
+//! assert_eq!(discord::timestamp_millis(&snowflake), 1_462_015_105_796);
+//! assert_eq!(discord::worker_id(&snowflake), 1);
+//! assert_eq!(discord::process_id(&snowflake), 0);
+//! assert_eq!(snowflake.sequence(), 7);
+//! ```
+
+use crate::{Snowflake, SnowflakeLayout};
+#[cfg(feature = "std")]
+use crate::{SnowflakeError, SnowflakeGenerator};
+
+/// The Discord epoch, the first millisecond of 2015, in milliseconds since the UNIX epoch.
+pub const EPOCH: u64 = SnowflakeLayout::DISCORD.epoch();
+
+/// The number of bits in the internal process ID.
+const PROCESS_ID_BITS: u32 = 5;
+
+/// The largest internal worker ID or process ID.
+const MAX_ID: u64 = (1 << PROCESS_ID_BITS) - 1;
+
+/// Parse a Discord ID into a `Snowflake`
+///
+/// # Arguments
+/// * `id` - The Discord ID to parse
+pub fn parse(id: u64) -> Snowflake {
+    Snowflake::parse_with_layout(id, &SnowflakeLayout::DISCORD)
+}
+
+/// Convert a `Snowflake` into a Discord ID
+///
+/// # Arguments
+/// * `snowflake` - The snowflake to convert
+pub fn to_id(snowflake: &Snowflake) -> u64 {
+    snowflake.to_id_with_layout(&SnowflakeLayout::DISCORD)
+}
+
+/// Get the internal worker ID of a Discord snowflake
+pub fn worker_id(snowflake: &Snowflake) -> u64 {
+    (snowflake.worker_id >> PROCESS_ID_BITS) & MAX_ID
+}
+
+/// Get the internal process ID of a Discord snowflake
+pub fn process_id(snowflake: &Snowflake) -> u64 {
+    snowflake.worker_id & MAX_ID
+}
+
+/// Get the creation time of a Discord snowflake in milliseconds since the UNIX epoch
+pub fn timestamp_millis(snowflake: &Snowflake) -> u64 {
+    SnowflakeLayout::DISCORD.millis_from_timestamp(snowflake.timestamp)
+}
+
+/// Create a generator for Discord IDs
+///
+/// Use [`to_id`] to convert the generated snowflakes into Discord IDs.
+///
+/// # Arguments
+/// * `worker_id` - The internal worker ID, between 0 and 31
+/// * `process_id` - The internal process ID, between 0 and 31
+///
+/// # Returns
+/// A new `SnowflakeGenerator`, or an error if either ID is larger than 31
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::discord;
+///
+/// let mut generator = discord::generator(1, 2).unwrap();
+///
+/// let snowflake = generator.next();
+///
+/// assert_eq!(discord::worker_id(snowflake), 1);
+/// assert_eq!(discord::process_id(snowflake), 2);
+/// println!("Generated Discord ID: {}", discord::to_id(snowflake));
+/// ```
+#[cfg(feature = "std")]
+pub fn generator(worker_id: u64, process_id: u64) -> Result<SnowflakeGenerator, SnowflakeError> {
+    if worker_id > MAX_ID {
+        return Err(SnowflakeError::WorkerIdOverflow {
+            worker_id,
+            max: MAX_ID,
+        });
+    }
+
+    if process_id > MAX_ID {
+        return Err(SnowflakeError::ProcessIdOverflow {
+            process_id,
+            max: MAX_ID,
+        });
+    }
+
+    SnowflakeGenerator::with_layout(
+        (worker_id << PROCESS_ID_BITS) | process_id,
+        SnowflakeLayout::DISCORD,
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let snowflake = parse(175928847299117063);
+
+        assert_eq!(snowflake.timestamp, 41944705796);
+        assert_eq!(timestamp_millis(&snowflake), 1_462_015_105_796);
+        assert_eq!(worker_id(&snowflake), 1);
+        assert_eq!(process_id(&snowflake), 0);
+        assert_eq!(snowflake.sequence, 7);
+    }
+
+    #[test]
+    fn test_to_id() {
+        assert_eq!(to_id(&parse(175928847299117063)), 175928847299117063);
+    }
+
+    #[test]
+    fn test_generator() {
+        let mut generator = generator(31, 17).unwrap();
+        let snowflake = *generator.next();
+
+        assert_eq!(worker_id(&snowflake), 31);
+        assert_eq!(process_id(&snowflake), 17);
+        assert_eq!(parse(to_id(&snowflake)), snowflake);
+    }
+
+    #[test]
+    fn test_generator_overflow() {
+        assert_eq!(
+            generator(32, 0).unwrap_err(),
+            SnowflakeError::WorkerIdOverflow {
+                worker_id: 32,
+                max: 31
+            }
+        );
+        assert_eq!(
+            generator(0, 32).unwrap_err(),
+            SnowflakeError::ProcessIdOverflow {
+                process_id: 32,
+                max: 31
+            }
+        );
+    }
+}
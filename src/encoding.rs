@@ -0,0 +1,681 @@
+//! Short text encodings for snowflake IDs.
+//!
+//! Every encoding here round-trips any `u64` (or `u128` for ULIDs), and decoding rejects
+//! characters outside the encoding's alphabet instead of guessing.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::SnowflakeError;
+
+/// The base62 alphabet, ordered so that encoded strings of the same length sort like their IDs.
+const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The base36 alphabet. Decoding also accepts uppercase letters.
+const BASE36: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The Crockford base32 alphabet used by ULIDs, which leaves out `I`, `L`, `O` and `U`.
+const CROCKFORD32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The extra symbols Crockford base32 uses for check values 32 to 36.
+const CROCKFORD32_CHECK: &[u8; 5] = b"*~$=U";
+
+/// The number of characters in an encoded ULID.
+const ULID_LENGTH: usize = 26;
+
+/// The number of Crockford base32 characters it takes to hold any `u64`.
+const SORTABLE_LENGTH: usize = 13;
+
+/// The number of base62 characters it takes to hold any `u64`.
+const SORTABLE_BASE62_LENGTH: usize = 11;
+
+/// Encode an ID as a base62 string
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_base62(0), "0");
+/// assert_eq!(encoding::encode_base62(61), "z");
+/// assert_eq!(encoding::encode_base62(62), "10");
+/// ```
+pub fn encode_base62(id: u64) -> String {
+    encode(id, BASE62)
+}
+
+/// Decode a base62 string into an ID
+///
+/// # Arguments
+/// * `encoded` - The base62 string to decode
+///
+/// # Returns
+/// The decoded ID, or an error if the string is empty, contains a character outside the
+/// base62 alphabet or doesn't fit in a `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_base62("10"), Ok(62));
+/// assert!(encoding::decode_base62("1-0").is_err());
+/// ```
+pub fn decode_base62(encoded: &str) -> Result<u64, SnowflakeError> {
+    decode(encoded, 62, |byte| match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'A'..=b'Z' => Some(byte - b'A' + 10),
+        b'a'..=b'z' => Some(byte - b'a' + 36),
+        _ => None,
+    })
+}
+
+/// Encode an ID as a lowercase base36 string
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_base36(35), "z");
+/// assert_eq!(encoding::encode_base36(36), "10");
+/// ```
+pub fn encode_base36(id: u64) -> String {
+    encode(id, BASE36)
+}
+
+/// Decode a base36 string into an ID
+///
+/// Letters are accepted in either case.
+///
+/// # Arguments
+/// * `encoded` - The base36 string to decode
+///
+/// # Returns
+/// The decoded ID, or an error if the string is empty, contains a character outside the
+/// base36 alphabet or doesn't fit in a `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_base36("Z"), Ok(35));
+/// assert!(encoding::decode_base36("_").is_err());
+/// ```
+pub fn decode_base36(encoded: &str) -> Result<u64, SnowflakeError> {
+    decode(encoded, 36, |byte| match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'z' => Some(byte - b'a' + 10),
+        b'A'..=b'Z' => Some(byte - b'A' + 10),
+        _ => None,
+    })
+}
+
+/// Encode an ID as a Crockford base32 string
+///
+/// Crockford base32 leaves out `I`, `L`, `O` and `U`, and decoding ignores case and hyphens,
+/// so IDs survive being read aloud or copied by hand.
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_crockford32(31), "Z");
+/// assert_eq!(encoding::encode_crockford32(32), "10");
+/// ```
+pub fn encode_crockford32(id: u64) -> String {
+    encode(id, CROCKFORD32)
+}
+
+/// Encode an ID as a Crockford base32 string followed by its check symbol
+///
+/// The check symbol is the ID modulo 37, written with the Crockford base32 digits and the
+/// extra symbols `*`, `~`, `$`, `=` and `U`. It catches any single mistyped character and any
+/// two swapped neighbouring characters.
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_crockford32_check(32), "10*");
+/// assert_eq!(encoding::encode_crockford32_check(37), "150");
+/// ```
+pub fn encode_crockford32_check(id: u64) -> String {
+    let mut encoded = encode_crockford32(id);
+    encoded.push(crockford32_check_symbol(id));
+    encoded
+}
+
+/// Decode a Crockford base32 string into an ID
+///
+/// Letters are accepted in either case, `I`, `L` and `O` are read as `1`, `1` and `0`, and
+/// hyphens are ignored.
+///
+/// # Arguments
+/// * `encoded` - The Crockford base32 string to decode
+///
+/// # Returns
+/// The decoded ID, or an error if the string has no digits, contains a character outside the
+/// Crockford base32 alphabet or doesn't fit in a `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_crockford32("10"), Ok(32));
+/// assert_eq!(encoding::decode_crockford32("4z-lo"), encoding::decode_crockford32("4Z10"));
+/// assert!(encoding::decode_crockford32("U").is_err());
+/// ```
+pub fn decode_crockford32(encoded: &str) -> Result<u64, SnowflakeError> {
+    let mut digits = 0;
+
+    let id = encoded
+        .char_indices()
+        .filter(|&(_, character)| character != '-')
+        .try_fold(0u64, |id, (index, character)| {
+            let value = u8::try_from(character)
+                .ok()
+                .and_then(crockford32_digit)
+                .ok_or(SnowflakeError::InvalidCharacter { character, index })?;
+            digits += 1;
+
+            id.checked_mul(32)
+                .and_then(|id| id.checked_add(value as u64))
+                .ok_or(SnowflakeError::EncodedIdTooLarge)
+        })?;
+
+    if digits == 0 {
+        return Err(SnowflakeError::EmptyString);
+    }
+
+    Ok(id)
+}
+
+/// Decode a Crockford base32 string followed by its check symbol into an ID
+///
+/// See [`encode_crockford32_check`] and [`decode_crockford32`].
+///
+/// # Arguments
+/// * `encoded` - The Crockford base32 string and check symbol to decode
+///
+/// # Returns
+/// The decoded ID, or `SnowflakeError::ChecksumMismatch` if the check symbol doesn't match the
+/// ID and any error [`decode_crockford32`] returns
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_crockford32_check("10*"), Ok(32));
+/// assert!(encoding::decode_crockford32_check("11*").is_err());
+/// ```
+pub fn decode_crockford32_check(encoded: &str) -> Result<u64, SnowflakeError> {
+    let (index, found) = encoded
+        .char_indices()
+        .next_back()
+        .ok_or(SnowflakeError::EmptyString)?;
+    let id = decode_crockford32(&encoded[..index])?;
+
+    let check = u8::try_from(found)
+        .ok()
+        .and_then(|byte| match byte.to_ascii_uppercase() {
+            b'U' => Some(36),
+            byte => CROCKFORD32_CHECK[..4]
+                .iter()
+                .position(|&symbol| symbol == byte)
+                .map(|position| position as u8 + 32)
+                .or_else(|| crockford32_digit(byte)),
+        })
+        .ok_or(SnowflakeError::InvalidCharacter {
+            character: found,
+            index,
+        })?;
+
+    if check as u64 != id % 37 {
+        return Err(SnowflakeError::ChecksumMismatch {
+            expected: crockford32_check_symbol(id),
+            found,
+        });
+    }
+
+    Ok(id)
+}
+
+/// Encode an ID as a 13 character Crockford base32 string that sorts like the ID
+///
+/// The string is padded with leading zeros, and the digits are in ASCII order, so comparing two
+/// strings byte by byte gives the same order as comparing the IDs. Use it for keys in systems
+/// that only sort strings, like S3 prefixes or DynamoDB sort keys. The letters are uppercase,
+/// so the order also holds in case-insensitive stores.
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_sortable(32), "0000000000010");
+/// assert!(encoding::encode_sortable(31) < encoding::encode_sortable(32));
+/// assert_eq!(encoding::encode_sortable(u64::MAX), "FZZZZZZZZZZZZ");
+/// ```
+pub fn encode_sortable(id: u64) -> String {
+    encode_padded(id, CROCKFORD32, SORTABLE_LENGTH)
+}
+
+/// Decode a 13 character Crockford base32 string made by [`encode_sortable`] into an ID
+///
+/// Letters are accepted in either case, and `I`, `L` and `O` are read as `1`, `1` and `0`.
+///
+/// # Arguments
+/// * `encoded` - The sortable string to decode
+///
+/// # Returns
+/// The decoded ID, or an error if the string isn't 13 characters long, contains a character
+/// outside the Crockford base32 alphabet or doesn't fit in a `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_sortable("0000000000010"), Ok(32));
+/// assert!(encoding::decode_sortable("10").is_err());
+/// ```
+pub fn decode_sortable(encoded: &str) -> Result<u64, SnowflakeError> {
+    check_length(encoded, SORTABLE_LENGTH)?;
+    decode(encoded, 32, crockford32_digit)
+}
+
+/// Encode an ID as an 11 character base62 string that sorts like the ID
+///
+/// Like [`encode_sortable`], but two characters shorter. Base62 uses both cases, so the order
+/// only holds in stores that compare strings case-sensitively.
+///
+/// # Arguments
+/// * `id` - The ID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_sortable_base62(62), "00000000010");
+/// assert!(encoding::encode_sortable_base62(61) < encoding::encode_sortable_base62(62));
+/// ```
+pub fn encode_sortable_base62(id: u64) -> String {
+    encode_padded(id, BASE62, SORTABLE_BASE62_LENGTH)
+}
+
+/// Decode an 11 character base62 string made by [`encode_sortable_base62`] into an ID
+///
+/// # Arguments
+/// * `encoded` - The sortable string to decode
+///
+/// # Returns
+/// The decoded ID, or an error if the string isn't 11 characters long, contains a character
+/// outside the base62 alphabet or doesn't fit in a `u64`
+pub fn decode_sortable_base62(encoded: &str) -> Result<u64, SnowflakeError> {
+    check_length(encoded, SORTABLE_BASE62_LENGTH)?;
+    decode_base62(encoded)
+}
+
+/// Encode a 128 bit ULID as its 26 character Crockford base32 string
+///
+/// # Arguments
+/// * `ulid` - The ULID to encode
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::encode_ulid(0), "00000000000000000000000000");
+/// assert_eq!(encoding::encode_ulid(u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+/// ```
+pub fn encode_ulid(ulid: u128) -> String {
+    (0..ULID_LENGTH)
+        .rev()
+        .map(|digit| CROCKFORD32[(ulid >> (digit * 5)) as usize & 0x1F] as char)
+        .collect()
+}
+
+/// Decode a 26 character Crockford base32 string into a 128 bit ULID
+///
+/// Letters are accepted in either case, and `I`, `L` and `O` are read as `1`, `1` and `0`.
+///
+/// # Arguments
+/// * `encoded` - The ULID string to decode
+///
+/// # Returns
+/// The decoded ULID, or an error if the string isn't 26 characters long, contains a character
+/// outside the Crockford base32 alphabet or doesn't fit in 128 bits
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::encoding;
+///
+/// assert_eq!(encoding::decode_ulid("0000000000000000000000000z"), Ok(31));
+/// assert!(encoding::decode_ulid("8ZZZZZZZZZZZZZZZZZZZZZZZZZ").is_err());
+/// ```
+pub fn decode_ulid(encoded: &str) -> Result<u128, SnowflakeError> {
+    check_length(encoded, ULID_LENGTH)?;
+
+    let ulid = encoded
+        .char_indices()
+        .try_fold(0u128, |ulid, (index, character)| {
+            let value = u8::try_from(character)
+                .ok()
+                .and_then(crockford32_digit)
+                .ok_or(SnowflakeError::InvalidCharacter { character, index })?;
+
+            Ok::<u128, SnowflakeError>((ulid << 5) | value as u128)
+        })?;
+
+    // 26 digits hold 130 bits, so the first digit can only use its lowest 3 bits
+    if encoded.as_bytes()[0] > b'7' {
+        return Err(SnowflakeError::EncodedIdTooLarge);
+    }
+
+    Ok(ulid)
+}
+
+/// Look up the value of a Crockford base32 digit
+fn crockford32_digit(byte: u8) -> Option<u8> {
+    match byte.to_ascii_uppercase() {
+        b'O' => Some(0),
+        b'I' | b'L' => Some(1),
+        byte => CROCKFORD32
+            .iter()
+            .position(|&digit| digit == byte)
+            .map(|value| value as u8),
+    }
+}
+
+/// Get the Crockford base32 check symbol of an ID
+fn crockford32_check_symbol(id: u64) -> char {
+    let check = (id % 37) as usize;
+
+    if check < 32 {
+        CROCKFORD32[check] as char
+    } else {
+        CROCKFORD32_CHECK[check - 32] as char
+    }
+}
+
+/// Check that `encoded` is exactly `expected` bytes long
+fn check_length(encoded: &str, expected: usize) -> Result<(), SnowflakeError> {
+    if encoded.is_empty() {
+        return Err(SnowflakeError::EmptyString);
+    }
+
+    if encoded.len() != expected {
+        return Err(SnowflakeError::InvalidLength {
+            length: encoded.len(),
+            expected,
+        });
+    }
+
+    Ok(())
+}
+
+/// Encode `id` using the digits in `alphabet`, padded with leading zero digits to `width`
+fn encode_padded(id: u64, alphabet: &[u8], width: usize) -> String {
+    let digits = encode(id, alphabet);
+    let mut padded = String::with_capacity(width.max(digits.len()));
+
+    for _ in digits.len()..width {
+        padded.push(alphabet[0] as char);
+    }
+    padded.push_str(&digits);
+
+    padded
+}
+
+/// Encode `id` using the digits in `alphabet`
+fn encode(mut id: u64, alphabet: &[u8]) -> String {
+    let base = alphabet.len() as u64;
+    let mut digits = Vec::new();
+
+    loop {
+        digits.push(alphabet[(id % base) as usize]);
+        id /= base;
+        if id == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().map(|&digit| digit as char).collect()
+}
+
+/// Decode `encoded` in the given base, using `digit` to look up the value of each byte
+fn decode(
+    encoded: &str,
+    base: u64,
+    digit: impl Fn(u8) -> Option<u8>,
+) -> Result<u64, SnowflakeError> {
+    if encoded.is_empty() {
+        return Err(SnowflakeError::EmptyString);
+    }
+
+    encoded
+        .char_indices()
+        .try_fold(0u64, |id, (index, character)| {
+            let value = u8::try_from(character)
+                .ok()
+                .and_then(&digit)
+                .ok_or(SnowflakeError::InvalidCharacter { character, index })?;
+
+            id.checked_mul(base)
+                .and_then(|id| id.checked_add(value as u64))
+                .ok_or(SnowflakeError::EncodedIdTooLarge)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDS: [u64; 6] = [0, 1, 61, 62, 175928847299117063, u64::MAX];
+
+    #[test]
+    fn test_base62_round_trip() {
+        for id in IDS {
+            assert_eq!(decode_base62(&encode_base62(id)), Ok(id));
+        }
+    }
+
+    #[test]
+    fn test_base36_round_trip() {
+        for id in IDS {
+            assert_eq!(decode_base36(&encode_base36(id)), Ok(id));
+            assert_eq!(decode_base36(&encode_base36(id).to_uppercase()), Ok(id));
+        }
+    }
+
+    #[test]
+    fn test_max_values() {
+        assert_eq!(encode_base62(u64::MAX), "LygHa16AHYF");
+        assert_eq!(encode_base36(u64::MAX), "3w5e11264sgsf");
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(
+            decode_base62("ab-c"),
+            Err(SnowflakeError::InvalidCharacter {
+                character: '-',
+                index: 2
+            })
+        );
+        assert_eq!(
+            decode_base36("1é"),
+            Err(SnowflakeError::InvalidCharacter {
+                character: 'é',
+                index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(decode_base62(""), Err(SnowflakeError::EmptyString));
+        assert_eq!(decode_base36(""), Err(SnowflakeError::EmptyString));
+    }
+
+    #[test]
+    fn test_crockford32() {
+        for id in IDS {
+            let encoded = encode_crockford32(id);
+            assert_eq!(decode_crockford32(&encoded), Ok(id));
+            assert_eq!(decode_crockford32(&encoded.to_lowercase()), Ok(id));
+            assert_eq!(
+                decode_crockford32_check(&encode_crockford32_check(id)),
+                Ok(id)
+            );
+        }
+
+        assert_eq!(encode_crockford32(u64::MAX), "FZZZZZZZZZZZZ");
+        assert_eq!(decode_crockford32("0-0-1"), Ok(1));
+        assert_eq!(decode_crockford32("oIl"), decode_crockford32("011"));
+        assert_eq!(decode_crockford32("-"), Err(SnowflakeError::EmptyString));
+        assert_eq!(
+            decode_crockford32("G000000000000"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_crockford32_check() {
+        // 36 uses the last extra check symbol, which is also read in lowercase
+        assert_eq!(encode_crockford32_check(36), "14U");
+        assert_eq!(decode_crockford32_check("14u"), Ok(36));
+        assert_eq!(decode_crockford32_check("1-4-U"), Ok(36));
+
+        // Swapping two neighbouring digits is caught
+        assert_eq!(
+            decode_crockford32_check("41U"),
+            Err(SnowflakeError::ChecksumMismatch {
+                expected: 'J',
+                found: 'U'
+            })
+        );
+        assert_eq!(
+            decode_crockford32_check("14#"),
+            Err(SnowflakeError::InvalidCharacter {
+                character: '#',
+                index: 2
+            })
+        );
+        assert_eq!(
+            decode_crockford32_check("U"),
+            Err(SnowflakeError::EmptyString)
+        );
+        assert_eq!(
+            decode_crockford32_check(""),
+            Err(SnowflakeError::EmptyString)
+        );
+    }
+
+    #[test]
+    fn test_sortable() {
+        let mut previous = (String::new(), String::new());
+
+        for id in IDS {
+            let encoded = (encode_sortable(id), encode_sortable_base62(id));
+            assert_eq!(encoded.0.len(), 13);
+            assert_eq!(encoded.1.len(), 11);
+            assert_eq!(decode_sortable(&encoded.0), Ok(id));
+            assert_eq!(decode_sortable_base62(&encoded.1), Ok(id));
+
+            // IDs are listed in ascending order
+            assert!(encoded > previous);
+            previous = encoded;
+        }
+    }
+
+    #[test]
+    fn test_invalid_sortable() {
+        assert_eq!(decode_sortable(""), Err(SnowflakeError::EmptyString));
+        assert_eq!(
+            decode_sortable("000000000001"),
+            Err(SnowflakeError::InvalidLength {
+                length: 12,
+                expected: 13
+            })
+        );
+        assert_eq!(
+            decode_sortable("G000000000000"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+        assert_eq!(
+            decode_sortable("000000-000001"),
+            Err(SnowflakeError::InvalidCharacter {
+                character: '-',
+                index: 6
+            })
+        );
+        assert_eq!(
+            decode_sortable_base62("LygHa16AHYG"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_ulid() {
+        assert_eq!(
+            decode_ulid("01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap() >> 80,
+            1_469_922_850_259
+        );
+
+        for ulid in [0, 1, 175928847299117063, u128::MAX] {
+            assert_eq!(decode_ulid(&encode_ulid(ulid)), Ok(ulid));
+        }
+
+        assert_eq!(
+            decode_ulid("0ILO0000000000000000000000"),
+            decode_ulid("01100000000000000000000000")
+        );
+    }
+
+    #[test]
+    fn test_invalid_ulid() {
+        assert_eq!(decode_ulid(""), Err(SnowflakeError::EmptyString));
+        assert_eq!(
+            decode_ulid("01ARZ3NDEK"),
+            Err(SnowflakeError::InvalidLength {
+                length: 10,
+                expected: 26
+            })
+        );
+        assert_eq!(
+            decode_ulid("01ARZ3NDEKTSV4RRFFQ69G5FAU"),
+            Err(SnowflakeError::InvalidCharacter {
+                character: 'U',
+                index: 25
+            })
+        );
+        assert_eq!(
+            decode_ulid("80000000000000000000000000"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            decode_base62("LygHa16AHYG"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+        assert_eq!(
+            decode_base36("3w5e11264sgsg"),
+            Err(SnowflakeError::EncodedIdTooLarge)
+        );
+    }
+}
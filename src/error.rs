@@ -0,0 +1,436 @@
+use alloc::string::String;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// An error returned when a snowflake can't be created or represented.
+///
+/// New variants may be added in minor releases, so matches on it need a wildcard arm.
+#[non_exhaustive]
+pub enum SnowflakeError {
+    /// The worker ID doesn't fit in the bits the layout gives it.
+    WorkerIdOverflow {
+        /// The worker ID that was requested
+        worker_id: u64,
+        /// The largest worker ID the layout can hold
+        max: u64,
+    },
+    /// The process ID doesn't fit in the bits the layout gives it.
+    ProcessIdOverflow {
+        /// The process ID that was requested
+        process_id: u64,
+        /// The largest process ID the layout can hold
+        max: u64,
+    },
+    /// The datacenter ID doesn't fit in the bits the layout gives it.
+    DatacenterIdOverflow {
+        /// The datacenter ID that was requested
+        datacenter_id: u64,
+        /// The largest datacenter ID the layout can hold
+        max: u64,
+    },
+    /// The machine ID doesn't fit in the bits the layout gives it.
+    MachineIdOverflow {
+        /// The machine ID that was requested
+        machine_id: u64,
+        /// The largest machine ID the layout can hold
+        max: u64,
+    },
+    /// The sequence number doesn't fit in the bits the layout gives it.
+    SequenceOverflow {
+        /// The sequence number that was requested
+        sequence: u64,
+        /// The largest sequence number the layout can hold
+        max: u64,
+    },
+    /// The extra value doesn't fit in the bits the layout gives it.
+    ExtraOverflow {
+        /// The extra value that was requested
+        extra: u64,
+        /// The largest extra value the layout can hold
+        max: u64,
+    },
+    /// Every sequence number of the current tick was used, and the generator is configured to
+    /// fail instead of waiting for the next tick.
+    SequenceExhausted {
+        /// The timestamp whose sequence numbers ran out
+        timestamp: u64,
+    },
+    /// The generator was asked for snowflakes faster than its rate limit allows, and is
+    /// configured to fail instead of waiting.
+    RateLimited {
+        /// The most snowflakes the generator hands out per second
+        max_ids_per_second: u64,
+        /// How long until the rate limit lets the next snowflake through, in microseconds
+        retry_after_micros: u64,
+    },
+    /// A rate limit was set higher than the generator can enforce.
+    RateLimitTooHigh {
+        /// The rate limit that was requested
+        max_ids_per_second: u64,
+        /// The highest rate limit a generator can enforce
+        max: u64,
+    },
+    /// Every thread ID of a thread-local generator is held by a live thread.
+    TooManyThreads {
+        /// The largest number of threads that can generate at the same time
+        max: u64,
+    },
+    /// A snowflake was created further in the future than the validation rules allow.
+    TimestampInFuture {
+        /// The timestamp of the snowflake
+        timestamp: u64,
+        /// The latest timestamp the rules accept
+        max: u64,
+    },
+    /// A snowflake was created earlier than the validation rules allow.
+    TimestampTooOld {
+        /// The timestamp of the snowflake
+        timestamp: u64,
+        /// The earliest timestamp the rules accept
+        min: u64,
+    },
+    /// A snowflake's worker ID isn't one the validation rules allow.
+    WorkerIdNotAllowed {
+        /// The worker ID of the snowflake
+        worker_id: u64,
+    },
+    /// The timestamp doesn't fit in the bits the target format gives it.
+    TimestampOverflow {
+        /// The timestamp that was requested
+        timestamp: u64,
+        /// The largest timestamp the target format can hold
+        max: u64,
+    },
+    /// The ID uses the sign bit, so it can't be represented as an `i64`.
+    IdExceedsI64 {
+        /// The ID that was requested
+        id: u64,
+    },
+    /// The ID was zero, which a `NonZeroU64` can't hold.
+    ZeroId,
+    /// A signed ID was negative.
+    NegativeId {
+        /// The ID that was given
+        id: i64,
+    },
+    /// The system clock is set to a time before the epoch.
+    ClockBeforeEpoch,
+    /// The system clock went backwards since the last snowflake was generated.
+    ClockMovedBackwards {
+        /// The timestamp of the last snowflake
+        last_timestamp: u64,
+        /// The current timestamp
+        timestamp: u64,
+    },
+    /// The wall clock drifted from the monotonic clock by more than a
+    /// [`ClockMonitor`](crate::ClockMonitor)'s threshold.
+    ClockDrift {
+        /// The time the monotonic clock says it should be, in milliseconds since the UNIX epoch
+        expected_millis: u64,
+        /// The time the wall clock reported, in milliseconds since the UNIX epoch
+        actual_millis: u64,
+    },
+    /// The clock is further ahead of a [`FutureGuard`](crate::FutureGuard)'s reference clock
+    /// than the guard allows.
+    ClockAhead {
+        /// The time the clock reported, in milliseconds since the UNIX epoch
+        clock_millis: u64,
+        /// The time the reference clock reported, in milliseconds since the UNIX epoch
+        reference_millis: u64,
+    },
+    /// The ID has bits set that its layout doesn't use.
+    ReservedBitsSet {
+        /// The ID that was given
+        id: u64,
+    },
+    /// A string couldn't be parsed as a decimal snowflake ID.
+    ParseInt(core::num::ParseIntError),
+    /// An encoded ID was an empty string.
+    EmptyString,
+    /// An encoded ID had the wrong number of characters for its encoding.
+    InvalidLength {
+        /// The number of bytes in the string
+        length: usize,
+        /// The number of characters the encoding needs
+        expected: usize,
+    },
+    /// An encoded ID contained a character outside the encoding's alphabet.
+    InvalidCharacter {
+        /// The invalid character
+        character: char,
+        /// The byte index of the character in the string
+        index: usize,
+    },
+    /// An encoded ID was too large to fit in a `u64`.
+    EncodedIdTooLarge,
+    /// The check symbol of an encoded ID didn't match the rest of the ID.
+    ChecksumMismatch {
+        /// The check symbol the rest of the ID has
+        expected: char,
+        /// The check symbol in the string
+        found: char,
+    },
+    /// A UUID wasn't a version 7 UUID.
+    InvalidUuid,
+    /// A layout can't be converted to or from a UUID or ULID without losing bits.
+    UnsupportedLayout(&'static str),
+    /// A key didn't start with the expected prefix and separator.
+    KeyPrefixMismatch,
+    /// A slice to write results to had a different length from the slice of inputs.
+    LengthMismatch {
+        /// The length of the output slice
+        length: usize,
+        /// The length of the input slice
+        expected: usize,
+    },
+    /// A buffer was too small for the bytes written to it.
+    BufferTooSmall {
+        /// The number of bytes needed
+        length: usize,
+        /// The size of the buffer
+        capacity: usize,
+    },
+    /// A JSON object couldn't be read as a snowflake.
+    InvalidJson(String),
+    /// A worker ID source couldn't determine a worker ID on this machine.
+    WorkerIdUnavailable(String),
+    /// The generator of the `global` module was used before it was initialized.
+    NotInitialized,
+    /// The generator of the `global` module was initialized more than once.
+    AlreadyInitialized {
+        /// The worker ID the global generator was initialized with first
+        worker_id: u64,
+    },
+}
+
+impl core::fmt::Display for SnowflakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            SnowflakeError::WorkerIdOverflow { worker_id, max } => {
+                write!(f, "worker ID {} exceeds the maximum of {}", worker_id, max)
+            }
+            SnowflakeError::ProcessIdOverflow { process_id, max } => {
+                write!(
+                    f,
+                    "process ID {} exceeds the maximum of {}",
+                    process_id, max
+                )
+            }
+            SnowflakeError::DatacenterIdOverflow { datacenter_id, max } => {
+                write!(
+                    f,
+                    "datacenter ID {} exceeds the maximum of {}",
+                    datacenter_id, max
+                )
+            }
+            SnowflakeError::MachineIdOverflow { machine_id, max } => {
+                write!(
+                    f,
+                    "machine ID {} exceeds the maximum of {}",
+                    machine_id, max
+                )
+            }
+            SnowflakeError::SequenceOverflow { sequence, max } => {
+                write!(f, "sequence {} exceeds the maximum of {}", sequence, max)
+            }
+            SnowflakeError::ExtraOverflow { extra, max } => {
+                write!(f, "extra value {} exceeds the maximum of {}", extra, max)
+            }
+            SnowflakeError::SequenceExhausted { timestamp } => {
+                write!(
+                    f,
+                    "sequence numbers for timestamp {} are exhausted",
+                    timestamp
+                )
+            }
+            SnowflakeError::RateLimited {
+                max_ids_per_second,
+                retry_after_micros,
+            } => write!(
+                f,
+                "rate limit of {} IDs per second exceeded, retry in {} microseconds",
+                max_ids_per_second, retry_after_micros
+            ),
+            SnowflakeError::RateLimitTooHigh {
+                max_ids_per_second,
+                max,
+            } => write!(
+                f,
+                "rate limit of {} IDs per second exceeds the maximum of {}",
+                max_ids_per_second, max
+            ),
+            SnowflakeError::TooManyThreads { max } => {
+                write!(f, "more than {} threads are generating snowflakes", max)
+            }
+            SnowflakeError::TimestampInFuture { timestamp, max } => {
+                write!(
+                    f,
+                    "timestamp {} is after the latest allowed {}",
+                    timestamp, max
+                )
+            }
+            SnowflakeError::TimestampTooOld { timestamp, min } => {
+                write!(
+                    f,
+                    "timestamp {} is before the earliest allowed {}",
+                    timestamp, min
+                )
+            }
+            SnowflakeError::WorkerIdNotAllowed { worker_id } => {
+                write!(f, "worker ID {} is not allowed", worker_id)
+            }
+            SnowflakeError::TimestampOverflow { timestamp, max } => {
+                write!(f, "timestamp {} exceeds the maximum of {}", timestamp, max)
+            }
+            SnowflakeError::IdExceedsI64 { id } => {
+                write!(f, "snowflake ID {} doesn't fit in an i64", id)
+            }
+            SnowflakeError::ZeroId => write!(f, "ID is zero"),
+            SnowflakeError::NegativeId { id } => write!(f, "snowflake ID {} is negative", id),
+            SnowflakeError::ClockBeforeEpoch => write!(f, "system clock is set before the epoch"),
+            SnowflakeError::ClockMovedBackwards {
+                last_timestamp,
+                timestamp,
+            } => write!(
+                f,
+                "clock moved backwards from {} to {}",
+                last_timestamp, timestamp
+            ),
+            SnowflakeError::ClockDrift {
+                expected_millis,
+                actual_millis,
+            } => write!(
+                f,
+                "clock drifted to {} ms when {} ms was expected",
+                actual_millis, expected_millis
+            ),
+            SnowflakeError::ClockAhead {
+                clock_millis,
+                reference_millis,
+            } => write!(
+                f,
+                "clock is at {} ms, ahead of the reference clock at {} ms",
+                clock_millis, reference_millis
+            ),
+            SnowflakeError::ReservedBitsSet { id } => {
+                write!(f, "snowflake ID {} has reserved bits set", id)
+            }
+            SnowflakeError::ParseInt(err) => write!(f, "invalid snowflake ID: {}", err),
+            SnowflakeError::EmptyString => write!(f, "encoded snowflake ID is empty"),
+            SnowflakeError::InvalidLength { length, expected } => {
+                write!(
+                    f,
+                    "encoded snowflake ID is {} characters long, expected {}",
+                    length, expected
+                )
+            }
+            SnowflakeError::InvalidCharacter { character, index } => {
+                write!(f, "invalid character {:?} at index {}", character, index)
+            }
+            SnowflakeError::EncodedIdTooLarge => {
+                write!(f, "encoded snowflake ID is too large to fit in 64 bits")
+            }
+            SnowflakeError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "check symbol {:?} doesn't match the expected {:?}",
+                found, expected
+            ),
+            SnowflakeError::InvalidUuid => write!(f, "UUID is not a version 7 UUID"),
+            SnowflakeError::UnsupportedLayout(reason) => {
+                write!(f, "layout can't be converted losslessly: {}", reason)
+            }
+            SnowflakeError::KeyPrefixMismatch => {
+                write!(f, "key doesn't start with the expected prefix")
+            }
+            SnowflakeError::LengthMismatch { length, expected } => write!(
+                f,
+                "output slice length {} doesn't match input length {}",
+                length, expected
+            ),
+            SnowflakeError::BufferTooSmall { length, capacity } => write!(
+                f,
+                "{} bytes don't fit in a buffer of {} bytes",
+                length, capacity
+            ),
+            SnowflakeError::InvalidJson(reason) => {
+                write!(f, "invalid snowflake JSON: {}", reason)
+            }
+            SnowflakeError::WorkerIdUnavailable(reason) => {
+                write!(f, "couldn't determine a worker ID: {}", reason)
+            }
+            SnowflakeError::NotInitialized => {
+                write!(f, "the global generator is not initialized")
+            }
+            SnowflakeError::AlreadyInitialized { worker_id } => write!(
+                f,
+                "the global generator is already initialized with worker ID {}",
+                worker_id
+            ),
+        }
+    }
+}
+
+impl core::error::Error for SnowflakeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            SnowflakeError::ParseInt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<core::num::ParseIntError> for SnowflakeError {
+    fn from(err: core::num::ParseIntError) -> SnowflakeError {
+        SnowflakeError::ParseInt(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            SnowflakeError::RateLimited {
+                max_ids_per_second: 100,
+                retry_after_micros: 2_500
+            }
+            .to_string(),
+            "rate limit of 100 IDs per second exceeded, retry in 2500 microseconds"
+        );
+        assert_eq!(
+            SnowflakeError::WorkerIdOverflow {
+                worker_id: 1025,
+                max: 1023
+            }
+            .to_string(),
+            "worker ID 1025 exceeds the maximum of 1023"
+        );
+        assert_eq!(
+            SnowflakeError::SequenceOverflow {
+                sequence: 4096,
+                max: 4095
+            }
+            .to_string(),
+            "sequence 4096 exceeds the maximum of 4095"
+        );
+        assert_eq!(
+            SnowflakeError::LengthMismatch {
+                length: 1,
+                expected: 2
+            }
+            .to_string(),
+            "output slice length 1 doesn't match input length 2"
+        );
+        assert_eq!(
+            SnowflakeError::ClockBeforeEpoch.to_string(),
+            "system clock is set before the epoch"
+        );
+        assert_eq!(
+            SnowflakeError::from("abc".parse::<u64>().unwrap_err()).to_string(),
+            "invalid snowflake ID: invalid digit found in string"
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use core::fmt;
+
+use crate::layout::{mask, shr};
+use crate::{Snowflake, SnowflakeLayout};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// A human-readable breakdown of a snowflake ID, for debugging and support.
+///
+/// Its `Display` shows each field of the ID with the bits it takes up, in binary and decoded,
+/// along with the UTC time the ID was created.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{Snowflake, SnowflakeLayout};
+///
+/// let explanation = Snowflake::explain_with_layout(175928847299117063, &SnowflakeLayout::DISCORD);
+///
+/// assert_eq!(
+///     explanation.to_string(),
+///     "ID 175928847299117063
+///   bits 63..22  timestamp   000000100111000100000110010110101100000100  41944705796 (2016-04-30T11:18:25.796Z)
+///   bits 21..17  datacenter  00001  1
+///   bits 16..12  machine     00000  0
+///   bits 11..0   sequence    000000000111  7
+/// worker 32, sequence 7, created 2016-04-30T11:18:25.796Z"
+/// );
+/// ```
+pub struct Explanation {
+    pub(crate) id: u64,
+    pub(crate) snowflake: Snowflake,
+    pub(crate) layout: SnowflakeLayout,
+}
+
+impl Explanation {
+    /// The ID being explained
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The ID decoded with the layout
+    pub const fn snowflake(&self) -> Snowflake {
+        self.snowflake
+    }
+
+    /// The layout the ID was decoded with
+    pub const fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// The time the ID was created, in milliseconds since the UNIX epoch
+    pub const fn created_at_millis(&self) -> u64 {
+        self.layout.millis_from_timestamp(self.snowflake.timestamp)
+    }
+
+    /// Write one field of the ID as its bit range, label, binary and decoded value
+    fn field(
+        &self,
+        f: &mut fmt::Formatter,
+        label: &str,
+        shift: u32,
+        bits: u8,
+        value: fmt::Arguments,
+    ) -> fmt::Result {
+        if bits == 0 {
+            return Ok(());
+        }
+
+        let high = shift + bits as u32 - 1;
+        let binary = shr(self.id, shift) & mask(bits);
+        writeln!(
+            f,
+            "  bits {:>2}..{:<2}  {:<10}  {:0width$b}  {}",
+            high,
+            shift,
+            label,
+            binary,
+            value,
+            width = bits as usize
+        )
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let layout = &self.layout;
+        let snowflake = &self.snowflake;
+        let millis = self.created_at_millis();
+        writeln!(f, "ID {}", self.id)?;
+
+        let used = layout.timestamp_shift() + layout.timestamp_bits() as u32;
+        if used < 64 {
+            self.field(
+                f,
+                "unused",
+                used,
+                (64 - used) as u8,
+                format_args!("{}", shr(self.id, used)),
+            )?;
+        }
+        self.field(
+            f,
+            "timestamp",
+            layout.timestamp_shift(),
+            layout.timestamp_bits(),
+            format_args!("{} ({})", snowflake.timestamp, Utc(millis)),
+        )?;
+        self.field(
+            f,
+            "extra",
+            layout.extra_shift(),
+            layout.extra_bits(),
+            format_args!("{}", snowflake.extra),
+        )?;
+
+        let worker_id = |f: &mut fmt::Formatter| {
+            if layout.datacenter_id_bits() == 0 {
+                return self.field(
+                    f,
+                    "worker ID",
+                    layout.worker_id_shift(),
+                    layout.worker_id_bits(),
+                    format_args!("{}", snowflake.worker_id),
+                );
+            }
+
+            let machine_id_shift = layout.worker_id_shift();
+            self.field(
+                f,
+                "datacenter",
+                machine_id_shift + layout.machine_id_bits() as u32,
+                layout.datacenter_id_bits(),
+                format_args!("{}", snowflake.datacenter_id_with_layout(layout)),
+            )?;
+            self.field(
+                f,
+                "machine",
+                machine_id_shift,
+                layout.machine_id_bits(),
+                format_args!("{}", snowflake.machine_id_with_layout(layout)),
+            )
+        };
+        let sequence = |f: &mut fmt::Formatter| {
+            self.field(
+                f,
+                "sequence",
+                layout.sequence_shift(),
+                layout.sequence_bits(),
+                format_args!("{}", snowflake.sequence),
+            )
+        };
+
+        if layout.worker_id_shift() > layout.sequence_shift() {
+            worker_id(f)?;
+            sequence(f)?;
+        } else {
+            sequence(f)?;
+            worker_id(f)?;
+        }
+
+        if layout.worker_id_bits() > 0 {
+            write!(f, "worker {}, ", snowflake.worker_id)?;
+        }
+        write!(
+            f,
+            "sequence {}, created {}",
+            snowflake.sequence,
+            Utc(millis)
+        )
+    }
+}
+
+impl Snowflake {
+    /// Break down an ID with [`SnowflakeLayout::DEFAULT`] for a human to read
+    ///
+    /// See [`Explanation`].
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID to explain
+    pub const fn explain(id: u64) -> Explanation {
+        Snowflake::explain_with_layout(id, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Break down an ID with a custom layout for a human to read
+    ///
+    /// See [`Explanation`].
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID to explain
+    /// * `layout` - The bit layout of the ID
+    pub const fn explain_with_layout(id: u64, layout: &SnowflakeLayout) -> Explanation {
+        Explanation {
+            id,
+            snowflake: Snowflake::parse_with_layout(id, layout),
+            layout: *layout,
+        }
+    }
+}
+
+/// Milliseconds since the UNIX epoch, displayed as a UTC time like `2024-01-31T12:00:00.123Z`
+pub(crate) struct Utc(pub(crate) u64);
+
+impl fmt::Display for Utc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.0 / 1_000;
+        let (year, month, day) = civil_from_days(seconds / 86_400);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            seconds / 3_600 % 24,
+            seconds / 60 % 60,
+            seconds % 60,
+            self.0 % 1_000
+        )
+    }
+}
+
+/// Get the date that is a number of days after 1970-01-01, with Howard Hinnant's algorithm
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_explain_default() {
+        let explanation = Snowflake::explain(1 << 63 | 1_700_000_000 << 22 | 1 << 12 | 2);
+
+        assert_eq!(explanation.snowflake().worker_id(), 1);
+        assert_eq!(explanation.created_at_millis(), 1_700_000_000_000);
+        assert_eq!(
+            explanation.to_string(),
+            "ID 9230502353654779906
+  bits 63..63  unused      1  1
+  bits 62..22  timestamp   00000000001100101010100111111000100000000  1700000000 (2023-11-14T22:13:20.000Z)
+  bits 21..17  datacenter  00000  0
+  bits 16..12  machine     00001  1
+  bits 11..0   sequence    000000000010  2
+worker 1, sequence 2, created 2023-11-14T22:13:20.000Z"
+        );
+    }
+
+    #[test]
+    fn test_explain_sequence_first() {
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let explanation = Snowflake::explain_with_layout(3 << 16 | 5, &layout);
+
+        assert_eq!(
+            explanation.to_string().lines().skip(1).collect::<alloc::vec::Vec<_>>(),
+            [
+                "  bits 63..63  unused      0  0",
+                "  bits 62..24  timestamp   000000000000000000000000000000000000000  0 (2014-09-01T00:00:00.000Z)",
+                "  bits 23..16  sequence    00000011  3",
+                "  bits 15..0   worker ID   0000000000000101  5",
+                "worker 5, sequence 3, created 2014-09-01T00:00:00.000Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_without_worker_id() {
+        let layout = SnowflakeLayout::SINGLE_PROCESS;
+        let explanation = Snowflake::explain_with_layout(7 << 22 | 5, &layout);
+
+        assert_eq!(
+            explanation.to_string().lines().skip(2).collect::<alloc::vec::Vec<_>>(),
+            [
+                "  bits 62..22  timestamp   00000000000000000000000000000000000000111  7 (2024-01-01T00:00:00.007Z)",
+                "  bits 21..0   sequence    0000000000000000000101  5",
+                "sequence 5, created 2024-01-01T00:00:00.007Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_extra() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .datacenter_id_bits(0)
+            .build()
+            .unwrap();
+        let explanation = Snowflake::explain_with_layout(3 << 22 | 1 << 12, &layout);
+
+        assert_eq!(
+            explanation.to_string().lines().nth(3),
+            Some("  bits 23..22  extra       11  3")
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
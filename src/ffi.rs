@@ -0,0 +1,108 @@
+//! C bindings for generating and parsing snowflakes.
+//!
+//! The declarations are in `include/rusty_snowflake.h`. Build a library to link against with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+//!
+//! A generator is an opaque pointer created by [`snowflake_generator_new`] and freed by
+//! [`snowflake_generator_free`]. Snowflakes are passed by value as [`Snowflake`], which has a C
+//! compatible layout.
+
+use crate::{Snowflake, SnowflakeGenerator};
+
+/// Create a generator with the given worker ID and [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT)
+///
+/// # Returns
+/// A generator to free with [`snowflake_generator_free`], or null if the worker ID doesn't fit
+/// in 10 bits or the system clock is set before the UNIX epoch
+#[no_mangle]
+pub extern "C" fn snowflake_generator_new(worker_id: u64) -> *mut SnowflakeGenerator {
+    match SnowflakeGenerator::try_new(worker_id) {
+        Ok(generator) => Box::into_raw(Box::new(generator)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free a generator created by [`snowflake_generator_new`]
+///
+/// # Safety
+/// `generator` must be null or a pointer returned by [`snowflake_generator_new`] that hasn't
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn snowflake_generator_free(generator: *mut SnowflakeGenerator) {
+    if !generator.is_null() {
+        drop(Box::from_raw(generator));
+    }
+}
+
+/// Generate the next snowflake ID
+///
+/// # Returns
+/// The ID, or 0 if `generator` is null or the generator failed
+///
+/// # Safety
+/// `generator` must be null or a live pointer returned by [`snowflake_generator_new`], and must
+/// not be used by another thread at the same time.
+#[no_mangle]
+pub unsafe extern "C" fn snowflake_next(generator: *mut SnowflakeGenerator) -> u64 {
+    match generator.as_mut() {
+        Some(generator) => generator
+            .try_next()
+            .map(|snowflake| snowflake.to_id())
+            .unwrap_or(0),
+        None => 0,
+    }
+}
+
+/// Parse a snowflake ID into its parts
+#[no_mangle]
+pub extern "C" fn snowflake_parse(id: u64) -> Snowflake {
+    Snowflake::parse(id)
+}
+
+/// Convert a snowflake's parts into a snowflake ID
+#[no_mangle]
+pub extern "C" fn snowflake_to_id(snowflake: Snowflake) -> u64 {
+    snowflake.to_id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generator() {
+        let generator = snowflake_generator_new(42);
+        assert!(!generator.is_null());
+
+        unsafe {
+            let first = snowflake_next(generator);
+            let second = snowflake_next(generator);
+
+            assert!(second > first);
+            assert_eq!(snowflake_parse(first).worker_id, 42);
+
+            snowflake_generator_free(generator);
+        }
+    }
+
+    #[test]
+    fn test_invalid_worker_id() {
+        assert!(snowflake_generator_new(1024).is_null());
+
+        unsafe {
+            assert_eq!(snowflake_next(std::ptr::null_mut()), 0);
+            snowflake_generator_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let id = (1 << 22) | (2 << 12) | 3;
+        let snowflake = snowflake_parse(id);
+
+        assert_eq!(snowflake.timestamp, 1);
+        assert_eq!(snowflake.worker_id, 2);
+        assert_eq!(snowflake.sequence, 3);
+        assert_eq!(snowflake_to_id(snowflake), id);
+    }
+}
@@ -1,10 +1,40 @@
-use super::Snowflake;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Instant;
+
+use crate::layout::shl;
+
+use super::{
+    Clock, ClockBackwardsPolicy, ClockMonitor, FutureGuard, GeneratorOptions, GeneratorState,
+    GeneratorStats, OverflowStrategy, RateLimitPolicy, Snowflake, SnowflakeError, SnowflakeLayout,
+    SystemClock, WaitStrategy,
+};
+
+/// The length of the rate limiter's bucket, in nanoseconds.
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 /// A snowflake generator that can be used to generate snowflake IDs.
-pub struct SnowflakeGenerator {
+///
+/// Timestamps are read from a [`Clock`], which is the system clock unless the generator was
+/// created with [`SnowflakeGenerator::with_clock`], and counted in the epoch and tick of the
+/// generator's [`SnowflakeLayout`].
+pub struct SnowflakeGenerator<C: Clock = SystemClock> {
     /// The last snowflake ID generated by the generator.
     pub last_snowflake: Snowflake,
+    /// The options controlling how the generator behaves.
+    options: GeneratorOptions,
+    /// The clock timestamps are read from.
+    clock: C,
+    /// The bit layout of the snowflakes generated.
+    layout: SnowflakeLayout,
+    /// What the generator has done since it was created.
+    stats: GeneratorStats,
+    /// The state of the random number generator picking sequence offsets, seeded the first time
+    /// it's used.
+    rng: u64,
+    /// When the rate limiter's bucket will be empty, in nanoseconds since the UNIX epoch.
+    rate_limit_drained_at: u128,
 }
 
 impl SnowflakeGenerator {
@@ -16,20 +46,38 @@ impl SnowflakeGenerator {
     /// # Returns
     /// A new `SnowflakeGenerator`
     ///
+    /// A system clock set before the UNIX epoch gives a timestamp of zero, and worker IDs that
+    /// don't fit in [`SnowflakeLayout::DEFAULT`] are silently truncated when converted to an ID.
+    #[deprecated(since = "0.3.0", note = "use `SnowflakeGenerator::try_new` instead")]
+    pub fn new(worker_id: u64) -> SnowflakeGenerator {
+        #[allow(deprecated)]
+        SnowflakeGenerator::start_at(&Snowflake::new(worker_id))
+    }
+
+    /// Create a new snowflake generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`] or the system clock is set before the UNIX epoch
+    ///
     /// # Example
     /// ```rust
     /// use rusty_snowflake::{SnowflakeGenerator, Snowflake};
     ///
     /// const WORKER_ID: u64 = 420;
     ///
-    /// let generator = SnowflakeGenerator::new(WORKER_ID);
+    /// let generator = SnowflakeGenerator::try_new(WORKER_ID).unwrap();
     ///
-    /// assert_eq!(generator.last_snowflake, Snowflake::new(WORKER_ID));
+    /// assert_eq!(generator.last_snowflake, Snowflake::try_new(WORKER_ID).unwrap());
+    /// assert!(SnowflakeGenerator::try_new(1025).is_err());
     /// ```
-    pub fn new(worker_id: u64) -> SnowflakeGenerator {
-        SnowflakeGenerator {
-            last_snowflake: Snowflake::new(worker_id),
-        }
+    pub fn try_new(worker_id: u64) -> Result<SnowflakeGenerator, SnowflakeError> {
+        Ok(SnowflakeGenerator::start_at(&Snowflake::try_new(
+            worker_id,
+        )?))
     }
 
     /// Start at the given snowflake
@@ -46,14 +94,387 @@ impl SnowflakeGenerator {
     ///
     /// const WORKER_ID: u64 = 420;
     ///
-    /// let generator = SnowflakeGenerator::start_at(&Snowflake::new(WORKER_ID));
+    /// let snowflake = Snowflake::try_new(WORKER_ID).unwrap();
+    /// let generator = SnowflakeGenerator::start_at(&snowflake);
     ///
-    /// assert_eq!(generator.last_snowflake, Snowflake::new(WORKER_ID));
+    /// assert_eq!(generator.last_snowflake, snowflake);
     /// ```
     pub fn start_at(snowflake: &Snowflake) -> SnowflakeGenerator {
+        SnowflakeGenerator::start_at_with_clock(snowflake, SystemClock)
+    }
+
+    /// Create a new snowflake generator that generates snowflakes with a custom bit layout
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    /// * `layout` - The bit layout of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if the worker ID doesn't fit in the layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// let mut generator = SnowflakeGenerator::with_layout(1, SnowflakeLayout::DISCORD).unwrap();
+    ///
+    /// let id = generator.next().to_id_with_layout(&SnowflakeLayout::DISCORD);
+    /// println!("Generated Discord ID: {}", id);
+    /// ```
+    pub fn with_layout(
+        worker_id: u64,
+        layout: SnowflakeLayout,
+    ) -> Result<SnowflakeGenerator, SnowflakeError> {
+        SnowflakeGenerator::with_layout_and_clock(worker_id, layout, SystemClock)
+    }
+
+    /// Create a new snowflake generator from a datacenter ID and a machine ID, like Twitter's
+    /// original snowflake
+    ///
+    /// # Arguments
+    /// * `datacenter_id` - The datacenter ID, stored in the high 5 bits of the worker ID
+    /// * `machine_id` - The machine ID, stored in the low 5 bits of the worker ID
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if either ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeGenerator;
+    ///
+    /// let mut generator = SnowflakeGenerator::with_datacenter(3, 17).unwrap();
+    /// let snowflake = *generator.next();
+    ///
+    /// assert_eq!(snowflake.datacenter_id(), 3);
+    /// assert_eq!(snowflake.machine_id(), 17);
+    /// assert!(SnowflakeGenerator::with_datacenter(32, 0).is_err());
+    /// ```
+    pub fn with_datacenter(
+        datacenter_id: u64,
+        machine_id: u64,
+    ) -> Result<SnowflakeGenerator, SnowflakeError> {
+        SnowflakeGenerator::with_datacenter_and_layout(
+            datacenter_id,
+            machine_id,
+            SnowflakeLayout::DEFAULT,
+        )
+    }
+
+    /// Create a new snowflake generator from a datacenter ID and a machine ID, split the way a
+    /// custom layout does
+    ///
+    /// # Arguments
+    /// * `datacenter_id` - The datacenter ID
+    /// * `machine_id` - The machine ID
+    /// * `layout` - The bit layout of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if either ID doesn't fit in the layout
+    pub fn with_datacenter_and_layout(
+        datacenter_id: u64,
+        machine_id: u64,
+        layout: SnowflakeLayout,
+    ) -> Result<SnowflakeGenerator, SnowflakeError> {
+        let max = layout.max_datacenter_id();
+        if datacenter_id > max {
+            return Err(SnowflakeError::DatacenterIdOverflow { datacenter_id, max });
+        }
+        let max = layout.max_machine_id();
+        if machine_id > max {
+            return Err(SnowflakeError::MachineIdOverflow { machine_id, max });
+        }
+
+        let worker_id = shl(datacenter_id, layout.machine_id_bits() as u32) | machine_id;
+        SnowflakeGenerator::with_layout(worker_id, layout)
+    }
+
+    /// Get the current timestamp in seconds since the epoch (1970-01-01 00:00:00 UTC).
+    ///
+    /// # Returns
+    /// The current timestamp in seconds, or zero if the system clock is set before the UNIX
+    /// epoch
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `SnowflakeGenerator::try_get_timestamp` instead"
+    )]
+    pub fn get_timestamp() -> u64 {
+        current_timestamp()
+    }
+
+    /// Get the current timestamp in seconds since the epoch (1970-01-01 00:00:00 UTC).
+    ///
+    /// This is the timestamp of [`SnowflakeLayout::DEFAULT`], whose ticks are one
+    /// [`TimeUnit::Seconds`](crate::TimeUnit::Seconds) long.
+    ///
+    /// # Returns
+    /// The current timestamp in seconds, or `SnowflakeError::ClockBeforeEpoch` if the system
+    /// clock is set before the UNIX epoch
+    pub fn try_get_timestamp() -> Result<u64, SnowflakeError> {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| {
+                SnowflakeLayout::DEFAULT.timestamp_from_micros(duration.as_micros() as u64)
+            })
+            .map_err(|_| SnowflakeError::ClockBeforeEpoch)
+    }
+
+    /// Wait for the next second and return the timestamp
+    ///
+    /// Like [`SnowflakeGenerator::try_get_timestamp`], this counts in the ticks of
+    /// [`SnowflakeLayout::DEFAULT`].
+    ///
+    /// # Arguments
+    /// * `last_timestamp` - The current timestamp in seconds
+    ///
+    /// # Returns
+    /// The timestamp of the next second, which is returned straight away if the system clock
+    /// is set before the UNIX epoch
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `SnowflakeGenerator::try_wait_next_timestamp` instead"
+    )]
+    pub fn wait_next_timestamp(last_timestamp: u64) -> u64 {
+        SnowflakeGenerator::try_wait_next_timestamp(last_timestamp)
+            .unwrap_or(last_timestamp.saturating_add(1))
+    }
+
+    /// Wait for the next second and return the timestamp
+    ///
+    /// # Arguments
+    /// * `last_timestamp` - The current timestamp in seconds
+    ///
+    /// # Returns
+    /// The timestamp of the next second, `SnowflakeError::ClockBeforeEpoch` if the system
+    /// clock is set before the UNIX epoch, or `SnowflakeError::TimestampOverflow` if no later
+    /// timestamp fits in [`SnowflakeLayout::DEFAULT`]
+    pub fn try_wait_next_timestamp(last_timestamp: u64) -> Result<u64, SnowflakeError> {
+        let max = SnowflakeLayout::DEFAULT.max_timestamp();
+        if last_timestamp >= max {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: last_timestamp.saturating_add(1),
+                max,
+            });
+        }
+
+        let next_tick =
+            SnowflakeLayout::DEFAULT.micros_from_timestamp(last_timestamp.saturating_add(1));
+
+        let mut timestamp = SnowflakeGenerator::try_get_timestamp()?;
+        while timestamp <= last_timestamp {
+            WaitStrategy::SleepUntilNextTick.pause(SystemClock.now_micros(), next_tick);
+            timestamp = SnowflakeGenerator::try_get_timestamp()?;
+        }
+        Ok(timestamp)
+    }
+}
+
+impl<C: Clock> SnowflakeGenerator<C> {
+    /// Create a new snowflake generator with the given worker ID that reads time from `clock`
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    /// * `clock` - The clock to read timestamps from
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{MockClock, SnowflakeGenerator};
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(420, clock.clone()).unwrap();
+    ///
+    /// assert_eq!(generator.next().timestamp(), 1_000);
+    /// assert_eq!(generator.next().sequence(), 2);
+    ///
+    /// clock.advance(1_000);
+    ///
+    /// assert_eq!(generator.next().timestamp(), 1_001);
+    /// assert_eq!(generator.last_snowflake.sequence(), 0);
+    /// ```
+    pub fn with_clock(worker_id: u64, clock: C) -> Result<SnowflakeGenerator<C>, SnowflakeError> {
+        SnowflakeGenerator::with_layout_and_clock(worker_id, SnowflakeLayout::DEFAULT, clock)
+    }
+
+    /// Create a new snowflake generator with a custom bit layout that reads time from `clock`
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake generator
+    /// * `layout` - The bit layout of the snowflakes generated
+    /// * `clock` - The clock to read timestamps from
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator`, or an error if the worker ID doesn't fit in the layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{MockClock, SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// let clock = MockClock::new(SnowflakeLayout::DISCORD.epoch() + 42);
+    /// let mut generator =
+    ///     SnowflakeGenerator::with_layout_and_clock(1, SnowflakeLayout::DISCORD, clock).unwrap();
+    ///
+    /// assert_eq!(generator.next().timestamp(), 42);
+    /// ```
+    pub fn with_layout_and_clock(
+        worker_id: u64,
+        layout: SnowflakeLayout,
+        clock: C,
+    ) -> Result<SnowflakeGenerator<C>, SnowflakeError> {
+        let max = layout.max_worker_id();
+        if worker_id > max {
+            return Err(SnowflakeError::WorkerIdOverflow { worker_id, max });
+        }
+
+        let snowflake = Snowflake {
+            worker_id,
+            sequence: 0,
+            timestamp: layout.timestamp_from_micros(clock.now_micros()),
+            extra: 0,
+        };
+
+        Ok(SnowflakeGenerator {
+            layout,
+            ..SnowflakeGenerator::start_at_with_clock(&snowflake, clock)
+        })
+    }
+
+    /// Start at the given snowflake, reading time from `clock`
+    ///
+    /// # Arguments
+    /// * `snowflake` - The snowflake to start at
+    /// * `clock` - The clock to read timestamps from
+    ///
+    /// # Returns
+    /// A `SnowflakeGenerator`
+    pub fn start_at_with_clock(snowflake: &Snowflake, clock: C) -> SnowflakeGenerator<C> {
         SnowflakeGenerator {
             last_snowflake: *snowflake,
+            options: GeneratorOptions::default(),
+            clock,
+            layout: SnowflakeLayout::DEFAULT,
+            stats: GeneratorStats::default(),
+            rng: 0,
+            rate_limit_drained_at: 0,
+        }
+    }
+
+    /// Get the clock the generator reads timestamps from
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Get statistics about the snowflakes generated so far
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{MockClock, SnowflakeGenerator};
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(420, clock).unwrap();
+    ///
+    /// generator.next();
+    /// generator.next();
+    ///
+    /// let stats = generator.stats();
+    /// assert_eq!(stats.ids_generated(), 2);
+    /// assert_eq!(stats.max_sequence_seen_per_tick(), 2);
+    /// assert_eq!(stats.sequence_rollovers(), 0);
+    /// ```
+    pub fn stats(&self) -> GeneratorStats {
+        self.stats
+    }
+
+    /// Get the position of the generator, to persist across restarts
+    ///
+    /// # Returns
+    /// The timestamp and sequence of the last snowflake generated
+    pub fn snapshot(&self) -> GeneratorState {
+        GeneratorState::new(self.last_snowflake.timestamp, self.last_snowflake.sequence)
+    }
+
+    /// Continue after a persisted position
+    ///
+    /// The generator only ever moves forward, so a state behind the last snowflake it already
+    /// generated is ignored. When the clock is behind the restored state, the generator's
+    /// `ClockBackwardsPolicy` applies as if it had generated the state's snowflake itself.
+    ///
+    /// # Arguments
+    /// * `state` - A state taken with [`SnowflakeGenerator::snapshot`] from a generator with the
+    ///   same worker ID and layout
+    ///
+    /// # Returns
+    /// An error if the state's timestamp or sequence doesn't fit in the generator's layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{GeneratorState, MockClock, SnowflakeGenerator};
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(420, clock).unwrap();
+    ///
+    /// // The last run got further than the clock says it is now
+    /// generator.restore(GeneratorState::new(1_005, 7)).unwrap();
+    ///
+    /// let next = generator.next();
+    /// assert_eq!((next.timestamp(), next.sequence()), (1_005, 8));
+    /// ```
+    pub fn restore(&mut self, state: GeneratorState) -> Result<(), SnowflakeError> {
+        if state.timestamp > self.layout.max_timestamp() {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: state.timestamp,
+                max: self.layout.max_timestamp(),
+            });
+        }
+
+        if state.sequence > self.layout.max_sequence() {
+            return Err(SnowflakeError::SequenceOverflow {
+                sequence: state.sequence,
+                max: self.layout.max_sequence(),
+            });
         }
+
+        let last = self.last_snowflake;
+        if (state.timestamp, state.sequence) > (last.timestamp, last.sequence) {
+            self.last_snowflake = Snowflake {
+                timestamp: state.timestamp,
+                sequence: state.sequence,
+                ..last
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Use the given options for the generator
+    ///
+    /// # Arguments
+    /// * `options` - The options controlling how the generator behaves
+    ///
+    /// # Returns
+    /// The `SnowflakeGenerator` with the options applied
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{ClockBackwardsPolicy, GeneratorOptions, SnowflakeGenerator};
+    ///
+    /// let mut generator = SnowflakeGenerator::try_new(420)
+    ///     .unwrap()
+    ///     .with_options(GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Wait));
+    ///
+    /// println!("Generated snowflake ID: {}", generator.next());
+    /// ```
+    pub fn with_options(mut self, options: GeneratorOptions) -> SnowflakeGenerator<C> {
+        self.options = options;
+        self
     }
 
     /// Generates the next snowflake ID and returns a reference to it.
@@ -67,7 +488,7 @@ impl SnowflakeGenerator {
     /// ```rust
     /// use rusty_snowflake::SnowflakeGenerator;
     ///
-    /// let mut generator = SnowflakeGenerator::new(420);
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
     ///
     /// // Generate the next snowflake ID
     /// let snowflake = generator.next();
@@ -80,97 +501,1346 @@ impl SnowflakeGenerator {
     ///
     /// # Panics
     ///
-    /// This method does not panic.
+    /// Panics if [`SnowflakeGenerator::try_next`] returns an error, which can only happen when
+    /// the generator is configured with `ClockBackwardsPolicy::Error`,
+    /// `OverflowStrategy::Error` or `RateLimitPolicy::Error`, or once the layout's timestamp runs out at
+    /// [`SnowflakeLayout::exhaustion_date`].
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> &Snowflake {
-        self.last_snowflake = self.last_snowflake.next();
+        if let Err(err) = self.try_next() {
+            generation_failed(err);
+        }
         &self.last_snowflake
     }
 
-    /// Get the current timestamp in seconds since the epoch (1970-01-01 00:00:00 UTC).
+    /// Generates the next snowflake ID.
+    ///
+    /// This increments the sequence number and, if necessary, adjusts the timestamp to ensure
+    /// uniqueness. If the clock has gone backwards since the last snowflake, the generator's
+    /// `ClockBackwardsPolicy` decides what happens, and if the sequence runs out within one tick
+    /// its `OverflowStrategy` does. A generator with
+    /// [`GeneratorOptions::max_ids_per_second`] set first waits for the rate limit, or fails
+    /// under `RateLimitPolicy::Error`.
+    ///
+    /// The snowflake a generator starts at counts as already handed out, so it never generates
+    /// the all-zero ID, even at the layout's epoch with worker ID `0`, and every ID converts to
+    /// a `NonZeroU64`.
     ///
     /// # Returns
-    /// The current timestamp in seconds
-    pub fn get_timestamp() -> u64 {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("SystemTime before UNIX EPOCH!")
-            .as_secs()
+    /// The generated snowflake, or an error if the clock went backwards under
+    /// `ClockBackwardsPolicy::Error`, the sequence ran out under `OverflowStrategy::Error`, the
+    /// rate limit was exceeded under `RateLimitPolicy::Error`, or the timestamp no longer fits
+    /// in the layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{ClockBackwardsPolicy, GeneratorOptions, Snowflake, SnowflakeGenerator};
+    ///
+    /// let future = Snowflake::builder()
+    ///     .timestamp(u64::MAX >> 23)
+    ///     .worker_id(1)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut generator = SnowflakeGenerator::start_at(&future)
+    ///     .with_options(GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error));
+    ///
+    /// assert!(generator.try_next().is_err());
+    /// ```
+    pub fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        self.throttle()?;
+        let timestamp = self.timestamp();
+        let snowflake = self.try_next_at(timestamp)?;
+        self.take_rate_limit_token();
+        Ok(snowflake)
     }
 
-    /// Wait for the next second and return the timestamp
+    /// Generates the next snowflake ID with a caller-defined extra field
+    ///
+    /// One generator can mint IDs of several kinds this way, told apart by
+    /// [`Snowflake::extra`] when they're parsed. The snowflakes share the generator's sequence,
+    /// so they're unique across kinds, but the extra field sits above the worker ID and
+    /// sequence, so IDs from the same tick sort by kind first. Later calls to
+    /// [`SnowflakeGenerator::try_next`] keep the extra value of the last snowflake.
     ///
     /// # Arguments
-    /// * `current_timestamp` - The current timestamp in seconds
+    /// * `extra` - The extra value, which has to fit in the layout's
+    ///   [extra bits](crate::SnowflakeLayoutBuilder::extra_bits)
     ///
     /// # Returns
-    /// The timestamp of the next second
-    pub fn wait_next_timestamp(last_timestamp: u64) -> u64 {
-        let mut timestamp = SnowflakeGenerator::get_timestamp();
-        while timestamp <= last_timestamp {
-            std::thread::sleep(std::time::Duration::from_millis(1));
-            timestamp = SnowflakeGenerator::get_timestamp();
+    /// The generated snowflake, or `SnowflakeError::ExtraOverflow` if the extra value doesn't
+    /// fit in the layout and any error [`SnowflakeGenerator::try_next`] returns
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// const USER: u64 = 0;
+    /// const FILE: u64 = 2;
+    ///
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .extra_bits(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut generator = SnowflakeGenerator::with_layout(1, layout).unwrap();
+    ///
+    /// let user = generator.try_next_with_extra(USER).unwrap();
+    /// let file = generator.try_next_with_extra(FILE).unwrap();
+    ///
+    /// let parsed = Snowflake::parse_with_layout(file.to_id_with_layout(&layout), &layout);
+    /// assert_eq!(parsed.extra(), FILE);
+    /// assert!(file.to_id_with_layout(&layout) > user.to_id_with_layout(&layout));
+    /// assert!(generator.try_next_with_extra(4).is_err());
+    /// ```
+    pub fn try_next_with_extra(&mut self, extra: u64) -> Result<Snowflake, SnowflakeError> {
+        let max = self.layout.max_extra();
+        if extra > max {
+            return Err(SnowflakeError::ExtraOverflow { extra, max });
         }
-        timestamp
-    }
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
 
-    #[test]
-    fn test_new() {
-        let generator = SnowflakeGenerator::new(420);
-        assert_eq!(generator.last_snowflake, Snowflake::new(420));
+        let last = self.last_snowflake;
+        self.last_snowflake.extra = extra;
+        if self.options.strict_monotonic && extra < last.extra {
+            // The extra field sits above the sequence, so a lower extra in the same tick would
+            // sort below the last snowflake. Treat the tick as full to move on to the next one.
+            self.last_snowflake.sequence = self.layout.max_sequence();
+        }
 
-        let generator = SnowflakeGenerator::new(69);
-        assert_eq!(generator.last_snowflake, Snowflake::new(69));
+        let result = self.try_next();
+        if result.is_err() {
+            self.last_snowflake = last;
+        }
+        result
     }
 
-    #[test]
-    fn test_start_at() {
-        let snowflake = Snowflake::new(420);
-        let generator = SnowflakeGenerator::start_at(&snowflake);
-        assert_eq!(generator.last_snowflake, snowflake);
-    }
+    /// Generates the next snowflake ID as if the clock read `timestamp`
+    fn try_next_at(&mut self, mut timestamp: u64) -> Result<Snowflake, SnowflakeError> {
+        let last = self.last_snowflake;
 
-    #[test]
-    fn test_next() {
-        let mut generator = SnowflakeGenerator::new(420);
+        let borrowed = match self.options.overflow_strategy {
+            OverflowStrategy::BorrowTime { max_ticks } => {
+                last.timestamp.saturating_sub(timestamp) <= max_ticks
+            }
+            _ => false,
+        };
 
-        for _ in 0..10 {
-            let snowflake = *generator.next();
-            assert_eq!(snowflake, generator.last_snowflake);
+        if timestamp < last.timestamp && borrowed {
+            timestamp = last.timestamp;
+        } else if timestamp < last.timestamp {
+            match self.options.clock_backwards_policy {
+                ClockBackwardsPolicy::Wait => {
+                    timestamp = self.wait_next_timestamp_after(last.timestamp - 1);
+                }
+                ClockBackwardsPolicy::Error if self.options.strict_monotonic => {
+                    timestamp = last.timestamp;
+                }
+                ClockBackwardsPolicy::Error => {
+                    return Err(SnowflakeError::ClockMovedBackwards {
+                        last_timestamp: last.timestamp,
+                        timestamp,
+                    });
+                }
+                ClockBackwardsPolicy::BorrowSequence => timestamp = last.timestamp,
+            }
         }
-    }
 
-    #[test]
-    fn test_eq() {
-        let gen1 = SnowflakeGenerator::new(420);
-        let gen2 = SnowflakeGenerator::new(420);
-        assert_eq!(gen1, gen2);
+        let snowflake = if timestamp > last.timestamp {
+            Snowflake {
+                sequence: self.first_sequence(), // Reset sequence because timestamp changed
+                timestamp,
+                ..last
+            }
+        } else if last.sequence < self.layout.max_sequence() {
+            Snowflake {
+                sequence: last.sequence + 1, // Increment sequence
+                ..last
+            }
+        } else if self.options.overflow_strategy == OverflowStrategy::Error
+            && !self.options.strict_monotonic
+        {
+            return Err(SnowflakeError::SequenceExhausted {
+                timestamp: last.timestamp,
+            });
+        } else if last.timestamp >= self.layout.max_timestamp() {
+            // No later timestamp fits in the layout, so waiting for one would never end
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: last.timestamp.saturating_add(1),
+                max: self.layout.max_timestamp(),
+            });
+        } else {
+            self.stats.sequence_rollovers += 1;
+            // Update timestamp when sequence overflows
+            let timestamp = match self.options.overflow_strategy {
+                OverflowStrategy::BorrowTime { max_ticks } => {
+                    // Wait only as long as needed to stay within the borrowing limit
+                    let limit = last.timestamp.saturating_sub(max_ticks);
+                    let now = match self.timestamp() {
+                        now if now <= limit => self.wait_next_timestamp_after(limit),
+                        now => now,
+                    };
+                    now.max(last.timestamp.saturating_add(1))
+                }
+                _ => self.wait_next_timestamp_after(last.timestamp),
+            };
 
-        let gen1 = Snowflake::new(420);
-        let gen2 = Snowflake::new(69);
-        assert_ne!(gen1, gen2);
-    }
+            Snowflake {
+                sequence: self.first_sequence(),
+                timestamp,
+                ..last
+            }
+        };
 
-    #[test]
-    fn test_snowflake_wait_next_timestamp() {
-        let timestamp = SnowflakeGenerator::get_timestamp();
+        let max = self.layout.max_timestamp();
+        if snowflake.timestamp > max {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: snowflake.timestamp,
+                max,
+            });
+        }
 
-        let next = SnowflakeGenerator::wait_next_timestamp(timestamp);
+        self.last_snowflake = snowflake;
+        self.record(snowflake.sequence, 1);
+        Ok(snowflake)
+    }
 
-        assert!(
-            next > timestamp,
-            "Snowflake.wait_next_timestamp didn't return a new timestamp"
-        );
+    /// Generates the next snowflake ID after checking the generator's clock with a
+    /// [`ClockMonitor`]
+    ///
+    /// # Arguments
+    /// * `monitor` - The monitor watching the clock for jumps
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if [`SnowflakeGenerator::try_next`] failed or the
+    /// monitor found drift and is set to [`ClockMonitor::error_on_drift`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::{ClockMonitor, MockClock, SnowflakeGenerator};
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+    /// let mut monitor = ClockMonitor::new(Duration::from_secs(1))
+    ///     .interval(Duration::ZERO)
+    ///     .error_on_drift(true);
+    ///
+    /// assert!(generator.try_next_monitored(&mut monitor).is_ok());
+    ///
+    /// clock.advance(60_000);
+    /// assert!(generator.try_next_monitored(&mut monitor).is_err());
+    /// ```
+    pub fn try_next_monitored(
+        &mut self,
+        monitor: &mut ClockMonitor,
+    ) -> Result<Snowflake, SnowflakeError> {
+        monitor.check(&self.clock)?;
+        self.try_next()
     }
 
-    #[test]
-    fn test_get_timestamp() {
-        let timestamp = SnowflakeGenerator::get_timestamp();
+    /// Generates the next snowflake ID after checking that the generator's clock isn't too far
+    /// ahead of a [`FutureGuard`]'s reference clock
+    ///
+    /// # Arguments
+    /// * `guard` - The guard holding the reference clock
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if [`SnowflakeGenerator::try_next`] failed or the
+    /// clock is too far ahead and the guard doesn't clamp
+    pub fn try_next_guarded<R: Clock>(
+        &mut self,
+        guard: &FutureGuard<R>,
+    ) -> Result<Snowflake, SnowflakeError> {
+        match guard.check(&self.clock)? {
+            Some(latest) => {
+                self.throttle()?;
+                let snowflake = self.try_next_at(self.layout.timestamp_from_micros(latest))?;
+                self.take_rate_limit_token();
+                Ok(snowflake)
+            }
+            None => self.try_next(),
+        }
+    }
+
+    /// Generates the next `n` snowflake IDs.
+    ///
+    /// Sequence numbers are reserved in contiguous blocks, so the clock is only read once per
+    /// block instead of once per snowflake. Each block holds as many of the remaining IDs as
+    /// fit in the current second.
+    ///
+    /// # Arguments
+    /// * `n` - The number of snowflakes to generate
+    ///
+    /// # Returns
+    /// The generated snowflakes in ascending order
+    ///
+    /// # Panics
+    /// Panics in the same cases as [`SnowflakeGenerator::next`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeGenerator;
+    ///
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// let batch = generator.next_batch(100);
+    ///
+    /// assert_eq!(batch.len(), 100);
+    /// assert_eq!(batch.last(), Some(&generator.last_snowflake));
+    /// ```
+    pub fn next_batch(&mut self, n: usize) -> Vec<Snowflake> {
+        self.try_next_batch(n)
+            .unwrap_or_else(|err| generation_failed(err))
+    }
+
+    /// Generates the next `n` snowflake IDs.
+    ///
+    /// See [`SnowflakeGenerator::next_batch`].
+    ///
+    /// # Arguments
+    /// * `n` - The number of snowflakes to generate
+    ///
+    /// # Returns
+    /// The generated snowflakes in ascending order, or the first error
+    /// [`SnowflakeGenerator::try_next`] returned
+    pub fn try_next_batch(&mut self, n: usize) -> Result<Vec<Snowflake>, SnowflakeError> {
+        let mut snowflakes = Vec::with_capacity(n);
+
+        while snowflakes.len() < n {
+            let (first, count) = self.try_reserve(n - snowflakes.len())?;
+            snowflakes.extend((0..count as u64).map(|offset| Snowflake {
+                sequence: first.sequence + offset,
+                ..first
+            }));
+        }
+
+        Ok(snowflakes)
+    }
+
+    /// Fills `ids` with the next snowflake IDs without allocating.
+    ///
+    /// This reserves sequence numbers the same way as [`SnowflakeGenerator::next_batch`].
+    ///
+    /// # Arguments
+    /// * `ids` - The slice to fill with IDs
+    ///
+    /// # Panics
+    /// Panics in the same cases as [`SnowflakeGenerator::next`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeGenerator;
+    ///
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// let mut ids = [0; 64];
+    /// generator.fill(&mut ids);
+    ///
+    /// assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    /// ```
+    pub fn fill(&mut self, ids: &mut [u64]) {
+        if let Err(err) = self.try_fill(ids) {
+            generation_failed(err);
+        }
+    }
+
+    /// Fills `ids` with the next snowflake IDs without allocating.
+    ///
+    /// See [`SnowflakeGenerator::fill`].
+    ///
+    /// # Arguments
+    /// * `ids` - The slice to fill with IDs
+    ///
+    /// # Returns
+    /// The first error [`SnowflakeGenerator::try_next`] returned, after which the rest of
+    /// `ids` is left as it was
+    pub fn try_fill(&mut self, ids: &mut [u64]) -> Result<(), SnowflakeError> {
+        self.try_fill_counted(ids, &mut 0)
+    }
+
+    /// Fills `ids` with the next snowflake IDs, keeping count of how many were written in
+    /// `filled` so callers can tell after an error
+    pub(crate) fn try_fill_counted(
+        &mut self,
+        ids: &mut [u64],
+        filled: &mut usize,
+    ) -> Result<(), SnowflakeError> {
+        while *filled < ids.len() {
+            let (first, count) = self.try_reserve(ids.len() - *filled)?;
+            for (offset, id) in ids[*filled..*filled + count].iter_mut().enumerate() {
+                *id = Snowflake {
+                    sequence: first.sequence + offset as u64,
+                    ..first
+                }
+                .to_id_with_layout(&self.layout);
+            }
+            *filled += count;
+        }
+
+        Ok(())
+    }
+
+    /// Reserve up to `max` consecutive sequence numbers under a single timestamp
+    ///
+    /// # Returns
+    /// The first snowflake of the block and the number of snowflakes reserved, or the error
+    /// [`SnowflakeGenerator::try_next`] returned
+    fn try_reserve(&mut self, max: usize) -> Result<(Snowflake, usize), SnowflakeError> {
+        let first = self.try_next()?;
+        let available = (self.layout.max_sequence() - first.sequence).saturating_add(1);
+        let count = match self.options.max_ids_per_second {
+            // Each snowflake has to get through the rate limit on its own
+            0 => available.min(max as u64),
+            _ => 1,
+        };
+
+        self.last_snowflake = Snowflake {
+            sequence: first.sequence + count - 1,
+            ..first
+        };
+        // `next` already recorded the first snowflake of the block
+        self.record(self.last_snowflake.sequence, count - 1);
+
+        Ok((first, count as usize))
+    }
+
+    /// Add `count` generated snowflakes, the last of which has sequence `sequence`, to the stats
+    fn record(&mut self, sequence: u64, count: u64) {
+        self.stats.ids_generated += count;
+        self.stats.max_sequence_seen_per_tick = self.stats.max_sequence_seen_per_tick.max(sequence);
+    }
+
+    /// Pick the first sequence number of a new tick, which is zero unless the generator is set
+    /// to a random sequence offset
+    fn first_sequence(&mut self) -> u64 {
+        let max_offset = self
+            .options
+            .max_sequence_offset
+            .min(self.layout.max_sequence());
+        if max_offset == 0 {
+            return 0;
+        }
+
+        if self.rng == 0 {
+            self.rng = RandomState::new().build_hasher().finish();
+        }
+
+        // SplitMix64
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut random = self.rng;
+        random = (random ^ (random >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        random = (random ^ (random >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        random ^= random >> 31;
+
+        match max_offset.checked_add(1) {
+            Some(range) => random % range,
+            None => random,
+        }
+    }
+
+    /// Get the current timestamp from the generator's clock, in the generator's layout
+    fn timestamp(&self) -> u64 {
+        self.layout.timestamp_from_micros(self.clock.now_micros())
+    }
+
+    /// Get how long [`SnowflakeGenerator::try_next`] would wait before it could generate the
+    /// next snowflake, for callers that wait some other way
+    ///
+    /// # Returns
+    /// The time to wait for the rate limit, the clock to catch up under
+    /// `ClockBackwardsPolicy::Wait` or the next tick once the sequence runs out, or `None` if
+    /// the next call returns straight away with a snowflake or an error
+    #[cfg(feature = "async")]
+    pub(crate) fn until_ready(&self) -> Option<std::time::Duration> {
+        let now = self.clock.now_micros();
+        let full_for = match self.options.rate_limit_policy {
+            RateLimitPolicy::Wait => self.rate_limit_full_for(now),
+            RateLimitPolicy::Error => 0,
+        };
+        let ready_at = match full_for {
+            0 => self.tick_ready_at(now)?,
+            full_for => now.saturating_add(full_for.div_ceil(1_000) as u64),
+        };
+
+        let wait = ready_at.saturating_sub(now).max(1);
+        Some(std::time::Duration::from_micros(wait))
+    }
+
+    /// Get when [`SnowflakeGenerator::try_next_at`] would stop waiting for the clock, following
+    /// the same steps as it does
+    ///
+    /// # Returns
+    /// The time in microseconds since the UNIX epoch, or `None` if it wouldn't wait
+    #[cfg(feature = "async")]
+    fn tick_ready_at(&self, now: u64) -> Option<u64> {
+        let last = self.last_snowflake;
+        let timestamp = self.layout.timestamp_from_micros(now);
+
+        let borrowed = match self.options.overflow_strategy {
+            OverflowStrategy::BorrowTime { max_ticks } => {
+                last.timestamp.saturating_sub(timestamp) <= max_ticks
+            }
+            _ => false,
+        };
+        if timestamp < last.timestamp && !borrowed {
+            match self.options.clock_backwards_policy {
+                ClockBackwardsPolicy::Wait => {
+                    return Some(self.layout.micros_from_timestamp(last.timestamp));
+                }
+                ClockBackwardsPolicy::Error if !self.options.strict_monotonic => return None,
+                ClockBackwardsPolicy::Error | ClockBackwardsPolicy::BorrowSequence => {}
+            }
+        }
+
+        if timestamp > last.timestamp
+            || last.sequence < self.layout.max_sequence()
+            || last.timestamp >= self.layout.max_timestamp()
+            || (self.options.overflow_strategy == OverflowStrategy::Error
+                && !self.options.strict_monotonic)
+        {
+            return None;
+        }
+
+        let after = match self.options.overflow_strategy {
+            OverflowStrategy::BorrowTime { max_ticks } => {
+                let limit = last.timestamp.saturating_sub(max_ticks);
+                if timestamp > limit {
+                    return None;
+                }
+                limit
+            }
+            _ => last.timestamp,
+        };
+        Some(self.layout.micros_from_timestamp(after.saturating_add(1)))
+    }
+
+    /// Wait until the rate limit has room for one more snowflake, or fail if the bucket is
+    /// full under `RateLimitPolicy::Error`
+    ///
+    /// The bucket holds one second of snowflakes, and `rate_limit_drained_at` is when it will be
+    /// empty. Nothing is taken from the bucket until the snowflake has been generated, see
+    /// [`SnowflakeGenerator::take_rate_limit_token`].
+    fn throttle(&mut self) -> Result<(), SnowflakeError> {
+        let max_ids_per_second = self.options.max_ids_per_second;
+        if max_ids_per_second == 0 {
+            return Ok(());
+        }
+
+        loop {
+            let now_micros = self.clock.now_micros();
+            let full_for = self.rate_limit_full_for(now_micros);
+            if full_for == 0 {
+                return Ok(());
+            }
+
+            let retry_after_micros = (full_for / 1_000).max(1) as u64;
+            match self.options.rate_limit_policy {
+                RateLimitPolicy::Error => {
+                    return Err(SnowflakeError::RateLimited {
+                        max_ids_per_second,
+                        retry_after_micros,
+                    });
+                }
+                RateLimitPolicy::Wait => self
+                    .options
+                    .wait_strategy
+                    .pause(now_micros, now_micros.saturating_add(retry_after_micros)),
+            }
+        }
+    }
+
+    /// Get how long the rate limiter's bucket stays too full for one more snowflake
+    ///
+    /// # Arguments
+    /// * `now` - The time the clock reads, in microseconds since the UNIX epoch
+    ///
+    /// # Returns
+    /// The time in nanoseconds, which is zero if there's room or the rate isn't limited
+    fn rate_limit_full_for(&self, now: u64) -> u128 {
+        let max_ids_per_second = self.options.max_ids_per_second;
+        if max_ids_per_second == 0 {
+            return 0;
+        }
+
+        let interval = NANOS_PER_SECOND / max_ids_per_second as u128;
+        let now = now as u128 * 1_000;
+        let drained_at = self.rate_limit_drained_at.max(now);
+        (drained_at + interval - now).saturating_sub(NANOS_PER_SECOND)
+    }
+
+    /// Count a generated snowflake towards the rate limit, moving the time the bucket is empty
+    /// one interval later
+    fn take_rate_limit_token(&mut self) {
+        let max_ids_per_second = self.options.max_ids_per_second;
+        if max_ids_per_second == 0 {
+            return;
+        }
+
+        let now = self.clock.now_micros() as u128 * 1_000;
+        self.rate_limit_drained_at =
+            self.rate_limit_drained_at.max(now) + NANOS_PER_SECOND / max_ids_per_second as u128;
+    }
+
+    /// Wait for the generator's clock to pass `last_timestamp` and return the new timestamp
+    ///
+    /// The generator's `WaitStrategy` decides how to wait, unless its `OverflowStrategy` is
+    /// `Spin` or `Yield`.
+    /// The time spent waiting is added to the generator's stats.
+    fn wait_next_timestamp_after(&mut self, last_timestamp: u64) -> u64 {
+        let next_tick = self
+            .layout
+            .micros_from_timestamp(last_timestamp.saturating_add(1));
+        let start = Instant::now();
+
+        loop {
+            let now = self.clock.now_micros();
+            let timestamp = self.layout.timestamp_from_micros(now);
+            if timestamp > last_timestamp {
+                self.stats.clock_wait_time_total += start.elapsed();
+                return timestamp;
+            }
+
+            let strategy = match self.options.overflow_strategy {
+                OverflowStrategy::Spin => WaitStrategy::Spin,
+                OverflowStrategy::Yield => WaitStrategy::Yield,
+                OverflowStrategy::SleepUntilNextTick
+                | OverflowStrategy::Error
+                | OverflowStrategy::BorrowTime { .. } => self.options.wait_strategy,
+            };
+            strategy.pause(now, next_tick);
+        }
+    }
+}
+
+impl<C: Clock> Iterator for SnowflakeGenerator<C> {
+    type Item = Snowflake;
+
+    /// Generates the next snowflake ID, or `None` if generating it failed
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeGenerator};
+    ///
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+    ///
+    /// let snowflakes: Vec<Snowflake> = generator.by_ref().take(100).collect();
+    ///
+    /// assert_eq!(snowflakes.len(), 100);
+    /// assert_eq!(snowflakes.last(), Some(&generator.last_snowflake));
+    /// ```
+    fn next(&mut self) -> Option<Snowflake> {
+        self.try_next().ok()
+    }
+}
+
+/// Get the current timestamp in seconds, or zero if the clock is before the UNIX epoch
+pub(crate) fn current_timestamp() -> u64 {
+    SnowflakeGenerator::try_get_timestamp().unwrap_or(0)
+}
+
+/// Panic with the error a fallible generator method returned
+///
+/// This is the only place the library panics. It's reached from the convenience methods with
+/// a `# Panics` section, each of which has a `try_` counterpart that returns the error instead.
+#[allow(clippy::panic)]
+#[track_caller]
+pub(crate) fn generation_failed(err: SnowflakeError) -> ! {
+    panic!("failed to generate snowflake: {}", err)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{MockClock, TimeUnit};
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_new() {
+        let generator = SnowflakeGenerator::new(420);
+        assert_eq!(generator.last_snowflake, Snowflake::new(420));
+
+        let generator = SnowflakeGenerator::new(69);
+        assert_eq!(generator.last_snowflake, Snowflake::new(69));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let generator = SnowflakeGenerator::try_new(420).unwrap();
+        assert_eq!(generator.last_snowflake, Snowflake::try_new(420).unwrap());
+
+        assert_eq!(
+            SnowflakeGenerator::try_new(1025),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1025,
+                max: SnowflakeLayout::DEFAULT.max_worker_id()
+            })
+        );
+    }
+
+    #[test]
+    fn test_with_datacenter() {
+        let mut generator = SnowflakeGenerator::with_datacenter(31, 31).unwrap();
+        assert_eq!(generator.next().worker_id(), 1023);
+
+        let layout = SnowflakeLayout::builder()
+            .datacenter_id_bits(3)
+            .build()
+            .unwrap();
+        let mut generator = SnowflakeGenerator::with_datacenter_and_layout(5, 100, layout).unwrap();
+        let snowflake = *generator.next();
+        assert_eq!(snowflake.worker_id(), 5 << 7 | 100);
+        assert_eq!(snowflake.datacenter_id_with_layout(&layout), 5);
+        assert_eq!(snowflake.machine_id_with_layout(&layout), 100);
+
+        assert_eq!(
+            SnowflakeGenerator::with_datacenter(32, 0),
+            Err(SnowflakeError::DatacenterIdOverflow {
+                datacenter_id: 32,
+                max: 31
+            })
+        );
+        assert_eq!(
+            SnowflakeGenerator::with_datacenter_and_layout(0, 128, layout),
+            Err(SnowflakeError::MachineIdOverflow {
+                machine_id: 128,
+                max: 127
+            })
+        );
+    }
+
+    #[test]
+    fn test_start_at() {
+        let snowflake = Snowflake::try_new(420).unwrap();
+        let generator = SnowflakeGenerator::start_at(&snowflake);
+        assert_eq!(generator.last_snowflake, snowflake);
+    }
+
+    #[test]
+    fn test_next() {
+        let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+
+        for _ in 0..10 {
+            let snowflake = *generator.next();
+            assert_eq!(snowflake, generator.last_snowflake);
+        }
+    }
+
+    #[test]
+    fn test_next_when_sequence_overflows() {
+        let time = current_timestamp();
+        let mut generator = SnowflakeGenerator::start_at(&Snowflake {
+            worker_id: 1,
+            sequence: SnowflakeLayout::DEFAULT.max_sequence(),
+            timestamp: time,
+            extra: 0,
+        });
+
+        let next = *generator.next();
+
+        assert_eq!(next.sequence, 0);
+        assert!(next.timestamp > time);
+    }
+
+    #[test]
+    fn test_clock_backwards_borrow_sequence() {
+        let future = Snowflake {
+            worker_id: 1,
+            sequence: 5,
+            timestamp: current_timestamp() + 100,
+            extra: 0,
+        };
+        let mut generator = SnowflakeGenerator::start_at(&future).with_options(
+            GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::BorrowSequence),
+        );
+
+        let next = generator.try_next().unwrap();
+
+        assert_eq!(next.timestamp, future.timestamp);
+        assert_eq!(next.sequence, 6);
+    }
+
+    #[test]
+    fn test_clock_backwards_error() {
+        let future = Snowflake {
+            worker_id: 1,
+            sequence: 5,
+            timestamp: current_timestamp() + 100,
+            extra: 0,
+        };
+        let mut generator = SnowflakeGenerator::start_at(&future).with_options(
+            GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error),
+        );
+
+        assert!(matches!(
+            generator.try_next(),
+            Err(SnowflakeError::ClockMovedBackwards { last_timestamp, .. }) if last_timestamp == future.timestamp
+        ));
+        assert_eq!(generator.last_snowflake, future);
+    }
+
+    #[test]
+    fn test_clock_backwards_wait() {
+        let future = Snowflake {
+            worker_id: 1,
+            sequence: 5,
+            timestamp: current_timestamp() + 1,
+            extra: 0,
+        };
+        let mut generator = SnowflakeGenerator::start_at(&future).with_options(
+            GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Wait),
+        );
+
+        let next = generator.try_next().unwrap();
+
+        assert!(next > future);
+        assert!(current_timestamp() >= future.timestamp);
+    }
+
+    #[test]
+    #[should_panic(expected = "clock moved backwards")]
+    fn test_next_panics_on_error() {
+        let future = Snowflake {
+            worker_id: 1,
+            sequence: 0,
+            timestamp: current_timestamp() + 100,
+            extra: 0,
+        };
+        let mut generator = SnowflakeGenerator::start_at(&future).with_options(
+            GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error),
+        );
+
+        generator.next();
+    }
+
+    #[test]
+    fn test_with_clock() {
+        let clock = MockClock::new(1_000_500);
+        let generator = SnowflakeGenerator::with_clock(420, clock.clone()).unwrap();
+
+        assert_eq!(
+            generator.last_snowflake,
+            Snowflake {
+                worker_id: 420,
+                sequence: 0,
+                timestamp: 1_000,
+                extra: 0
+            }
+        );
+        assert!(SnowflakeGenerator::with_clock(1024, clock).is_err());
+    }
+
+    #[test]
+    fn test_mock_clock_ordering() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+
+        let first = *generator.next();
+        assert_eq!((first.timestamp, first.sequence), (1_000, 1));
+
+        clock.advance(999);
+        let second = *generator.next();
+        assert_eq!((second.timestamp, second.sequence), (1_000, 2));
+
+        clock.advance(1);
+        let third = *generator.next();
+        assert_eq!((third.timestamp, third.sequence), (1_001, 0));
+
+        assert!(first < second && second < third);
+    }
+
+    #[test]
+    fn test_mock_clock_backwards() {
+        let clock = MockClock::new(5_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(
+                GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error),
+            );
+
+        clock.set(4_000_000);
+
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::ClockMovedBackwards {
+                last_timestamp: 5_000,
+                timestamp: 4_000
+            })
+        );
+    }
+
+    #[test]
+    fn test_mock_clock_sequence_overflow() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+        generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(1_000);
+        });
+
+        let next = *generator.next();
+        ticker.join().unwrap();
+
+        assert_eq!((next.timestamp, next.sequence), (1_001, 0));
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+        generator.next();
+
+        let state = generator.snapshot();
+        assert_eq!(state, GeneratorState::new(1_000, 1));
+
+        let mut restarted = SnowflakeGenerator::with_clock(1, clock).unwrap();
+        restarted.restore(GeneratorState::new(1_000, 9)).unwrap();
+        restarted.restore(state).unwrap();
+        assert_eq!(restarted.snapshot(), GeneratorState::new(1_000, 9));
+
+        assert_eq!(
+            restarted.restore(GeneratorState::new(0, 4096)),
+            Err(SnowflakeError::SequenceOverflow {
+                sequence: 4096,
+                max: 4095
+            })
+        );
+        assert_eq!(
+            restarted.restore(GeneratorState::new(1 << 41, 0)),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 1 << 41,
+                max: (1 << 41) - 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+
+        generator.next_batch(10);
+        assert_eq!(generator.stats().ids_generated(), 10);
+        assert_eq!(generator.stats().max_sequence_seen_per_tick(), 10);
+
+        generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(1_000);
+        });
+
+        generator.next();
+        ticker.join().unwrap();
+
+        let stats = generator.stats();
+        assert_eq!(stats.ids_generated(), 11);
+        assert_eq!(stats.sequence_rollovers(), 1);
+        assert!(stats.clock_wait_time_total() >= std::time::Duration::from_millis(10));
+        assert_eq!(stats.max_sequence_seen_per_tick(), 10);
+    }
+
+    #[test]
+    fn test_overflow_strategy_error() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().overflow_strategy(OverflowStrategy::Error));
+        generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::SequenceExhausted { timestamp: 1_000 })
+        );
+
+        clock.advance(1_000);
+        assert_eq!(generator.try_next().unwrap().timestamp, 1_001);
+    }
+
+    #[test]
+    fn test_overflow_strategy_borrow_time() {
+        let layout = SnowflakeLayout::builder().sequence_bits(2).build().unwrap();
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone())
+            .unwrap()
+            .with_options(
+                GeneratorOptions::new()
+                    .overflow_strategy(OverflowStrategy::BorrowTime { max_ticks: 2 })
+                    .clock_backwards_policy(ClockBackwardsPolicy::Error),
+            );
+
+        // Three ticks' worth of sequence numbers without the clock moving
+        let snowflakes = (0..11).map(|_| *generator.next()).collect::<Vec<_>>();
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(snowflakes[10].timestamp, 1_002);
+        assert!(generator.stats().clock_wait_time_total().is_zero());
+
+        // Borrowing more than two ticks waits for the clock
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(1_000);
+        });
+        let next = *generator.next();
+        ticker.join().unwrap();
+        assert_eq!((next.timestamp, next.sequence), (1_003, 0));
+        assert!(!generator.stats().clock_wait_time_total().is_zero());
+
+        // The clock catching up continues from the borrowed time
+        clock.advance(1_000);
+        assert_eq!(generator.next().timestamp, 1_003);
+        clock.advance(10_000);
+        assert_eq!(generator.next().timestamp, 1_012);
+    }
+
+    #[test]
+    fn test_strict_monotonic() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(
+                GeneratorOptions::new()
+                    .clock_backwards_policy(ClockBackwardsPolicy::Error)
+                    .overflow_strategy(OverflowStrategy::Error)
+                    .strict_monotonic(true),
+            );
+        let first = generator.try_next().unwrap();
+
+        // The clock going backwards borrows the last timestamp instead of failing
+        clock.set(500_000);
+        let borrowed = generator.try_next().unwrap();
+        assert!(borrowed > first);
+        assert_eq!(borrowed.timestamp, 1_000);
+
+        // A full tick waits for the next one instead of failing
+        clock.set(1_000_000);
+        generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(1_000);
+        });
+        let next = generator.try_next().unwrap();
+        ticker.join().unwrap();
+        assert_eq!((next.timestamp, next.sequence), (1_001, 0));
+    }
+
+    #[test]
+    fn test_strict_monotonic_with_extra() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .build()
+            .unwrap();
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().strict_monotonic(true));
+
+        let first = generator.try_next_with_extra(2).unwrap();
+        // A higher extra stays in the same tick
+        let higher = generator.try_next_with_extra(3).unwrap();
+        assert_eq!(higher.timestamp, first.timestamp);
+
+        // A lower extra waits for the next tick
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(layout.tick_micros() / 1_000);
+        });
+        let lower = generator.try_next_with_extra(0).unwrap();
+        ticker.join().unwrap();
+        assert_eq!(lower.timestamp, first.timestamp + 1);
+        assert_eq!(lower.extra, 0);
+
+        let ids = [
+            first,
+            higher,
+            lower,
+            generator.try_next_with_extra(1).unwrap(),
+        ]
+        .map(|snowflake| snowflake.to_id_with_layout(&layout));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", ids);
+    }
+
+    #[test]
+    fn test_strict_monotonic_stress() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::{Arc, Mutex};
+
+        // Few sequence numbers per millisecond, so threads keep running out of them
+        let layout = SnowflakeLayout::builder()
+            .tick_millis(1)
+            .sequence_bits(4)
+            .build()
+            .unwrap();
+        let clock = MockClock::new(1_000_000);
+        let generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone())
+            .unwrap()
+            .with_options(
+                GeneratorOptions::new()
+                    .clock_backwards_policy(ClockBackwardsPolicy::Error)
+                    .strict_monotonic(true),
+            );
+        let generated = Arc::new(Mutex::new((generator, Vec::new())));
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Jitter the clock back and forth while it creeps forward
+        let jitter = {
+            let (clock, done) = (clock.clone(), Arc::clone(&done));
+            std::thread::spawn(move || {
+                let mut millis = 1_000_000;
+                while !done.load(Ordering::Relaxed) {
+                    millis += 3;
+                    clock.set(millis);
+                    std::thread::yield_now();
+                    clock.set(millis - 5);
+                    std::thread::yield_now();
+                }
+            })
+        };
+
+        let workers = (0..4)
+            .map(|_| {
+                let generated = Arc::clone(&generated);
+                std::thread::spawn(move || {
+                    let mut last = None;
+                    for _ in 0..2_000 {
+                        let mut generated = generated.lock().unwrap();
+                        let (generator, ids) = &mut *generated;
+                        let id = generator.try_next().unwrap().to_id_with_layout(&layout);
+                        ids.push(id);
+                        assert!(last < Some(id), "{:?} then {}", last, id);
+                        last = Some(id);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        jitter.join().unwrap();
+
+        // Every ID is greater than every ID generated before it, whichever thread asked
+        let ids = &generated.lock().unwrap().1;
+        assert_eq!(ids.len(), 8_000);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_random_sequence_offset() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().random_sequence_offset(100));
+
+        let mut firsts = Vec::new();
+        for _ in 0..50 {
+            clock.advance(1_000);
+            let first = *generator.next();
+            assert!(first.sequence <= 100);
+            assert_eq!(generator.next().sequence, first.sequence + 1);
+            firsts.push(first.sequence);
+        }
+
+        firsts.sort_unstable();
+        firsts.dedup();
+        assert!(firsts.len() > 10, "offsets {:?} aren't random", firsts);
+    }
+
+    #[test]
+    fn test_random_sequence_offset_capped() {
+        let layout = SnowflakeLayout::builder().sequence_bits(1).build().unwrap();
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().random_sequence_offset(u64::MAX));
+
+        for _ in 0..20 {
+            clock.advance(1_000);
+            assert!(generator.next().sequence <= 1);
+        }
+    }
+
+    #[test]
+    fn test_overflow_strategy_spin_and_yield() {
+        for strategy in [OverflowStrategy::Spin, OverflowStrategy::Yield] {
+            let clock = MockClock::new(1_000_000);
+            let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+                .unwrap()
+                .with_options(GeneratorOptions::new().overflow_strategy(strategy));
+            generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+
+            let handle = clock.clone();
+            let ticker = std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                handle.advance(1_000);
+            });
+
+            let next = generator.try_next().unwrap();
+            ticker.join().unwrap();
+
+            assert_eq!((next.timestamp, next.sequence), (1_001, 0));
+        }
+    }
+
+    #[test]
+    fn test_with_layout() {
+        let layout = SnowflakeLayout::builder()
+            .worker_id_bits(16)
+            .sequence_bits(4)
+            .epoch(1_000_000)
+            .tick_millis(10)
+            .build()
+            .unwrap();
+        let clock = MockClock::new(1_000_105);
+        let mut generator =
+            SnowflakeGenerator::with_layout_and_clock(40_000, layout, clock.clone()).unwrap();
+
+        assert_eq!(generator.layout(), &layout);
+        assert_eq!(generator.last_snowflake.timestamp, 10);
+
+        let batch = generator.next_batch(15);
+        assert_eq!(batch[14].sequence, 15);
+        assert_eq!(batch[14].timestamp, 10);
+
+        clock.advance(10);
+        let batch = generator.next_batch(5);
+        assert_eq!((batch[0].timestamp, batch[0].sequence), (11, 0));
+
+        assert!(SnowflakeGenerator::with_layout_and_clock(1 << 16, layout, clock).is_err());
+    }
+
+    #[test]
+    fn test_microsecond_ticks() {
+        let layout = SnowflakeLayout::builder()
+            .time_unit(TimeUnit::Microseconds)
+            .epoch(1_700_000_000_000)
+            .timestamp_bits(51)
+            .sequence_bits(2)
+            .build()
+            .unwrap();
+        let mut generator = SnowflakeGenerator::with_layout(1, layout).unwrap();
+
+        let snowflakes = generator.next_batch(100);
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let first = snowflakes[0].created_at_with_layout(&layout);
+        let last = snowflakes[99].created_at_with_layout(&layout);
+        // 4 IDs per microsecond, so 100 IDs span at least 24 microseconds
+        assert!(last.duration_since(first).unwrap() >= std::time::Duration::from_micros(24));
+        assert_eq!(
+            snowflakes[99].duration_since_with_layout(snowflakes[0], &layout),
+            last.duration_since(first).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_timestamp_exhaustion() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(4)
+            .epoch(0)
+            .tick_millis(1)
+            .build()
+            .unwrap();
+        let clock = MockClock::new(layout.exhaustion_millis() - 1);
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone())
+            .unwrap()
+            .with_options(GeneratorOptions::new().overflow_strategy(OverflowStrategy::Error));
+
+        assert_eq!(generator.try_next().unwrap().timestamp, 15);
+
+        clock.advance(1);
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 16,
+                max: 15
+            })
+        );
+        assert_eq!(generator.last_snowflake.timestamp, 15);
+    }
+
+    #[test]
+    fn test_with_layout_system_clock() {
+        let mut generator = SnowflakeGenerator::with_layout(1, SnowflakeLayout::DISCORD).unwrap();
+        let snowflake = *generator.next();
+
+        let millis = SnowflakeLayout::DISCORD.millis_from_timestamp(snowflake.timestamp);
+        let now = SystemClock.now_millis();
+        assert!(millis <= now && now - millis < 1_000);
+    }
+
+    #[test]
+    fn test_next_batch() {
+        let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+        let batch = generator.next_batch(5000);
+
+        assert_eq!(batch.len(), 5000);
+        assert!(batch.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(batch.last(), Some(&generator.last_snowflake));
+        assert!(generator.next_batch(0).is_empty());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+        let mut ids = vec![0; 5000];
+        generator.fill(&mut ids);
+
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ids.last(), Some(&generator.last_snowflake.to_id()));
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+        let snowflakes: Vec<Snowflake> = generator.by_ref().take(100).collect();
+
+        assert_eq!(snowflakes.len(), 100);
+        assert!(snowflakes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(snowflakes.last(), Some(&generator.last_snowflake));
+
+        let ids: Vec<u64> = generator
+            .take(10)
+            .map(|snowflake| snowflake.to_id())
+            .collect();
+        assert_eq!(ids.len(), 10);
+    }
+
+    #[test]
+    fn test_eq() {
+        let gen1 = SnowflakeGenerator::try_new(420).unwrap();
+        let gen2 = SnowflakeGenerator::try_new(420).unwrap();
+        assert_eq!(gen1, gen2);
+
+        let gen1 = Snowflake::try_new(420).unwrap();
+        let gen2 = Snowflake::try_new(69).unwrap();
+        assert_ne!(gen1, gen2);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_snowflake_wait_next_timestamp() {
+        let timestamp = current_timestamp();
+
+        let next = SnowflakeGenerator::wait_next_timestamp(timestamp);
+
+        assert!(
+            next > timestamp,
+            "Snowflake.wait_next_timestamp didn't return a new timestamp"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_get_timestamp() {
+        let timestamp = SnowflakeGenerator::get_timestamp();
 
         assert!(
             timestamp
@@ -180,4 +1850,280 @@ mod tests {
                     .as_secs()
         );
     }
+
+    #[test]
+    fn test_try_get_timestamp() {
+        assert_eq!(
+            SnowflakeGenerator::try_get_timestamp(),
+            Ok(current_timestamp())
+        );
+    }
+
+    #[test]
+    fn test_try_wait_next_timestamp_overflow() {
+        let max = SnowflakeLayout::DEFAULT.max_timestamp();
+
+        for last_timestamp in [max, u64::MAX] {
+            assert_eq!(
+                SnowflakeGenerator::try_wait_next_timestamp(last_timestamp),
+                Err(SnowflakeError::TimestampOverflow {
+                    timestamp: last_timestamp.saturating_add(1),
+                    max
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_last_timestamp_exhausted() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(2)
+            .sequence_bits(1)
+            .build()
+            .unwrap();
+        let clock = MockClock::new(layout.millis_from_timestamp(3));
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(1, layout, clock).unwrap();
+        generator.restore(GeneratorState::new(3, 1)).unwrap();
+
+        // There's no later tick to wait for
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 4,
+                max: 3
+            })
+        );
+        assert_eq!(
+            generator.try_next_batch(2),
+            generator.try_next().map(|_| vec![])
+        );
+        assert_eq!(Iterator::next(&mut generator), None);
+    }
+
+    #[test]
+    fn test_full_width_sequence() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(0)
+            .worker_id_bits(0)
+            .sequence_bits(64)
+            .build()
+            .unwrap();
+        let mut generator =
+            SnowflakeGenerator::with_layout_and_clock(0, layout, MockClock::new(0)).unwrap();
+
+        let batch = generator.try_next_batch(3).unwrap();
+        let mut ids = [0; 3];
+        generator.try_fill(&mut ids).unwrap();
+        assert_eq!(ids[0], batch[2].sequence() + 1);
+
+        // Any sequence number can start a tick
+        let mut generator =
+            generator.with_options(GeneratorOptions::new().random_sequence_offset(u64::MAX));
+        generator.first_sequence();
+    }
+
+    #[test]
+    fn test_iterator_ends_on_error() {
+        let future = Snowflake {
+            worker_id: 1,
+            sequence: 0,
+            timestamp: current_timestamp() + 100,
+            extra: 0,
+        };
+        let mut generator = SnowflakeGenerator::start_at(&future).with_options(
+            GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error),
+        );
+
+        assert_eq!(generator.by_ref().count(), 0);
+        assert!(generator.try_next_batch(1).is_err());
+        assert!(generator.try_fill(&mut [0; 1]).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_error() {
+        let clock = MockClock::new(1_000_000);
+        let options = GeneratorOptions::new()
+            .max_ids_per_second(4)
+            .unwrap()
+            .rate_limit_policy(RateLimitPolicy::Error);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(options);
+
+        // A second's worth of snowflakes fits in the bucket, and batches count every one
+        assert_eq!(generator.try_next_batch(3).unwrap().len(), 3);
+        generator.try_next().unwrap();
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::RateLimited {
+                max_ids_per_second: 4,
+                retry_after_micros: 250_000,
+            })
+        );
+
+        clock.advance(100);
+        assert!(matches!(
+            generator.try_fill(&mut [0; 1]),
+            Err(SnowflakeError::RateLimited {
+                retry_after_micros: 150_000,
+                ..
+            })
+        ));
+
+        clock.advance(150);
+        assert!(generator.try_next().is_ok());
+
+        // Strict mode doesn't turn the error into a wait
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(options.strict_monotonic(true));
+        generator.try_next_batch(4).unwrap();
+        assert!(matches!(
+            generator.try_next(),
+            Err(SnowflakeError::RateLimited { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let mut generator = SnowflakeGenerator::try_new(1)
+            .unwrap()
+            .with_options(GeneratorOptions::new().max_ids_per_second(1_000).unwrap());
+        let start = Instant::now();
+
+        // The first second's worth goes through at once, and the rest at the steady rate
+        assert_eq!(generator.try_next_batch(1_020).unwrap().len(), 1_020);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(19));
+    }
+
+    #[test]
+    fn test_rate_limit_wait_on_mock_clock() {
+        let clock = MockClock::new(1_000_000);
+        let options = GeneratorOptions::new()
+            .max_ids_per_second(2)
+            .unwrap()
+            .wait_strategy(WaitStrategy::Sleep(std::time::Duration::from_millis(1)));
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(options);
+        generator.try_next_batch(2).unwrap();
+
+        let handle = clock.clone();
+        let ticker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.advance(500);
+        });
+
+        // The wait ends when the mock clock drains the bucket, not after half a second of
+        // wall time
+        let start = Instant::now();
+        let next = generator.try_next().unwrap();
+        ticker.join().unwrap();
+
+        assert_eq!(next.timestamp, 1_000);
+        assert_eq!(clock.now_millis(), 1_000_500);
+        assert!(start.elapsed() < std::time::Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_rate_limit_failed_generation_keeps_budget() {
+        let clock = MockClock::new(1_000_000);
+        let options = GeneratorOptions::new()
+            .max_ids_per_second(10)
+            .unwrap()
+            .rate_limit_policy(RateLimitPolicy::Error)
+            .clock_backwards_policy(ClockBackwardsPolicy::Error);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+            .unwrap()
+            .with_options(options);
+        generator.try_next().unwrap();
+
+        clock.set(999_950);
+        for _ in 0..3 {
+            assert!(matches!(
+                generator.try_next(),
+                Err(SnowflakeError::ClockMovedBackwards { .. })
+            ));
+        }
+
+        // The failed calls left the other nine snowflakes of the second in the bucket
+        clock.set(1_000_000);
+        assert_eq!(generator.try_next_batch(9).unwrap().len(), 9);
+        assert!(matches!(
+            generator.try_next(),
+            Err(SnowflakeError::RateLimited { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_too_high() {
+        assert_eq!(
+            GeneratorOptions::new().max_ids_per_second(1_000_000_001),
+            Err(SnowflakeError::RateLimitTooHigh {
+                max_ids_per_second: 1_000_000_001,
+                max: GeneratorOptions::MAX_IDS_PER_SECOND,
+            })
+        );
+        assert!(GeneratorOptions::new()
+            .max_ids_per_second(GeneratorOptions::MAX_IDS_PER_SECOND)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_never_generates_zero() {
+        let layout = SnowflakeLayout::SINGLE_PROCESS;
+        let clock = MockClock::new(layout.epoch());
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(0, layout, clock).unwrap();
+        assert_eq!(generator.try_next().unwrap().to_id_with_layout(&layout), 1);
+
+        // Without sequence bits the generator waits for the next tick instead
+        let layout = SnowflakeLayout::builder()
+            .sequence_bits(0)
+            .worker_id_bits(0)
+            .epoch(u64::MAX)
+            .build()
+            .unwrap();
+        let mut generator = SnowflakeGenerator::with_layout_and_clock(0, layout, MockClock::new(0))
+            .unwrap()
+            .with_options(GeneratorOptions::new().overflow_strategy(OverflowStrategy::Error));
+        assert_eq!(
+            generator.try_next(),
+            Err(SnowflakeError::SequenceExhausted { timestamp: 0 })
+        );
+    }
+
+    #[test]
+    fn test_wait_strategies() {
+        for strategy in [
+            WaitStrategy::Spin,
+            WaitStrategy::Yield,
+            WaitStrategy::Sleep(std::time::Duration::from_millis(1)),
+            WaitStrategy::Park(std::time::Duration::from_secs(60)),
+        ] {
+            let clock = MockClock::new(1_000_000);
+            let mut generator = SnowflakeGenerator::with_clock(1, clock.clone())
+                .unwrap()
+                .with_options(GeneratorOptions::new().wait_strategy(strategy));
+            generator.last_snowflake.sequence = SnowflakeLayout::DEFAULT.max_sequence();
+
+            let handle = clock.clone();
+            let waiter = std::thread::current();
+            let ticker = std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                handle.advance(1_000);
+                // A parked waiter would otherwise sleep until the tick was due by its clock
+                waiter.unpark();
+            });
+
+            let next = generator.try_next().unwrap();
+            ticker.join().unwrap();
+
+            assert_eq!(
+                (next.timestamp, next.sequence),
+                (1_001, 0),
+                "{:?}",
+                strategy
+            );
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//! A process-wide generator, for applications that just want IDs without passing a generator
+//! through every layer.
+//!
+//! Call [`init`] once at startup with the worker ID of the process, then call [`next_id`] from
+//! anywhere. The generator is an [`AtomicSnowflakeGenerator`], so threads share it without a
+//! lock. `init` and `next_id` are also available at the crate root.
+//!
+//! # Example
+//! ```rust
+//! rusty_snowflake::init(420).unwrap();
+//!
+//! let first = rusty_snowflake::next_id();
+//! let second = std::thread::spawn(rusty_snowflake::next_id).join().unwrap();
+//!
+//! assert!(second > first);
+//! ```
+
+use std::sync::OnceLock;
+
+use crate::generator::generation_failed;
+use crate::{AtomicSnowflakeGenerator, SnowflakeError};
+
+/// The generator set by [`init`].
+static GENERATOR: OnceLock<AtomicSnowflakeGenerator> = OnceLock::new();
+
+/// Initialize the global generator
+///
+/// # Arguments
+/// * `worker_id` - The worker ID of every snowflake generated in this process
+///
+/// # Returns
+/// An error if the worker ID doesn't fit in
+/// [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT), the system clock is set before
+/// the UNIX epoch, or the global generator was already initialized
+pub fn init(worker_id: u64) -> Result<(), SnowflakeError> {
+    let generator = AtomicSnowflakeGenerator::try_new(worker_id)?;
+
+    GENERATOR
+        .set(generator)
+        .map_err(|_| SnowflakeError::AlreadyInitialized {
+            worker_id: GENERATOR.get().map_or(worker_id, |generator| {
+                generator.last_snowflake().worker_id()
+            }),
+        })
+}
+
+/// Get the global generator, if it was initialized
+pub fn generator() -> Option<&'static AtomicSnowflakeGenerator> {
+    GENERATOR.get()
+}
+
+/// Generate the next snowflake ID from the global generator
+///
+/// # Panics
+/// Panics if [`init`] wasn't called first, or the system clock is set before the UNIX epoch.
+/// Use [`try_next_id`] to handle those instead.
+pub fn next_id() -> u64 {
+    match try_next_id() {
+        Ok(id) => id,
+        Err(err) => generation_failed(err),
+    }
+}
+
+/// Generate the next snowflake ID from the global generator
+///
+/// # Returns
+/// The ID, or `SnowflakeError::NotInitialized` if [`init`] wasn't called first and
+/// `SnowflakeError::ClockBeforeEpoch` if the system clock is set before the UNIX epoch
+pub fn try_next_id() -> Result<u64, SnowflakeError> {
+    let generator = GENERATOR.get().ok_or(SnowflakeError::NotInitialized)?;
+    Ok(generator.try_next()?.to_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The global generator is shared by every test in the binary, so this is the only test
+    /// that touches it
+    #[test]
+    fn test_global() {
+        assert_eq!(try_next_id(), Err(SnowflakeError::NotInitialized));
+        assert!(generator().is_none());
+
+        assert!(matches!(
+            init(1 << 10),
+            Err(SnowflakeError::WorkerIdOverflow { .. })
+        ));
+        init(7).unwrap();
+        assert_eq!(
+            init(8),
+            Err(SnowflakeError::AlreadyInitialized { worker_id: 7 })
+        );
+
+        let ids = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| scope.spawn(|| (0..1_000).map(|_| next_id()).collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<std::collections::HashSet<_>>()
+        });
+
+        assert_eq!(ids.len(), 4_000);
+        assert_eq!(generator().unwrap().last_snowflake().worker_id(), 7);
+    }
+}
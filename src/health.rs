@@ -0,0 +1,376 @@
+use std::time::{Duration, Instant};
+
+use crate::coordination::WorkerLease;
+use crate::{Clock, SnowflakeError, SnowflakeGenerator};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// The result of checking whether a [`SnowflakeGenerator`] can hand out IDs right now, for
+/// readiness probes of services that issue IDs.
+///
+/// See [`SnowflakeGenerator::health_check`].
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{HealthProblem, MockClock, SnowflakeError, SnowflakeGenerator};
+///
+/// let clock = MockClock::new(1_000_000);
+/// let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+/// generator.next();
+///
+/// let report = generator.health_check();
+/// assert!(report.is_healthy());
+/// assert_eq!(report.sequence_headroom(), 4094);
+///
+/// clock.set(990_000);
+/// assert_eq!(
+///     generator.health_check().problems(),
+///     [HealthProblem::Clock(SnowflakeError::ClockMovedBackwards {
+///         last_timestamp: 1_000,
+///         timestamp: 990,
+///     })]
+/// );
+/// ```
+pub struct HealthReport {
+    sequence_headroom: u64,
+    timestamp_headroom: u64,
+    problems: Vec<HealthProblem>,
+}
+
+impl HealthReport {
+    /// Check whether no problems were found
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// The problems found, empty if the generator is healthy
+    pub fn problems(&self) -> &[HealthProblem] {
+        &self.problems
+    }
+
+    /// The number of snowflakes the generator can still hand out in the current tick without
+    /// waiting for the next one
+    pub fn sequence_headroom(&self) -> u64 {
+        self.sequence_headroom
+    }
+
+    /// The number of ticks left before the clock passes the last timestamp the layout can
+    /// hold
+    pub fn timestamp_headroom(&self) -> u64 {
+        self.timestamp_headroom
+    }
+}
+
+impl std::fmt::Display for HealthReport {
+    /// Display `healthy`, or every problem found separated by `; `
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.problems.is_empty() {
+            return write!(f, "healthy");
+        }
+
+        for (index, problem) in self.problems.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+/// A problem found by a [`HealthReport`].
+pub enum HealthProblem {
+    /// The clock is before the layout's epoch, behind the last snowflake generated or past the
+    /// last timestamp the layout can hold.
+    Clock(SnowflakeError),
+    /// Every sequence number of the last tick the layout can hold was used, so no more
+    /// snowflakes can be generated.
+    NoSequenceHeadroom {
+        /// The timestamp of the last snowflake generated
+        timestamp: u64,
+    },
+    /// The worker lease couldn't be renewed, or is for another worker ID than the generator's.
+    Lease(String),
+}
+
+impl std::fmt::Display for HealthProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HealthProblem::Clock(err) => write!(f, "clock: {}", err),
+            HealthProblem::NoSequenceHeadroom { timestamp } => {
+                write!(f, "sequence: no sequence numbers left at {}", timestamp)
+            }
+            HealthProblem::Lease(reason) => write!(f, "lease: {}", reason),
+        }
+    }
+}
+
+impl<C: Clock> SnowflakeGenerator<C> {
+    /// Check whether the generator can hand out snowflakes right now
+    ///
+    /// The clock has to be after the layout's epoch, not behind the last snowflake generated
+    /// and not past the last timestamp the layout can hold, and the last tick the layout can
+    /// hold can't be full. A full tick before that isn't a problem, since the generator moves
+    /// on to the next one, but shows in [`HealthReport::sequence_headroom`]. Nothing is
+    /// generated.
+    ///
+    /// # Returns
+    /// A [`HealthReport`] listing the problems found
+    pub fn health_check(&self) -> HealthReport {
+        let layout = self.layout();
+        let last = self.last_snowflake;
+        let now = self.clock().now_micros();
+        let timestamp = layout.timestamp_from_micros(now);
+        let mut problems = Vec::new();
+
+        if now < layout.epoch().saturating_mul(1_000) {
+            problems.push(HealthProblem::Clock(SnowflakeError::ClockBeforeEpoch));
+        } else if timestamp < last.timestamp {
+            problems.push(HealthProblem::Clock(SnowflakeError::ClockMovedBackwards {
+                last_timestamp: last.timestamp,
+                timestamp,
+            }));
+        }
+
+        if timestamp > layout.max_timestamp() {
+            problems.push(HealthProblem::Clock(SnowflakeError::TimestampOverflow {
+                timestamp,
+                max: layout.max_timestamp(),
+            }));
+        }
+
+        let sequence_headroom = if timestamp > last.timestamp {
+            layout.max_sequence().saturating_add(1)
+        } else {
+            layout.max_sequence().saturating_sub(last.sequence)
+        };
+        // A full tick only delays the next snowflake until the next one, unless no later tick
+        // fits in the layout
+        if sequence_headroom == 0 && last.timestamp >= layout.max_timestamp() {
+            problems.push(HealthProblem::NoSequenceHeadroom {
+                timestamp: last.timestamp,
+            });
+        }
+
+        HealthReport {
+            sequence_headroom,
+            timestamp_headroom: layout.max_timestamp().saturating_sub(timestamp),
+            problems,
+        }
+    }
+
+    /// Check whether the generator can hand out snowflakes right now, and renew the lease on
+    /// its worker ID
+    ///
+    /// See [`SnowflakeGenerator::health_check`].
+    ///
+    /// # Arguments
+    /// * `lease` - The lease on the generator's worker ID
+    ///
+    /// # Returns
+    /// A [`HealthReport`] listing the problems found, including a failed renewal
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::coordination::{MemoryWorkerRegistry, WorkerLease, WorkerRegistry};
+    /// use rusty_snowflake::SnowflakeGenerator;
+    ///
+    /// let registry = MemoryWorkerRegistry::new();
+    /// let mut lease = registry.lease().unwrap();
+    /// let generator = SnowflakeGenerator::try_new(lease.worker_id()).unwrap();
+    ///
+    /// assert!(generator.health_check_with_lease(&mut lease).is_healthy());
+    /// ```
+    pub fn health_check_with_lease<L: WorkerLease>(&self, lease: &mut L) -> HealthReport {
+        let mut report = self.health_check();
+        let worker_id = self.last_snowflake.worker_id;
+
+        if lease.worker_id() != worker_id {
+            report.problems.push(HealthProblem::Lease(format!(
+                "lease is for worker ID {}, not {}",
+                lease.worker_id(),
+                worker_id
+            )));
+        } else if let Err(err) = lease.renew() {
+            report.problems.push(HealthProblem::Lease(err.to_string()));
+        }
+
+        report
+    }
+
+    /// Wait for the clock to catch up with the last snowflake generated, then check the
+    /// generator's health
+    ///
+    /// Call this before marking a service ready, so a generator restored from a snapshot or
+    /// started after a clock adjustment doesn't hand out its first snowflakes while the clock
+    /// is still behind.
+    ///
+    /// # Arguments
+    /// * `timeout` - The longest to wait for the clock
+    ///
+    /// # Returns
+    /// The [`HealthReport`] after waiting, which still reports the clock as behind if it didn't
+    /// catch up in time
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::{GeneratorState, SnowflakeGenerator};
+    ///
+    /// let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+    /// let state = generator.snapshot();
+    ///
+    /// // The last run got a second ahead of the clock
+    /// generator
+    ///     .restore(GeneratorState::new(state.timestamp() + 1, 0))
+    ///     .unwrap();
+    ///
+    /// assert!(generator.warm_up(Duration::from_secs(3)).is_healthy());
+    /// ```
+    pub fn warm_up(&mut self, timeout: Duration) -> HealthReport {
+        let deadline = Instant::now() + timeout;
+        let last_timestamp = self.last_snowflake.timestamp;
+        let caught_up = self.layout().micros_from_timestamp(last_timestamp);
+
+        loop {
+            let now = self.clock().now_micros();
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if now >= caught_up || remaining.is_zero() {
+                break;
+            }
+
+            std::thread::sleep(remaining.min(Duration::from_micros(caught_up - now)));
+        }
+
+        self.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordination::{CoordinationError, MemoryWorkerRegistry, WorkerRegistry};
+    use crate::{GeneratorState, MockClock, SnowflakeLayout};
+
+    /// A lease that can't be renewed
+    struct LostLease;
+
+    impl WorkerLease for LostLease {
+        fn worker_id(&self) -> u64 {
+            1
+        }
+
+        fn renew(&mut self) -> Result<(), CoordinationError> {
+            Err(CoordinationError::LeaseLost { worker_id: 1 })
+        }
+
+        fn release(self) -> Result<(), CoordinationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sequence_headroom() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock.clone()).unwrap();
+        assert_eq!(generator.health_check().sequence_headroom(), 4095);
+
+        // A full tick under load is only reported as data
+        generator.restore(GeneratorState::new(1_000, 4095)).unwrap();
+        let report = generator.health_check();
+        assert!(report.is_healthy());
+        assert_eq!(report.sequence_headroom(), 0);
+
+        clock.advance(1_000);
+        assert_eq!(generator.health_check().sequence_headroom(), 4096);
+    }
+
+    #[test]
+    fn test_last_tick_full() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(4)
+            .tick_millis(1)
+            .epoch(0)
+            .build()
+            .unwrap();
+        let mut generator =
+            SnowflakeGenerator::with_layout_and_clock(1, layout, MockClock::new(15)).unwrap();
+        assert!(generator.health_check().is_healthy());
+
+        generator
+            .restore(GeneratorState::new(15, layout.max_sequence()))
+            .unwrap();
+        let report = generator.health_check();
+        assert_eq!(
+            report.problems(),
+            [HealthProblem::NoSequenceHeadroom { timestamp: 15 }]
+        );
+        assert_eq!(
+            report.to_string(),
+            "sequence: no sequence numbers left at 15"
+        );
+    }
+
+    #[test]
+    fn test_clock() {
+        let layout = SnowflakeLayout::DISCORD;
+        let clock = MockClock::new(layout.epoch());
+        let generator =
+            SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone()).unwrap();
+        assert!(generator.health_check().is_healthy());
+
+        clock.set(layout.epoch() - 1);
+        assert_eq!(
+            generator.health_check().problems(),
+            [HealthProblem::Clock(SnowflakeError::ClockBeforeEpoch)]
+        );
+
+        clock.set(layout.exhaustion_millis());
+        let report = generator.health_check();
+        assert!(matches!(
+            report.problems(),
+            [HealthProblem::Clock(
+                SnowflakeError::TimestampOverflow { .. }
+            )]
+        ));
+        assert_eq!(report.timestamp_headroom(), 0);
+    }
+
+    #[test]
+    fn test_lease() {
+        let registry = MemoryWorkerRegistry::new();
+        let mut lease = registry.lease().unwrap();
+        let generator = SnowflakeGenerator::with_clock(1, MockClock::new(1_000_000)).unwrap();
+
+        assert_eq!(
+            generator.health_check_with_lease(&mut lease).problems(),
+            [HealthProblem::Lease(
+                "lease is for worker ID 0, not 1".to_string()
+            )]
+        );
+        assert_eq!(
+            generator
+                .health_check_with_lease(&mut LostLease)
+                .to_string(),
+            "lease: lease on worker ID 1 was lost"
+        );
+    }
+
+    #[test]
+    fn test_warm_up_timeout() {
+        let clock = MockClock::new(1_000_000);
+        let mut generator = SnowflakeGenerator::with_clock(1, clock).unwrap();
+        generator.restore(GeneratorState::new(1_060, 0)).unwrap();
+
+        // The mock clock never moves, so the generator is still behind after the timeout
+        let report = generator.warm_up(Duration::from_millis(10));
+        assert!(matches!(
+            report.problems(),
+            [HealthProblem::Clock(
+                SnowflakeError::ClockMovedBackwards { .. }
+            )]
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::explain::Utc;
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+impl Snowflake {
+    /// Write the snowflake as a JSON object, for debug endpoints and enriching logs
+    ///
+    /// The object has the keys `id`, `timestamp_ms`, `datetime`, `worker_id` and `sequence`,
+    /// in that order. The ID is a string, since JSON numbers lose precision past 2^53 in
+    /// JavaScript, and `datetime` is the UTC creation time like `2016-04-30T11:18:25.796Z`.
+    ///
+    /// # Returns
+    /// The JSON object, without whitespace
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::parse(7_130_316_795_809_799);
+    ///
+    /// assert_eq!(
+    ///     snowflake.to_json_detail(),
+    ///     r#"{"id":"7130316795809799","timestamp_ms":1699999999000,"datetime":"2023-11-14T22:13:19.000Z","worker_id":1,"sequence":7}"#
+    /// );
+    /// assert_eq!(Snowflake::from_json_detail(&snowflake.to_json_detail()), Ok(snowflake));
+    /// ```
+    pub fn to_json_detail(self) -> String {
+        self.to_json_detail_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Write the snowflake as a JSON object, using a custom layout
+    ///
+    /// See [`Snowflake::to_json_detail`]. Layouts with
+    /// [extra bits](crate::SnowflakeLayoutBuilder::extra_bits) add an `extra` key at the end.
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the snowflake was parsed with
+    pub fn to_json_detail_with_layout(self, layout: &SnowflakeLayout) -> String {
+        let millis = layout.millis_from_timestamp(self.timestamp);
+        let mut json = String::new();
+
+        // Writing to a `String` can't fail
+        let _ = write!(
+            json,
+            concat!(
+                "{{\"id\":\"{}\",\"timestamp_ms\":{},\"datetime\":\"{}\",",
+                "\"worker_id\":{},\"sequence\":{}"
+            ),
+            self.to_id_with_layout(layout),
+            millis,
+            Utc(millis),
+            self.worker_id,
+            self.sequence
+        );
+        if layout.extra_bits() > 0 {
+            let _ = write!(json, ",\"extra\":{}", self.extra);
+        }
+        json.push('}');
+
+        json
+    }
+
+    /// Read a snowflake from a JSON object written by [`Snowflake::to_json_detail`]
+    ///
+    /// Only `id` is required, as a string or a number. The other keys of
+    /// [`Snowflake::to_json_detail`] are checked against it when they're there, and any other
+    /// keys are ignored, so logs can add their own.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON object
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::InvalidJson` if the JSON isn't a flat object, has no
+    /// `id` or has a key that doesn't match the ID
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let log = r#"{"id": "7130316795809799", "service": "api"}"#;
+    /// assert_eq!(Snowflake::from_json_detail(log).unwrap().worker_id(), 1);
+    ///
+    /// let wrong = r#"{"id": "7130316795809799", "worker_id": 2}"#;
+    /// assert!(Snowflake::from_json_detail(wrong).is_err());
+    /// ```
+    pub fn from_json_detail(json: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::from_json_detail_with_layout(json, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Read a snowflake from a JSON object written by [`Snowflake::to_json_detail_with_layout`]
+    ///
+    /// See [`Snowflake::from_json_detail`].
+    ///
+    /// # Arguments
+    /// * `json` - The JSON object
+    /// * `layout` - The layout the snowflake was written with
+    pub fn from_json_detail_with_layout(
+        json: &str,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        let members = parse_object(json)?;
+
+        let id = match members.iter().rev().find(|(key, _)| key == "id") {
+            Some((_, Value::String(id))) => id.parse::<u64>()?,
+            Some((_, Value::Number(id))) => id.parse::<u64>()?,
+            Some(_) => return Err(invalid("id isn't a string or a number")),
+            None => return Err(invalid("id is missing")),
+        };
+        let snowflake = Snowflake::parse_with_layout(id, layout);
+        let millis = layout.millis_from_timestamp(snowflake.timestamp);
+
+        for (key, value) in &members {
+            let expected = match key.as_str() {
+                "timestamp_ms" => millis,
+                "worker_id" => snowflake.worker_id,
+                "sequence" => snowflake.sequence,
+                "extra" => snowflake.extra,
+                "datetime" => {
+                    if *value != Value::String(Utc(millis).to_string()) {
+                        return Err(invalid(format!("datetime doesn't match ID {}", id)));
+                    }
+                    continue;
+                }
+                // Other keys are ignored
+                _ => continue,
+            };
+
+            if !matches!(value, Value::Number(number) if number.parse() == Ok(expected)) {
+                return Err(invalid(format!("{} doesn't match ID {}", key, id)));
+            }
+        }
+
+        Ok(snowflake)
+    }
+}
+
+/// A value in a flat JSON object
+#[derive(Debug, PartialEq)]
+enum Value<'a> {
+    String(String),
+    Number(&'a str),
+    /// `true`, `false` or `null`
+    Literal,
+}
+
+/// Parse a JSON object whose values are all strings, numbers, booleans or `null`
+///
+/// # Returns
+/// The keys and values in the order they appear
+fn parse_object(json: &str) -> Result<Vec<(String, Value<'_>)>, SnowflakeError> {
+    let mut parser = Parser { json, index: 0 };
+    let mut members = Vec::new();
+
+    parser.expect(b'{')?;
+    if !parser.eat(b'}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            members.push((key, parser.value()?));
+
+            if parser.eat(b'}') {
+                break;
+            }
+            parser.expect(b',')?;
+        }
+    }
+
+    parser.skip_whitespace();
+    if parser.index < json.len() {
+        return Err(invalid("trailing characters after the object"));
+    }
+
+    Ok(members)
+}
+
+/// A cursor over the bytes of a JSON document
+struct Parser<'a> {
+    json: &'a str,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Skip whitespace, then get the next byte without consuming it
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.json.as_bytes().get(self.index).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.json.as_bytes().get(self.index) {
+            self.index += 1;
+        }
+    }
+
+    /// Consume `byte` if it's next
+    fn eat(&mut self, byte: u8) -> bool {
+        let next = self.peek() == Some(byte);
+        if next {
+            self.index += 1;
+        }
+        next
+    }
+
+    /// Consume `byte`, which has to be next
+    fn expect(&mut self, byte: u8) -> Result<(), SnowflakeError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "expected '{}' at byte {}",
+                byte as char, self.index
+            )))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value<'a>, SnowflakeError> {
+        match self.peek() {
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.index;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') =
+                    self.json.as_bytes().get(self.index)
+                {
+                    self.index += 1;
+                }
+                Ok(Value::Number(&self.json[start..self.index]))
+            }
+            _ => {
+                for literal in ["true", "false", "null"] {
+                    if self.json[self.index..].starts_with(literal) {
+                        self.index += literal.len();
+                        return Ok(Value::Literal);
+                    }
+                }
+                Err(invalid(format!("unsupported value at byte {}", self.index)))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SnowflakeError> {
+        self.expect(b'"')?;
+        let mut string = String::new();
+        let mut chars = self.json[self.index..].char_indices();
+
+        while let Some((offset, character)) = chars.next() {
+            match character {
+                '"' => {
+                    self.index += offset + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, escaped)| escaped) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex: String =
+                                chars.by_ref().take(4).map(|(_, digit)| digit).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(invalid("invalid escape in a string")),
+                    };
+                    string.push(escaped);
+                }
+                character => string.push(character),
+            }
+        }
+
+        Err(invalid("unterminated string"))
+    }
+}
+
+/// Make an `InvalidJson` error
+fn invalid(reason: impl Into<String>) -> SnowflakeError {
+    SnowflakeError::InvalidJson(reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .build()
+            .unwrap();
+        let snowflake = Snowflake::builder()
+            .timestamp(1_700_000_000)
+            .worker_id(1023)
+            .sequence(4095)
+            .extra(3)
+            .layout(layout)
+            .build()
+            .unwrap();
+
+        let json = snowflake.to_json_detail_with_layout(&layout);
+        assert!(json.ends_with(",\"worker_id\":1023,\"sequence\":4095,\"extra\":3}"));
+        assert_eq!(
+            Snowflake::from_json_detail_with_layout(&json, &layout),
+            Ok(snowflake)
+        );
+
+        for id in [0, u64::MAX] {
+            let snowflake = Snowflake::parse(id);
+            assert_eq!(
+                Snowflake::from_json_detail(&snowflake.to_json_detail()),
+                Ok(snowflake)
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_json_detail() {
+        let snowflake = Snowflake::parse(7_130_316_795_809_799);
+        let enriched = r#" {
+            "level": "info", "id": 7130316795809799, "ok": true,
+            "msg": "say \"hi\" \u00e9", "datetime": "2023-11-14T22:13:19.000Z"
+        } "#;
+        assert_eq!(Snowflake::from_json_detail(enriched), Ok(snowflake));
+
+        // Only flat objects are supported
+        let nested = r#"{"id": 7130316795809799, "request": {"path": "/"}}"#;
+        assert!(Snowflake::from_json_detail(nested).is_err());
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        for (json, reason) in [
+            ("", "expected '{' at byte 0"),
+            ("{}", "id is missing"),
+            ("{\"id\":null}", "id isn't a string or a number"),
+            (
+                "{\"id\":\"1\",\"sequence\":2}",
+                "sequence doesn't match ID 1",
+            ),
+            (
+                "{\"id\":\"1\",\"sequence\":\"1\"}",
+                "sequence doesn't match ID 1",
+            ),
+            (
+                "{\"id\":\"1\",\"datetime\":\"now\"}",
+                "datetime doesn't match ID 1",
+            ),
+            ("{\"id\":\"1\"} {}", "trailing characters after the object"),
+            ("{\"id\":\"1", "unterminated string"),
+            ("{\"id\":\"\\x\"}", "invalid escape in a string"),
+            ("{\"id\" \"1\"}", "expected ':' at byte 6"),
+        ] {
+            assert_eq!(
+                Snowflake::from_json_detail(json),
+                Err(SnowflakeError::InvalidJson(reason.to_string())),
+                "{}",
+                json
+            );
+        }
+
+        assert!(matches!(
+            Snowflake::from_json_detail("{\"id\":\"-1\"}"),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+    }
+}
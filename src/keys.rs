@@ -0,0 +1,270 @@
+//! Cache and store keys of the form `prefix:id`.
+//!
+//! Keys put the decimal ID after a prefix naming what the key holds, like
+//! `user:175928847299117063` or `session:user:175928847299117063`, the usual convention for Redis
+//! and Memcached keys.
+
+use alloc::string::String;
+
+use crate::{Snowflake, SnowflakeError};
+
+/// The character between the prefix and the ID.
+pub const SEPARATOR: char = ':';
+
+/// The most characters a decimal `u64` takes.
+pub const MAX_ID_LENGTH: usize = 20;
+
+/// Format a key for an ID
+///
+/// # Arguments
+/// * `prefix` - What the key holds, such as `user`
+/// * `id` - The ID
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::keys;
+///
+/// assert_eq!(keys::format_key("user", 175928847299117063), "user:175928847299117063");
+/// ```
+pub fn format_key(prefix: &str, id: u64) -> String {
+    let mut buffer = [0; MAX_ID_LENGTH];
+    let digits = write_digits(id, &mut buffer);
+
+    let mut key = String::with_capacity(prefix.len() + 1 + digits.len());
+    key.push_str(prefix);
+    key.push(SEPARATOR);
+    key.push_str(digits);
+    key
+}
+
+/// Write a key for an ID into a buffer, without allocating
+///
+/// A buffer of `prefix.len() + 1 + MAX_ID_LENGTH` bytes fits any key with the prefix.
+///
+/// # Arguments
+/// * `prefix` - What the key holds, such as `user`
+/// * `id` - The ID
+/// * `buffer` - The buffer to write the key to
+///
+/// # Returns
+/// The key, borrowed from the start of the buffer, or `SnowflakeError::BufferTooSmall` if it
+/// doesn't fit
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::keys;
+///
+/// let mut buffer = [0; 64];
+///
+/// assert_eq!(keys::write_key("user", 42, &mut buffer), Ok("user:42"));
+/// assert!(keys::write_key("user", 42, &mut buffer[..6]).is_err());
+/// ```
+pub fn write_key<'a>(
+    prefix: &str,
+    id: u64,
+    buffer: &'a mut [u8],
+) -> Result<&'a str, SnowflakeError> {
+    let mut digits = [0; MAX_ID_LENGTH];
+    let digits = write_digits(id, &mut digits);
+
+    let length = prefix.len() + 1 + digits.len();
+    if length > buffer.len() {
+        return Err(SnowflakeError::BufferTooSmall {
+            length,
+            capacity: buffer.len(),
+        });
+    }
+
+    let (head, tail) = buffer[..length].split_at_mut(prefix.len());
+    head.copy_from_slice(prefix.as_bytes());
+    tail[0] = SEPARATOR as u8;
+    tail[1..].copy_from_slice(digits.as_bytes());
+
+    // The prefix is a str and the rest is ASCII
+    Ok(core::str::from_utf8(&buffer[..length]).unwrap_or_default())
+}
+
+/// Parse the ID out of a key with a known prefix
+///
+/// # Arguments
+/// * `prefix` - The prefix the key should have
+/// * `key` - The key to parse
+///
+/// # Returns
+/// The ID, or `SnowflakeError::KeyPrefixMismatch` if the key doesn't start with the prefix and
+/// separator, or `SnowflakeError::ParseInt` if the rest isn't a decimal `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::keys;
+///
+/// assert_eq!(keys::parse_key("user", "user:42"), Ok(42));
+/// assert!(keys::parse_key("user", "team:42").is_err());
+/// ```
+pub fn parse_key(prefix: &str, key: &str) -> Result<u64, SnowflakeError> {
+    Ok(strip_prefix(prefix, key)?.parse()?)
+}
+
+/// Split a key into its prefix and ID at the last separator
+///
+/// # Arguments
+/// * `key` - The key to split
+///
+/// # Returns
+/// The prefix and the ID, or `SnowflakeError::KeyPrefixMismatch` if the key has no separator,
+/// or `SnowflakeError::ParseInt` if the part after it isn't a decimal `u64`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::keys;
+///
+/// assert_eq!(keys::split_key("session:user:42"), Ok(("session:user", 42)));
+/// ```
+pub fn split_key(key: &str) -> Result<(&str, u64), SnowflakeError> {
+    let (prefix, id) = key
+        .rsplit_once(SEPARATOR)
+        .ok_or(SnowflakeError::KeyPrefixMismatch)?;
+
+    Ok((prefix, id.parse()?))
+}
+
+impl Snowflake {
+    /// Format a key for the snowflake's ID
+    ///
+    /// See [`format_key`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::parse(175928847299117063);
+    ///
+    /// assert_eq!(snowflake.to_key("user"), "user:175928847299117063");
+    /// assert_eq!(Snowflake::from_key("user", &snowflake.to_key("user")), Ok(snowflake));
+    /// ```
+    pub fn to_key(self, prefix: &str) -> String {
+        format_key(prefix, self.to_id())
+    }
+
+    /// Parse a snowflake out of a key with a known prefix
+    ///
+    /// See [`parse_key`]. Like [`Snowflake::parse_str`], this also returns
+    /// `SnowflakeError::ReservedBitsSet` if the ID uses bits above the timestamp of
+    /// [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT).
+    pub fn from_key(prefix: &str, key: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::parse_str(strip_prefix(prefix, key)?)
+    }
+}
+
+/// Get the part of `key` after `prefix` and the separator
+fn strip_prefix<'a>(prefix: &str, key: &'a str) -> Result<&'a str, SnowflakeError> {
+    key.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix(SEPARATOR))
+        .ok_or(SnowflakeError::KeyPrefixMismatch)
+}
+
+/// The two-digit decimal numbers `00` to `99`, back to back.
+const DIGIT_PAIRS: &[u8; 200] = b"\
+0001020304050607080910111213141516171819\
+2021222324252627282930313233343536373839\
+4041424344454647484950515253545556575859\
+6061626364656667686970717273747576777879\
+8081828384858687888990919293949596979899";
+
+/// Write the decimal digits of `id` to the end of `buffer`, two at a time
+///
+/// # Returns
+/// The digits, borrowed from the buffer
+pub(crate) fn write_digits(mut id: u64, buffer: &mut [u8; MAX_ID_LENGTH]) -> &str {
+    let mut start = MAX_ID_LENGTH;
+    while id >= 10 {
+        let pair = (id % 100) as usize * 2;
+        id /= 100;
+        start -= 2;
+        buffer[start..start + 2].copy_from_slice(&DIGIT_PAIRS[pair..pair + 2]);
+    }
+    // The last digit, unless the pairs used up the whole number
+    if id > 0 || start == MAX_ID_LENGTH {
+        start -= 1;
+        buffer[start] = b'0' + id as u8;
+    }
+
+    // The digits are ASCII
+    core::str::from_utf8(&buffer[start..]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_format_and_write_agree() {
+        let mut buffer = [0; 4 + 1 + MAX_ID_LENGTH];
+
+        for id in [
+            0,
+            9,
+            10,
+            99,
+            100,
+            1_000,
+            12_345,
+            175928847299117063,
+            u64::MAX,
+        ] {
+            let key = format_key("user", id);
+            assert_eq!(key, alloc::format!("user:{}", id));
+            assert_eq!(write_key("user", id, &mut buffer), Ok(key.as_str()));
+            assert_eq!(parse_key("user", &key), Ok(id));
+        }
+    }
+
+    #[test]
+    fn test_write_key_buffer_too_small() {
+        let mut buffer = [0; 8];
+
+        assert_eq!(
+            write_key("user", 12345, &mut buffer),
+            Err(SnowflakeError::BufferTooSmall {
+                length: 10,
+                capacity: 8
+            })
+        );
+        assert_eq!(write_key("", 1234567, &mut buffer), Ok(":1234567"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_key("user", "users:1"),
+            Err(SnowflakeError::KeyPrefixMismatch)
+        );
+        assert_eq!(
+            parse_key("user", "user1"),
+            Err(SnowflakeError::KeyPrefixMismatch)
+        );
+        assert!(matches!(
+            parse_key("user", "user:-1"),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+        assert_eq!(split_key("42"), Err(SnowflakeError::KeyPrefixMismatch));
+        assert_eq!(split_key(":42"), Ok(("", 42)));
+    }
+
+    #[test]
+    fn test_snowflake_keys() {
+        let snowflake = Snowflake::parse(175928847299117063);
+
+        assert_eq!(
+            Snowflake::from_key("a:b", &snowflake.to_key("a:b")),
+            Ok(snowflake)
+        );
+        assert_eq!(
+            Snowflake::from_key("user", &("user:".to_string() + &u64::MAX.to_string())),
+            Err(SnowflakeError::ReservedBitsSet { id: u64::MAX })
+        );
+    }
+}
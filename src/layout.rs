@@ -0,0 +1,1017 @@
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// Describes how the fields of a snowflake are packed into a `u64` ID.
+///
+/// From the most significant bit to the least significant bit an ID is laid out as
+/// `timestamp | extra | worker_id | sequence`, or `timestamp | extra | sequence | worker_id`
+/// with [`FieldOrder::SequenceWorkerId`]. Any bits left over above the timestamp are always
+/// zero.
+///
+/// The timestamp counts ticks since `epoch`, which is itself given in milliseconds since the
+/// UNIX epoch. Ticks are usually one [`TimeUnit`] long: longer ticks make the timestamp last
+/// longer, and shorter ticks give each worker more sequence numbers per second.
+///
+/// The worker ID is further split into a datacenter ID in its high bits and a machine ID in its
+/// low bits, like the original Twitter snowflake. Multi-region deployments can use the
+/// datacenter ID as a region code, so every ID can be traced back to the region it came from.
+///
+/// The extra field is empty unless the layout is built with
+/// [`SnowflakeLayoutBuilder::extra_bits`]. It holds a value chosen by the caller for each ID,
+/// such as the type of the thing the ID names, which can be read back when the ID is parsed.
+pub struct SnowflakeLayout {
+    timestamp_bits: u8,
+    worker_id_bits: u8,
+    sequence_bits: u8,
+    extra_bits: u8,
+    datacenter_id_bits: u8,
+    epoch: u64,
+    tick_micros: u64,
+    field_order: FieldOrder,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// A common length of timestamp tick.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{SnowflakeLayout, TimeUnit};
+///
+/// let layout = SnowflakeLayout::builder()
+///     .time_unit(TimeUnit::Centiseconds)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(layout.time_unit(), Some(TimeUnit::Centiseconds));
+/// assert_eq!(SnowflakeLayout::SONYFLAKE.time_unit(), Some(TimeUnit::Centiseconds));
+/// ```
+pub enum TimeUnit {
+    /// One second, the tick of [`SnowflakeLayout::DEFAULT`].
+    Seconds,
+    /// Ten milliseconds, the tick of Sonyflake.
+    Centiseconds,
+    /// One millisecond, the tick of most snowflake formats.
+    Milliseconds,
+    /// One microsecond.
+    Microseconds,
+}
+
+impl TimeUnit {
+    /// The number of microseconds in one unit
+    pub const fn micros(self) -> u64 {
+        match self {
+            TimeUnit::Seconds => 1_000_000,
+            TimeUnit::Centiseconds => 10_000,
+            TimeUnit::Milliseconds => 1_000,
+            TimeUnit::Microseconds => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+/// The order of the worker ID and sequence fields below the timestamp.
+pub enum FieldOrder {
+    /// `timestamp | worker_id | sequence`, used by most snowflake formats.
+    #[default]
+    WorkerIdSequence,
+    /// `timestamp | sequence | worker_id`, used by Sonyflake.
+    SequenceWorkerId,
+}
+
+impl SnowflakeLayout {
+    /// The layout used by [`Snowflake::to_id`](crate::Snowflake::to_id) and
+    /// [`Snowflake::parse`](crate::Snowflake::parse).
+    ///
+    /// 41 bits of timestamp in seconds since the UNIX epoch, 10 bits of worker ID and 12 bits
+    /// of sequence. The worker ID holds a 5 bit datacenter ID and a 5 bit machine ID.
+    pub const DEFAULT: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 41,
+        worker_id_bits: 10,
+        sequence_bits: 12,
+        extra_bits: 0,
+        datacenter_id_bits: 5,
+        epoch: 0,
+        tick_micros: TimeUnit::Seconds.micros(),
+        field_order: FieldOrder::WorkerIdSequence,
+    };
+
+    /// The layout of Discord IDs.
+    ///
+    /// 42 bits of timestamp in milliseconds since 2015-01-01, 10 bits of worker ID and 12 bits
+    /// of sequence. Discord splits the worker ID into a 5 bit internal worker ID and a 5 bit
+    /// process ID, see the [`discord`](crate::discord) module.
+    pub const DISCORD: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 42,
+        worker_id_bits: 10,
+        sequence_bits: 12,
+        extra_bits: 0,
+        datacenter_id_bits: 5,
+        epoch: 1_420_070_400_000,
+        tick_micros: TimeUnit::Milliseconds.micros(),
+        field_order: FieldOrder::WorkerIdSequence,
+    };
+
+    /// The layout of classic Twitter/X IDs.
+    ///
+    /// 41 bits of timestamp in milliseconds since 2010-11-04 01:42:54.657 UTC, 10 bits of
+    /// worker ID, made up of a 5 bit datacenter ID and a 5 bit machine ID, and 12 bits of
+    /// sequence.
+    pub const TWITTER: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 41,
+        worker_id_bits: 10,
+        sequence_bits: 12,
+        extra_bits: 0,
+        datacenter_id_bits: 5,
+        epoch: 1_288_834_974_657,
+        tick_micros: TimeUnit::Milliseconds.micros(),
+        field_order: FieldOrder::WorkerIdSequence,
+    };
+
+    /// The layout of Instagram IDs.
+    ///
+    /// 41 bits of timestamp in milliseconds since 2011-08-24 21:07:01.721 UTC, 13 bits of
+    /// logical shard ID and 10 bits of sequence. See
+    /// [`ShardedGenerator`](crate::ShardedGenerator).
+    pub const INSTAGRAM: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 41,
+        worker_id_bits: 13,
+        sequence_bits: 10,
+        extra_bits: 0,
+        datacenter_id_bits: 0,
+        epoch: 1_314_220_021_721,
+        tick_micros: TimeUnit::Milliseconds.micros(),
+        field_order: FieldOrder::WorkerIdSequence,
+    };
+
+    /// The layout of Sonyflake IDs.
+    ///
+    /// 39 bits of timestamp in units of 10 milliseconds since 2014-09-01, 8 bits of sequence
+    /// and 16 bits of machine ID, with the sequence above the machine ID.
+    pub const SONYFLAKE: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 39,
+        worker_id_bits: 16,
+        sequence_bits: 8,
+        extra_bits: 0,
+        datacenter_id_bits: 0,
+        epoch: 1_409_529_600_000,
+        tick_micros: TimeUnit::Centiseconds.micros(),
+        field_order: FieldOrder::SequenceWorkerId,
+    };
+
+    /// A layout without a worker ID, for a single process that wants as many IDs per tick as
+    /// possible, such as coarse event keys.
+    ///
+    /// 41 bits of timestamp in milliseconds since 2024-01-01, which last until 2093, and 22
+    /// bits of sequence, so over four million IDs per millisecond. The worker ID is zero bits
+    /// wide, so the only worker ID is `0`, and generators, registries and worker ID sources
+    /// handle it like any other layout.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// let layout = SnowflakeLayout::SINGLE_PROCESS;
+    /// let mut generator = SnowflakeGenerator::with_layout(0, layout).unwrap();
+    ///
+    /// assert_eq!(layout.max_worker_id(), 0);
+    /// assert_eq!(layout.max_sequence(), (1 << 22) - 1);
+    /// assert!(SnowflakeGenerator::with_layout(1, layout).is_err());
+    /// assert_eq!(generator.try_next_batch(10_000).unwrap().len(), 10_000);
+    /// ```
+    pub const SINGLE_PROCESS: SnowflakeLayout = SnowflakeLayout {
+        timestamp_bits: 41,
+        worker_id_bits: 0,
+        sequence_bits: 22,
+        extra_bits: 0,
+        datacenter_id_bits: 0,
+        epoch: 1_704_067_200_000,
+        tick_micros: TimeUnit::Milliseconds.micros(),
+        field_order: FieldOrder::WorkerIdSequence,
+    };
+
+    /// Create a builder for a custom layout
+    ///
+    /// The builder starts out as [`SnowflakeLayout::DEFAULT`].
+    ///
+    /// # Returns
+    /// A new `SnowflakeLayoutBuilder`
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .worker_id_bits(16)
+    ///     .sequence_bits(8)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(layout.max_worker_id(), 0xFFFF);
+    /// ```
+    pub fn builder() -> SnowflakeLayoutBuilder {
+        SnowflakeLayoutBuilder {
+            layout: SnowflakeLayout::DEFAULT,
+        }
+    }
+
+    /// Create a custom layout whose field widths are checked at compile time
+    ///
+    /// The widths must add up to 63 or 64 bits, or the code doesn't compile. The rest of the
+    /// layout is [`SnowflakeLayout::DEFAULT`], without a datacenter ID, and can be changed with
+    /// [`SnowflakeLayout::with_epoch`] and [`SnowflakeLayout::with_time_unit`]. Assigned to a
+    /// `const`, the layout's shifts and masks are worked out by the compiler.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{SnowflakeLayout, TimeUnit};
+    ///
+    /// const LAYOUT: SnowflakeLayout = SnowflakeLayout::custom::<41, 10, 12>()
+    ///     .with_epoch(1_700_000_000_000)
+    ///     .with_time_unit(TimeUnit::Milliseconds);
+    ///
+    /// assert_eq!(LAYOUT.timestamp_shift(), 22);
+    /// assert_eq!(LAYOUT.max_worker_id(), 1023);
+    /// ```
+    ///
+    /// ```rust,compile_fail
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// // 65 bits don't fit in an ID
+    /// const LAYOUT: SnowflakeLayout = SnowflakeLayout::custom::<42, 11, 12>();
+    /// ```
+    pub const fn custom<
+        const TIMESTAMP_BITS: u8,
+        const WORKER_ID_BITS: u8,
+        const SEQUENCE_BITS: u8,
+    >() -> SnowflakeLayout {
+        const {
+            let total = TIMESTAMP_BITS as u32 + WORKER_ID_BITS as u32 + SEQUENCE_BITS as u32;
+            assert!(
+                total == 63 || total == 64,
+                "layout fields must add up to 63 or 64 bits"
+            );
+        }
+
+        SnowflakeLayout {
+            timestamp_bits: TIMESTAMP_BITS,
+            worker_id_bits: WORKER_ID_BITS,
+            sequence_bits: SEQUENCE_BITS,
+            datacenter_id_bits: 0,
+            ..SnowflakeLayout::DEFAULT
+        }
+    }
+
+    /// Change the epoch of the layout
+    ///
+    /// # Arguments
+    /// * `epoch` - The epoch in milliseconds since the UNIX epoch
+    pub const fn with_epoch(self, epoch: u64) -> SnowflakeLayout {
+        SnowflakeLayout { epoch, ..self }
+    }
+
+    /// Change the length of one timestamp tick to a unit of time
+    ///
+    /// # Arguments
+    /// * `unit` - The unit the timestamp counts
+    pub const fn with_time_unit(self, unit: TimeUnit) -> SnowflakeLayout {
+        SnowflakeLayout {
+            tick_micros: unit.micros(),
+            ..self
+        }
+    }
+
+    /// The number of bits used by the timestamp
+    pub const fn timestamp_bits(&self) -> u8 {
+        self.timestamp_bits
+    }
+
+    /// The number of bits used by the worker ID
+    pub const fn worker_id_bits(&self) -> u8 {
+        self.worker_id_bits
+    }
+
+    /// The number of bits used by the sequence number
+    pub const fn sequence_bits(&self) -> u8 {
+        self.sequence_bits
+    }
+
+    /// The number of bits used by the extra field
+    pub const fn extra_bits(&self) -> u8 {
+        self.extra_bits
+    }
+
+    /// The number of high worker ID bits used by the datacenter ID
+    ///
+    /// This is capped at the number of worker ID bits.
+    pub const fn datacenter_id_bits(&self) -> u8 {
+        if self.datacenter_id_bits < self.worker_id_bits {
+            self.datacenter_id_bits
+        } else {
+            self.worker_id_bits
+        }
+    }
+
+    /// The number of low worker ID bits used by the machine ID
+    pub const fn machine_id_bits(&self) -> u8 {
+        self.worker_id_bits - self.datacenter_id_bits()
+    }
+
+    /// The order of the worker ID and sequence fields
+    pub const fn field_order(&self) -> FieldOrder {
+        self.field_order
+    }
+
+    /// The epoch timestamps are counted from, in milliseconds since the UNIX epoch
+    pub const fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The number of milliseconds in one timestamp tick, rounded down
+    ///
+    /// This is zero for ticks shorter than a millisecond, see [`SnowflakeLayout::tick_micros`].
+    pub const fn tick_millis(&self) -> u64 {
+        self.tick_micros / 1_000
+    }
+
+    /// The number of microseconds in one timestamp tick
+    pub const fn tick_micros(&self) -> u64 {
+        self.tick_micros
+    }
+
+    /// The unit the timestamp is counted in, if the tick is exactly one [`TimeUnit`] long
+    pub const fn time_unit(&self) -> Option<TimeUnit> {
+        match self.tick_micros {
+            1_000_000 => Some(TimeUnit::Seconds),
+            10_000 => Some(TimeUnit::Centiseconds),
+            1_000 => Some(TimeUnit::Milliseconds),
+            1 => Some(TimeUnit::Microseconds),
+            _ => None,
+        }
+    }
+
+    /// Convert a time in milliseconds since the UNIX epoch into a timestamp for this layout
+    ///
+    /// Times before the layout's epoch become `0`.
+    ///
+    /// # Arguments
+    /// * `millis` - The time in milliseconds since the UNIX epoch
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// assert_eq!(SnowflakeLayout::DEFAULT.timestamp_from_millis(1_500), 1);
+    /// assert_eq!(SnowflakeLayout::DISCORD.timestamp_from_millis(1_420_070_400_042), 42);
+    /// ```
+    pub const fn timestamp_from_millis(&self, millis: u64) -> u64 {
+        self.timestamp_from_micros(millis.saturating_mul(1_000))
+    }
+
+    /// Convert a time in microseconds since the UNIX epoch into a timestamp for this layout
+    ///
+    /// Times before the layout's epoch become `0`.
+    ///
+    /// # Arguments
+    /// * `micros` - The time in microseconds since the UNIX epoch
+    pub const fn timestamp_from_micros(&self, micros: u64) -> u64 {
+        let since_epoch = (micros as u128).saturating_sub(self.epoch as u128 * 1_000);
+        (since_epoch / self.tick_micros as u128) as u64
+    }
+
+    /// Convert a timestamp for this layout into milliseconds since the UNIX epoch
+    ///
+    /// The time is rounded down to the millisecond, and saturates at `u64::MAX`.
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in ticks since the layout's epoch
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// assert_eq!(SnowflakeLayout::DEFAULT.millis_from_timestamp(1), 1_000);
+    /// assert_eq!(SnowflakeLayout::DISCORD.millis_from_timestamp(42), 1_420_070_400_042);
+    /// ```
+    pub const fn millis_from_timestamp(&self, timestamp: u64) -> u64 {
+        saturate(self.micros_since_unix_epoch(timestamp) / 1_000)
+    }
+
+    /// Convert a timestamp for this layout into microseconds since the UNIX epoch
+    ///
+    /// The time saturates at `u64::MAX`.
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in ticks since the layout's epoch
+    pub const fn micros_from_timestamp(&self, timestamp: u64) -> u64 {
+        saturate(self.micros_since_unix_epoch(timestamp))
+    }
+
+    /// The start of a tick in microseconds since the UNIX epoch, which can take more than 64 bits
+    const fn micros_since_unix_epoch(&self, timestamp: u64) -> u128 {
+        let since_epoch = timestamp as u128 * self.tick_micros as u128;
+        since_epoch.saturating_add(self.epoch as u128 * 1_000)
+    }
+
+    /// Check whether this layout could have packed an ID
+    ///
+    /// This is the check [`Snowflake::try_parse_with_layout`](crate::Snowflake::try_parse_with_layout)
+    /// makes, as a `bool` so it can be used in constants.
+    ///
+    /// # Arguments
+    /// * `id` - The ID to check
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// assert!(SnowflakeLayout::DEFAULT.can_pack(175928847299117063));
+    /// assert!(!SnowflakeLayout::DEFAULT.can_pack(1 << 63));
+    /// assert!(SnowflakeLayout::DISCORD.can_pack(1 << 63));
+    /// ```
+    pub const fn can_pack(&self, id: u64) -> bool {
+        shr(id, self.timestamp_shift()) <= self.max_representable_timestamp()
+    }
+
+    /// The largest timestamp that fits in this layout and whose time fits in `u64` microseconds
+    /// since the UNIX epoch
+    pub(crate) const fn max_representable_timestamp(&self) -> u64 {
+        let micros = (u64::MAX as u128).saturating_sub(self.epoch as u128 * 1_000);
+        let ticks = micros / self.tick_micros as u128;
+        if ticks < self.max_timestamp() as u128 {
+            ticks as u64
+        } else {
+            self.max_timestamp()
+        }
+    }
+
+    /// The first time that no longer fits in this layout's timestamp, in milliseconds since the
+    /// UNIX epoch
+    ///
+    /// Generators return `SnowflakeError::TimestampOverflow` from this time on. Layouts that
+    /// outlast `u64` milliseconds report `u64::MAX`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// // 2^42 milliseconds after the Discord epoch, in 2154
+    /// assert_eq!(SnowflakeLayout::DISCORD.exhaustion_millis(), 5_818_116_911_104);
+    /// ```
+    pub const fn exhaustion_millis(&self) -> u64 {
+        self.millis_from_timestamp(self.max_timestamp().saturating_add(1))
+    }
+
+    /// The first time that no longer fits in this layout's timestamp
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::SystemTime;
+    /// use rusty_snowflake::SnowflakeLayout;
+    ///
+    /// assert!(SnowflakeLayout::TWITTER.exhaustion_date() > SystemTime::now());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn exhaustion_date(&self) -> SystemTime {
+        let timestamp = self.max_timestamp().saturating_add(1);
+        UNIX_EPOCH + Duration::from_micros(self.micros_from_timestamp(timestamp))
+    }
+
+    /// The offset of the worker ID from the least significant bit
+    pub const fn worker_id_shift(&self) -> u32 {
+        match self.field_order {
+            FieldOrder::WorkerIdSequence => self.sequence_bits as u32,
+            FieldOrder::SequenceWorkerId => 0,
+        }
+    }
+
+    /// The offset of the sequence number from the least significant bit
+    pub const fn sequence_shift(&self) -> u32 {
+        match self.field_order {
+            FieldOrder::WorkerIdSequence => 0,
+            FieldOrder::SequenceWorkerId => self.worker_id_bits as u32,
+        }
+    }
+
+    /// The offset of the extra field from the least significant bit
+    pub const fn extra_shift(&self) -> u32 {
+        self.sequence_bits as u32 + self.worker_id_bits as u32
+    }
+
+    /// The offset of the timestamp from the least significant bit
+    pub const fn timestamp_shift(&self) -> u32 {
+        self.extra_shift() + self.extra_bits as u32
+    }
+
+    /// The largest timestamp that fits in this layout
+    pub const fn max_timestamp(&self) -> u64 {
+        mask(self.timestamp_bits)
+    }
+
+    /// The largest worker ID that fits in this layout
+    pub const fn max_worker_id(&self) -> u64 {
+        mask(self.worker_id_bits)
+    }
+
+    /// The largest sequence number that fits in this layout
+    pub const fn max_sequence(&self) -> u64 {
+        mask(self.sequence_bits)
+    }
+
+    /// The largest extra value that fits in this layout
+    pub const fn max_extra(&self) -> u64 {
+        mask(self.extra_bits)
+    }
+
+    /// The largest datacenter ID that fits in this layout
+    pub const fn max_datacenter_id(&self) -> u64 {
+        mask(self.datacenter_id_bits())
+    }
+
+    /// The largest machine ID that fits in this layout
+    pub const fn max_machine_id(&self) -> u64 {
+        mask(self.machine_id_bits())
+    }
+}
+
+impl Default for SnowflakeLayout {
+    fn default() -> SnowflakeLayout {
+        SnowflakeLayout::DEFAULT
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// A builder for [`SnowflakeLayout`].
+pub struct SnowflakeLayoutBuilder {
+    layout: SnowflakeLayout,
+}
+
+impl SnowflakeLayoutBuilder {
+    /// Set the number of bits used by the timestamp
+    pub fn timestamp_bits(mut self, bits: u8) -> SnowflakeLayoutBuilder {
+        self.layout.timestamp_bits = bits;
+        self
+    }
+
+    /// Set the number of bits used by the worker ID
+    pub fn worker_id_bits(mut self, bits: u8) -> SnowflakeLayoutBuilder {
+        self.layout.worker_id_bits = bits;
+        self
+    }
+
+    /// Set the number of bits used by the sequence number
+    pub fn sequence_bits(mut self, bits: u8) -> SnowflakeLayoutBuilder {
+        self.layout.sequence_bits = bits;
+        self
+    }
+
+    /// Set the number of bits used by the extra field, between the timestamp and the worker ID
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// // Take 2 bits from the timestamp to tell users, messages and files apart
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .extra_bits(2)
+    ///     .build()
+    ///     .unwrap();
+    /// const MESSAGE: u64 = 1;
+    ///
+    /// let mut generator = SnowflakeGenerator::with_layout(42, layout).unwrap();
+    /// let id = generator.try_next_with_extra(MESSAGE).unwrap().to_id_with_layout(&layout);
+    ///
+    /// assert_eq!(Snowflake::parse_with_layout(id, &layout).extra(), MESSAGE);
+    /// ```
+    pub fn extra_bits(mut self, bits: u8) -> SnowflakeLayoutBuilder {
+        self.layout.extra_bits = bits;
+        self
+    }
+
+    /// Set how many of the worker ID bits hold the datacenter ID; the rest hold the machine ID
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeGenerator, SnowflakeLayout};
+    ///
+    /// // Up to 8 regions with 128 workers each
+    /// let layout = SnowflakeLayout::builder().datacenter_id_bits(3).build().unwrap();
+    /// const EU_WEST: u64 = 5;
+    ///
+    /// let mut generator = SnowflakeGenerator::with_datacenter_and_layout(EU_WEST, 42, layout)
+    ///     .unwrap();
+    /// let id = generator.next().to_id_with_layout(&layout);
+    ///
+    /// let snowflake = Snowflake::parse_with_layout(id, &layout);
+    /// assert_eq!(snowflake.datacenter_id_with_layout(&layout), EU_WEST);
+    /// assert_eq!(snowflake.machine_id_with_layout(&layout), 42);
+    /// ```
+    pub fn datacenter_id_bits(mut self, bits: u8) -> SnowflakeLayoutBuilder {
+        self.layout.datacenter_id_bits = bits;
+        self
+    }
+
+    /// Set the epoch timestamps are counted from
+    ///
+    /// # Arguments
+    /// * `epoch` - The epoch in milliseconds since the UNIX epoch
+    pub fn epoch(mut self, epoch: u64) -> SnowflakeLayoutBuilder {
+        self.layout.epoch = epoch;
+        self
+    }
+
+    /// Set the number of milliseconds in one timestamp tick
+    pub fn tick_millis(mut self, millis: u64) -> SnowflakeLayoutBuilder {
+        self.layout.tick_micros = millis.saturating_mul(1_000);
+        self
+    }
+
+    /// Set the number of microseconds in one timestamp tick
+    pub fn tick_micros(mut self, micros: u64) -> SnowflakeLayoutBuilder {
+        self.layout.tick_micros = micros;
+        self
+    }
+
+    /// Set the timestamp tick to one unit of time
+    pub fn time_unit(mut self, unit: TimeUnit) -> SnowflakeLayoutBuilder {
+        self.layout.tick_micros = unit.micros();
+        self
+    }
+
+    /// Set the order of the worker ID and sequence fields
+    pub fn field_order(mut self, order: FieldOrder) -> SnowflakeLayoutBuilder {
+        self.layout.field_order = order;
+        self
+    }
+
+    /// Build the layout
+    ///
+    /// # Returns
+    /// The `SnowflakeLayout`, or a `LayoutError` if the fields don't fit in 64 bits or the tick
+    /// is zero microseconds long
+    pub fn build(self) -> Result<SnowflakeLayout, LayoutError> {
+        if self.layout.tick_micros == 0 {
+            return Err(LayoutError::ZeroTick);
+        }
+
+        let total = self.layout.timestamp_bits as u32
+            + self.layout.worker_id_bits as u32
+            + self.layout.sequence_bits as u32
+            + self.layout.extra_bits as u32;
+
+        if total > 64 {
+            return Err(LayoutError::TooManyBits { total });
+        }
+
+        Ok(self.layout)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// An error returned when building an invalid [`SnowflakeLayout`].
+pub enum LayoutError {
+    /// The fields add up to more than the 64 bits available in an ID.
+    TooManyBits {
+        /// The total number of bits requested
+        total: u32,
+    },
+    /// The timestamp tick is zero microseconds long.
+    ZeroTick,
+}
+
+impl core::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            LayoutError::TooManyBits { total } => {
+                write!(f, "layout uses {} bits but an ID only has 64", total)
+            }
+            LayoutError::ZeroTick => write!(f, "timestamp tick must be at least 1 microsecond"),
+        }
+    }
+}
+
+impl core::error::Error for LayoutError {}
+
+/// Clamp a `u128` to the `u64` range
+const fn saturate(value: u128) -> u64 {
+    if value > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        value as u64
+    }
+}
+
+/// Get a mask covering the lowest `bits` bits
+pub(crate) const fn mask(bits: u8) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// Shift `value` left, discarding it entirely if the shift is 64 bits or more
+pub(crate) const fn shl(value: u64, shift: u32) -> u64 {
+    match value.checked_shl(shift) {
+        Some(shifted) => shifted,
+        None => 0,
+    }
+}
+
+/// Shift `value` right, returning zero if the shift is 64 bits or more
+pub(crate) const fn shr(value: u64, shift: u32) -> u64 {
+    match value.checked_shr(shift) {
+        Some(shifted) => shifted,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let layout = SnowflakeLayout::default();
+        assert_eq!(layout, SnowflakeLayout::DEFAULT);
+        assert_eq!(layout.worker_id_shift(), 12);
+        assert_eq!(layout.timestamp_shift(), 22);
+        assert_eq!(layout.max_sequence(), 0xFFF);
+        assert_eq!(layout.max_worker_id(), 0x3FF);
+        assert_eq!(layout.max_timestamp(), 0x1FFFFFFFFFF);
+    }
+
+    #[test]
+    fn test_builder() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .worker_id_bits(16)
+            .sequence_bits(8)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.timestamp_bits(), 39);
+        assert_eq!(layout.worker_id_bits(), 16);
+        assert_eq!(layout.sequence_bits(), 8);
+        assert_eq!(layout.worker_id_shift(), 8);
+        assert_eq!(layout.timestamp_shift(), 24);
+    }
+
+    #[test]
+    fn test_custom() {
+        const LAYOUT: SnowflakeLayout = SnowflakeLayout::custom::<39, 8, 16>()
+            .with_epoch(1_409_529_600_000)
+            .with_time_unit(TimeUnit::Centiseconds);
+
+        assert_eq!(
+            SnowflakeLayout::builder()
+                .timestamp_bits(39)
+                .worker_id_bits(8)
+                .sequence_bits(16)
+                .datacenter_id_bits(0)
+                .epoch(1_409_529_600_000)
+                .time_unit(TimeUnit::Centiseconds)
+                .build(),
+            Ok(LAYOUT)
+        );
+        assert_eq!(
+            SnowflakeLayout::custom::<42, 10, 12>().timestamp_shift(),
+            22
+        );
+    }
+
+    #[test]
+    fn test_extra_bits() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(38)
+            .extra_bits(3)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.extra_shift(), 22);
+        assert_eq!(layout.timestamp_shift(), 25);
+        assert_eq!(layout.max_extra(), 7);
+        assert_eq!(SnowflakeLayout::DEFAULT.max_extra(), 0);
+        assert_eq!(
+            SnowflakeLayout::builder().extra_bits(2).build(),
+            Err(LayoutError::TooManyBits { total: 65 })
+        );
+    }
+
+    #[test]
+    fn test_builder_too_many_bits() {
+        let result = SnowflakeLayout::builder()
+            .timestamp_bits(48)
+            .worker_id_bits(16)
+            .sequence_bits(8)
+            .build();
+
+        assert_eq!(result, Err(LayoutError::TooManyBits { total: 72 }));
+    }
+
+    #[test]
+    fn test_builder_zero_tick() {
+        let result = SnowflakeLayout::builder().tick_millis(0).build();
+        assert_eq!(result, Err(LayoutError::ZeroTick));
+    }
+
+    #[test]
+    fn test_epoch_and_tick() {
+        let layout = SnowflakeLayout::builder()
+            .epoch(1_000_000)
+            .tick_millis(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.timestamp_from_millis(1_000_095), 9);
+        assert_eq!(layout.timestamp_from_millis(999_999), 0);
+        assert_eq!(layout.millis_from_timestamp(9), 1_000_090);
+    }
+
+    #[test]
+    fn test_time_unit() {
+        assert_eq!(
+            SnowflakeLayout::DEFAULT.time_unit(),
+            Some(TimeUnit::Seconds)
+        );
+        assert_eq!(
+            SnowflakeLayout::DISCORD.time_unit(),
+            Some(TimeUnit::Milliseconds)
+        );
+        assert_eq!(
+            SnowflakeLayout::builder()
+                .tick_millis(5)
+                .build()
+                .unwrap()
+                .time_unit(),
+            None
+        );
+
+        let layout = SnowflakeLayout::builder()
+            .time_unit(TimeUnit::Microseconds)
+            .epoch(1_000)
+            .build()
+            .unwrap();
+        assert_eq!(layout.tick_micros(), 1);
+        assert_eq!(layout.tick_millis(), 0);
+        assert_eq!(layout.timestamp_from_micros(1_000_042), 42);
+        assert_eq!(layout.timestamp_from_millis(1_001), 1_000);
+        assert_eq!(layout.micros_from_timestamp(42), 1_000_042);
+        assert_eq!(layout.millis_from_timestamp(1_999), 1_001);
+
+        let layout = SnowflakeLayout::builder()
+            .time_unit(TimeUnit::Centiseconds)
+            .build()
+            .unwrap();
+        assert_eq!(layout.timestamp_from_millis(1_234), 123);
+        assert_eq!(layout.millis_from_timestamp(123), 1_230);
+    }
+
+    #[test]
+    fn test_discord() {
+        let layout = SnowflakeLayout::DISCORD;
+        assert_eq!(layout.timestamp_shift(), 22);
+        assert_eq!(layout.max_timestamp(), (1 << 42) - 1);
+        assert_eq!(layout.epoch(), 1_420_070_400_000);
+        assert_eq!(layout.tick_millis(), 1);
+    }
+
+    #[test]
+    fn test_twitter() {
+        let layout = SnowflakeLayout::TWITTER;
+        assert_eq!(layout.timestamp_shift(), 22);
+        assert_eq!(layout.max_timestamp(), (1 << 41) - 1);
+        assert_eq!(
+            layout.millis_from_timestamp(1212092628029698048 >> 22),
+            1_577_820_376_771
+        );
+    }
+
+    #[test]
+    fn test_instagram() {
+        let layout = SnowflakeLayout::INSTAGRAM;
+        assert_eq!(layout.timestamp_shift(), 23);
+        assert_eq!(layout.worker_id_shift(), 10);
+        assert_eq!(layout.max_worker_id(), 8191);
+        assert_eq!(layout.max_sequence(), 1023);
+    }
+
+    #[test]
+    fn test_sonyflake() {
+        let layout = SnowflakeLayout::SONYFLAKE;
+        assert_eq!(layout.timestamp_shift(), 24);
+        assert_eq!(layout.sequence_shift(), 16);
+        assert_eq!(layout.worker_id_shift(), 0);
+        assert_eq!(layout.max_worker_id(), 0xFFFF);
+        assert_eq!(layout.max_sequence(), 0xFF);
+        assert_eq!(layout.timestamp_from_millis(1_409_529_600_025), 2);
+    }
+
+    #[test]
+    fn test_single_process() {
+        let layout = SnowflakeLayout::SINGLE_PROCESS;
+        assert_eq!(layout.timestamp_shift(), 22);
+        assert_eq!(layout.worker_id_shift(), 22);
+        assert_eq!(layout.max_worker_id(), 0);
+        assert_eq!(layout.machine_id_bits(), 0);
+        assert_eq!(layout.max_sequence(), 0x3F_FFFF);
+        assert_eq!(SnowflakeLayout::custom::<41, 0, 22>().max_worker_id(), 0);
+
+        // The ID is just the timestamp and sequence
+        let snowflake = crate::Snowflake::parse_with_layout((7 << 22) | 5, &layout);
+        assert_eq!(snowflake.timestamp(), 7);
+        assert_eq!(snowflake.worker_id(), 0);
+        assert_eq!(snowflake.sequence(), 5);
+    }
+
+    #[test]
+    fn test_datacenter_and_machine_bits() {
+        let layout = SnowflakeLayout::DEFAULT;
+        assert_eq!(layout.datacenter_id_bits(), 5);
+        assert_eq!(layout.machine_id_bits(), 5);
+        assert_eq!(layout.max_datacenter_id(), 31);
+        assert_eq!(layout.max_machine_id(), 31);
+
+        let layout = SnowflakeLayout::builder()
+            .datacenter_id_bits(3)
+            .build()
+            .unwrap();
+        assert_eq!(layout.max_datacenter_id(), 7);
+        assert_eq!(layout.max_machine_id(), 127);
+
+        let layout = SnowflakeLayout::builder()
+            .worker_id_bits(4)
+            .build()
+            .unwrap();
+        assert_eq!(layout.datacenter_id_bits(), 4);
+        assert_eq!(layout.machine_id_bits(), 0);
+        assert_eq!(SnowflakeLayout::SONYFLAKE.machine_id_bits(), 16);
+    }
+
+    #[test]
+    fn test_exhaustion() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(10)
+            .epoch(5_000)
+            .tick_millis(10)
+            .build()
+            .unwrap();
+        assert_eq!(layout.exhaustion_millis(), 5_000 + 1024 * 10);
+        assert_eq!(
+            layout.timestamp_from_millis(layout.exhaustion_millis()),
+            layout.max_timestamp() + 1
+        );
+
+        let full = SnowflakeLayout::builder()
+            .timestamp_bits(64)
+            .worker_id_bits(0)
+            .sequence_bits(0)
+            .build()
+            .unwrap();
+        assert_eq!(full.exhaustion_millis(), u64::MAX);
+    }
+
+    #[test]
+    fn test_field_order() {
+        let layout = SnowflakeLayout::builder()
+            .field_order(FieldOrder::SequenceWorkerId)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.worker_id_shift(), 0);
+        assert_eq!(layout.sequence_shift(), 10);
+        assert_eq!(layout.timestamp_shift(), 22);
+    }
+
+    #[test]
+    fn test_full_width_field() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(0)
+            .worker_id_bits(0)
+            .sequence_bits(64)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.max_sequence(), u64::MAX);
+        assert_eq!(layout.max_timestamp(), 0);
+        assert_eq!(shl(1, layout.timestamp_shift()), 0);
+    }
+
+    #[test]
+    fn test_extreme_times_saturate() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(64)
+            .worker_id_bits(0)
+            .sequence_bits(0)
+            .tick_micros(u64::MAX)
+            .epoch(u64::MAX)
+            .build()
+            .unwrap();
+
+        assert_eq!(layout.micros_from_timestamp(u64::MAX), u64::MAX);
+        assert_eq!(layout.millis_from_timestamp(u64::MAX), u64::MAX);
+        assert_eq!(layout.timestamp_from_micros(u64::MAX), 0);
+    }
+}
@@ -1,5 +1,125 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(
+    not(test),
+    deny(clippy::panic, clippy::unwrap_used, clippy::expect_used)
+)]
+
+extern crate alloc;
+
+#[cfg(feature = "audit")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod bulk;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "std")]
+pub mod coordination;
+pub mod discord;
+pub mod encoding;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "global")]
+pub mod global;
+pub mod keys;
+pub mod migrate;
+#[cfg(feature = "obfuscation")]
+pub mod obfuscation;
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "std")]
+mod age;
+#[cfg(feature = "async")]
+mod async_generator;
+#[cfg(feature = "std")]
+mod atomic;
+mod bucket;
+#[cfg(feature = "std")]
+mod buffered;
+mod clock;
+#[cfg(feature = "std")]
+mod config;
+mod describe;
+#[cfg(feature = "std")]
+mod deterministic;
+mod error;
+mod explain;
+#[cfg(feature = "std")]
 mod generator;
+#[cfg(feature = "std")]
+mod health;
+mod json;
+mod layout;
+mod macros;
+#[cfg(feature = "std")]
+mod monitor;
+#[cfg(feature = "std")]
+mod monotonic;
+mod options;
+#[cfg(feature = "std")]
+mod range;
+#[cfg(feature = "std")]
+mod sharded;
 mod snowflake;
+mod snowflake128;
+#[cfg(feature = "std")]
+mod state;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod thread_local_generator;
+#[cfg(feature = "std")]
+mod uniqueness;
+mod validation;
+#[cfg(feature = "std")]
+mod worker_id;
 
+#[cfg(feature = "async")]
+pub use async_generator::{AsyncSnowflakeGenerator, SnowflakeStream};
+#[cfg(feature = "std")]
+pub use atomic::AtomicSnowflakeGenerator;
+#[cfg(feature = "std")]
+pub use buffered::BufferedGenerator;
+pub use clock::Clock;
+#[cfg(feature = "std")]
+pub use clock::{MockClock, SystemClock};
+#[cfg(feature = "std")]
+pub use config::{ConfigError, GeneratorConfig};
+pub use describe::{FieldDescription, LayoutDescription};
+#[cfg(feature = "std")]
+pub use deterministic::DeterministicGenerator;
+pub use error::SnowflakeError;
+pub use explain::Explanation;
+#[cfg(feature = "std")]
 pub use generator::SnowflakeGenerator;
-pub use snowflake::Snowflake;
+#[cfg(feature = "global")]
+pub use global::{init, next_id};
+#[cfg(feature = "std")]
+pub use health::{HealthProblem, HealthReport};
+pub use layout::{FieldOrder, LayoutError, SnowflakeLayout, SnowflakeLayoutBuilder, TimeUnit};
+#[cfg(feature = "std")]
+pub use monitor::{ClockDrift, ClockMonitor, FutureGuard};
+#[cfg(feature = "std")]
+pub use monotonic::MonotonicGenerator;
+pub use options::{
+    ClockBackwardsPolicy, GeneratorOptions, OverflowStrategy, RateLimitPolicy, WaitStrategy,
+};
+#[cfg(feature = "std")]
+pub use range::SnowflakeRange;
+#[cfg(feature = "std")]
+pub use sharded::ShardedGenerator;
+pub use snowflake::{Snowflake, SnowflakeBuilder};
+pub use snowflake128::Snowflake128;
+#[cfg(feature = "std")]
+pub use snowflake128::SnowflakeGenerator128;
+#[cfg(feature = "std")]
+pub use state::GeneratorState;
+#[cfg(feature = "std")]
+pub use stats::GeneratorStats;
+#[cfg(feature = "std")]
+pub use thread_local_generator::ThreadLocalGenerator;
+#[cfg(feature = "std")]
+pub use uniqueness::{Collision, UniquenessChecker};
+pub use validation::ValidationRules;
+#[cfg(feature = "std")]
+pub use worker_id::{CollisionWarning, IpStrategy, WorkerIdSource};
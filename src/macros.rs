@@ -0,0 +1,38 @@
+/// Decode a snowflake ID at compile time
+///
+/// The ID is parsed with [`SnowflakeLayout::DEFAULT`](crate::SnowflakeLayout::DEFAULT), or
+/// the layout given as the second argument, which has to be a constant. An ID the layout
+/// couldn't have packed, see [`Snowflake::try_parse_with_layout`](crate::Snowflake::try_parse_with_layout),
+/// fails to compile.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{snowflake, Snowflake, SnowflakeLayout};
+///
+/// const SYSTEM_ACCOUNT: Snowflake = snowflake!(175928847299117063);
+/// assert_eq!(SYSTEM_ACCOUNT.sequence(), 7);
+///
+/// let discord = snowflake!(175928847299117063, SnowflakeLayout::DISCORD);
+/// assert_eq!(discord.timestamp(), 41_944_705_796);
+/// ```
+///
+/// ```rust,compile_fail
+/// use rusty_snowflake::snowflake;
+///
+/// // The top bit isn't used by the default layout
+/// let invalid = snowflake!(0x8000_0000_0000_0000);
+/// ```
+#[macro_export]
+macro_rules! snowflake {
+    ($id:expr) => {
+        $crate::snowflake!($id, $crate::SnowflakeLayout::DEFAULT)
+    };
+    ($id:expr, $layout:expr) => {{
+        const SNOWFLAKE: $crate::Snowflake = if $layout.can_pack($id) {
+            $crate::Snowflake::parse_with_layout($id, &$layout)
+        } else {
+            ::core::panic!("snowflake ID can't have been packed with the layout")
+        };
+        SNOWFLAKE
+    }};
+}
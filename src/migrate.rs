@@ -0,0 +1,164 @@
+//! Rewriting existing IDs into another layout, such as when a schema migration moves a dataset
+//! from [`SnowflakeLayout::DEFAULT`] IDs in seconds since the UNIX epoch to a custom epoch
+//! counted in milliseconds.
+//!
+//! Each ID keeps its creation time, worker ID and sequence number, so IDs that were unique and
+//! in order stay unique and in order as long as the new tick is no coarser than the old one.
+//! Moving to a coarser tick puts IDs from neighbouring ticks under the same timestamp, where
+//! their sequence numbers can collide.
+//!
+//! # Example
+//! ```rust
+//! use rusty_snowflake::{migrate, Snowflake, SnowflakeLayout};
+//!
+//! let new_layout = SnowflakeLayout::builder()
+//!     .tick_millis(1)
+//!     .epoch(1_600_000_000_000)
+//!     .build()
+//!     .unwrap();
+//!
+//! let old_id = Snowflake::builder()
+//!     .timestamp(1_700_000_000)
+//!     .worker_id(42)
+//!     .sequence(7)
+//!     .build()
+//!     .unwrap()
+//!     .to_id();
+//! let new_id = migrate::rebase_epoch(old_id, &SnowflakeLayout::DEFAULT, &new_layout).unwrap();
+//!
+//! let snowflake = Snowflake::parse_with_layout(new_id, &new_layout);
+//! assert_eq!(snowflake.timestamp(), 100_000_000_000);
+//! assert_eq!(snowflake.worker_id(), 42);
+//! assert_eq!(snowflake.sequence(), 7);
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+/// Rewrite an ID from one layout into another, keeping its creation time, worker ID and
+/// sequence number
+///
+/// The layouts carry the epoch and tick, so this also converts between time units. The
+/// creation time is rounded down to the new layout's tick.
+///
+/// # Arguments
+/// * `id` - The ID to rewrite
+/// * `from` - The layout the ID was packed with
+/// * `to` - The layout to pack the new ID with
+///
+/// # Returns
+/// The ID in the new layout, or an error if the ID isn't consistent with `from` (see
+/// [`Snowflake::try_parse_with_layout`]), it was created before the epoch of `to`, or one of
+/// its fields doesn't fit in `to`
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{migrate, SnowflakeError, SnowflakeLayout};
+///
+/// // Discord IDs from before 2015 don't exist, so a default ID from 2014 can't move over
+/// let id = 1_400_000_000 << 22;
+///
+/// assert_eq!(
+///     migrate::rebase_epoch(id, &SnowflakeLayout::DEFAULT, &SnowflakeLayout::DISCORD),
+///     Err(SnowflakeError::ClockBeforeEpoch)
+/// );
+/// ```
+pub fn rebase_epoch(
+    id: u64,
+    from: &SnowflakeLayout,
+    to: &SnowflakeLayout,
+) -> Result<u64, SnowflakeError> {
+    let snowflake = Snowflake::try_parse_with_layout(id, from)?;
+
+    let micros = from.micros_from_timestamp(snowflake.timestamp);
+    if micros < to.micros_from_timestamp(0) {
+        return Err(SnowflakeError::ClockBeforeEpoch);
+    }
+
+    let rebased = snowflake
+        .to_builder()
+        .timestamp(to.timestamp_from_micros(micros))
+        .layout(*to)
+        .build()?;
+
+    Ok(rebased.to_id_with_layout(to))
+}
+
+/// Rewrite many IDs from one layout into another
+///
+/// See [`rebase_epoch`].
+///
+/// # Arguments
+/// * `ids` - The IDs to rewrite
+/// * `from` - The layout the IDs were packed with
+/// * `to` - The layout to pack the new IDs with
+///
+/// # Returns
+/// The IDs in the new layout, in the same order, or the error of the first ID that couldn't be
+/// rewritten
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{migrate, SnowflakeLayout};
+///
+/// let ids = [1_700_000_000 << 22, 1_700_000_001 << 22];
+/// let rebased =
+///     migrate::rebase_epoch_many(&ids, &SnowflakeLayout::DEFAULT, &SnowflakeLayout::DISCORD)
+///         .unwrap();
+///
+/// assert_eq!(rebased[1] - rebased[0], 1_000 << 22);
+/// ```
+pub fn rebase_epoch_many(
+    ids: &[u64],
+    from: &SnowflakeLayout,
+    to: &SnowflakeLayout,
+) -> Result<Vec<u64>, SnowflakeError> {
+    ids.iter().map(|&id| rebase_epoch(id, from, to)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_epoch_keeps_order() {
+        let to = SnowflakeLayout::DISCORD;
+        let ids = [
+            1_700_000_000 << 22 | 1 << 12 | 4_095,
+            1_700_000_001 << 22,
+            1_700_000_001 << 22 | 2 << 12,
+        ];
+
+        let rebased = rebase_epoch_many(&ids, &SnowflakeLayout::DEFAULT, &to).unwrap();
+
+        assert!(rebased.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            to.millis_from_timestamp(Snowflake::parse_with_layout(rebased[0], &to).timestamp),
+            1_700_000_000_000
+        );
+        assert_eq!(
+            rebase_epoch_many(&rebased, &to, &SnowflakeLayout::DEFAULT).unwrap(),
+            ids
+        );
+    }
+
+    #[test]
+    fn test_rebase_epoch_errors() {
+        let from = SnowflakeLayout::DEFAULT;
+        let to = SnowflakeLayout::SONYFLAKE;
+
+        assert_eq!(
+            rebase_epoch(u64::MAX, &from, &to),
+            Err(SnowflakeError::ReservedBitsSet { id: u64::MAX })
+        );
+        assert_eq!(
+            rebase_epoch(1_700_000_000 << 22 | 300, &from, &to),
+            Err(SnowflakeError::SequenceOverflow {
+                sequence: 300,
+                max: 255
+            })
+        );
+        assert!(rebase_epoch_many(&[1_700_000_000 << 22, 0], &from, &to).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use std::time::{Duration, Instant};
+
+use crate::{Clock, SnowflakeError};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// A jump of the wall clock away from the monotonic clock, found by a [`ClockMonitor`].
+pub struct ClockDrift {
+    pub(crate) expected_millis: u64,
+    pub(crate) actual_millis: u64,
+}
+
+impl ClockDrift {
+    /// The time the monotonic clock says it should be, in milliseconds since the UNIX epoch
+    pub const fn expected_millis(&self) -> u64 {
+        self.expected_millis
+    }
+
+    /// The time the wall clock reported, in milliseconds since the UNIX epoch
+    pub const fn actual_millis(&self) -> u64 {
+        self.actual_millis
+    }
+
+    /// How far the wall clock is ahead of the monotonic clock in milliseconds, negative if it's
+    /// behind
+    pub const fn offset_millis(&self) -> i64 {
+        self.actual_millis.wrapping_sub(self.expected_millis) as i64
+    }
+}
+
+impl From<ClockDrift> for SnowflakeError {
+    fn from(drift: ClockDrift) -> SnowflakeError {
+        SnowflakeError::ClockDrift {
+            expected_millis: drift.expected_millis,
+            actual_millis: drift.actual_millis,
+        }
+    }
+}
+
+/// Watches a [`Clock`] for jumps, such as NTP stepping the system clock, by comparing it with
+/// the monotonic clock.
+///
+/// The monitor remembers the wall clock time at a monotonic [`Instant`], and on every check
+/// works out what the wall clock should read now. When the two differ by more than the
+/// threshold the drift callback runs and the monitor starts again from the new wall clock time,
+/// so a single jump is reported once. Checks closer together than the interval are skipped.
+///
+/// Pass the monitor to [`SnowflakeGenerator::try_next_monitored`] to check the generator's
+/// clock before each snowflake.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rusty_snowflake::{ClockMonitor, SnowflakeGenerator};
+///
+/// let mut monitor = ClockMonitor::new(Duration::from_millis(500))
+///     .on_drift(|drift| eprintln!("clock jumped by {} ms", drift.offset_millis()));
+/// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+///
+/// let snowflake = generator.try_next_monitored(&mut monitor).unwrap();
+/// ```
+///
+/// [`SnowflakeGenerator::try_next_monitored`]: crate::SnowflakeGenerator::try_next_monitored
+pub struct ClockMonitor {
+    threshold: Duration,
+    interval: Duration,
+    error_on_drift: bool,
+    on_drift: Option<Box<dyn FnMut(ClockDrift) + Send>>,
+    /// The wall clock time in milliseconds at a monotonic instant.
+    reference: Option<(Instant, u64)>,
+    last_check: Option<Instant>,
+}
+
+impl ClockMonitor {
+    /// Create a monitor that reports drift beyond `threshold`, checking at most once a second
+    pub fn new(threshold: Duration) -> ClockMonitor {
+        ClockMonitor {
+            threshold,
+            interval: Duration::from_secs(1),
+            error_on_drift: false,
+            on_drift: None,
+            reference: None,
+            last_check: None,
+        }
+    }
+
+    /// Set how long to wait between checks
+    pub fn interval(mut self, interval: Duration) -> ClockMonitor {
+        self.interval = interval;
+        self
+    }
+
+    /// Call `callback` with every drift found
+    pub fn on_drift(mut self, callback: impl FnMut(ClockDrift) + Send + 'static) -> ClockMonitor {
+        self.on_drift = Some(Box::new(callback));
+        self
+    }
+
+    /// Set whether [`ClockMonitor::check`] fails with `SnowflakeError::ClockDrift` when it finds
+    /// drift, which stops [`SnowflakeGenerator::try_next_monitored`] from generating
+    ///
+    /// Defaults to `false`, which only calls the drift callback.
+    ///
+    /// [`SnowflakeGenerator::try_next_monitored`]: crate::SnowflakeGenerator::try_next_monitored
+    pub fn error_on_drift(mut self, error_on_drift: bool) -> ClockMonitor {
+        self.error_on_drift = error_on_drift;
+        self
+    }
+
+    /// Compare `clock` with the monotonic clock, unless the last check was less than the
+    /// interval ago
+    ///
+    /// # Returns
+    /// The drift found, if any, or an error if drift was found and the monitor is set to
+    /// [`ClockMonitor::error_on_drift`]
+    pub fn check<C: Clock>(&mut self, clock: &C) -> Result<Option<ClockDrift>, SnowflakeError> {
+        let now = Instant::now();
+        if self
+            .last_check
+            .is_some_and(|last_check| now.duration_since(last_check) < self.interval)
+        {
+            return Ok(None);
+        }
+        self.last_check = Some(now);
+
+        let actual_millis = clock.now_millis();
+        let (instant, millis) = *self.reference.get_or_insert((now, actual_millis));
+        let expected_millis = millis + now.duration_since(instant).as_millis() as u64;
+
+        if actual_millis.abs_diff(expected_millis) <= self.threshold.as_millis() as u64 {
+            return Ok(None);
+        }
+
+        let drift = ClockDrift {
+            expected_millis,
+            actual_millis,
+        };
+        self.reference = Some((now, actual_millis));
+        if let Some(callback) = &mut self.on_drift {
+            callback(drift);
+        }
+
+        if self.error_on_drift {
+            Err(drift.into())
+        } else {
+            Ok(Some(drift))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Stops a generator from putting timestamps in the future into its IDs, by comparing its clock
+/// with a trusted reference clock.
+///
+/// A clock that runs ahead poisons time-ordered queries: its IDs sort after IDs generated
+/// later by healthy workers. When the generator's clock is more than the maximum skew ahead of
+/// the reference, [`SnowflakeGenerator::try_next_guarded`] fails with
+/// `SnowflakeError::ClockAhead`, or, if the guard clamps, generates the snowflake at the
+/// reference time plus the maximum skew instead.
+///
+/// The reference can be any [`Clock`], such as one kept in sync with a time server.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rusty_snowflake::{FutureGuard, MockClock, SnowflakeGenerator, SnowflakeLayout};
+///
+/// let layout = SnowflakeLayout::DISCORD;
+/// let reference = MockClock::new(layout.epoch() + 1_000_000);
+/// let clock = MockClock::new(layout.epoch() + 1_000_000);
+/// let mut generator =
+///     SnowflakeGenerator::with_layout_and_clock(1, layout, clock.clone()).unwrap();
+///
+/// // The clock jumps a minute ahead
+/// clock.advance(60_000);
+///
+/// let guard = FutureGuard::new(reference.clone(), Duration::from_secs(1));
+/// assert!(generator.try_next_guarded(&guard).is_err());
+///
+/// let guard = FutureGuard::new(reference, Duration::from_secs(1)).clamp(true);
+/// assert_eq!(generator.try_next_guarded(&guard).unwrap().timestamp(), 1_001_000);
+/// ```
+///
+/// [`SnowflakeGenerator::try_next_guarded`]: crate::SnowflakeGenerator::try_next_guarded
+pub struct FutureGuard<R: Clock> {
+    reference: R,
+    max_skew: Duration,
+    clamp: bool,
+}
+
+impl<R: Clock> FutureGuard<R> {
+    /// Create a guard that rejects clocks more than `max_skew` ahead of `reference`
+    pub fn new(reference: R, max_skew: Duration) -> FutureGuard<R> {
+        FutureGuard {
+            reference,
+            max_skew,
+            clamp: false,
+        }
+    }
+
+    /// Set whether to generate at the latest allowed time instead of failing when the clock is
+    /// too far ahead
+    ///
+    /// Defaults to `false`, which fails with `SnowflakeError::ClockAhead`.
+    pub fn clamp(mut self, clamp: bool) -> FutureGuard<R> {
+        self.clamp = clamp;
+        self
+    }
+
+    /// Get the reference clock
+    pub fn reference(&self) -> &R {
+        &self.reference
+    }
+
+    /// Compare `clock` with the reference clock
+    ///
+    /// # Returns
+    /// `None` if the clock is within the maximum skew, the latest allowed time in microseconds
+    /// since the UNIX epoch if it's ahead and the guard clamps, or `SnowflakeError::ClockAhead`
+    /// if it's ahead and the guard doesn't
+    pub fn check<C: Clock>(&self, clock: &C) -> Result<Option<u64>, SnowflakeError> {
+        let now = clock.now_micros();
+        let reference = self.reference.now_micros();
+        let latest = reference.saturating_add(self.max_skew.as_micros() as u64);
+
+        if now <= latest {
+            Ok(None)
+        } else if self.clamp {
+            Ok(Some(latest))
+        } else {
+            Err(SnowflakeError::ClockAhead {
+                clock_millis: now / 1_000,
+                reference_millis: reference / 1_000,
+            })
+        }
+    }
+}
+
+impl std::fmt::Debug for ClockMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ClockMonitor")
+            .field("threshold", &self.threshold)
+            .field("interval", &self.interval)
+            .field("error_on_drift", &self.error_on_drift)
+            .field("on_drift", &self.on_drift.is_some())
+            .field("reference", &self.reference)
+            .field("last_check", &self.last_check)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn test_detects_jumps() {
+        let clock = MockClock::new(1_000_000);
+        let drifts = Arc::new(Mutex::new(Vec::new()));
+        let seen = drifts.clone();
+        let mut monitor = ClockMonitor::new(Duration::from_secs(1))
+            .interval(Duration::ZERO)
+            .on_drift(move |drift| seen.lock().unwrap().push(drift.offset_millis()));
+
+        assert_eq!(monitor.check(&clock), Ok(None));
+
+        clock.advance(60_000);
+        let drift = monitor.check(&clock).unwrap().unwrap();
+        assert_eq!(drift.actual_millis(), 1_060_000);
+        assert!(drift.offset_millis() > 59_000);
+
+        // The jump is only reported once
+        assert_eq!(monitor.check(&clock), Ok(None));
+
+        clock.set(1_000_000);
+        assert!(monitor.check(&clock).unwrap().unwrap().offset_millis() < -59_000);
+
+        let drifts = drifts.lock().unwrap();
+        assert_eq!(drifts.len(), 2);
+        assert!(drifts[0] > 0 && drifts[1] < 0);
+    }
+
+    #[test]
+    fn test_future_guard() {
+        let reference = MockClock::new(1_000_000);
+        let clock = MockClock::new(1_000_500);
+        let guard = FutureGuard::new(reference.clone(), Duration::from_secs(1));
+
+        assert_eq!(guard.check(&clock), Ok(None));
+
+        clock.advance(1_000);
+        assert_eq!(
+            guard.check(&clock),
+            Err(SnowflakeError::ClockAhead {
+                clock_millis: 1_001_500,
+                reference_millis: 1_000_000
+            })
+        );
+        assert_eq!(guard.clamp(true).check(&clock), Ok(Some(1_001_000_000)));
+
+        // A clock behind the reference is left to the generator's ClockBackwardsPolicy
+        reference.advance(10_000);
+        let guard = FutureGuard::new(reference, Duration::ZERO);
+        assert_eq!(guard.check(&clock), Ok(None));
+    }
+
+    #[test]
+    fn test_error_on_drift() {
+        let clock = MockClock::new(1_000_000);
+        let mut monitor = ClockMonitor::new(Duration::from_millis(10))
+            .interval(Duration::ZERO)
+            .error_on_drift(true);
+
+        monitor.check(&clock).unwrap();
+        clock.advance(5_000);
+
+        assert!(matches!(
+            monitor.check(&clock),
+            Err(SnowflakeError::ClockDrift {
+                actual_millis: 1_005_000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_interval() {
+        let clock = MockClock::new(1_000_000);
+        let mut monitor = ClockMonitor::new(Duration::from_millis(10));
+
+        monitor.check(&clock).unwrap();
+        clock.advance(5_000);
+
+        // Too soon after the first check
+        assert_eq!(monitor.check(&clock), Ok(None));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{Clock, Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout, SystemClock};
+
+#[derive(Debug)]
+/// A generator that can't be cloned, so no two holders can generate the same snowflake.
+///
+/// Snowflakes and [`SnowflakeGenerator`]s can be copied, and every copy carries on from the same
+/// last snowflake, so two copies advanced in the same tick return the same ID. A
+/// `MonotonicGenerator` owns its state instead: the only way to get a snowflake from it is
+/// through `&mut self`, and the borrow checker makes sure there is one of it. Share it between
+/// threads behind a `Mutex`, or use an
+/// [`AtomicSnowflakeGenerator`](crate::AtomicSnowflakeGenerator).
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{MonotonicGenerator, Snowflake};
+///
+/// let mut generator = Snowflake::try_new(420).unwrap().into_generator();
+///
+/// let first = generator.try_next().unwrap();
+/// let second = generator.try_next().unwrap();
+///
+/// assert!(second > first);
+/// ```
+pub struct MonotonicGenerator<C: Clock = SystemClock> {
+    generator: SnowflakeGenerator<C>,
+}
+
+impl MonotonicGenerator {
+    /// Create a new monotonic generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `MonotonicGenerator`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`] or the system clock is set before the UNIX epoch
+    pub fn try_new(worker_id: u64) -> Result<MonotonicGenerator, SnowflakeError> {
+        Ok(MonotonicGenerator::start_at(Snowflake::try_new(worker_id)?))
+    }
+
+    /// Create a new monotonic generator that generates snowflakes with a custom bit layout
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    /// * `layout` - The bit layout of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `MonotonicGenerator`, or an error if the worker ID doesn't fit in the layout
+    pub fn with_layout(
+        worker_id: u64,
+        layout: SnowflakeLayout,
+    ) -> Result<MonotonicGenerator, SnowflakeError> {
+        Ok(MonotonicGenerator {
+            generator: SnowflakeGenerator::with_layout(worker_id, layout)?,
+        })
+    }
+
+    /// Start after the given snowflake
+    ///
+    /// # Arguments
+    /// * `snowflake` - The last snowflake, which the generator won't return again
+    pub fn start_at(snowflake: Snowflake) -> MonotonicGenerator {
+        MonotonicGenerator {
+            generator: SnowflakeGenerator::start_at(&snowflake),
+        }
+    }
+}
+
+impl<C: Clock> MonotonicGenerator<C> {
+    /// Take ownership of a generator
+    ///
+    /// The generator is moved in, but since it can be cloned the caller can still keep a copy
+    /// that generates the same snowflakes. Don't use the original again.
+    ///
+    /// # Arguments
+    /// * `generator` - The generator to take ownership of
+    pub fn from_generator(generator: SnowflakeGenerator<C>) -> MonotonicGenerator<C> {
+        MonotonicGenerator { generator }
+    }
+
+    /// Get the last snowflake generated
+    pub fn last_snowflake(&self) -> Snowflake {
+        self.generator.last_snowflake
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        self.generator.layout()
+    }
+
+    /// Generates the next snowflake ID
+    ///
+    /// See [`SnowflakeGenerator::try_next`].
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if the generator's options say to fail or the
+    /// timestamp no longer fits in the layout
+    pub fn try_next(&mut self) -> Result<Snowflake, SnowflakeError> {
+        self.generator.try_next()
+    }
+}
+
+impl<C: Clock> Iterator for MonotonicGenerator<C> {
+    type Item = Snowflake;
+
+    /// Generates the next snowflake ID, or `None` if generating it failed
+    fn next(&mut self) -> Option<Snowflake> {
+        self.try_next().ok()
+    }
+}
+
+impl Snowflake {
+    /// Turn the snowflake into a generator that continues after it
+    ///
+    /// Unlike [`Snowflake::next`], the generator keeps its own state, so every snowflake it
+    /// returns is new.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    /// let ids = snowflake.into_generator().take(3).collect::<Vec<_>>();
+    ///
+    /// assert!(ids[0] > snowflake && ids[1] > ids[0] && ids[2] > ids[1]);
+    /// ```
+    pub fn into_generator(self) -> MonotonicGenerator {
+        MonotonicGenerator::start_at(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn test_copies_of_a_generator_collide() {
+        let clock = MockClock::new(1_000);
+        let mut original = SnowflakeGenerator::with_clock(1, clock).unwrap();
+        let mut copy = original.clone();
+
+        // The footgun a MonotonicGenerator avoids
+        assert_eq!(original.next(), copy.next());
+
+        let mut generator = MonotonicGenerator::from_generator(original);
+        let ids = (0..100)
+            .map(|_| generator.try_next().unwrap())
+            .collect::<Vec<_>>();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_from_generator() {
+        let clock = MockClock::new(5_000);
+        let generator = SnowflakeGenerator::with_clock(3, clock).unwrap();
+        let mut generator = MonotonicGenerator::from_generator(generator);
+
+        assert_eq!(generator.next().unwrap().sequence(), 1);
+        assert_eq!(generator.last_snowflake().sequence(), 1);
+        assert_eq!(generator.last_snowflake().worker_id(), 3);
+        assert_eq!(generator.layout(), &SnowflakeLayout::DEFAULT);
+    }
+}
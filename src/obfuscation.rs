@@ -0,0 +1,143 @@
+//! Hiding how many IDs were generated from anyone who sees them.
+//!
+//! Consecutive snowflakes differ only in their sequence, so public IDs reveal how many IDs a
+//! service issues per tick. [`obfuscate`] scrambles an ID with a keyed permutation of all 64
+//! bit values before it leaves the service, and [`deobfuscate`] turns it back into the original
+//! ID, which still sorts and parses as usual.
+//!
+//! The permutation is an 8 round Feistel network. It stops IDs from being read or counted
+//! without the key, but it isn't a vetted cipher, so don't rely on it to protect secrets.
+//!
+//! # Example
+//! ```rust
+//! use rusty_snowflake::obfuscation::{deobfuscate, obfuscate};
+//! use rusty_snowflake::SnowflakeGenerator;
+//!
+//! const KEY: u128 = 0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210;
+//!
+//! let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+//! let first = generator.next().to_id();
+//! let second = generator.next().to_id();
+//!
+//! let public = obfuscate(second, KEY);
+//! assert_ne!(public.abs_diff(obfuscate(first, KEY)), second - first);
+//! assert_eq!(deobfuscate(public, KEY), second);
+//! ```
+
+/// The number of Feistel rounds.
+const ROUNDS: usize = 8;
+
+/// The 64 bit golden ratio, used to spread the round keys apart.
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Scramble an ID with a keyed permutation
+///
+/// Every key maps each `u64` to a different `u64`, so obfuscated IDs stay unique.
+///
+/// # Arguments
+/// * `id` - The ID to scramble
+/// * `key` - The secret key
+///
+/// # Returns
+/// The scrambled ID, which [`deobfuscate`] turns back into `id` with the same key
+pub const fn obfuscate(id: u64, key: u128) -> u64 {
+    let keys = round_keys(key);
+    let (mut left, mut right) = ((id >> 32) as u32, id as u32);
+
+    let mut round = 0;
+    while round < ROUNDS {
+        (left, right) = (right, left ^ mix(right, keys[round]));
+        round += 1;
+    }
+
+    (left as u64) << 32 | right as u64
+}
+
+/// Recover an ID scrambled with [`obfuscate`]
+///
+/// # Arguments
+/// * `id` - The scrambled ID
+/// * `key` - The key the ID was scrambled with
+///
+/// # Returns
+/// The original ID. With the wrong key this is a different, meaningless ID.
+pub const fn deobfuscate(id: u64, key: u128) -> u64 {
+    let keys = round_keys(key);
+    let (mut left, mut right) = ((id >> 32) as u32, id as u32);
+
+    let mut round = ROUNDS;
+    while round > 0 {
+        round -= 1;
+        (left, right) = (right ^ mix(left, keys[round]), left);
+    }
+
+    (left as u64) << 32 | right as u64
+}
+
+/// Derive a key for every round from the secret key
+const fn round_keys(key: u128) -> [u64; ROUNDS] {
+    let mut keys = [0; ROUNDS];
+    let (high, low) = ((key >> 64) as u64, key as u64);
+
+    let mut round = 0;
+    while round < ROUNDS {
+        let step = GOLDEN_GAMMA.wrapping_mul(round as u64 + 1);
+        keys[round] = finalize(low.wrapping_add(step)) ^ finalize(high ^ step).rotate_left(32);
+        round += 1;
+    }
+    keys
+}
+
+/// The Feistel round function
+const fn mix(half: u32, round_key: u64) -> u32 {
+    (finalize(half as u64 ^ round_key) >> 32) as u32
+}
+
+/// The SplitMix64 finalizer, which spreads every input bit over the whole output
+const fn finalize(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    const KEY: u128 = 0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210;
+
+    const IDS: [u64; 6] = [0, 1, 2, 4096, 175928847299117063, u64::MAX];
+
+    #[test]
+    fn test_round_trip() {
+        for id in IDS {
+            assert_eq!(deobfuscate(obfuscate(id, KEY), KEY), id);
+            assert_eq!(obfuscate(deobfuscate(id, KEY), KEY), id);
+        }
+    }
+
+    #[test]
+    fn test_hides_sequence() {
+        let base = 175928847299117063;
+        let obfuscated = (0..64)
+            .map(|offset| obfuscate(base + offset, KEY))
+            .collect::<Vec<_>>();
+
+        // Consecutive IDs land far apart, and not in order
+        assert!(obfuscated
+            .windows(2)
+            .all(|pair| pair[0].abs_diff(pair[1]) > 1 << 32));
+        assert!(!obfuscated.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_key_matters() {
+        let id = 175928847299117063;
+        let other_key = KEY ^ 1;
+
+        assert_ne!(obfuscate(id, KEY), obfuscate(id, other_key));
+        assert_ne!(deobfuscate(obfuscate(id, KEY), other_key), id);
+    }
+}
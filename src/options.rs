@@ -0,0 +1,306 @@
+use core::time::Duration;
+
+use crate::SnowflakeError;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// What a generator does when the system clock goes backwards.
+///
+/// This happens when NTP steps the clock back, or when a generator is started at a
+/// snowflake from the future.
+pub enum ClockBackwardsPolicy {
+    /// Block until the clock catches up with the last timestamp.
+    Wait,
+    /// Return `SnowflakeError::ClockMovedBackwards`.
+    Error,
+    /// Keep generating under the last timestamp, waiting for the clock to catch up only once
+    /// the sequence is exhausted.
+    #[default]
+    BorrowSequence,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// What a generator does when it runs out of sequence numbers within one tick.
+///
+/// `Spin`, `Yield` and `SleepUntilNextTick` wait for the next tick, and differ in how the wait
+/// uses the CPU. `BorrowTime` only waits once it's too far ahead of the clock. Except for
+/// `Spin` and `Yield`, waits use the generator's [`WaitStrategy`].
+pub enum OverflowStrategy {
+    /// Busy-wait, which has the lowest latency but keeps a core busy.
+    Spin,
+    /// Yield to the OS scheduler between checks of the clock.
+    Yield,
+    /// Wait for the next tick with the generator's [`WaitStrategy`], which sleeps until the
+    /// clock is expected to reach it unless set otherwise.
+    #[default]
+    SleepUntilNextTick,
+    /// Return `SnowflakeError::SequenceExhausted` instead of blocking.
+    Error,
+    /// Move on to the next tick without waiting for the clock to reach it, like Baidu's
+    /// uid-generator, as long as that's at most `max_ticks` ahead of the clock.
+    ///
+    /// A burst of IDs then runs ahead of the clock and the generator catches up when the burst
+    /// is over. Snowflakes generated while it's ahead carry timestamps from the future. While
+    /// the clock is behind the last snowflake by at most `max_ticks`, the generator keeps
+    /// generating after it, whatever its `ClockBackwardsPolicy`, since it can't tell a small
+    /// step back of the clock from time it borrowed.
+    BorrowTime {
+        /// How many ticks the generator may run ahead of the clock
+        max_ticks: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// How a generator waits for its clock to reach the next tick, after running out of sequence
+/// numbers or when the clock went backwards under `ClockBackwardsPolicy::Wait`.
+///
+/// The generator checks the clock again after each pause, so every strategy returns as soon as
+/// it sees the next tick. They trade latency for CPU: spinning notices the tick first but keeps
+/// a core busy, and sleeping until the tick is due uses no CPU but wakes up as late as the OS
+/// scheduler makes it.
+pub enum WaitStrategy {
+    /// Busy-wait, hinting to the CPU that it's in a spin loop.
+    Spin,
+    /// Yield to the OS scheduler between checks of the clock.
+    Yield,
+    /// Sleep for a fixed time between checks of the clock.
+    Sleep(Duration),
+    /// Park the thread between checks of the clock, for at most the given time and no longer
+    /// than until the next tick is due, so another thread can wake it early with
+    /// [`Thread::unpark`](std::thread::Thread::unpark).
+    Park(Duration),
+    /// Sleep until the clock is expected to reach the next tick.
+    #[default]
+    SleepUntilNextTick,
+}
+
+#[cfg(feature = "std")]
+impl WaitStrategy {
+    /// Pause once while waiting for the next tick
+    ///
+    /// # Arguments
+    /// * `now` - The time the clock reads, in microseconds since the UNIX epoch
+    /// * `next_tick` - The time the next tick starts, in microseconds since the UNIX epoch
+    pub(crate) fn pause(self, now: u64, next_tick: u64) {
+        let until_next_tick = Duration::from_micros(next_tick.saturating_sub(now).max(1));
+
+        match self {
+            WaitStrategy::Spin => core::hint::spin_loop(),
+            WaitStrategy::Yield => std::thread::yield_now(),
+            WaitStrategy::Sleep(interval) => std::thread::sleep(interval),
+            WaitStrategy::Park(timeout) => std::thread::park_timeout(timeout.min(until_next_tick)),
+            WaitStrategy::SleepUntilNextTick => std::thread::sleep(until_next_tick),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// What a generator does when it's asked for snowflakes faster than
+/// [`GeneratorOptions::max_ids_per_second`] allows.
+pub enum RateLimitPolicy {
+    /// Wait until the generator's clock reaches the time the rate limit lets the next
+    /// snowflake through, pausing with the generator's [`WaitStrategy`].
+    #[default]
+    Wait,
+    /// Return `SnowflakeError::RateLimited` instead of blocking.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// Options controlling how a [`SnowflakeGenerator`](crate::SnowflakeGenerator) behaves.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{ClockBackwardsPolicy, GeneratorOptions, SnowflakeGenerator};
+///
+/// let options = GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error);
+///
+/// let generator = SnowflakeGenerator::try_new(420).unwrap().with_options(options);
+/// ```
+pub struct GeneratorOptions {
+    pub(crate) clock_backwards_policy: ClockBackwardsPolicy,
+    pub(crate) overflow_strategy: OverflowStrategy,
+    pub(crate) max_sequence_offset: u64,
+    pub(crate) strict_monotonic: bool,
+    pub(crate) max_ids_per_second: u64,
+    pub(crate) rate_limit_policy: RateLimitPolicy,
+    pub(crate) wait_strategy: WaitStrategy,
+}
+
+impl GeneratorOptions {
+    /// The highest rate limit a generator can enforce, one snowflake per nanosecond
+    pub const MAX_IDS_PER_SECOND: u64 = 1_000_000_000;
+
+    /// Create the default generator options
+    pub fn new() -> GeneratorOptions {
+        GeneratorOptions::default()
+    }
+
+    /// Set what the generator does when the system clock goes backwards
+    ///
+    /// Defaults to `ClockBackwardsPolicy::BorrowSequence`.
+    pub fn clock_backwards_policy(mut self, policy: ClockBackwardsPolicy) -> GeneratorOptions {
+        self.clock_backwards_policy = policy;
+        self
+    }
+
+    /// Set what the generator does when it runs out of sequence numbers within one tick
+    ///
+    /// Defaults to `OverflowStrategy::SleepUntilNextTick`.
+    pub fn overflow_strategy(mut self, strategy: OverflowStrategy) -> GeneratorOptions {
+        self.overflow_strategy = strategy;
+        self
+    }
+
+    /// Start the sequence of each tick at a random number up to `max_offset` instead of zero
+    ///
+    /// Sequence numbers counting up from zero tell anyone who sees two IDs from the same tick
+    /// how many IDs were generated between them, such as how many orders a shop took that
+    /// second. A random start hides that, at the cost of fewer sequence numbers left in the
+    /// tick: a generator that runs out waits for the next tick sooner. Offsets bigger than the
+    /// layout's largest sequence number are capped to it.
+    ///
+    /// Defaults to `0`, which always starts at zero.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{GeneratorOptions, SnowflakeGenerator};
+    ///
+    /// let options = GeneratorOptions::new().random_sequence_offset(2048);
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap().with_options(options);
+    ///
+    /// assert!(generator.next().sequence() <= 4095);
+    /// ```
+    pub fn random_sequence_offset(mut self, max_offset: u64) -> GeneratorOptions {
+        self.max_sequence_offset = max_offset;
+        self
+    }
+
+    /// Never fail because of the clock or a full tick, so every call returns a snowflake
+    /// strictly greater than the one before
+    ///
+    /// A generator always moves forward: when the clock goes backwards it either waits for the
+    /// clock or keeps generating under the last timestamp, and each snowflake is greater than
+    /// the last one it generated. With `ClockBackwardsPolicy::Error` or `OverflowStrategy::Error`
+    /// it can return an error instead, and callers that retry or fall back to another generator
+    /// can lose that ordering. In strict mode a clock regression is handled by borrowing the last
+    /// timestamp, or by waiting under `ClockBackwardsPolicy::Wait`, and a full tick by sleeping
+    /// until the next one. Because the extra field sorts above the sequence number,
+    /// [`try_next_with_extra`](crate::SnowflakeGenerator::try_next_with_extra) with a lower extra
+    /// than the last snowflake also waits for the next tick. The only errors left are
+    /// `SnowflakeError::TimestampOverflow` once the layout runs out of timestamps, and
+    /// `SnowflakeError::RateLimited` under `RateLimitPolicy::Error`, which doesn't affect the
+    /// ordering since no snowflake is generated.
+    ///
+    /// The guarantee holds for one generator, including when it's shared behind a `Mutex`. Two
+    /// copies of a generator each keep their own last snowflake, see
+    /// [`MonotonicGenerator`](crate::MonotonicGenerator).
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{ClockBackwardsPolicy, GeneratorOptions, MockClock, SnowflakeGenerator};
+    ///
+    /// let options = GeneratorOptions::new()
+    ///     .clock_backwards_policy(ClockBackwardsPolicy::Error)
+    ///     .strict_monotonic(true);
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(420, clock.clone())
+    ///     .unwrap()
+    ///     .with_options(options);
+    ///
+    /// let first = *generator.next();
+    /// clock.set(900_000);
+    ///
+    /// assert!(generator.try_next().unwrap() > first);
+    /// ```
+    pub fn strict_monotonic(mut self, strict: bool) -> GeneratorOptions {
+        self.strict_monotonic = strict;
+        self
+    }
+
+    /// Limit how many snowflakes the generator hands out per second, to protect downstream
+    /// systems that key on the IDs or to simulate a production rate in tests
+    ///
+    /// The limit is a leaky bucket that holds one second of snowflakes and drains at
+    /// `max_ids_per_second`, so after an idle second the generator can hand out a burst of up
+    /// to `max_ids_per_second` snowflakes before it's throttled to the steady rate. Time is
+    /// read from the generator's clock. Batches are throttled one snowflake at a time, and a
+    /// call that fails to generate a snowflake doesn't count towards the limit.
+    ///
+    /// Defaults to `0`, which doesn't limit the rate.
+    ///
+    /// # Returns
+    /// The options, or `SnowflakeError::RateLimitTooHigh` if the limit is above
+    /// [`GeneratorOptions::MAX_IDS_PER_SECOND`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{GeneratorOptions, MockClock, RateLimitPolicy, SnowflakeGenerator};
+    ///
+    /// let options = GeneratorOptions::new()
+    ///     .max_ids_per_second(2)
+    ///     .unwrap()
+    ///     .rate_limit_policy(RateLimitPolicy::Error);
+    ///
+    /// let clock = MockClock::new(1_000_000);
+    /// let mut generator = SnowflakeGenerator::with_clock(420, clock.clone())
+    ///     .unwrap()
+    ///     .with_options(options);
+    ///
+    /// assert!(generator.try_next().is_ok());
+    /// assert!(generator.try_next().is_ok());
+    /// assert!(generator.try_next().is_err());
+    ///
+    /// // Half a second drains one snowflake from the bucket
+    /// clock.advance(500);
+    /// assert!(generator.try_next().is_ok());
+    /// ```
+    pub fn max_ids_per_second(
+        mut self,
+        max_ids_per_second: u64,
+    ) -> Result<GeneratorOptions, SnowflakeError> {
+        if max_ids_per_second > GeneratorOptions::MAX_IDS_PER_SECOND {
+            return Err(SnowflakeError::RateLimitTooHigh {
+                max_ids_per_second,
+                max: GeneratorOptions::MAX_IDS_PER_SECOND,
+            });
+        }
+
+        self.max_ids_per_second = max_ids_per_second;
+        Ok(self)
+    }
+
+    /// Set what the generator does when it's asked for snowflakes faster than
+    /// [`GeneratorOptions::max_ids_per_second`] allows
+    ///
+    /// Defaults to `RateLimitPolicy::Wait`.
+    pub fn rate_limit_policy(mut self, policy: RateLimitPolicy) -> GeneratorOptions {
+        self.rate_limit_policy = policy;
+        self
+    }
+
+    /// Set how the generator waits for its clock to reach the next tick
+    ///
+    /// `OverflowStrategy::Spin` and `OverflowStrategy::Yield` still spin or yield when the
+    /// sequence runs out, whatever the wait strategy.
+    ///
+    /// Defaults to `WaitStrategy::SleepUntilNextTick`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::{GeneratorOptions, SnowflakeGenerator, WaitStrategy};
+    ///
+    /// let options = GeneratorOptions::new().wait_strategy(WaitStrategy::Park(Duration::from_micros(50)));
+    /// let mut generator = SnowflakeGenerator::try_new(420).unwrap().with_options(options);
+    ///
+    /// let batch = generator.next_batch(5_000);
+    /// assert!(batch.windows(2).all(|pair| pair[0] < pair[1]));
+    /// ```
+    pub fn wait_strategy(mut self, strategy: WaitStrategy) -> GeneratorOptions {
+        self.wait_strategy = strategy;
+        self
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::RangeInclusive;
+use std::time::SystemTime;
+
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// An inclusive range of snowflake IDs covering a window of time.
+///
+/// Use it to query IDs by creation time, for example with `WHERE id BETWEEN ? AND ?`, without
+/// storing a separate timestamp column.
+///
+/// # Example
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+/// use rusty_snowflake::{Snowflake, SnowflakeRange};
+///
+/// let now = SystemTime::now();
+/// let last_hour = SnowflakeRange::between(now - Duration::from_secs(3600), now).unwrap();
+///
+/// let snowflake = Snowflake::try_new(1).unwrap();
+/// assert!(last_hour.contains(snowflake.to_id()));
+///
+/// println!("SELECT * FROM events WHERE id BETWEEN {} AND {}", last_hour.start(), last_hour.end());
+/// ```
+pub struct SnowflakeRange {
+    start: u64,
+    end: u64,
+}
+
+impl SnowflakeRange {
+    /// Get the range of IDs created between `start` and `end`, inclusive
+    ///
+    /// # Arguments
+    /// * `start` - The earliest creation time in the range
+    /// * `end` - The latest creation time in the range
+    ///
+    /// # Returns
+    /// The `SnowflakeRange`, `SnowflakeError::ClockBeforeEpoch` if either time is before the
+    /// UNIX epoch, or `SnowflakeError::TimestampOverflow` if either time is past the last
+    /// timestamp the layout can hold
+    pub fn between(start: SystemTime, end: SystemTime) -> Result<SnowflakeRange, SnowflakeError> {
+        SnowflakeRange::between_with_layout(start, end, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the range of IDs created between `start` and `end`, inclusive, using a custom layout
+    ///
+    /// # Arguments
+    /// * `start` - The earliest creation time in the range
+    /// * `end` - The latest creation time in the range
+    /// * `layout` - The layout of the IDs in the range
+    ///
+    /// # Returns
+    /// The `SnowflakeRange`, `SnowflakeError::ClockBeforeEpoch` if either time is before the
+    /// layout's epoch, or `SnowflakeError::TimestampOverflow` if either time is past the last
+    /// timestamp the layout can hold
+    pub fn between_with_layout(
+        start: SystemTime,
+        end: SystemTime,
+        layout: &SnowflakeLayout,
+    ) -> Result<SnowflakeRange, SnowflakeError> {
+        let start = Snowflake::first_id_at_with_layout(start, layout)?;
+        let end = Snowflake::first_id_at_with_layout(end, layout)?;
+
+        Ok(SnowflakeRange {
+            start: start.to_id_with_layout(layout),
+            end: Snowflake::max_for_timestamp_with_layout(end.timestamp, layout)?
+                .to_id_with_layout(layout),
+        })
+    }
+
+    /// The smallest ID in the range
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// The largest ID in the range
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Check whether an ID is in the range
+    ///
+    /// # Arguments
+    /// * `id` - The ID to check
+    pub fn contains(&self, id: u64) -> bool {
+        self.start <= id && id <= self.end
+    }
+}
+
+impl From<SnowflakeRange> for RangeInclusive<u64> {
+    fn from(range: SnowflakeRange) -> RangeInclusive<u64> {
+        range.start..=range.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn test_between() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let end = UNIX_EPOCH + Duration::from_secs(2_000);
+        let range = SnowflakeRange::between(start, end).unwrap();
+
+        assert_eq!(range.start(), 1_000 << 22);
+        assert_eq!(range.end(), (2_000 << 22) | 0x3F_FFFF);
+
+        assert!(range.contains(1_000 << 22));
+        assert!(range.contains((2_000 << 22) | 0x3F_FFFF));
+        assert!(!range.contains((1_000 << 22) - 1));
+        assert!(!range.contains(2_001 << 22));
+    }
+
+    #[test]
+    fn test_between_with_layout() {
+        let layout = SnowflakeLayout::DISCORD;
+        let start = UNIX_EPOCH + Duration::from_millis(layout.epoch() + 10);
+        let end = UNIX_EPOCH + Duration::from_millis(layout.epoch() + 20);
+        let range = SnowflakeRange::between_with_layout(start, end, &layout).unwrap();
+
+        assert_eq!(range.start(), 10 << 22);
+        assert_eq!(range.end(), (20 << 22) | 0x3F_FFFF);
+    }
+
+    #[test]
+    fn test_between_with_extra_bits() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .build()
+            .unwrap();
+        let tick = Duration::from_micros(layout.tick_micros());
+        let epoch = UNIX_EPOCH + Duration::from_millis(layout.epoch());
+        let range =
+            SnowflakeRange::between_with_layout(epoch + tick * 10, epoch + tick * 20, &layout)
+                .unwrap();
+
+        let id = |timestamp, extra| {
+            Snowflake {
+                worker_id: 0,
+                sequence: 0,
+                timestamp,
+                extra,
+            }
+            .to_id_with_layout(&layout)
+        };
+        assert!(range.contains(id(10, 0)));
+        assert!(range.contains(id(20, 3)));
+        assert!(!range.contains(id(21, 0)));
+        assert_eq!(range.end() + 1, id(21, 0));
+    }
+
+    #[test]
+    fn test_between_past_max_timestamp() {
+        let layout = SnowflakeLayout::DISCORD;
+        let start = UNIX_EPOCH + Duration::from_millis(layout.epoch());
+        let end = UNIX_EPOCH + Duration::from_secs(10_000_000_000);
+
+        assert!(matches!(
+            SnowflakeRange::between_with_layout(start, end, &layout),
+            Err(SnowflakeError::TimestampOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_between_before_epoch() {
+        assert_eq!(
+            SnowflakeRange::between(UNIX_EPOCH - Duration::from_secs(1), UNIX_EPOCH),
+            Err(SnowflakeError::ClockBeforeEpoch)
+        );
+    }
+
+    #[test]
+    fn test_into_range_inclusive() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_000);
+        let range: RangeInclusive<u64> = SnowflakeRange::between(time, time).unwrap().into();
+
+        assert_eq!(range, (1_000 << 22)..=((1_000 << 22) | 0x3F_FFFF));
+    }
+}
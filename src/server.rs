@@ -0,0 +1,274 @@
+//! A tiny HTTP service that hands out snowflake IDs from one central generator.
+//!
+//! Run one [`IdServer`] per worker ID and point clients at it, like Twitter's original
+//! snowflake daemon. `GET /next?count=n` responds with `n` IDs as decimal numbers, one per line,
+//! and `GET /health` answers readiness probes.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::net::TcpListener;
+//! use rusty_snowflake::server::IdServer;
+//! use rusty_snowflake::SnowflakeGenerator;
+//!
+//! let server = IdServer::new(SnowflakeGenerator::try_new(1).unwrap());
+//!
+//! // curl 'http://127.0.0.1:8080/next?count=10'
+//! server.serve(TcpListener::bind("127.0.0.1:8080").unwrap());
+//! ```
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::{Clock, SnowflakeGenerator, SystemClock};
+
+/// How long a client may take to send its request before the connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+/// Serves IDs from a [`SnowflakeGenerator`] over HTTP/1.1.
+///
+/// Every connection carries a single request and is closed after the response.
+///
+/// | Request               | Response                                                      |
+/// | --------------------- | ------------------------------------------------------------- |
+/// | `GET /next`           | `200` with one ID                                             |
+/// | `GET /next?count=n`   | `200` with `n` IDs in ascending order, one per line           |
+/// | bad or too big `n`    | `400`                                                         |
+/// | generator error       | `503` with the error, such as the clock moving backwards      |
+/// | `GET /health`         | `200` if healthy, otherwise `503` with the problems found     |
+pub struct IdServer<C: Clock = SystemClock> {
+    generator: Mutex<SnowflakeGenerator<C>>,
+    max_count: u64,
+}
+
+impl<C: Clock> IdServer<C> {
+    /// Create a server that hands out IDs from `generator`, up to 1000 per request
+    ///
+    /// IDs are encoded with the generator's layout.
+    pub fn new(generator: SnowflakeGenerator<C>) -> IdServer<C> {
+        IdServer {
+            generator: Mutex::new(generator),
+            max_count: 1000,
+        }
+    }
+
+    /// Set the most IDs a single request can ask for
+    pub fn max_count(mut self, max_count: u64) -> IdServer<C> {
+        self.max_count = max_count;
+        self
+    }
+
+    /// Accept connections on `listener` forever, handling each on its own thread
+    ///
+    /// Connections that fail, for example because the client hung up, are dropped without
+    /// stopping the server.
+    pub fn serve(&self, listener: TcpListener)
+    where
+        C: Send,
+    {
+        std::thread::scope(|scope| {
+            for stream in listener.incoming().flatten() {
+                scope.spawn(|| {
+                    let _ = self.handle(stream);
+                });
+            }
+        });
+    }
+
+    /// Read one request from `stream` and write the response
+    pub fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        // Skip the headers, the request never has a body
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let (status, body) = self.respond(&request_line);
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    /// Get the status line and body answering a request line like `GET /next?count=5 HTTP/1.1`
+    fn respond(&self, request_line: &str) -> (&'static str, String) {
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method, target),
+            _ => return ("400 Bad Request", "malformed request\n".to_string()),
+        };
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        if path != "/next" && path != "/health" {
+            return ("404 Not Found", "not found\n".to_string());
+        }
+        if method != "GET" {
+            return (
+                "405 Method Not Allowed",
+                "only GET is allowed\n".to_string(),
+            );
+        }
+
+        if path == "/health" {
+            let report = self
+                .generator
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .health_check();
+            let status = if report.is_healthy() {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            return (status, format!("{}\n", report));
+        }
+
+        let count = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("count="))
+            .map_or(Ok(1), str::parse::<u64>);
+        let count = match count {
+            Ok(count @ 1..) if count <= self.max_count => count,
+            _ => {
+                let message = format!("count must be between 1 and {}\n", self.max_count);
+                return ("400 Bad Request", message);
+            }
+        };
+
+        let mut generator = self
+            .generator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let layout = *generator.layout();
+
+        let mut body = String::new();
+        for _ in 0..count {
+            match generator.try_next() {
+                Ok(snowflake) => {
+                    body.push_str(&snowflake.to_id_with_layout(&layout).to_string());
+                    body.push('\n');
+                }
+                Err(err) => return ("503 Service Unavailable", format!("{}\n", err)),
+            }
+        }
+
+        ("200 OK", body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::{ClockBackwardsPolicy, GeneratorOptions, MockClock, Snowflake};
+
+    fn server() -> IdServer<MockClock> {
+        let generator = SnowflakeGenerator::with_clock(7, MockClock::new(1_000_000)).unwrap();
+        IdServer::new(generator).max_count(10)
+    }
+
+    /// Send `request` to `server` over a real connection and read the whole response
+    fn request(server: &IdServer<MockClock>, request: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        client.write_all(request.as_bytes()).unwrap();
+        server.handle(stream).unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_next() {
+        let server = server();
+
+        let response = request(&server, "GET /next?count=3 HTTP/1.1\r\nHost: x\r\n\r\n");
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+
+        let ids = body
+            .lines()
+            .map(|line| Snowflake::parse(line.parse().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| id.worker_id() == 7));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let response = request(&server, "GET /next HTTP/1.1\r\n\r\n");
+        assert_eq!(
+            response.split_once("\r\n\r\n").unwrap().1.lines().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = server();
+
+        assert_eq!(
+            server.respond("GET /next?count=0 HTTP/1.1").0,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            server.respond("GET /next?count=11 HTTP/1.1").0,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            server.respond("GET /next?count=x HTTP/1.1").0,
+            "400 Bad Request"
+        );
+        assert_eq!(server.respond("GET").0, "400 Bad Request");
+        assert_eq!(server.respond("GET /other HTTP/1.1").0, "404 Not Found");
+        assert_eq!(
+            server.respond("POST /next HTTP/1.1").0,
+            "405 Method Not Allowed"
+        );
+    }
+
+    #[test]
+    fn test_generator_error() {
+        let future = Snowflake::builder().timestamp(2_000).build().unwrap();
+        let generator = SnowflakeGenerator::start_at_with_clock(&future, MockClock::new(0))
+            .with_options(
+                GeneratorOptions::new().clock_backwards_policy(ClockBackwardsPolicy::Error),
+            );
+
+        let (status, body) = IdServer::new(generator).respond("GET /next HTTP/1.1");
+
+        assert_eq!(status, "503 Service Unavailable");
+        assert!(body.contains("backwards"));
+    }
+
+    #[test]
+    fn test_health() {
+        assert_eq!(
+            server().respond("GET /health HTTP/1.1"),
+            ("200 OK", "healthy\n".to_string())
+        );
+
+        let future = Snowflake::builder().timestamp(2_000).build().unwrap();
+        let generator = SnowflakeGenerator::start_at_with_clock(&future, MockClock::new(0));
+        let (status, body) = IdServer::new(generator).respond("GET /health HTTP/1.1");
+
+        assert_eq!(status, "503 Service Unavailable");
+        assert!(body.starts_with("clock: "));
+    }
+}
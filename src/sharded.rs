@@ -0,0 +1,144 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::{Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+/// A generator that puts a logical shard ID in the worker ID field of each snowflake.
+///
+/// The shard is computed from a caller-supplied key every time an ID is generated, so IDs
+/// co-locate with the shard that owns them. Each shard has its own sequence, like the
+/// per-shard sequences of Instagram's IDs.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{ShardedGenerator, SnowflakeLayout};
+///
+/// let mut generator = ShardedGenerator::new(|user_id: &u64| user_id % 2000);
+///
+/// let snowflake = generator.next_for(&31341).unwrap();
+///
+/// assert_eq!(snowflake.worker_id(), 1341);
+/// println!("Generated Instagram ID: {}", snowflake.to_id_with_layout(&SnowflakeLayout::INSTAGRAM));
+/// ```
+pub struct ShardedGenerator<K: ?Sized, F: Fn(&K) -> u64> {
+    /// Computes the shard ID of a key.
+    shard_fn: F,
+    /// The bit layout of the snowflakes generated.
+    layout: SnowflakeLayout,
+    /// The generator of each shard that has been used so far.
+    shards: HashMap<u64, SnowflakeGenerator>,
+    key: PhantomData<fn(&K)>,
+}
+
+impl<K: ?Sized, F: Fn(&K) -> u64> ShardedGenerator<K, F> {
+    /// Create a new sharded generator that generates snowflakes with
+    /// [`SnowflakeLayout::INSTAGRAM`]
+    ///
+    /// # Arguments
+    /// * `shard_fn` - Computes the shard ID of a key
+    ///
+    /// # Returns
+    /// A new `ShardedGenerator`
+    pub fn new(shard_fn: F) -> ShardedGenerator<K, F> {
+        ShardedGenerator::with_layout(SnowflakeLayout::INSTAGRAM, shard_fn)
+    }
+
+    /// Create a new sharded generator with a custom bit layout
+    ///
+    /// # Arguments
+    /// * `layout` - The bit layout of the snowflakes generated
+    /// * `shard_fn` - Computes the shard ID of a key
+    ///
+    /// # Returns
+    /// A new `ShardedGenerator`
+    pub fn with_layout(layout: SnowflakeLayout, shard_fn: F) -> ShardedGenerator<K, F> {
+        ShardedGenerator {
+            shard_fn,
+            layout,
+            shards: HashMap::new(),
+            key: PhantomData,
+        }
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Generates the next snowflake ID for the shard that owns `key`.
+    ///
+    /// # Arguments
+    /// * `key` - The key to compute the shard ID from
+    ///
+    /// # Returns
+    /// The generated snowflake, or an error if the shard ID doesn't fit in the layout's worker ID
+    /// field or the shard's generator fails
+    pub fn next_for(&mut self, key: &K) -> Result<Snowflake, SnowflakeError> {
+        let shard_id = (self.shard_fn)(key);
+
+        let generator = match self.shards.entry(shard_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(SnowflakeGenerator::with_layout(shard_id, self.layout)?)
+            }
+        };
+
+        generator.try_next()
+    }
+}
+
+impl<K: ?Sized, F: Fn(&K) -> u64> std::fmt::Debug for ShardedGenerator<K, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ShardedGenerator")
+            .field("layout", &self.layout)
+            .field("shards", &self.shards)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_for() {
+        let mut generator = ShardedGenerator::new(|user_id: &u64| user_id % 2000);
+
+        let first = generator.next_for(&31341).unwrap();
+        let second = generator.next_for(&33341).unwrap();
+        let other = generator.next_for(&1).unwrap();
+
+        assert_eq!(first.worker_id, 1341);
+        assert_eq!(second.worker_id, 1341);
+        assert!(second > first);
+        assert_eq!(other.worker_id, 1);
+    }
+
+    #[test]
+    fn test_str_keys() {
+        let mut generator =
+            ShardedGenerator::new(|name: &str| name.bytes().map(u64::from).sum::<u64>() % 8192);
+
+        let snowflake = generator.next_for("instagram").unwrap();
+        let id = snowflake.to_id_with_layout(generator.layout());
+
+        assert_eq!(
+            Snowflake::parse_with_layout(id, &SnowflakeLayout::INSTAGRAM),
+            snowflake
+        );
+    }
+
+    #[test]
+    fn test_shard_overflow() {
+        let mut generator = ShardedGenerator::new(|_: &u64| 8192);
+
+        assert_eq!(
+            generator.next_for(&0),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 8192,
+                max: 8191
+            })
+        );
+    }
+}
@@ -1,46 +1,320 @@
-use crate::SnowflakeGenerator;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use crate::encoding;
+#[cfg(feature = "std")]
+use crate::generator::current_timestamp;
+use crate::keys::{write_digits, MAX_ID_LENGTH};
+use crate::layout::{shl, shr};
+#[cfg(feature = "std")]
+use crate::{Clock, SnowflakeGenerator, SystemClock};
+use crate::{SnowflakeError, SnowflakeLayout, ValidationRules};
+
+/// The largest creation time UUIDv7s and ULIDs can hold, in milliseconds since the UNIX epoch.
+const MAX_48_BIT_MILLIS: u64 = (1 << 48) - 1;
+
+/// The version bits of a UUIDv7.
+const UUID_VERSION_7: u128 = 0x7 << 76;
+const UUID_VERSION_MASK: u128 = 0xF << 76;
+
+/// The variant bits of an RFC 9562 UUID.
+const UUID_VARIANT: u128 = 0b10 << 62;
+const UUID_VARIANT_MASK: u128 = 0b11 << 62;
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[repr(C)]
 pub struct Snowflake {
     /// The worker ID of the snowflake.
     /// This is a unique identifier for the host or thread that created the snowflake.
-    pub worker_id: u64,
+    pub(crate) worker_id: u64,
     /// The sequence number of the snowflake.
-    /// This increments every time the snowflake is created within the same second.
+    /// This increments every time a snowflake is created within the same tick.
     /// This will automatically reset to 0 when the timestamp changes or
-    /// when the sequence overflows (2^16 - 1).
-    pub sequence: u64,
-    /// The timestamp of the snowflake creation in seconds since the epoch (1970-01-01 00:00:00 UTC).
-    pub timestamp: u64,
+    /// when the sequence passes the layout's largest sequence.
+    pub(crate) sequence: u64,
+    /// The timestamp of the snowflake creation in ticks of the layout since its epoch.
+    /// The default layout counts seconds since the UNIX epoch.
+    pub(crate) timestamp: u64,
+    /// The caller-defined extra field of the snowflake, such as the type of the thing it names.
+    /// This is always zero unless the layout has extra bits.
+    pub(crate) extra: u64,
 }
 
 impl Snowflake {
-    /// Create a new snowflake with the given worker ID
+    /// Create a builder for a snowflake that checks every field fits in its layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeError};
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(42)
+    ///     .sequence(7)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(Snowflake::parse(snowflake.to_id()), snowflake);
+    ///
+    /// assert_eq!(
+    ///     Snowflake::builder().worker_id(1024).build(),
+    ///     Err(SnowflakeError::WorkerIdOverflow { worker_id: 1024, max: 1023 })
+    /// );
+    /// ```
+    pub fn builder() -> SnowflakeBuilder {
+        SnowflakeBuilder {
+            snowflake: Snowflake::default(),
+            layout: SnowflakeLayout::DEFAULT,
+        }
+    }
+
+    /// The worker ID of the snowflake
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder().worker_id(42).build().unwrap();
+    /// assert_eq!(snowflake.worker_id(), 42);
+    /// ```
+    pub const fn worker_id(self) -> u64 {
+        self.worker_id
+    }
+
+    /// The datacenter ID in the high bits of the worker ID
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder().worker_id(3 << 5 | 7).build().unwrap();
+    /// assert_eq!(snowflake.datacenter_id(), 3);
+    /// assert_eq!(snowflake.machine_id(), 7);
+    /// ```
+    pub const fn datacenter_id(self) -> u64 {
+        self.datacenter_id_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// The datacenter ID in the high bits of the worker ID, split the way a custom layout does
+    pub const fn datacenter_id_with_layout(self, layout: &SnowflakeLayout) -> u64 {
+        shr(self.worker_id, layout.machine_id_bits() as u32) & layout.max_datacenter_id()
+    }
+
+    /// The machine ID in the low bits of the worker ID
+    pub const fn machine_id(self) -> u64 {
+        self.machine_id_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// The machine ID in the low bits of the worker ID, split the way a custom layout does
+    pub const fn machine_id_with_layout(self, layout: &SnowflakeLayout) -> u64 {
+        self.worker_id & layout.max_machine_id()
+    }
+
+    /// The sequence number of the snowflake within its timestamp
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder().sequence(7).build().unwrap();
+    /// assert_eq!(snowflake.sequence(), 7);
+    /// ```
+    pub const fn sequence(self) -> u64 {
+        self.sequence
+    }
+
+    /// The timestamp of the snowflake, in ticks since the epoch of its layout
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder().timestamp(1_700_000_000).build().unwrap();
+    /// assert_eq!(snowflake.timestamp(), 1_700_000_000);
+    /// ```
+    pub const fn timestamp(self) -> u64 {
+        self.timestamp
+    }
+
+    /// The caller-defined extra field of the snowflake
+    ///
+    /// This is zero unless the snowflake was parsed with or built for a layout with
+    /// [extra bits](crate::SnowflakeLayoutBuilder::extra_bits).
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeLayout};
+    ///
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .extra_bits(2)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let snowflake = Snowflake::builder().extra(3).layout(layout).build().unwrap();
+    /// assert_eq!(snowflake.extra(), 3);
+    /// ```
+    pub const fn extra(self) -> u64 {
+        self.extra
+    }
+
+    /// Create a builder that starts out with the snowflake's fields
+    ///
+    /// Use it to change fields of a snowflake with a custom layout, which
+    /// [`Snowflake::with_worker_id`] and friends don't check against.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeLayout};
+    ///
+    /// let layout = SnowflakeLayout::SONYFLAKE;
+    /// let snowflake = Snowflake::parse_with_layout(1 << 24 | 3 << 16 | 7, &layout);
+    ///
+    /// let next = snowflake.to_builder().layout(layout).sequence(4).build().unwrap();
+    /// assert_eq!((next.timestamp(), next.sequence(), next.worker_id()), (1, 4, 7));
+    /// ```
+    pub fn to_builder(self) -> SnowflakeBuilder {
+        SnowflakeBuilder {
+            snowflake: self,
+            layout: SnowflakeLayout::DEFAULT,
+        }
+    }
+
+    /// Change the worker ID of the snowflake
     ///
     /// # Arguments
-    /// * `worker_id` - The worker ID of the snowflake
+    /// * `worker_id` - The new worker ID
     ///
     /// # Returns
-    /// A new `Snowflake`
+    /// The changed snowflake, or `SnowflakeError::WorkerIdOverflow` if the worker ID doesn't fit
+    /// in [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::parse(175928847299117063);
+    ///
+    /// assert_eq!(snowflake.with_worker_id(5).unwrap().worker_id(), 5);
+    /// assert!(snowflake.with_worker_id(1024).is_err());
+    /// ```
+    pub fn with_worker_id(self, worker_id: u64) -> Result<Snowflake, SnowflakeError> {
+        self.to_builder().worker_id(worker_id).build()
+    }
+
+    /// Change the sequence number of the snowflake
+    ///
+    /// # Arguments
+    /// * `sequence` - The new sequence number
+    ///
+    /// # Returns
+    /// The changed snowflake, or `SnowflakeError::SequenceOverflow` if the sequence number
+    /// doesn't fit in [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::parse(175928847299117063);
+    ///
+    /// // The last ID of the same worker and second, as an inclusive range bound
+    /// let last = snowflake.with_sequence(4095).unwrap();
+    /// assert!(last > snowflake);
+    /// assert!(snowflake.with_sequence(4096).is_err());
+    /// ```
+    pub fn with_sequence(self, sequence: u64) -> Result<Snowflake, SnowflakeError> {
+        self.to_builder().sequence(sequence).build()
+    }
+
+    /// Change the timestamp of the snowflake
+    ///
+    /// # Arguments
+    /// * `timestamp` - The new timestamp, in ticks since the epoch of the layout
+    ///
+    /// # Returns
+    /// The changed snowflake, or `SnowflakeError::TimestampOverflow` if the timestamp doesn't
+    /// fit in [`SnowflakeLayout::DEFAULT`]
     ///
     /// # Example
     /// ```rust
     /// use rusty_snowflake::Snowflake;
     ///
-    /// let snowflake = Snowflake::new(420);
+    /// let snowflake = Snowflake::parse(175928847299117063);
+    ///
+    /// assert_eq!(snowflake.with_timestamp(42).unwrap().timestamp(), 42);
+    /// assert!(snowflake.with_timestamp(1 << 41).is_err());
     /// ```
+    pub fn with_timestamp(self, timestamp: u64) -> Result<Snowflake, SnowflakeError> {
+        self.to_builder().timestamp(timestamp).build()
+    }
+
+    /// Create a new snowflake with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake
+    ///
+    /// # Returns
+    /// A new `Snowflake`
+    ///
+    /// A system clock set before the UNIX epoch gives a timestamp of zero, and worker IDs that
+    /// don't fit in [`SnowflakeLayout::DEFAULT`] are silently truncated when converted to an ID.
+    #[cfg(feature = "std")]
+    #[deprecated(since = "0.3.0", note = "use `Snowflake::try_new` instead")]
     pub fn new(worker_id: u64) -> Snowflake {
         Snowflake {
             worker_id,
             sequence: 0,
-            timestamp: SnowflakeGenerator::get_timestamp(),
+            timestamp: current_timestamp(),
+            extra: 0,
+        }
+    }
+
+    /// Create a new snowflake with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflake
+    ///
+    /// # Returns
+    /// A new `Snowflake`, or an error if the worker ID doesn't fit in
+    /// [`SnowflakeLayout::DEFAULT`] or the system clock is set before the UNIX epoch
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeError};
+    ///
+    /// let snowflake = Snowflake::try_new(420).unwrap();
+    /// assert_eq!(snowflake.worker_id(), 420);
+    ///
+    /// assert_eq!(
+    ///     Snowflake::try_new(1025),
+    ///     Err(SnowflakeError::WorkerIdOverflow { worker_id: 1025, max: 1023 })
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn try_new(worker_id: u64) -> Result<Snowflake, SnowflakeError> {
+        let max = SnowflakeLayout::DEFAULT.max_worker_id();
+        if worker_id > max {
+            return Err(SnowflakeError::WorkerIdOverflow { worker_id, max });
         }
+
+        Ok(Snowflake {
+            worker_id,
+            sequence: 0,
+            timestamp: SnowflakeGenerator::try_get_timestamp()?,
+            extra: 0,
+        })
     }
 
-    /// Generate a new snowflake ID
-    /// This function will return a new snowflake ID every time it is called
-    /// with the same worker ID.
-    /// If the timestamp is the same as the previous call, the sequence number will be incremented.
+    /// Generate the snowflake ID that follows this one
+    /// If the timestamp is the same as this snowflake's, the sequence number will be incremented.
+    ///
+    /// This doesn't change the snowflake, so calling it twice on the same snowflake, or on two
+    /// copies of it, returns the same ID both times within one tick. Use
+    /// [`Snowflake::into_generator`] to get a [`MonotonicGenerator`](crate::MonotonicGenerator)
+    /// that can't return an ID twice.
     ///
     /// # Returns
     /// A new snowflake ID
@@ -49,22 +323,29 @@ impl Snowflake {
     /// ```rust
     /// use rusty_snowflake::Snowflake;
     ///
-    /// let snowflake = Snowflake::new(420);
-    /// let next = snowflake.next();
-    /// println!("{}", next);
-    /// println!("{}", next.next());
+    /// let mut generator = Snowflake::try_new(420).unwrap().into_generator();
+    /// println!("{}", generator.try_next().unwrap());
+    /// println!("{}", generator.try_next().unwrap());
     /// ```
-    pub fn next(&self) -> Snowflake {
-        let mut timestamp = SnowflakeGenerator::get_timestamp();
+    #[cfg(feature = "std")]
+    #[deprecated(
+        since = "0.3.0",
+        note = "copies of a snowflake return the same next ID; use `Snowflake::into_generator` instead"
+    )]
+    pub fn next(self) -> Snowflake {
+        let max_sequence = SnowflakeLayout::DEFAULT.max_sequence();
+        let mut timestamp = current_timestamp();
         let mut sequence = self.sequence;
 
         if timestamp < self.timestamp {
             timestamp = self.timestamp; // Reset timestamp
-            sequence = (sequence + 1) & 0xFFFF; // Increment sequence
+            sequence = sequence.wrapping_add(1) & max_sequence; // Increment sequence
         } else if timestamp == self.timestamp {
-            sequence = (sequence + 1) & 0xFFFF; // Increment sequence
+            sequence = sequence.wrapping_add(1) & max_sequence; // Increment sequence
             if sequence == 0 {
-                timestamp = SnowflakeGenerator::wait_next_timestamp(timestamp); // Update timestamp when sequence overflows
+                #[allow(deprecated)]
+                let next = SnowflakeGenerator::wait_next_timestamp(timestamp);
+                timestamp = next; // Update timestamp when sequence overflows
             }
         } else {
             sequence = 0; // Reset sequence because timestamp changed
@@ -74,6 +355,7 @@ impl Snowflake {
             worker_id: self.worker_id,
             sequence,
             timestamp,
+            extra: 0,
         }
     }
 
@@ -84,7 +366,7 @@ impl Snowflake {
     /// ```rust
     /// use rusty_snowflake::Snowflake;
     ///
-    /// let snowflake = Snowflake::new(1); // Create
+    /// let snowflake = Snowflake::try_new(1).unwrap(); // Create
     ///
     /// let id = snowflake.to_id();
     ///
@@ -92,188 +374,2436 @@ impl Snowflake {
     ///
     /// assert_eq!(snowflake, parsed);
     /// ```
-    pub fn to_id(&self) -> u64 {
-        (self.timestamp << 22) | (self.worker_id << 12) | self.sequence
+    pub const fn to_id(self) -> u64 {
+        self.to_id_with_layout(&SnowflakeLayout::DEFAULT)
     }
 
-    /// Parse a snowflake ID into a `Snowflake`
+    /// Convert a Snowflake ID into a u64 id using a custom bit layout
+    ///
+    /// Each field is truncated to the number of bits the layout gives it.
+    ///
+    /// # Arguments
+    /// * `layout` - The bit layout to pack the fields with
+    ///
     /// # Example
+    ///
     /// ```rust
-    /// use rusty_snowflake::Snowflake;
+    /// use rusty_snowflake::{Snowflake, SnowflakeLayout};
     ///
-    /// let snowflake = Snowflake::new(1);
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .worker_id_bits(16)
+    ///     .sequence_bits(8)
+    ///     .build()
+    ///     .unwrap();
     ///
-    /// let id = snowflake.to_id();
-    /// let parsed = Snowflake::parse(id);
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(40_000)
+    ///     .layout(layout)
+    ///     .build()
+    ///     .unwrap();
     ///
-    /// assert_eq!(snowflake, parsed);
+    /// let id = snowflake.to_id_with_layout(&layout);
+    ///
+    /// assert_eq!(snowflake, Snowflake::parse_with_layout(id, &layout));
     /// ```
-    pub fn parse(id: u64) -> Snowflake {
-        let timestamp = (id >> 22) & 0x1FFFFFFFFFF;
-        let worker_id = (id >> 12) & 0x3FF;
-        let sequence = id & 0xFFF;
-
-        Snowflake {
-            worker_id,
-            sequence,
-            timestamp: timestamp,
-        }
+    pub const fn to_id_with_layout(self, layout: &SnowflakeLayout) -> u64 {
+        shl(
+            self.timestamp & layout.max_timestamp(),
+            layout.timestamp_shift(),
+        ) | shl(
+            self.worker_id & layout.max_worker_id(),
+            layout.worker_id_shift(),
+        ) | shl(
+            self.sequence & layout.max_sequence(),
+            layout.sequence_shift(),
+        ) | shl(self.extra & layout.max_extra(), layout.extra_shift())
     }
-}
 
-impl std::fmt::Display for Snowflake {
-    /// Display the Snowflake
+    /// Write the snowflake's ID in decimal to a writer, without allocating
+    ///
+    /// This is what `Display` does, and is faster than `write!(writer, "{}", snowflake)` when
+    /// formatting many IDs into the same `String` or log line.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the ID to
+    ///
     /// # Example
     /// ```rust
     /// use rusty_snowflake::Snowflake;
     ///
-    /// let mut snowflake = Snowflake::new(1);
-    /// println!("{}", snowflake); // u64 ID
+    /// let mut line = String::from("id=");
+    /// Snowflake::parse(175928847299117063).write_to(&mut line).unwrap();
+    ///
+    /// assert_eq!(line, "id=175928847299117063");
     /// ```
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.to_id())
+    pub fn write_to<W: core::fmt::Write>(self, writer: &mut W) -> core::fmt::Result {
+        let mut buffer = [0; MAX_ID_LENGTH];
+        writer.write_str(write_digits(self.to_id(), &mut buffer))
     }
-}
 
-impl From<u64> for Snowflake {
-    fn from(id: u64) -> Snowflake {
-        Snowflake::parse(id)
-    }
-}
+    /// Write the snowflake's ID in decimal into a buffer, without allocating
+    ///
+    /// A buffer of [`keys::MAX_ID_LENGTH`](crate::keys::MAX_ID_LENGTH) bytes fits any ID.
+    ///
+    /// # Arguments
+    /// * `buffer` - The buffer to write the ID to
+    ///
+    /// # Returns
+    /// The ID, borrowed from the start of the buffer, or `SnowflakeError::BufferTooSmall` if it
+    /// doesn't fit
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    /// use rusty_snowflake::keys::MAX_ID_LENGTH;
+    ///
+    /// let snowflake = Snowflake::parse(175928847299117063);
+    /// let mut buffer = [0; MAX_ID_LENGTH];
+    ///
+    /// assert_eq!(snowflake.encode_to_slice(&mut buffer), Ok("175928847299117063"));
+    /// assert!(snowflake.encode_to_slice(&mut buffer[..8]).is_err());
+    /// ```
+    pub fn encode_to_slice(self, buffer: &mut [u8]) -> Result<&str, SnowflakeError> {
+        let mut digits = [0; MAX_ID_LENGTH];
+        let digits = write_digits(self.to_id(), &mut digits);
 
-impl Ord for Snowflake {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.to_id().cmp(&other.to_id())
+        let length = digits.len();
+        if length > buffer.len() {
+            return Err(SnowflakeError::BufferTooSmall {
+                length,
+                capacity: buffer.len(),
+            });
+        }
+
+        buffer[..length].copy_from_slice(digits.as_bytes());
+        // The digits are ASCII
+        Ok(core::str::from_utf8(&buffer[..length]).unwrap_or_default())
     }
-}
 
-impl PartialOrd for Snowflake {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// Convert a Snowflake ID into a u64 id, checking that every field fits
+    ///
+    /// # Returns
+    /// The ID, or an error if the timestamp, worker ID, sequence or extra value don't fit in
+    /// [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeError, SnowflakeLayout};
+    ///
+    /// let layout = SnowflakeLayout::builder().sequence_bits(13).build().unwrap();
+    /// let snowflake = Snowflake::builder()
+    ///     .worker_id(1)
+    ///     .sequence(4096)
+    ///     .layout(layout)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     snowflake.try_to_id(),
+    ///     Err(SnowflakeError::SequenceOverflow { sequence: 4096, max: 4095 })
+    /// );
+    /// ```
+    pub fn try_to_id(self) -> Result<u64, SnowflakeError> {
+        let checked = SnowflakeBuilder {
+            snowflake: self,
+            layout: SnowflakeLayout::DEFAULT,
+        }
+        .build()?;
+
+        Ok(checked.to_id())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Parse a snowflake ID into a `Snowflake`
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// let id = snowflake.to_id();
+    /// let parsed = Snowflake::parse(id);
+    ///
+    /// assert_eq!(snowflake, parsed);
+    /// ```
+    pub const fn parse(id: u64) -> Snowflake {
+        Snowflake::parse_with_layout(id, &SnowflakeLayout::DEFAULT)
+    }
 
-    #[test]
-    fn test_worker_id() {
-        const IDS: [u64; 6] = [42, 69, 420, 123, 777, 1000];
+    /// Parse a snowflake ID into a `Snowflake` using a custom bit layout
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID to parse
+    /// * `layout` - The bit layout the ID was packed with
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeLayout};
+    ///
+    /// let layout = SnowflakeLayout::builder()
+    ///     .timestamp_bits(39)
+    ///     .worker_id_bits(16)
+    ///     .sequence_bits(8)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let parsed = Snowflake::parse_with_layout(0x0100_0203, &layout);
+    ///
+    /// assert_eq!(parsed.timestamp(), 1);
+    /// assert_eq!(parsed.worker_id(), 2);
+    /// assert_eq!(parsed.sequence(), 3);
+    /// ```
+    pub const fn parse_with_layout(id: u64, layout: &SnowflakeLayout) -> Snowflake {
+        let timestamp = shr(id, layout.timestamp_shift()) & layout.max_timestamp();
+        let worker_id = shr(id, layout.worker_id_shift()) & layout.max_worker_id();
+        let sequence = shr(id, layout.sequence_shift()) & layout.max_sequence();
+        let extra = shr(id, layout.extra_shift()) & layout.max_extra();
 
-        for i in 0..IDS.len() {
-            let snowflake = Snowflake::new(IDS[i]);
-            assert_eq!(snowflake.worker_id, IDS[i]);
+        Snowflake {
+            worker_id,
+            sequence,
+            timestamp,
+            extra,
         }
     }
 
-    #[test]
-    fn test_sequence() {
-        let mut snowflake = Snowflake::new(1);
+    /// Parse a snowflake ID, rejecting IDs that [`SnowflakeLayout::DEFAULT`] couldn't have
+    /// packed
+    ///
+    /// See [`Snowflake::try_parse_with_layout`].
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID to parse
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeError};
+    ///
+    /// assert_eq!(
+    ///     Snowflake::try_parse(175928847299117063),
+    ///     Ok(Snowflake::parse(175928847299117063))
+    /// );
+    /// assert_eq!(
+    ///     Snowflake::try_parse(1 << 63),
+    ///     Err(SnowflakeError::ReservedBitsSet { id: 1 << 63 })
+    /// );
+    /// ```
+    pub const fn try_parse(id: u64) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse_with_layout(id, &SnowflakeLayout::DEFAULT)
+    }
 
-        for i in 1..10 {
-            snowflake = snowflake.next();
-            let id = snowflake.to_id();
-            assert_eq!(Snowflake::parse(id).sequence, i);
+    /// Parse a snowflake ID using a custom bit layout, rejecting IDs the layout couldn't have
+    /// packed
+    ///
+    /// Unlike [`Snowflake::parse_with_layout`], which ignores bits the layout doesn't use, this
+    /// checks that the ID is consistent with the layout.
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID to parse
+    /// * `layout` - The bit layout the ID was packed with
+    ///
+    /// # Returns
+    /// The `Snowflake`, or `SnowflakeError::ReservedBitsSet` if any bit above the layout's
+    /// timestamp is set and `SnowflakeError::TimestampOverflow` if the timestamp is past the
+    /// last time the layout's epoch and tick can represent
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{Snowflake, SnowflakeError, SnowflakeLayout, TimeUnit};
+    ///
+    /// // 51 bits of seconds count past the end of `u64` microseconds
+    /// let layout = SnowflakeLayout::custom::<51, 6, 6>().with_time_unit(TimeUnit::Seconds);
+    /// let id = u64::MAX >> 1;
+    ///
+    /// assert!(matches!(
+    ///     Snowflake::try_parse_with_layout(id, &layout),
+    ///     Err(SnowflakeError::TimestampOverflow { .. })
+    /// ));
+    /// ```
+    pub const fn try_parse_with_layout(
+        id: u64,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        if shr(id, layout.timestamp_shift()) > layout.max_timestamp() {
+            return Err(SnowflakeError::ReservedBitsSet { id });
         }
-    }
 
-    #[test]
-    fn test_timestamp() {
-        let snowflake = Snowflake::new(1);
-        assert_eq!(snowflake.timestamp, SnowflakeGenerator::get_timestamp());
+        let snowflake = Snowflake::parse_with_layout(id, layout);
+        let max = layout.max_representable_timestamp();
+        if snowflake.timestamp > max {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: snowflake.timestamp,
+                max,
+            });
+        }
+
+        Ok(snowflake)
     }
 
-    #[test]
-    fn test_snowflake_parse() {
-        let snowflake = Snowflake::new(1);
+    /// Parse many snowflake IDs at once
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs to parse
+    ///
+    /// # Returns
+    /// The parsed snowflakes, in the same order as the IDs
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflakes = Snowflake::parse_many(&[1 << 22, 2 << 22]);
+    ///
+    /// assert_eq!(snowflakes[0].timestamp(), 1);
+    /// assert_eq!(snowflakes[1].timestamp(), 2);
+    /// ```
+    pub fn parse_many(ids: &[u64]) -> Vec<Snowflake> {
+        Snowflake::parse_many_with_layout(ids, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Parse many snowflake IDs at once, using a custom bit layout
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs to parse
+    /// * `layout` - The bit layout the IDs were packed with
+    ///
+    /// # Returns
+    /// The parsed snowflakes, in the same order as the IDs
+    pub fn parse_many_with_layout(ids: &[u64], layout: &SnowflakeLayout) -> Vec<Snowflake> {
+        ids.iter()
+            .map(|&id| Snowflake::parse_with_layout(id, layout))
+            .collect()
+    }
+
+    /// Decode just the timestamps of many snowflake IDs, without allocating
+    ///
+    /// The shift and mask are computed once for the whole slice, so the loop compiles down to a
+    /// shift and an `and` per ID that the compiler can vectorize.
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs to decode
+    /// * `timestamps` - The slice to write the timestamps to, in the same order as the IDs
+    ///
+    /// # Returns
+    /// `SnowflakeError::LengthMismatch` if the two slices have different lengths, in which case
+    /// nothing is written
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let ids = [1 << 22, (2 << 22) | 5];
+    /// let mut timestamps = [0; 2];
+    ///
+    /// Snowflake::decode_timestamps(&ids, &mut timestamps).unwrap();
+    ///
+    /// assert_eq!(timestamps, [1, 2]);
+    /// ```
+    pub fn decode_timestamps(ids: &[u64], timestamps: &mut [u64]) -> Result<(), SnowflakeError> {
+        Snowflake::decode_timestamps_with_layout(ids, timestamps, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Decode just the timestamps of many snowflake IDs, using a custom bit layout
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs to decode
+    /// * `timestamps` - The slice to write the timestamps to, in the same order as the IDs
+    /// * `layout` - The bit layout the IDs were packed with
+    ///
+    /// # Returns
+    /// `SnowflakeError::LengthMismatch` if the two slices have different lengths, in which case
+    /// nothing is written
+    pub fn decode_timestamps_with_layout(
+        ids: &[u64],
+        timestamps: &mut [u64],
+        layout: &SnowflakeLayout,
+    ) -> Result<(), SnowflakeError> {
+        if ids.len() != timestamps.len() {
+            return Err(SnowflakeError::LengthMismatch {
+                length: timestamps.len(),
+                expected: ids.len(),
+            });
+        }
+
+        let shift = layout.timestamp_shift();
+        let max = layout.max_timestamp();
+
+        for (timestamp, &id) in timestamps.iter_mut().zip(ids) {
+            *timestamp = shr(id, shift) & max;
+        }
+
+        Ok(())
+    }
+
+    /// Parse a snowflake ID from an untrusted source, checking it against validation rules
+    ///
+    /// # Arguments
+    /// * `id` - The ID to validate
+    /// * `rules` - The rules the ID has to follow
+    ///
+    /// # Returns
+    /// The parsed snowflake, or an error describing the first rule the ID breaks
+    #[cfg(feature = "std")]
+    pub fn validate(id: u64, rules: &ValidationRules) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::validate_at(id, rules, SystemClock.now_millis())
+    }
+
+    /// Parse a snowflake ID from an untrusted source, checking it against validation rules at
+    /// a given time
+    ///
+    /// Use this without the `std` feature, or to validate against a clock other than the
+    /// system clock.
+    ///
+    /// # Arguments
+    /// * `id` - The ID to validate
+    /// * `rules` - The rules the ID has to follow
+    /// * `now_millis` - The current time in milliseconds since the UNIX epoch
+    ///
+    /// # Returns
+    /// The parsed snowflake, or an error describing the first rule the ID breaks
+    pub fn validate_at(
+        id: u64,
+        rules: &ValidationRules,
+        now_millis: u64,
+    ) -> Result<Snowflake, SnowflakeError> {
+        rules.check(id, now_millis)
+    }
+
+    /// Convert a Snowflake into its ID as an `i64`, for databases and protocols without
+    /// unsigned 64 bit integers
+    ///
+    /// The sign bit is reserved and always zero, so IDs sort the same as signed and unsigned
+    /// numbers. [`SnowflakeLayout::DEFAULT`] only uses 63 bits, so this never fails for it.
+    ///
+    /// # Returns
+    /// The ID, or `SnowflakeError::IdExceedsI64` if the ID uses the sign bit
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(1)
+    ///     .sequence(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let id = snowflake.to_i64().unwrap();
+    ///
+    /// assert_eq!(Snowflake::from_i64(id), Ok(snowflake));
+    /// ```
+    pub fn to_i64(self) -> Result<i64, SnowflakeError> {
+        self.to_i64_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Convert a Snowflake into its ID as an `i64`, using a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The bit layout to pack the fields with
+    ///
+    /// # Returns
+    /// The ID, or `SnowflakeError::IdExceedsI64` if the ID uses the sign bit, which only
+    /// happens with layouts that use all 64 bits
+    pub fn to_i64_with_layout(self, layout: &SnowflakeLayout) -> Result<i64, SnowflakeError> {
+        let id = self.to_id_with_layout(layout);
+
+        i64::try_from(id).map_err(|_| SnowflakeError::IdExceedsI64 { id })
+    }
+
+    /// Parse an `i64` snowflake ID into a `Snowflake`
+    ///
+    /// # Arguments
+    /// * `id` - The ID to parse
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::NegativeId` if the ID is negative
+    pub fn from_i64(id: i64) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::from_i64_with_layout(id, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Parse an `i64` snowflake ID into a `Snowflake`, using a custom layout
+    ///
+    /// # Arguments
+    /// * `id` - The ID to parse
+    /// * `layout` - The bit layout the ID was packed with
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::NegativeId` if the ID is negative
+    pub fn from_i64_with_layout(
+        id: i64,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        let id = u64::try_from(id).map_err(|_| SnowflakeError::NegativeId { id })?;
+
+        Ok(Snowflake::parse_with_layout(id, layout))
+    }
+
+    /// Convert a Snowflake into its ID as 8 big-endian bytes
+    ///
+    /// Big-endian bytes compare in the same order as the IDs they encode, so they make sortable
+    /// keys for stores like RocksDB or Kafka, and match the network byte order of binary
+    /// protocols.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let first = Snowflake::parse(175928847299117063);
+    /// let second = Snowflake::parse(175928847299117064);
+    ///
+    /// assert!(first.to_be_bytes() < second.to_be_bytes());
+    /// assert_eq!(Snowflake::from_be_bytes(first.to_be_bytes()), first);
+    /// ```
+    pub const fn to_be_bytes(self) -> [u8; 8] {
+        self.to_id().to_be_bytes()
+    }
+
+    /// Convert a Snowflake into its ID as 8 big-endian bytes, using a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The bit layout to pack the fields with
+    pub const fn to_be_bytes_with_layout(self, layout: &SnowflakeLayout) -> [u8; 8] {
+        self.to_id_with_layout(layout).to_be_bytes()
+    }
+
+    /// Convert a Snowflake into its ID as 8 little-endian bytes
+    ///
+    /// Little-endian bytes don't sort in ID order, so prefer [`Snowflake::to_be_bytes`] for
+    /// keys.
+    pub const fn to_le_bytes(self) -> [u8; 8] {
+        self.to_id().to_le_bytes()
+    }
+
+    /// Convert a Snowflake into its ID as 8 little-endian bytes, using a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The bit layout to pack the fields with
+    pub const fn to_le_bytes_with_layout(self, layout: &SnowflakeLayout) -> [u8; 8] {
+        self.to_id_with_layout(layout).to_le_bytes()
+    }
+
+    /// Parse an ID encoded as 8 big-endian bytes into a `Snowflake`
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to parse
+    pub const fn from_be_bytes(bytes: [u8; 8]) -> Snowflake {
+        Snowflake::parse(u64::from_be_bytes(bytes))
+    }
+
+    /// Parse an ID encoded as 8 big-endian bytes into a `Snowflake`, using a custom layout
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to parse
+    /// * `layout` - The bit layout the ID was packed with
+    pub const fn from_be_bytes_with_layout(bytes: [u8; 8], layout: &SnowflakeLayout) -> Snowflake {
+        Snowflake::parse_with_layout(u64::from_be_bytes(bytes), layout)
+    }
+
+    /// Parse an ID encoded as 8 little-endian bytes into a `Snowflake`
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to parse
+    pub const fn from_le_bytes(bytes: [u8; 8]) -> Snowflake {
+        Snowflake::parse(u64::from_le_bytes(bytes))
+    }
+
+    /// Parse an ID encoded as 8 little-endian bytes into a `Snowflake`, using a custom layout
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to parse
+    /// * `layout` - The bit layout the ID was packed with
+    pub const fn from_le_bytes_with_layout(bytes: [u8; 8], layout: &SnowflakeLayout) -> Snowflake {
+        Snowflake::parse_with_layout(u64::from_le_bytes(bytes), layout)
+    }
+
+    /// Convert a Snowflake into its ID as a decimal string
+    ///
+    /// JavaScript numbers lose precision above 2^53, so IDs sent to browsers
+    /// should be sent as strings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert_eq!(snowflake.to_string_id(), snowflake.to_id().to_string());
+    /// ```
+    pub fn to_string_id(self) -> String {
+        self.to_id().to_string()
+    }
+
+    /// Parse a snowflake ID from a decimal string
+    ///
+    /// # Arguments
+    /// * `id` - The snowflake ID as a decimal string
+    ///
+    /// This is also available through [`str::parse`] and `TryFrom<&str>`.
+    ///
+    /// # Returns
+    /// The parsed `Snowflake`, or `SnowflakeError::ParseInt` if the string isn't a valid `u64`
+    /// and `SnowflakeError::ReservedBitsSet` if the ID uses bits above the timestamp of
+    /// [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert_eq!(Snowflake::parse_str(&snowflake.to_string_id()), Ok(snowflake));
+    /// assert_eq!("175928847299117063".parse::<Snowflake>(), Ok(Snowflake::parse(175928847299117063)));
+    /// assert!(Snowflake::parse_str("not an id").is_err());
+    /// ```
+    pub fn parse_str(id: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(id.parse()?)
+    }
+
+    /// Encode the Snowflake ID as a base62 string
+    ///
+    /// See [`encoding::encode_base62`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert_eq!(Snowflake::from_base62(&snowflake.to_base62()), Ok(snowflake));
+    /// ```
+    pub fn to_base62(self) -> String {
+        encoding::encode_base62(self.to_id())
+    }
+
+    /// Parse a base62 encoded snowflake ID into a `Snowflake`
+    ///
+    /// See [`encoding::decode_base62`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_base62(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_base62(encoded)?)
+    }
+
+    /// Encode the Snowflake ID as a lowercase base36 string
+    ///
+    /// See [`encoding::encode_base36`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert_eq!(Snowflake::from_base36(&snowflake.to_base36()), Ok(snowflake));
+    /// ```
+    pub fn to_base36(self) -> String {
+        encoding::encode_base36(self.to_id())
+    }
+
+    /// Parse a base36 encoded snowflake ID into a `Snowflake`
+    ///
+    /// See [`encoding::decode_base36`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_base36(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_base36(encoded)?)
+    }
+
+    /// Encode the Snowflake ID as an uppercase Crockford base32 string
+    ///
+    /// See [`encoding::encode_crockford32`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    /// let encoded = snowflake.to_crockford32();
+    ///
+    /// assert_eq!(Snowflake::from_crockford32(&encoded.to_lowercase()), Ok(snowflake));
+    /// ```
+    pub fn to_crockford32(self) -> String {
+        encoding::encode_crockford32(self.to_id())
+    }
+
+    /// Encode the Snowflake ID as an uppercase Crockford base32 string followed by its check
+    /// symbol
+    ///
+    /// See [`encoding::encode_crockford32_check`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::try_new(1).unwrap();
+    ///
+    /// assert_eq!(
+    ///     Snowflake::from_crockford32_check(&snowflake.to_crockford32_check()),
+    ///     Ok(snowflake)
+    /// );
+    /// ```
+    pub fn to_crockford32_check(self) -> String {
+        encoding::encode_crockford32_check(self.to_id())
+    }
+
+    /// Parse a Crockford base32 encoded snowflake ID into a `Snowflake`
+    ///
+    /// See [`encoding::decode_crockford32`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_crockford32(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_crockford32(encoded)?)
+    }
+
+    /// Parse a Crockford base32 encoded snowflake ID followed by its check symbol into a
+    /// `Snowflake`
+    ///
+    /// See [`encoding::decode_crockford32_check`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_crockford32_check(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_crockford32_check(encoded)?)
+    }
+
+    /// Encode the Snowflake ID as a 13 character string that sorts like the ID
+    ///
+    /// See [`encoding::encode_sortable`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let first = Snowflake::builder().timestamp(1_700_000_000).build().unwrap();
+    /// let second = Snowflake::builder().timestamp(1_700_000_001).build().unwrap();
+    ///
+    /// assert!(first.to_sortable_string() < second.to_sortable_string());
+    /// assert_eq!(Snowflake::from_sortable_string(&first.to_sortable_string()), Ok(first));
+    /// ```
+    pub fn to_sortable_string(self) -> String {
+        encoding::encode_sortable(self.to_id())
+    }
+
+    /// Parse a snowflake ID encoded by [`Snowflake::to_sortable_string`] into a `Snowflake`
+    ///
+    /// See [`encoding::decode_sortable`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_sortable_string(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_sortable(encoded)?)
+    }
+
+    /// Encode the Snowflake ID as an 11 character base62 string that sorts like the ID
+    ///
+    /// See [`encoding::encode_sortable_base62`].
+    pub fn to_sortable_base62(self) -> String {
+        encoding::encode_sortable_base62(self.to_id())
+    }
+
+    /// Parse a snowflake ID encoded by [`Snowflake::to_sortable_base62`] into a `Snowflake`
+    ///
+    /// See [`encoding::decode_sortable_base62`]. Like [`Snowflake::parse_str`], IDs that
+    /// [`SnowflakeLayout::DEFAULT`] couldn't have packed are rejected, see [`Snowflake::try_parse`].
+    pub fn from_sortable_base62(encoded: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_parse(encoding::decode_sortable_base62(encoded)?)
+    }
+
+    /// Get the time the snowflake was created
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(1)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(snowflake.created_at(), UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn created_at(self) -> SystemTime {
+        self.created_at_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the time the snowflake was created, using the epoch and tick of a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the snowflake was parsed with
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use rusty_snowflake::{Snowflake, SnowflakeLayout};
+    ///
+    /// let tweet = Snowflake::parse_with_layout(1212092628029698048, &SnowflakeLayout::TWITTER);
+    ///
+    /// assert_eq!(
+    ///     tweet.created_at_with_layout(&SnowflakeLayout::TWITTER),
+    ///     UNIX_EPOCH + Duration::from_millis(1_577_820_376_771)
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn created_at_with_layout(self, layout: &SnowflakeLayout) -> SystemTime {
+        UNIX_EPOCH + Duration::from_micros(layout.micros_from_timestamp(self.timestamp))
+    }
+
+    /// Get how much later the snowflake was created than `earlier`
+    ///
+    /// # Arguments
+    /// * `earlier` - The snowflake to measure from
+    ///
+    /// # Returns
+    /// The time between the two creation times, or zero if `earlier` was created later
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let first = Snowflake::builder().timestamp(1_700_000_000).build().unwrap();
+    /// let second = Snowflake::builder().timestamp(1_700_000_060).build().unwrap();
+    ///
+    /// assert_eq!(second.duration_since(first), Duration::from_secs(60));
+    /// assert_eq!(first.duration_since(second), Duration::ZERO);
+    /// ```
+    pub fn duration_since(self, earlier: Snowflake) -> Duration {
+        self.duration_since_with_layout(earlier, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get how much later the snowflake was created than `earlier`, using the tick of a custom
+    /// layout
+    ///
+    /// # Arguments
+    /// * `earlier` - The snowflake to measure from
+    /// * `layout` - The layout both snowflakes were parsed with
+    ///
+    /// # Returns
+    /// The time between the two creation times, or zero if `earlier` was created later
+    pub fn duration_since_with_layout(
+        self,
+        earlier: Snowflake,
+        layout: &SnowflakeLayout,
+    ) -> Duration {
+        let ticks = self.timestamp.saturating_sub(earlier.timestamp);
+        Duration::from_micros(ticks.saturating_mul(layout.tick_micros()))
+    }
+
+    /// Check whether the snowflake was created in a later tick than `other`
+    ///
+    /// Unlike `>`, this ignores the worker ID and sequence, so two snowflakes from the same tick
+    /// are never after each other.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let first = Snowflake::builder().timestamp(1_700_000_000).sequence(1).build().unwrap();
+    /// let second = Snowflake::builder().timestamp(1_700_000_000).sequence(2).build().unwrap();
+    /// let third = Snowflake::builder().timestamp(1_700_000_001).build().unwrap();
+    ///
+    /// assert!(second > first && !second.is_after(first));
+    /// assert!(third.is_after(second));
+    /// ```
+    pub const fn is_after(self, other: Snowflake) -> bool {
+        self.timestamp > other.timestamp
+    }
+
+    /// Check whether the snowflake was created in an earlier tick than `other`
+    pub const fn is_before(self, other: Snowflake) -> bool {
+        self.timestamp < other.timestamp
+    }
+
+    /// Check whether the snowflake was created in the same tick as `other`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let first = Snowflake::builder().timestamp(1_700_000_000).worker_id(1).build().unwrap();
+    /// let second = Snowflake::builder().timestamp(1_700_000_000).worker_id(2).build().unwrap();
+    ///
+    /// assert!(first.same_tick(second));
+    /// ```
+    pub const fn same_tick(self, other: Snowflake) -> bool {
+        self.timestamp == other.timestamp
+    }
+
+    /// Get the smallest snowflake created at the given time
+    ///
+    /// This has a worker ID and sequence of `0`, so every snowflake created at or after `time`
+    /// compares greater than or equal to it.
+    ///
+    /// # Arguments
+    /// * `time` - The time to get the first snowflake of
+    ///
+    /// # Returns
+    /// The first snowflake at `time`, `SnowflakeError::ClockBeforeEpoch` if `time` is before
+    /// the UNIX epoch, or `SnowflakeError::TimestampOverflow` if `time` is past the last
+    /// timestamp the layout can hold
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let first = Snowflake::first_id_at(time).unwrap();
+    ///
+    /// assert_eq!(first.created_at(), time);
+    /// assert_eq!(first.sequence(), 0);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn first_id_at(time: SystemTime) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::first_id_at_with_layout(time, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the smallest snowflake created at the given time, using the epoch and tick of a
+    /// custom layout
+    ///
+    /// # Arguments
+    /// * `time` - The time to get the first snowflake of
+    /// * `layout` - The layout to count the timestamp in
+    ///
+    /// # Returns
+    /// The first snowflake at `time`, `SnowflakeError::ClockBeforeEpoch` if `time` is before
+    /// the layout's epoch, or `SnowflakeError::TimestampOverflow` if `time` is past the last
+    /// timestamp the layout can hold
+    #[cfg(feature = "std")]
+    pub fn first_id_at_with_layout(
+        time: SystemTime,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        let micros = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| SnowflakeError::ClockBeforeEpoch)?
+            .as_micros();
+
+        let since_epoch = micros
+            .checked_sub(u128::from(layout.epoch()) * 1_000)
+            .ok_or(SnowflakeError::ClockBeforeEpoch)?;
+        let ticks = since_epoch / u128::from(layout.tick_micros());
+        let timestamp = u64::try_from(ticks).unwrap_or(u64::MAX);
+
+        Snowflake::builder()
+            .layout(*layout)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    /// Get the smallest snowflake with the given timestamp
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in seconds since the UNIX epoch
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::TimestampOverflow` if the timestamp doesn't fit in
+    /// 41 bits
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let min = Snowflake::min_for_timestamp(1_700_000_000).unwrap();
+    /// let max = Snowflake::max_for_timestamp(1_700_000_000).unwrap();
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(42)
+    ///     .sequence(7)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(min <= snowflake && snowflake <= max);
+    /// ```
+    pub fn min_for_timestamp(timestamp: u64) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::min_for_timestamp_with_layout(timestamp, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the smallest snowflake with the given timestamp in a custom layout
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in the layout's epoch and tick
+    /// * `layout` - The layout the timestamp has to fit in
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::TimestampOverflow` if the timestamp doesn't fit in
+    /// the layout
+    pub fn min_for_timestamp_with_layout(
+        timestamp: u64,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::builder()
+            .layout(*layout)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    /// Get the largest snowflake with the given timestamp
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in seconds since the UNIX epoch
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::TimestampOverflow` if the timestamp doesn't fit in
+    /// 41 bits
+    pub fn max_for_timestamp(timestamp: u64) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::max_for_timestamp_with_layout(timestamp, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Get the largest snowflake with the given timestamp in a custom layout
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp in the layout's epoch and tick
+    /// * `layout` - The layout to get the largest worker ID, sequence and extra value from
+    ///
+    /// # Returns
+    /// The snowflake, or `SnowflakeError::TimestampOverflow` if the timestamp doesn't fit in
+    /// the layout
+    pub fn max_for_timestamp_with_layout(
+        timestamp: u64,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::builder()
+            .layout(*layout)
+            .timestamp(timestamp)
+            .worker_id(layout.max_worker_id())
+            .sequence(layout.max_sequence())
+            .extra(layout.max_extra())
+            .build()
+    }
+
+    /// Convert the snowflake into a UUIDv7
+    ///
+    /// The UUID's 48 bit timestamp is the snowflake's creation time in milliseconds, and its
+    /// random bits hold the worker ID and sequence, so UUIDs sort in the same order as the
+    /// snowflakes they came from. Use `uuid::Uuid::from_u128` to get a `Uuid`.
+    ///
+    /// # Returns
+    /// The UUID as a `u128`, or `SnowflakeError::TimestampOverflow` if the creation time
+    /// doesn't fit in 48 bits of milliseconds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(42)
+    ///     .sequence(7)
+    ///     .build()
+    ///     .unwrap();
+    /// let uuid = snowflake.to_uuid_v7().unwrap();
+    ///
+    /// assert_eq!(uuid >> 80, 1_700_000_000_000);
+    /// assert_eq!(Snowflake::try_from_uuid(uuid), Ok(snowflake));
+    /// ```
+    pub fn to_uuid_v7(self) -> Result<u128, SnowflakeError> {
+        self.to_uuid_v7_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Convert the snowflake into a UUIDv7, using a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout to read the creation time, worker ID and sequence with
+    ///
+    /// # Returns
+    /// The UUID as a `u128`, `SnowflakeError::TimestampOverflow` if the creation time doesn't
+    /// fit in 48 bits of milliseconds, or `SnowflakeError::UnsupportedLayout` if the layout's
+    /// tick isn't a whole number of milliseconds or its fields below the timestamp use more
+    /// than 62 bits
+    pub fn to_uuid_v7_with_layout(self, layout: &SnowflakeLayout) -> Result<u128, SnowflakeError> {
+        check_uuid_layout(layout)?;
+        let (millis, fields) = self.millis_and_fields(layout)?;
+
+        Ok(u128::from(millis) << 80 | UUID_VERSION_7 | UUID_VARIANT | u128::from(fields))
+    }
+
+    /// Convert a UUIDv7 created by [`Snowflake::to_uuid_v7`] back into a snowflake
+    ///
+    /// # Arguments
+    /// * `uuid` - The UUID as a `u128`, from `uuid::Uuid::as_u128`
+    ///
+    /// # Returns
+    /// The snowflake, or an error if the UUID isn't a version 7 UUID or its time is before the
+    /// epoch
+    pub fn try_from_uuid(uuid: u128) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::try_from_uuid_with_layout(uuid, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Convert a UUIDv7 created by [`Snowflake::to_uuid_v7_with_layout`] back into a snowflake
+    ///
+    /// # Arguments
+    /// * `uuid` - The UUID as a `u128`, from `uuid::Uuid::as_u128`
+    /// * `layout` - The layout the snowflake was converted with
+    ///
+    /// # Returns
+    /// The snowflake, or an error if the UUID isn't a version 7 UUID, its time is before the
+    /// layout's epoch, or the layout can't be converted, see
+    /// [`Snowflake::to_uuid_v7_with_layout`]
+    pub fn try_from_uuid_with_layout(
+        uuid: u128,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        check_uuid_layout(layout)?;
+        if uuid & UUID_VERSION_MASK != UUID_VERSION_7 || uuid & UUID_VARIANT_MASK != UUID_VARIANT {
+            return Err(SnowflakeError::InvalidUuid);
+        }
+
+        Snowflake::from_millis_and_fields((uuid >> 80) as u64, uuid as u64, layout)
+    }
+
+    /// Convert the snowflake into a ULID string
+    ///
+    /// The ULID's timestamp is the snowflake's creation time in milliseconds, and its random
+    /// bits hold the worker ID and sequence, so the same snowflake always maps to the same ULID
+    /// and ULIDs sort in the same order as the snowflakes they came from.
+    ///
+    /// # Returns
+    /// The 26 character ULID, or `SnowflakeError::TimestampOverflow` if the creation time
+    /// doesn't fit in 48 bits of milliseconds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let snowflake = Snowflake::builder()
+    ///     .timestamp(1_700_000_000)
+    ///     .worker_id(42)
+    ///     .sequence(7)
+    ///     .build()
+    ///     .unwrap();
+    /// let ulid = snowflake.to_ulid().unwrap();
+    ///
+    /// assert_eq!(ulid, "01HF7YAT000000000000005807");
+    /// assert_eq!(Snowflake::from_ulid(&ulid), Ok(snowflake));
+    /// ```
+    pub fn to_ulid(self) -> Result<String, SnowflakeError> {
+        self.to_ulid_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Convert the snowflake into a ULID string, using a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout to read the creation time, worker ID and sequence with
+    ///
+    /// # Returns
+    /// The 26 character ULID, `SnowflakeError::TimestampOverflow` if the creation time doesn't
+    /// fit in 48 bits of milliseconds, or `SnowflakeError::UnsupportedLayout` if the layout's
+    /// tick isn't a whole number of milliseconds
+    pub fn to_ulid_with_layout(self, layout: &SnowflakeLayout) -> Result<String, SnowflakeError> {
+        let (millis, fields) = self.millis_and_fields(layout)?;
+
+        Ok(encoding::encode_ulid(
+            u128::from(millis) << 80 | u128::from(fields),
+        ))
+    }
+
+    /// Convert a ULID string back into a snowflake
+    ///
+    /// Random bits beyond the worker ID and sequence are ignored, so any ULID can be converted,
+    /// but only ULIDs created by [`Snowflake::to_ulid`] convert back without losing bits.
+    ///
+    /// # Arguments
+    /// * `ulid` - The ULID to convert
+    ///
+    /// # Returns
+    /// The snowflake, or an error if the string isn't a valid ULID or its time is before the
+    /// epoch
+    pub fn from_ulid(ulid: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::from_ulid_with_layout(ulid, &SnowflakeLayout::DEFAULT)
+    }
+
+    /// Convert a ULID string back into a snowflake, using a custom layout
+    ///
+    /// # Arguments
+    /// * `ulid` - The ULID to convert
+    /// * `layout` - The layout the snowflake was converted with
+    ///
+    /// # Returns
+    /// The snowflake, or an error if the string isn't a valid ULID, its time is before the
+    /// layout's epoch, or the layout's tick isn't a whole number of milliseconds
+    pub fn from_ulid_with_layout(
+        ulid: &str,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        let ulid = encoding::decode_ulid(ulid)?;
+
+        Snowflake::from_millis_and_fields((ulid >> 80) as u64, ulid as u64, layout)
+    }
+
+    /// Get the creation time in milliseconds since the UNIX epoch, checked to fit in 48 bits,
+    /// and the ID with just the worker ID and sequence
+    fn millis_and_fields(self, layout: &SnowflakeLayout) -> Result<(u64, u64), SnowflakeError> {
+        check_millisecond_ticks(layout)?;
+        let millis = layout.millis_from_timestamp(self.timestamp & layout.max_timestamp());
+        if millis > MAX_48_BIT_MILLIS {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp: self.timestamp,
+                max: layout.timestamp_from_millis(MAX_48_BIT_MILLIS),
+            });
+        }
+
+        let fields = Snowflake {
+            timestamp: 0,
+            ..self
+        }
+        .to_id_with_layout(layout);
+
+        Ok((millis, fields))
+    }
+
+    /// The inverse of [`Snowflake::millis_and_fields`]
+    fn from_millis_and_fields(
+        millis: u64,
+        fields: u64,
+        layout: &SnowflakeLayout,
+    ) -> Result<Snowflake, SnowflakeError> {
+        check_millisecond_ticks(layout)?;
+        if millis < layout.epoch() {
+            return Err(SnowflakeError::ClockBeforeEpoch);
+        }
+
+        Ok(Snowflake {
+            timestamp: layout.timestamp_from_millis(millis),
+            ..Snowflake::parse_with_layout(fields, layout)
+        })
+    }
+}
+
+/// Check a layout's ticks are whole milliseconds, so UUID and ULID timestamps keep every tick
+fn check_millisecond_ticks(layout: &SnowflakeLayout) -> Result<(), SnowflakeError> {
+    if !layout.tick_micros().is_multiple_of(1_000) {
+        return Err(SnowflakeError::UnsupportedLayout(
+            "tick is not a whole number of milliseconds",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a layout's fields below the timestamp stay clear of the UUID variant bits
+fn check_uuid_layout(layout: &SnowflakeLayout) -> Result<(), SnowflakeError> {
+    if layout.timestamp_shift() > 62 {
+        return Err(SnowflakeError::UnsupportedLayout(
+            "fields below the timestamp overlap the UUID variant bits",
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// A builder for [`Snowflake`] that checks every field fits in its layout.
+///
+/// Fields that aren't set are zero.
+pub struct SnowflakeBuilder {
+    snowflake: Snowflake,
+    layout: SnowflakeLayout,
+}
+
+impl SnowflakeBuilder {
+    /// Set the timestamp, in the layout's epoch and tick
+    pub fn timestamp(mut self, timestamp: u64) -> SnowflakeBuilder {
+        self.snowflake.timestamp = timestamp;
+        self
+    }
+
+    /// Set the worker ID
+    pub fn worker_id(mut self, worker_id: u64) -> SnowflakeBuilder {
+        self.snowflake.worker_id = worker_id;
+        self
+    }
+
+    /// Set the sequence number
+    pub fn sequence(mut self, sequence: u64) -> SnowflakeBuilder {
+        self.snowflake.sequence = sequence;
+        self
+    }
+
+    /// Set the extra field
+    pub fn extra(mut self, extra: u64) -> SnowflakeBuilder {
+        self.snowflake.extra = extra;
+        self
+    }
+
+    /// Set the layout the fields have to fit in
+    ///
+    /// Defaults to [`SnowflakeLayout::DEFAULT`].
+    pub fn layout(mut self, layout: SnowflakeLayout) -> SnowflakeBuilder {
+        self.layout = layout;
+        self
+    }
+
+    /// Build the snowflake
+    ///
+    /// # Returns
+    /// The `Snowflake`, or an error naming the first field that doesn't fit in the layout
+    pub fn build(self) -> Result<Snowflake, SnowflakeError> {
+        let Snowflake {
+            worker_id,
+            sequence,
+            timestamp,
+            extra,
+        } = self.snowflake;
+        let layout = self.layout;
+
+        if timestamp > layout.max_timestamp() {
+            return Err(SnowflakeError::TimestampOverflow {
+                timestamp,
+                max: layout.max_timestamp(),
+            });
+        }
+
+        if worker_id > layout.max_worker_id() {
+            return Err(SnowflakeError::WorkerIdOverflow {
+                worker_id,
+                max: layout.max_worker_id(),
+            });
+        }
+
+        if sequence > layout.max_sequence() {
+            return Err(SnowflakeError::SequenceOverflow {
+                sequence,
+                max: layout.max_sequence(),
+            });
+        }
+
+        if extra > layout.max_extra() {
+            return Err(SnowflakeError::ExtraOverflow {
+                extra,
+                max: layout.max_extra(),
+            });
+        }
+
+        Ok(self.snowflake)
+    }
+}
+
+impl core::fmt::Display for Snowflake {
+    /// Display the Snowflake
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::Snowflake;
+    ///
+    /// let mut snowflake = Snowflake::try_new(1).unwrap();
+    /// println!("{}", snowflake); // u64 ID
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut buffer = [0; MAX_ID_LENGTH];
+        f.pad_integral(true, "", write_digits(self.to_id(), &mut buffer))
+    }
+}
+
+impl From<u64> for Snowflake {
+    fn from(id: u64) -> Snowflake {
+        Snowflake::parse(id)
+    }
+}
+
+impl From<Snowflake> for u64 {
+    fn from(snowflake: Snowflake) -> u64 {
+        snowflake.to_id()
+    }
+}
+
+/// Split a snowflake into its `(timestamp, worker_id, sequence)` fields
+impl From<Snowflake> for (u64, u64, u64) {
+    fn from(snowflake: Snowflake) -> (u64, u64, u64) {
+        (snowflake.timestamp, snowflake.worker_id, snowflake.sequence)
+    }
+}
+
+/// Build a snowflake from its `(timestamp, worker_id, sequence)` fields, checking each fits in
+/// [`SnowflakeLayout::DEFAULT`]
+impl TryFrom<(u64, u64, u64)> for Snowflake {
+    type Error = SnowflakeError;
+
+    fn try_from(
+        (timestamp, worker_id, sequence): (u64, u64, u64),
+    ) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::builder()
+            .timestamp(timestamp)
+            .worker_id(worker_id)
+            .sequence(sequence)
+            .build()
+    }
+}
+
+/// Convert a snowflake to its ID with [`SnowflakeLayout::DEFAULT`], for APIs that use
+/// `Option<NonZeroU64>`
+///
+/// Generators never hand out the all-zero ID, so this only fails for snowflakes built by hand.
+///
+/// # Example
+/// ```rust
+/// use std::num::NonZeroU64;
+/// use rusty_snowflake::{Snowflake, SnowflakeGenerator};
+///
+/// let mut generator = SnowflakeGenerator::try_new(0).unwrap();
+/// let id = NonZeroU64::try_from(*generator.next()).unwrap();
+///
+/// assert_eq!(Snowflake::from(id), generator.last_snowflake);
+/// assert!(NonZeroU64::try_from(Snowflake::parse(0)).is_err());
+/// ```
+impl TryFrom<Snowflake> for core::num::NonZeroU64 {
+    type Error = SnowflakeError;
+
+    fn try_from(snowflake: Snowflake) -> Result<core::num::NonZeroU64, SnowflakeError> {
+        core::num::NonZeroU64::new(snowflake.to_id()).ok_or(SnowflakeError::ZeroId)
+    }
+}
+
+impl From<core::num::NonZeroU64> for Snowflake {
+    fn from(id: core::num::NonZeroU64) -> Snowflake {
+        Snowflake::parse(id.get())
+    }
+}
+
+impl core::str::FromStr for Snowflake {
+    type Err = SnowflakeError;
+
+    fn from_str(id: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::parse_str(id)
+    }
+}
+
+impl TryFrom<&str> for Snowflake {
+    type Error = SnowflakeError;
+
+    fn try_from(id: &str) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::parse_str(id)
+    }
+}
+
+impl TryFrom<i64> for Snowflake {
+    type Error = SnowflakeError;
+
+    fn try_from(id: i64) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::from_i64(id)
+    }
+}
+
+impl Ord for Snowflake {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (self.timestamp, self.extra, self.worker_id, self.sequence).cmp(&(
+            other.timestamp,
+            other.extra,
+            other.worker_id,
+            other.sequence,
+        ))
+    }
+}
+
+impl PartialOrd for Snowflake {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{FieldOrder, TimeUnit};
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_new() {
+        let snowflake = Snowflake::new(420);
+        assert_eq!(snowflake.worker_id, 420);
+        assert_eq!(snowflake.sequence, 0);
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(
+            Snowflake::try_new(1024),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_to_id() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(snowflake.try_to_id(), Ok(snowflake.to_id()));
+
+        let snowflake = Snowflake {
+            worker_id: 1024,
+            sequence: 0,
+            timestamp: 0,
+            extra: 0,
+        };
+        assert_eq!(
+            snowflake.try_to_id(),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_to_id_timestamp_and_extra() {
+        let snowflake = Snowflake {
+            worker_id: 0,
+            sequence: 0,
+            timestamp: 10_000_000_000_000,
+            extra: 0,
+        };
+        assert_eq!(
+            snowflake.try_to_id(),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 10_000_000_000_000,
+                max: 2_199_023_255_551
+            })
+        );
+
+        let snowflake = Snowflake {
+            worker_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            extra: 1,
+        };
+        assert_eq!(
+            snowflake.try_to_id(),
+            Err(SnowflakeError::ExtraOverflow { extra: 1, max: 0 })
+        );
+    }
+
+    #[test]
+    #[allow(deprecated, clippy::needless_range_loop)]
+    fn test_worker_id() {
+        const IDS: [u64; 6] = [42, 69, 420, 123, 777, 1000];
+
+        for i in 0..IDS.len() {
+            let snowflake = Snowflake::new(IDS[i]);
+            assert_eq!(snowflake.worker_id, IDS[i]);
+        }
+    }
+
+    #[test]
+    fn test_try_new_worker_id() {
+        const IDS: [u64; 6] = [42, 69, 420, 123, 777, 1000];
+
+        for id in IDS {
+            let snowflake = Snowflake::try_new(id).unwrap();
+            assert_eq!(snowflake.worker_id, id);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_sequence() {
+        let mut snowflake = Snowflake::try_new(1).unwrap();
+
+        for i in 1..10 {
+            snowflake = snowflake.next();
+            let id = snowflake.to_id();
+            assert_eq!(Snowflake::parse(id).sequence, i);
+        }
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(snowflake.timestamp, current_timestamp());
+    }
+
+    #[test]
+    fn test_snowflake_parse() {
+        let snowflake = Snowflake::try_new(1).unwrap();
         let id = snowflake.to_id();
         let parsed = Snowflake::parse(id);
         assert_eq!(snowflake, parsed, "Snowflake ID didn't parse correctly");
     }
     #[test]
-    fn test_next_timestamp_change() {
+    #[allow(deprecated)]
+    fn test_next_timestamp_change() {
+        let snowflake = Snowflake {
+            worker_id: 1,
+            timestamp: 100,
+            sequence: 0,
+            extra: 0,
+        };
+
+        let snowflake = snowflake.next();
+
+        // Assert that timestamp is updated to timestamp
+        assert_eq!(
+            snowflake.timestamp,
+            current_timestamp(),
+            "Timestamp didn't update correctly"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_next_sequence_change() {
+        let mut snowflake = Snowflake {
+            worker_id: 1,
+            timestamp: current_timestamp(),
+            sequence: 0,
+            extra: 0,
+        };
+
+        snowflake = snowflake.next();
+
+        // Assert that sequence is incremented
+        assert_eq!(snowflake.sequence, 1);
+
+        snowflake = snowflake.next();
+
+        assert_eq!(snowflake.sequence, 2);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_next_when_sequence_overflows() {
+        let time = current_timestamp();
+
+        let snowflake = Snowflake {
+            worker_id: 1,
+            sequence: 0xFFFF, // Maximum sequence value
+            timestamp: time,
+            extra: 0,
+        };
+
+        let next = snowflake.next();
+
+        // Assert that sequence is reset to 0
+        assert_eq!(next.sequence, 0);
+
+        assert!(next.timestamp > time);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_next_when_timestamp_is_greater_than_timestamp() {
+        let snowflake = Snowflake {
+            worker_id: 1,
+            timestamp: current_timestamp() + 100,
+            sequence: 0,
+            extra: 0,
+        };
+
+        let next = snowflake.next();
+
+        assert_ne!(snowflake, next, "Snowflake didn't change");
+    }
+
+    #[test]
+    fn test_snowflake_to_id() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        let id = snowflake.to_id();
+
+        assert_eq!(
+            snowflake,
+            Snowflake::parse(id),
+            "Snowflake ID didn't parse correctly"
+        );
+    }
+
+    #[test]
+    fn test_snowflake_to_string() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(snowflake.to_string(), format!("{}", snowflake.to_id()));
+    }
+
+    #[test]
+    fn test_to_id_with_layout() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .worker_id_bits(16)
+            .sequence_bits(8)
+            .build()
+            .unwrap();
+
+        let snowflake = Snowflake {
+            worker_id: 0xABCD,
+            sequence: 0x12,
+            timestamp: 100,
+            extra: 0,
+        };
+
+        assert_eq!(
+            snowflake.to_id_with_layout(&layout),
+            (100 << 24) | (0xABCD << 8) | 0x12
+        );
+        assert_eq!(
+            Snowflake::parse_with_layout(snowflake.to_id_with_layout(&layout), &layout),
+            snowflake
+        );
+    }
+
+    #[test]
+    fn test_to_id_with_layout_truncates() {
+        let layout = SnowflakeLayout::builder()
+            .worker_id_bits(4)
+            .build()
+            .unwrap();
+
+        let snowflake = Snowflake {
+            worker_id: 0x1F,
+            sequence: 0,
+            timestamp: 0,
+            extra: 0,
+        };
+
+        let parsed = Snowflake::parse_with_layout(snowflake.to_id_with_layout(&layout), &layout);
+        assert_eq!(parsed.worker_id, 0xF);
+    }
+
+    #[test]
+    fn test_to_string_id() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(snowflake.to_string_id(), snowflake.to_string());
+    }
+
+    #[test]
+    fn test_parse_str() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(
+            Snowflake::parse_str(&snowflake.to_string_id()),
+            Ok(snowflake)
+        );
+
+        assert!(matches!(
+            Snowflake::parse_str(""),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+        assert!(matches!(
+            Snowflake::parse_str("-1"),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+        assert!(matches!(
+            Snowflake::parse_str("18446744073709551616"),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+    }
+
+    #[test]
+    fn test_base62() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(
+            Snowflake::from_base62(&snowflake.to_base62()),
+            Ok(snowflake)
+        );
+        assert!(Snowflake::from_base62("!").is_err());
+    }
+
+    #[test]
+    fn test_string_decoders_reject_reserved_bits() {
+        let id = 1 << 63;
+        let reserved = Err(SnowflakeError::ReservedBitsSet { id });
+
+        assert_eq!(Snowflake::parse_str(&id.to_string()), reserved);
+        assert_eq!(
+            Snowflake::from_base62(&encoding::encode_base62(id)),
+            reserved
+        );
+        assert_eq!(
+            Snowflake::from_base36(&encoding::encode_base36(id)),
+            reserved
+        );
+        assert_eq!(
+            Snowflake::from_crockford32(&encoding::encode_crockford32(id)),
+            reserved
+        );
+        assert_eq!(
+            Snowflake::from_crockford32_check(&encoding::encode_crockford32_check(id)),
+            reserved
+        );
+        assert_eq!(
+            Snowflake::from_sortable_string(&encoding::encode_sortable(id)),
+            reserved
+        );
+        assert_eq!(
+            Snowflake::from_sortable_base62(&encoding::encode_sortable_base62(id)),
+            reserved
+        );
+    }
+
+    #[test]
+    fn test_base36() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(
+            Snowflake::from_base36(&snowflake.to_base36()),
+            Ok(snowflake)
+        );
+        assert!(Snowflake::from_base36("!").is_err());
+    }
+
+    #[test]
+    fn test_crockford32() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(
+            Snowflake::from_crockford32(&snowflake.to_crockford32()),
+            Ok(snowflake)
+        );
+        assert_eq!(
+            Snowflake::from_crockford32_check(&snowflake.to_crockford32_check()),
+            Ok(snowflake)
+        );
+        assert!(Snowflake::from_crockford32("U").is_err());
+    }
+
+    #[test]
+    fn test_sortable() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        assert_eq!(
+            Snowflake::from_sortable_string(&snowflake.to_sortable_string()),
+            Ok(snowflake)
+        );
+        assert_eq!(
+            Snowflake::from_sortable_base62(&snowflake.to_sortable_base62()),
+            Ok(snowflake)
+        );
+        assert!(Snowflake::from_sortable_string(&snowflake.to_crockford32()).is_err());
+    }
+
+    #[test]
+    fn test_created_at() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        let created_at = snowflake
+            .created_at()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        assert_eq!(created_at, snowflake.timestamp);
+    }
+
+    #[test]
+    fn test_created_at_with_layout() {
+        let tweet = Snowflake::parse_with_layout(1212092628029698048, &SnowflakeLayout::TWITTER);
+
+        assert_eq!(tweet.worker_id, 327);
+        assert_eq!(tweet.sequence, 0);
+        assert_eq!(
+            tweet.created_at_with_layout(&SnowflakeLayout::TWITTER),
+            UNIX_EPOCH + Duration::from_millis(1_577_820_376_771)
+        );
+    }
+
+    #[test]
+    fn test_sonyflake() {
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let snowflake = Snowflake {
+            worker_id: 0xBEEF,
+            sequence: 0x12,
+            timestamp: 0x7F_FFFF_FFFF,
+            extra: 0,
+        };
+
+        let id = snowflake.to_id_with_layout(&layout);
+
+        assert_eq!(id, (0x7F_FFFF_FFFF << 24) | (0x12 << 16) | 0xBEEF);
+        assert_eq!(Snowflake::parse_with_layout(id, &layout), snowflake);
+    }
+
+    #[test]
+    fn test_first_id_at() {
+        let snowflake = Snowflake::try_new(1).unwrap();
+        let first = Snowflake::first_id_at(snowflake.created_at()).unwrap();
+
+        assert_eq!(first.timestamp, snowflake.timestamp);
+        assert!(first <= snowflake);
+
+        assert_eq!(
+            Snowflake::first_id_at(UNIX_EPOCH - Duration::from_secs(1)),
+            Err(SnowflakeError::ClockBeforeEpoch)
+        );
+    }
+
+    #[test]
+    fn test_first_id_at_with_layout() {
+        let layout = SnowflakeLayout::DISCORD;
+        let time = UNIX_EPOCH + Duration::from_millis(layout.epoch() + 42);
+
+        let first = Snowflake::first_id_at_with_layout(time, &layout).unwrap();
+        assert_eq!(first.timestamp, 42);
+        assert_eq!(first.created_at_with_layout(&layout), time);
+
+        assert_eq!(
+            Snowflake::first_id_at_with_layout(UNIX_EPOCH, &layout),
+            Err(SnowflakeError::ClockBeforeEpoch)
+        );
+    }
+
+    #[test]
+    fn test_first_id_at_past_max_timestamp() {
+        let layout = SnowflakeLayout::DISCORD;
+        let time = UNIX_EPOCH + Duration::from_secs(10_000_000_000);
+
+        assert_eq!(
+            Snowflake::first_id_at_with_layout(time, &layout),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 10_000_000_000_000 - layout.epoch(),
+                max: layout.max_timestamp(),
+            })
+        );
+
+        let last = UNIX_EPOCH
+            + Duration::from_millis(layout.millis_from_timestamp(layout.max_timestamp()));
+        let first = Snowflake::first_id_at_with_layout(last, &layout).unwrap();
+        assert_eq!(first.timestamp, layout.max_timestamp());
+    }
+
+    #[test]
+    fn test_first_id_at_sub_millisecond_ticks() {
+        let layout = SnowflakeLayout::builder().tick_micros(100).build().unwrap();
+        let time = UNIX_EPOCH + Duration::from_micros(1_000_000_250);
+
+        let first = Snowflake::first_id_at_with_layout(time, &layout).unwrap();
+        assert_eq!(first.timestamp, 10_000_002);
+    }
+
+    #[test]
+    fn test_min_max_for_timestamp() {
+        let min = Snowflake::min_for_timestamp(100).unwrap();
+        let max = Snowflake::max_for_timestamp(100).unwrap();
+
+        assert_eq!(min.to_id(), 100 << 22);
+        assert_eq!(max.to_id(), (100 << 22) | 0x3F_FFFF);
+        assert!(Snowflake::max_for_timestamp(99).unwrap() < min);
+        assert!(Snowflake::min_for_timestamp(101).unwrap() > max);
+
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let max = Snowflake::max_for_timestamp_with_layout(100, &layout).unwrap();
+        assert_eq!(max.to_id_with_layout(&layout), (100 << 24) | 0xFF_FFFF);
+    }
+
+    #[test]
+    fn test_min_max_for_timestamp_overflow() {
+        let overflow = Err(SnowflakeError::TimestampOverflow {
+            timestamp: 1 << 41,
+            max: (1 << 41) - 1,
+        });
+        assert_eq!(Snowflake::min_for_timestamp(1 << 41), overflow);
+        assert_eq!(Snowflake::max_for_timestamp(1 << 41), overflow);
+
+        let wide = SnowflakeLayout::builder()
+            .timestamp_bits(48)
+            .worker_id_bits(8)
+            .sequence_bits(8)
+            .build()
+            .unwrap();
+        assert!(Snowflake::min_for_timestamp_with_layout(1 << 41, &wide).is_ok());
+
+        let layout = SnowflakeLayout::SONYFLAKE;
+        assert_eq!(
+            Snowflake::max_for_timestamp_with_layout(1 << 39, &layout),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 1 << 39,
+                max: (1 << 39) - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_uuid_v7() {
+        let snowflake = Snowflake {
+            worker_id: 1023,
+            sequence: 4095,
+            timestamp: 1_700_000_000,
+            extra: 0,
+        };
+        let uuid = snowflake.to_uuid_v7().unwrap();
+
+        assert_eq!(uuid, 0x018b_cfe5_6800_7000_8000_0000_003f_ffff);
+        assert_eq!(Snowflake::try_from_uuid(uuid), Ok(snowflake));
+
+        let earlier = Snowflake {
+            timestamp: 1_699_999_999,
+            ..snowflake
+        };
+        assert!(earlier.to_uuid_v7().unwrap() < uuid);
+    }
+
+    #[test]
+    fn test_uuid_v7_with_layout() {
+        let layout = SnowflakeLayout::SONYFLAKE;
         let snowflake = Snowflake {
-            worker_id: 1,
-            timestamp: 100,
+            worker_id: 65535,
+            sequence: 3,
+            timestamp: 42,
+            extra: 0,
+        };
+        let uuid = snowflake.to_uuid_v7_with_layout(&layout).unwrap();
+
+        assert_eq!((uuid >> 80) as u64, layout.epoch() + 420);
+        assert_eq!(
+            Snowflake::try_from_uuid_with_layout(uuid, &layout),
+            Ok(snowflake)
+        );
+    }
+
+    #[test]
+    fn test_uuid_v7_errors() {
+        let snowflake = Snowflake {
+            worker_id: 0,
             sequence: 0,
+            timestamp: 1 << 40,
+            extra: 0,
         };
+        assert!(matches!(
+            snowflake.to_uuid_v7(),
+            Err(SnowflakeError::TimestampOverflow { .. })
+        ));
 
-        let snowflake = snowflake.next();
+        let uuid_v4 = 0x5521_7e3f_4c1a_4b2e_9f3d_1c2b_3a4d_5e6f;
+        assert_eq!(
+            Snowflake::try_from_uuid(uuid_v4),
+            Err(SnowflakeError::InvalidUuid)
+        );
 
-        // Assert that timestamp is updated to timestamp
+        let before_discord = Snowflake::parse(0).to_uuid_v7().unwrap();
         assert_eq!(
-            snowflake.timestamp,
-            SnowflakeGenerator::get_timestamp(),
-            "Timestamp didn't update correctly"
+            Snowflake::try_from_uuid_with_layout(before_discord, &SnowflakeLayout::DISCORD),
+            Err(SnowflakeError::ClockBeforeEpoch)
         );
     }
 
     #[test]
-    fn test_next_sequence_change() {
-        let mut snowflake = Snowflake {
+    fn test_uuid_v7_and_ulid_unsupported_layouts() {
+        let snowflake = Snowflake {
             worker_id: 1,
-            timestamp: SnowflakeGenerator::get_timestamp(),
-            sequence: 0,
+            sequence: 1,
+            timestamp: 1,
+            extra: 0,
         };
 
-        snowflake = snowflake.next();
+        let sub_millisecond = SnowflakeLayout::builder().tick_micros(100).build().unwrap();
+        assert!(matches!(
+            snowflake.to_uuid_v7_with_layout(&sub_millisecond),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
+        assert!(matches!(
+            snowflake.to_ulid_with_layout(&sub_millisecond),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
+        let uuid = snowflake.to_uuid_v7().unwrap();
+        assert!(matches!(
+            Snowflake::try_from_uuid_with_layout(uuid, &sub_millisecond),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
+        let ulid = snowflake.to_ulid().unwrap();
+        assert!(matches!(
+            Snowflake::from_ulid_with_layout(&ulid, &sub_millisecond),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
 
-        // Assert that sequence is incremented
-        assert_eq!(snowflake.sequence, 1);
+        let wide_fields = SnowflakeLayout::builder()
+            .timestamp_bits(1)
+            .worker_id_bits(31)
+            .sequence_bits(32)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            snowflake.to_uuid_v7_with_layout(&wide_fields),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
+        assert!(matches!(
+            Snowflake::try_from_uuid_with_layout(uuid, &wide_fields),
+            Err(SnowflakeError::UnsupportedLayout(_))
+        ));
+        let ulid = snowflake.to_ulid_with_layout(&wide_fields).unwrap();
+        assert_eq!(
+            Snowflake::from_ulid_with_layout(&ulid, &wide_fields),
+            Ok(snowflake)
+        );
 
-        snowflake = snowflake.next();
+        let whole_milliseconds = SnowflakeLayout::builder().tick_millis(2).build().unwrap();
+        let uuid = snowflake
+            .to_uuid_v7_with_layout(&whole_milliseconds)
+            .unwrap();
+        assert_eq!(
+            Snowflake::try_from_uuid_with_layout(uuid, &whole_milliseconds),
+            Ok(snowflake)
+        );
+    }
 
-        assert_eq!(snowflake.sequence, 2);
+    #[test]
+    fn test_ulid() {
+        let snowflake = Snowflake {
+            worker_id: 1023,
+            sequence: 4095,
+            timestamp: 1_700_000_000,
+            extra: 0,
+        };
+        let ulid = snowflake.to_ulid().unwrap();
+
+        assert_eq!(Snowflake::from_ulid(&ulid), Ok(snowflake));
+        assert_eq!(Snowflake::from_ulid(&ulid.to_lowercase()), Ok(snowflake));
+
+        let later = Snowflake {
+            timestamp: 1_700_000_001,
+            sequence: 0,
+            ..snowflake
+        };
+        assert!(later.to_ulid().unwrap() > ulid);
     }
 
     #[test]
-    fn test_next_when_sequence_overflows() {
-        let time = SnowflakeGenerator::get_timestamp();
+    fn test_ulid_with_layout() {
+        let layout = SnowflakeLayout::DISCORD;
+        let snowflake = Snowflake::parse_with_layout(175928847299117063, &layout);
+        let ulid = snowflake.to_ulid_with_layout(&layout).unwrap();
+
+        assert_eq!(
+            encoding::decode_ulid(&ulid).unwrap() >> 80,
+            1_462_015_105_796
+        );
+        assert_eq!(
+            Snowflake::from_ulid_with_layout(&ulid, &layout),
+            Ok(snowflake)
+        );
+    }
+
+    #[test]
+    fn test_from_foreign_ulid() {
+        let snowflake = Snowflake::from_ulid("01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+
+        assert_eq!(snowflake.timestamp, 1_469_922_850);
+        assert!(snowflake.worker_id <= 1023);
+        assert!(snowflake.sequence <= 4095);
+    }
 
+    #[test]
+    fn test_i64() {
         let snowflake = Snowflake {
-            worker_id: 1,
-            sequence: 0xFFFF, // Maximum sequence value
-            timestamp: time,
+            worker_id: 1023,
+            sequence: 4095,
+            timestamp: (1 << 41) - 1,
+            extra: 0,
         };
 
-        let next = snowflake.next();
+        assert_eq!(snowflake.to_i64(), Ok(i64::MAX));
+        assert_eq!(Snowflake::from_i64(i64::MAX), Ok(snowflake));
+        assert_eq!(Snowflake::try_from(i64::MAX), Ok(snowflake));
+        assert_eq!(
+            Snowflake::from_i64(-1),
+            Err(SnowflakeError::NegativeId { id: -1 })
+        );
+    }
 
-        // Assert that sequence is reset to 0
-        assert_eq!(next.sequence, 0);
+    #[test]
+    fn test_bytes() {
+        let snowflake = Snowflake::parse(175928847299117063);
 
-        assert!(next.timestamp > time);
+        assert_eq!(snowflake.to_be_bytes(), 175928847299117063u64.to_be_bytes());
+        assert_eq!(snowflake.to_le_bytes(), 175928847299117063u64.to_le_bytes());
+        assert_eq!(Snowflake::from_be_bytes(snowflake.to_be_bytes()), snowflake);
+        assert_eq!(Snowflake::from_le_bytes(snowflake.to_le_bytes()), snowflake);
+
+        let layout = SnowflakeLayout::DISCORD;
+        let snowflake = Snowflake::parse_with_layout(u64::MAX, &layout);
+        assert_eq!(snowflake.to_be_bytes_with_layout(&layout), [0xFF; 8]);
+        assert_eq!(
+            Snowflake::from_le_bytes_with_layout(
+                snowflake.to_le_bytes_with_layout(&layout),
+                &layout
+            ),
+            snowflake
+        );
+        assert_eq!(
+            Snowflake::from_be_bytes_with_layout([0xFF; 8], &layout),
+            snowflake
+        );
+
+        // Big-endian bytes sort like the IDs
+        let mut ids = [3u64, 1 << 40, 256, 1, u64::MAX >> 1];
+        let mut keys = ids.map(|id| Snowflake::parse(id).to_be_bytes());
+        ids.sort();
+        keys.sort();
+        assert_eq!(keys.map(|key| Snowflake::from_be_bytes(key).to_id()), ids);
     }
 
     #[test]
-    fn test_next_when_timestamp_is_greater_than_timestamp() {
-        let snowflake = Snowflake {
-            worker_id: 1,
-            timestamp: SnowflakeGenerator::get_timestamp() + 100,
-            sequence: 0,
+    fn test_i64_with_layout() {
+        let layout = SnowflakeLayout::DISCORD;
+        let snowflake = Snowflake::parse_with_layout(u64::MAX, &layout);
+
+        assert_eq!(
+            snowflake.to_i64_with_layout(&layout),
+            Err(SnowflakeError::IdExceedsI64 { id: u64::MAX })
+        );
+
+        let snowflake = Snowflake::parse_with_layout(175928847299117063, &layout);
+        let id = snowflake.to_i64_with_layout(&layout).unwrap();
+
+        assert_eq!(id, 175928847299117063);
+        assert_eq!(Snowflake::from_i64_with_layout(id, &layout), Ok(snowflake));
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let ids = [0, 175928847299117063, u64::MAX];
+        let snowflakes = Snowflake::parse_many(&ids);
+
+        assert_eq!(
+            snowflakes,
+            ids.iter()
+                .map(|&id| Snowflake::parse(id))
+                .collect::<Vec<_>>()
+        );
+
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let snowflakes = Snowflake::parse_many_with_layout(&ids, &layout);
+        assert_eq!(snowflakes[1], Snowflake::parse_with_layout(ids[1], &layout));
+    }
+
+    #[test]
+    fn test_decode_timestamps() {
+        let ids = [0, 175928847299117063, u64::MAX];
+        let mut timestamps = [0; 3];
+
+        Snowflake::decode_timestamps(&ids, &mut timestamps).unwrap();
+        for (timestamp, id) in timestamps.iter().zip(ids) {
+            assert_eq!(*timestamp, Snowflake::parse(id).timestamp);
+        }
+
+        let layout = SnowflakeLayout::DISCORD;
+        Snowflake::decode_timestamps_with_layout(&ids, &mut timestamps, &layout).unwrap();
+        assert_eq!(timestamps[1], 41944705796);
+        assert_eq!(timestamps[2], layout.max_timestamp());
+    }
+
+    #[test]
+    fn test_decode_timestamps_length_mismatch() {
+        let mut timestamps = [0; 1];
+
+        assert_eq!(
+            Snowflake::decode_timestamps(&[1 << 22, 2], &mut timestamps),
+            Err(SnowflakeError::LengthMismatch {
+                length: 1,
+                expected: 2
+            })
+        );
+        assert_eq!(timestamps, [0]);
+    }
+
+    #[test]
+    fn test_extreme_inputs_dont_panic() {
+        let build = |timestamp_bits, worker_id_bits, sequence_bits, tick_micros, epoch| {
+            SnowflakeLayout::builder()
+                .timestamp_bits(timestamp_bits)
+                .worker_id_bits(worker_id_bits)
+                .sequence_bits(sequence_bits)
+                .tick_micros(tick_micros)
+                .epoch(epoch)
+                .build()
+                .unwrap()
         };
+        let layouts = [
+            SnowflakeLayout::DEFAULT,
+            SnowflakeLayout::DISCORD,
+            build(64, 0, 0, 1, 0),
+            build(0, 64, 0, u64::MAX, u64::MAX),
+            build(0, 0, 64, 1, u64::MAX),
+            build(1, 1, 1, u64::MAX, u64::MAX / 2),
+        ];
+        let times = [
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::from_micros(u64::MAX),
+            UNIX_EPOCH + Duration::from_secs(u64::MAX / 2),
+        ];
 
-        let next = snowflake.next();
+        for layout in &layouts {
+            for id in [0, 1, 1 << 63, u64::MAX] {
+                let snowflake = Snowflake::parse_with_layout(id, layout);
+                let _ = snowflake.to_id_with_layout(layout);
+                let _ = snowflake.created_at_with_layout(layout);
+                let _ = snowflake.duration_since_with_layout(Snowflake::parse(0), layout);
+                let _ = snowflake.to_uuid_v7_with_layout(layout);
+                let _ = snowflake.to_ulid_with_layout(layout);
+                let _ = snowflake.to_i64_with_layout(layout);
+                let _ = Snowflake::try_parse_with_layout(id, layout);
+                let _ = Snowflake::try_from_uuid_with_layout(id as u128 | u128::MAX << 64, layout);
+                let _ = Snowflake::max_for_timestamp_with_layout(id, layout);
+                let _ = Snowflake::explain_with_layout(id, layout).to_string();
+                let _ = crate::migrate::rebase_epoch(id, layout, &SnowflakeLayout::DEFAULT);
+                let _ = crate::migrate::rebase_epoch(id, &SnowflakeLayout::DEFAULT, layout);
+            }
 
-        assert_ne!(snowflake, next, "Snowflake didn't change");
+            for time in times {
+                let _ = Snowflake::first_id_at_with_layout(time, layout);
+            }
+        }
+
+        for encoded in [
+            "",
+            "é",
+            "éé0000000000",
+            "-1",
+            "18446744073709551616",
+            "Z".repeat(26).as_str(),
+        ] {
+            let _ = Snowflake::parse_str(encoded);
+            let _ = Snowflake::from_base62(encoded);
+            let _ = Snowflake::from_crockford32_check(encoded);
+            let _ = Snowflake::from_sortable_string(encoded);
+            let _ = Snowflake::from_ulid(encoded);
+            let _ = crate::keys::split_key(encoded);
+        }
     }
 
     #[test]
-    fn test_snowflake_to_id() {
-        let snowflake = Snowflake::new(1);
-        let id = snowflake.to_id();
+    fn test_hash_map_key() {
+        let snowflake = Snowflake::parse(175928847299117063);
+        let mut names = std::collections::HashMap::new();
+        names.insert(snowflake, "discord");
+
+        assert_eq!(
+            names.get(&Snowflake::parse(175928847299117063)),
+            Some(&"discord")
+        );
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(Snowflake::default(), Snowflake::parse(0));
+        assert_eq!(Snowflake::default().to_id(), 0);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let snowflake = Snowflake::parse(175928847299117063);
+
+        assert_eq!("175928847299117063".parse::<Snowflake>(), Ok(snowflake));
+        assert_eq!(Snowflake::try_from("175928847299117063"), Ok(snowflake));
+        assert!(matches!(
+            "-1".parse::<Snowflake>(),
+            Err(SnowflakeError::ParseInt(_))
+        ));
+        assert_eq!(
+            u64::MAX.to_string().parse::<Snowflake>(),
+            Err(SnowflakeError::ReservedBitsSet { id: u64::MAX })
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let snowflake = Snowflake::builder()
+            .timestamp(1_700_000_000)
+            .worker_id(1023)
+            .sequence(4095)
+            .build()
+            .unwrap();
 
         assert_eq!(
             snowflake,
-            Snowflake::parse(id),
-            "Snowflake ID didn't parse correctly"
+            Snowflake {
+                worker_id: 1023,
+                sequence: 4095,
+                timestamp: 1_700_000_000,
+                extra: 0
+            }
         );
+        assert_eq!(Snowflake::builder().build(), Ok(Snowflake::default()));
     }
 
     #[test]
-    fn test_snowflake_to_string() {
-        let snowflake = Snowflake::new(1);
-        assert_eq!(snowflake.to_string(), format!("{}", snowflake.to_id()));
+    fn test_builder_overflow() {
+        assert_eq!(
+            Snowflake::builder().timestamp(1 << 41).build(),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: 1 << 41,
+                max: (1 << 41) - 1
+            })
+        );
+        assert_eq!(
+            Snowflake::builder().sequence(4096).build(),
+            Err(SnowflakeError::SequenceOverflow {
+                sequence: 4096,
+                max: 4095
+            })
+        );
+
+        let builder = Snowflake::builder()
+            .layout(SnowflakeLayout::SONYFLAKE)
+            .worker_id(65535);
+        assert!(builder.build().is_ok());
+        assert!(builder.sequence(256).build().is_err());
+        assert_eq!(
+            Snowflake::builder().extra(1).build(),
+            Err(SnowflakeError::ExtraOverflow { extra: 1, max: 0 })
+        );
+    }
+
+    #[test]
+    fn test_extra() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(39)
+            .extra_bits(2)
+            .build()
+            .unwrap();
+        let snowflake = Snowflake::builder()
+            .timestamp(1_700_000_000)
+            .worker_id(42)
+            .sequence(7)
+            .extra(2)
+            .layout(layout)
+            .build()
+            .unwrap();
+
+        let id = snowflake.to_id_with_layout(&layout);
+        assert_eq!(id, 1_700_000_000 << 24 | 2 << 22 | 42 << 12 | 7);
+        assert_eq!(Snowflake::parse_with_layout(id, &layout), snowflake);
+
+        // Layouts without extra bits drop the field
+        assert_eq!(Snowflake::parse(snowflake.to_id()).extra(), 0);
+    }
+
+    #[test]
+    fn test_with_fields() {
+        let snowflake = Snowflake::parse(175928847299117063);
+
+        let changed = snowflake
+            .with_worker_id(1023)
+            .and_then(|snowflake| snowflake.with_sequence(0))
+            .and_then(|snowflake| snowflake.with_timestamp(1))
+            .unwrap();
+        assert_eq!(
+            changed,
+            Snowflake {
+                worker_id: 1023,
+                sequence: 0,
+                timestamp: 1,
+                extra: 0
+            }
+        );
+        assert_eq!(
+            snowflake.with_worker_id(1024),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            })
+        );
+
+        // Fields already out of range for the default layout are caught too
+        let wide = Snowflake::parse_with_layout(u64::MAX, &SnowflakeLayout::SONYFLAKE);
+        assert!(wide.with_sequence(0).is_err());
     }
 
     #[test]
@@ -288,11 +2818,317 @@ mod tests {
     }
 
     #[test]
+    fn test_const_parse() {
+        const SNOWFLAKE: Snowflake = Snowflake::parse(175928847299117063);
+        const ID: u64 = SNOWFLAKE.to_id();
+
+        assert_eq!(ID, 175928847299117063);
+        assert_eq!(crate::snowflake!(175928847299117063), SNOWFLAKE);
+        assert_eq!(Snowflake::try_parse(ID), Ok(SNOWFLAKE));
+        assert!(Snowflake::try_parse(1 << 63).is_err());
+        assert!(Snowflake::try_parse_with_layout(1 << 63, &SnowflakeLayout::DISCORD).is_ok());
+    }
+
+    #[test]
+    fn test_try_parse() {
+        assert_eq!(
+            Snowflake::try_parse(1 << 22 | 3),
+            Ok(Snowflake::parse(1 << 22 | 3))
+        );
+        assert_eq!(
+            Snowflake::try_parse(u64::MAX),
+            Err(SnowflakeError::ReservedBitsSet { id: u64::MAX })
+        );
+
+        let layout = SnowflakeLayout::custom::<51, 6, 6>().with_time_unit(TimeUnit::Seconds);
+        let max = layout.max_representable_timestamp();
+        assert_eq!(max, u64::MAX / 1_000_000);
+        assert!(Snowflake::try_parse_with_layout(max << 12, &layout).is_ok());
+        assert_eq!(
+            Snowflake::try_parse_with_layout((max + 1) << 12, &layout),
+            Err(SnowflakeError::TimestampOverflow {
+                timestamp: max + 1,
+                max
+            })
+        );
+    }
+
+    #[test]
+    fn test_time_comparisons() {
+        let first = Snowflake::parse(175928847299117063);
+        let same_tick = Snowflake {
+            sequence: 8,
+            ..first
+        };
+        let later = Snowflake {
+            timestamp: first.timestamp + 3,
+            sequence: 0,
+            ..first
+        };
+
+        assert!(first.same_tick(same_tick));
+        assert!(!same_tick.is_after(first) && !same_tick.is_before(first));
+        assert!(later.is_after(first) && first.is_before(later));
+        assert!(!later.same_tick(first));
+
+        assert_eq!(later.duration_since(first), Duration::from_secs(3));
+        assert_eq!(first.duration_since(later), Duration::ZERO);
+        assert_eq!(
+            later.duration_since_with_layout(first, &SnowflakeLayout::SONYFLAKE),
+            Duration::from_millis(30)
+        );
+    }
+
+    #[test]
+    fn test_datacenter_and_machine_id() {
+        let snowflake = Snowflake::parse(175928847299117063);
+        assert_eq!(snowflake.datacenter_id(), 1);
+        assert_eq!(snowflake.machine_id(), 0);
+
+        let layout = SnowflakeLayout::builder()
+            .datacenter_id_bits(2)
+            .build()
+            .unwrap();
+        let snowflake = Snowflake::builder().worker_id(0x3FF).build().unwrap();
+        assert_eq!(snowflake.datacenter_id_with_layout(&layout), 3);
+        assert_eq!(snowflake.machine_id_with_layout(&layout), 0xFF);
+
+        let sonyflake = Snowflake::builder().worker_id(0xFFFF).build();
+        assert!(sonyflake.is_err());
+        let snowflake = Snowflake::parse_with_layout(0xFFFF, &SnowflakeLayout::SONYFLAKE);
+        assert_eq!(
+            snowflake.datacenter_id_with_layout(&SnowflakeLayout::SONYFLAKE),
+            0
+        );
+        assert_eq!(
+            snowflake.machine_id_with_layout(&SnowflakeLayout::SONYFLAKE),
+            0xFFFF
+        );
+    }
+
+    #[test]
+    fn test_getters() {
+        let snowflake = Snowflake::parse(175928847299117063);
+
+        assert_eq!(snowflake.timestamp(), 41944705796);
+        assert_eq!(snowflake.worker_id(), 32);
+        assert_eq!(snowflake.sequence(), 7);
+    }
+
+    #[test]
+    fn test_tuple_and_u64_conversions() {
+        let snowflake = Snowflake::parse(175928847299117063);
+
+        let fields: (u64, u64, u64) = snowflake.into();
+        assert_eq!(fields, (41944705796, 32, 7));
+        assert_eq!(Snowflake::try_from(fields), Ok(snowflake));
+        assert_eq!(u64::from(snowflake), 175928847299117063);
+
+        assert_eq!(
+            Snowflake::try_from((0, 1024, 0)),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_zero_u64() {
+        use core::num::NonZeroU64;
+
+        let snowflake = Snowflake::parse(175928847299117063);
+        let id = NonZeroU64::try_from(snowflake).unwrap();
+        assert_eq!(id.get(), 175928847299117063);
+        assert_eq!(Snowflake::from(id), snowflake);
+        assert_eq!(Snowflake::from(NonZeroU64::MIN).sequence(), 1);
+
+        assert_eq!(
+            NonZeroU64::try_from(Snowflake::parse(0)),
+            Err(SnowflakeError::ZeroId)
+        );
+        assert_eq!(SnowflakeError::ZeroId.to_string(), "ID is zero");
+    }
+
+    #[test]
+    fn test_write_to_and_encode_to_slice() {
+        let mut buffer = [0; MAX_ID_LENGTH];
+
+        for id in [0, 7, 4_198_401, 175928847299117063, u64::MAX >> 1] {
+            let snowflake = Snowflake::parse(id);
+            let expected = id.to_string();
+
+            let mut written = String::new();
+            snowflake.write_to(&mut written).unwrap();
+            assert_eq!(written, expected);
+            assert_eq!(snowflake.to_string(), expected);
+            assert_eq!(
+                snowflake.encode_to_slice(&mut buffer),
+                Ok(expected.as_str())
+            );
+        }
+
+        assert_eq!(format!("{:>6}", Snowflake::parse(42)), "    42");
+        assert_eq!(
+            Snowflake::parse(12345).encode_to_slice(&mut buffer[..4]),
+            Err(SnowflakeError::BufferTooSmall {
+                length: 5,
+                capacity: 4
+            })
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn test_partial_ord() {
-        let snowflake = Snowflake::new(1);
+        let snowflake = Snowflake::try_new(1).unwrap();
         let snowflake2 = snowflake.next();
         assert!(snowflake < snowflake2);
         assert!(snowflake2.next() > snowflake2);
         assert!(snowflake.next() == snowflake2);
     }
+
+    #[test]
+    fn test_ord_matches_eq() {
+        let snowflake = Snowflake {
+            worker_id: 0,
+            sequence: 0,
+            timestamp: 42,
+            extra: 0,
+        };
+        let wide_worker = Snowflake {
+            worker_id: 1024,
+            ..snowflake
+        };
+        let with_extra = Snowflake {
+            extra: 1,
+            ..snowflake
+        };
+
+        assert!(snowflake < wide_worker);
+        assert!(snowflake < with_extra);
+        assert!(wide_worker < with_extra);
+
+        let mut snowflakes = vec![with_extra, snowflake, wide_worker, snowflake];
+        snowflakes.sort();
+        snowflakes.dedup();
+        assert_eq!(snowflakes, vec![snowflake, wide_worker, with_extra]);
+    }
+
+    /// A xorshift generator, so the round-trip tests can cover many random inputs without a
+    /// property testing dependency. The seed is fixed to keep failures reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// A random value in `0..=max`
+        fn up_to(&mut self, max: u64) -> u64 {
+            match max.checked_add(1) {
+                Some(bound) => self.next_u64() % bound,
+                None => self.next_u64(),
+            }
+        }
+
+        fn layout(&mut self) -> SnowflakeLayout {
+            let timestamp_bits = self.up_to(64) as u8;
+            let worker_id_bits = self.up_to(64 - timestamp_bits as u64) as u8;
+            let sequence_bits =
+                self.up_to(64 - timestamp_bits as u64 - worker_id_bits as u64) as u8;
+            let extra_bits = self
+                .up_to(64 - timestamp_bits as u64 - worker_id_bits as u64 - sequence_bits as u64)
+                as u8;
+            let field_order = if self.next_u64() & 1 == 0 {
+                FieldOrder::WorkerIdSequence
+            } else {
+                FieldOrder::SequenceWorkerId
+            };
+
+            SnowflakeLayout::builder()
+                .timestamp_bits(timestamp_bits)
+                .worker_id_bits(worker_id_bits)
+                .sequence_bits(sequence_bits)
+                .extra_bits(extra_bits)
+                .field_order(field_order)
+                .build()
+                .unwrap()
+        }
+
+        fn snowflake(&mut self, layout: &SnowflakeLayout) -> Snowflake {
+            Snowflake::builder()
+                .timestamp(self.up_to(layout.max_timestamp()))
+                .worker_id(self.up_to(layout.max_worker_id()))
+                .sequence(self.up_to(layout.max_sequence()))
+                .extra(self.up_to(layout.max_extra()))
+                .layout(*layout)
+                .build()
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_random_layouts() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+
+        for _ in 0..10_000 {
+            let layout = rng.layout();
+            let snowflake = rng.snowflake(&layout);
+            let id = snowflake.to_id_with_layout(&layout);
+
+            assert_eq!(
+                Snowflake::parse_with_layout(id, &layout),
+                snowflake,
+                "{:?}",
+                layout
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_random_strings() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0..10_000 {
+            let snowflake = rng.snowflake(&SnowflakeLayout::DEFAULT);
+
+            assert_eq!(
+                Snowflake::parse_str(&snowflake.to_string_id()),
+                Ok(snowflake)
+            );
+            assert_eq!(
+                Snowflake::from_base62(&snowflake.to_base62()),
+                Ok(snowflake)
+            );
+            assert_eq!(
+                Snowflake::from_base36(&snowflake.to_base36()),
+                Ok(snowflake)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parsers_reject_random_input() {
+        const BYTES: &[u8] = b"0123456789azAZ+-_ \xff";
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+
+        for _ in 0..10_000 {
+            let len = rng.up_to(24) as usize;
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| BYTES[rng.up_to(BYTES.len() as u64 - 1) as usize])
+                .collect();
+            let input = String::from_utf8_lossy(&bytes);
+
+            // Only checks that malformed and overflowing input is an error rather than a panic
+            let _ = Snowflake::parse_str(&input);
+            let _ = Snowflake::from_base62(&input);
+            let _ = Snowflake::from_base36(&input);
+            let _ = Snowflake::from_ulid(&input);
+            let _ = input.parse::<Snowflake>();
+        }
+    }
 }
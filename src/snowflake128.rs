@@ -0,0 +1,423 @@
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::{Clock, SystemClock, TimeUnit};
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+/// The number of bits in the worker ID of a [`Snowflake128`].
+const WORKER_ID_BITS: u32 = 32;
+
+/// The number of bits in the sequence number of a [`Snowflake128`].
+const SEQUENCE_BITS: u32 = 32;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A 128-bit snowflake, for systems that need more headroom than 64 bits allow.
+///
+/// The ID is a 64-bit timestamp, a 32-bit worker ID and a 32-bit sequence number, from the
+/// highest bits to the lowest. The timestamp counts ticks of a [`SnowflakeLayout`]'s epoch and
+/// tick, the same way a 64-bit snowflake's does, but none of the layout's field widths apply:
+/// 64 bits of microseconds last for over half a million years, and four billion IDs per tick
+/// per worker are rarely used up.
+///
+/// A 64-bit snowflake always fits in a `Snowflake128` with the same layout, as long as its
+/// worker ID and sequence number fit in 32 bits, and a `Snowflake128` converts back when its
+/// fields fit in the 64-bit layout.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{Snowflake, Snowflake128, SnowflakeLayout};
+///
+/// let snowflake = Snowflake::parse(175928847299117063);
+/// let wide = Snowflake128::try_from(snowflake).unwrap();
+///
+/// assert_eq!(Snowflake128::from_u128(wide.to_u128()), wide);
+/// assert_eq!(wide.try_into_snowflake(&SnowflakeLayout::DEFAULT), Ok(snowflake));
+/// ```
+pub struct Snowflake128 {
+    // Declared in order of significance, so the derived `Ord` matches the order of the IDs
+    pub(crate) timestamp: u64,
+    pub(crate) worker_id: u64,
+    pub(crate) sequence: u64,
+}
+
+impl Snowflake128 {
+    /// The largest worker ID a `Snowflake128` can hold
+    pub const MAX_WORKER_ID: u64 = u32::MAX as u64;
+
+    /// The largest sequence number a `Snowflake128` can hold
+    pub const MAX_SEQUENCE: u64 = u32::MAX as u64;
+
+    /// Create a 128-bit snowflake from its fields
+    ///
+    /// # Arguments
+    /// * `timestamp` - The timestamp, in the epoch and tick of the layout it's used with
+    /// * `worker_id` - The worker ID
+    /// * `sequence` - The sequence number
+    ///
+    /// # Returns
+    /// The `Snowflake128`, or an error if the worker ID or sequence number doesn't fit in 32
+    /// bits
+    pub const fn new(
+        timestamp: u64,
+        worker_id: u64,
+        sequence: u64,
+    ) -> Result<Snowflake128, SnowflakeError> {
+        if worker_id > Snowflake128::MAX_WORKER_ID {
+            return Err(SnowflakeError::WorkerIdOverflow {
+                worker_id,
+                max: Snowflake128::MAX_WORKER_ID,
+            });
+        }
+
+        if sequence > Snowflake128::MAX_SEQUENCE {
+            return Err(SnowflakeError::SequenceOverflow {
+                sequence,
+                max: Snowflake128::MAX_SEQUENCE,
+            });
+        }
+
+        Ok(Snowflake128 {
+            timestamp,
+            worker_id,
+            sequence,
+        })
+    }
+
+    /// Get the timestamp, in the epoch and tick of the layout it's used with
+    pub const fn timestamp(self) -> u64 {
+        self.timestamp
+    }
+
+    /// Get the worker ID
+    pub const fn worker_id(self) -> u64 {
+        self.worker_id
+    }
+
+    /// Get the sequence number
+    pub const fn sequence(self) -> u64 {
+        self.sequence
+    }
+
+    /// Pack the snowflake into its 128-bit ID
+    pub const fn to_u128(self) -> u128 {
+        (self.timestamp as u128) << (WORKER_ID_BITS + SEQUENCE_BITS)
+            | (self.worker_id as u128) << SEQUENCE_BITS
+            | self.sequence as u128
+    }
+
+    /// Unpack a 128-bit ID
+    ///
+    /// Every `u128` is a valid ID.
+    pub const fn from_u128(id: u128) -> Snowflake128 {
+        Snowflake128 {
+            timestamp: (id >> (WORKER_ID_BITS + SEQUENCE_BITS)) as u64,
+            worker_id: (id >> SEQUENCE_BITS) as u64 & Snowflake128::MAX_WORKER_ID,
+            sequence: id as u64 & Snowflake128::MAX_SEQUENCE,
+        }
+    }
+
+    /// Convert to a 64-bit snowflake with the same fields, if they all fit in a layout
+    ///
+    /// # Arguments
+    /// * `layout` - The 64-bit layout, with the epoch and tick the timestamp was counted in
+    ///
+    /// # Returns
+    /// The `Snowflake`, or an error naming the first field that doesn't fit in the layout
+    pub fn try_into_snowflake(self, layout: &SnowflakeLayout) -> Result<Snowflake, SnowflakeError> {
+        Snowflake::builder()
+            .timestamp(self.timestamp)
+            .worker_id(self.worker_id)
+            .sequence(self.sequence)
+            .layout(*layout)
+            .build()
+    }
+}
+
+impl TryFrom<Snowflake> for Snowflake128 {
+    type Error = SnowflakeError;
+
+    /// Widen a 64-bit snowflake, keeping its timestamp in the same epoch and tick
+    ///
+    /// # Returns
+    /// The `Snowflake128`, or an error if the worker ID or sequence number of a custom layout
+    /// doesn't fit in 32 bits or the snowflake has an extra field
+    fn try_from(snowflake: Snowflake) -> Result<Snowflake128, SnowflakeError> {
+        // A `Snowflake128` has nowhere to keep the extra field
+        if snowflake.extra != 0 {
+            return Err(SnowflakeError::ExtraOverflow {
+                extra: snowflake.extra,
+                max: 0,
+            });
+        }
+
+        Snowflake128::new(snowflake.timestamp, snowflake.worker_id, snowflake.sequence)
+    }
+}
+
+impl From<Snowflake128> for u128 {
+    fn from(snowflake: Snowflake128) -> u128 {
+        snowflake.to_u128()
+    }
+}
+
+impl From<u128> for Snowflake128 {
+    fn from(id: u128) -> Snowflake128 {
+        Snowflake128::from_u128(id)
+    }
+}
+
+impl fmt::Display for Snowflake128 {
+    /// Display the 128-bit ID in decimal
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_u128(), f)
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+/// A generator of [`Snowflake128`]s.
+///
+/// It counts time in microseconds since the UNIX epoch unless created with another layout. When
+/// the clock goes backwards it keeps generating under the last timestamp, and when a tick's
+/// sequence numbers run out it waits for the next tick.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::SnowflakeGenerator128;
+///
+/// let mut generator = SnowflakeGenerator128::try_new(70_000).unwrap();
+///
+/// let first = generator.try_next().unwrap();
+/// let second = generator.try_next().unwrap();
+///
+/// assert!(second > first);
+/// assert_eq!(first.worker_id(), 70_000);
+/// ```
+pub struct SnowflakeGenerator128<C: Clock = SystemClock> {
+    last_snowflake: Snowflake128,
+    layout: SnowflakeLayout,
+    clock: C,
+}
+
+#[cfg(feature = "std")]
+impl SnowflakeGenerator128 {
+    /// The layout a generator counts time in unless created with another: microseconds since
+    /// the UNIX epoch
+    pub const LAYOUT: SnowflakeLayout =
+        SnowflakeLayout::DEFAULT.with_time_unit(TimeUnit::Microseconds);
+
+    /// Create a new generator with the given worker ID
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator128`, or an error if the worker ID doesn't fit in 32 bits
+    pub fn try_new(worker_id: u64) -> Result<SnowflakeGenerator128, SnowflakeError> {
+        SnowflakeGenerator128::with_layout_and_clock(
+            worker_id,
+            SnowflakeGenerator128::LAYOUT,
+            SystemClock,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> SnowflakeGenerator128<C> {
+    /// Create a new generator that counts time in the epoch and tick of a layout, reading time
+    /// from `clock`
+    ///
+    /// Only the layout's epoch and tick are used. Generating with the layout of a 64-bit
+    /// generator lets the snowflakes convert to 64 bits with
+    /// [`Snowflake128::try_into_snowflake`] while their fields fit.
+    ///
+    /// # Arguments
+    /// * `worker_id` - The worker ID of the snowflakes generated
+    /// * `layout` - The layout whose epoch and tick the timestamps are counted in
+    /// * `clock` - The clock to read timestamps from
+    ///
+    /// # Returns
+    /// A new `SnowflakeGenerator128`, or an error if the worker ID doesn't fit in 32 bits
+    pub fn with_layout_and_clock(
+        worker_id: u64,
+        layout: SnowflakeLayout,
+        clock: C,
+    ) -> Result<SnowflakeGenerator128<C>, SnowflakeError> {
+        let timestamp = layout.timestamp_from_micros(clock.now_micros());
+
+        Ok(SnowflakeGenerator128 {
+            last_snowflake: Snowflake128::new(timestamp, worker_id, 0)?,
+            layout,
+            clock,
+        })
+    }
+
+    /// Get the last snowflake generated
+    pub fn last_snowflake(&self) -> Snowflake128 {
+        self.last_snowflake
+    }
+
+    /// Get the layout whose epoch and tick the timestamps are counted in
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Generates the next snowflake
+    ///
+    /// # Returns
+    /// The generated snowflake, or `SnowflakeError::TimestampOverflow` once the timestamp no
+    /// longer fits in 64 bits
+    pub fn try_next(&mut self) -> Result<Snowflake128, SnowflakeError> {
+        let last = self.last_snowflake;
+        let timestamp = self.timestamp();
+
+        let snowflake = if timestamp > last.timestamp {
+            Snowflake128 {
+                timestamp,
+                sequence: 0,
+                ..last
+            }
+        } else if last.sequence < Snowflake128::MAX_SEQUENCE {
+            // Keep generating under the last timestamp if the clock went backwards
+            Snowflake128 {
+                sequence: last.sequence + 1,
+                ..last
+            }
+        } else {
+            let next = last
+                .timestamp
+                .checked_add(1)
+                .ok_or(SnowflakeError::TimestampOverflow {
+                    timestamp: last.timestamp,
+                    max: u64::MAX,
+                })?;
+
+            let mut timestamp = self.timestamp();
+            while timestamp < next {
+                let wait = self
+                    .layout
+                    .micros_from_timestamp(next)
+                    .saturating_sub(self.clock.now_micros())
+                    .max(1);
+                std::thread::sleep(std::time::Duration::from_micros(wait));
+                timestamp = self.timestamp();
+            }
+
+            Snowflake128 {
+                timestamp,
+                sequence: 0,
+                ..last
+            }
+        };
+
+        self.last_snowflake = snowflake;
+        Ok(snowflake)
+    }
+
+    /// Get the current timestamp from the generator's clock
+    fn timestamp(&self) -> u64 {
+        self.layout.timestamp_from_micros(self.clock.now_micros())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> Iterator for SnowflakeGenerator128<C> {
+    type Item = Snowflake128;
+
+    /// Generates the next snowflake, or `None` once the timestamp no longer fits in 64 bits
+    fn next(&mut self) -> Option<Snowflake128> {
+        self.try_next().ok()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn test_pack() {
+        let snowflake = Snowflake128::new(u64::MAX, 7, u32::MAX as u64).unwrap();
+
+        assert_eq!(
+            snowflake.to_u128(),
+            (u64::MAX as u128) << 64 | 7 << 32 | u32::MAX as u128
+        );
+        assert_eq!(Snowflake128::from(u128::from(snowflake)), snowflake);
+        assert_eq!(
+            Snowflake128::new(1, 2, 3).unwrap().to_string(),
+            ((1u128 << 64) | (2 << 32) | 3).to_string()
+        );
+        assert_eq!(
+            Snowflake128::new(0, 1 << 32, 0),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1 << 32,
+                max: u32::MAX as u64
+            })
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        let first = Snowflake128::new(1, 9, 9).unwrap();
+        let second = Snowflake128::new(2, 0, 0).unwrap();
+
+        assert!(second > first);
+        assert!(second.to_u128() > first.to_u128());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let layout = SnowflakeLayout::SONYFLAKE;
+        let snowflake = Snowflake::parse_with_layout(u64::MAX >> 1, &layout);
+
+        let wide = Snowflake128::try_from(snowflake).unwrap();
+        assert_eq!(wide.try_into_snowflake(&layout), Ok(snowflake));
+
+        // Fields wider than the 64-bit layout don't convert back
+        let wide = Snowflake128::new(0, 1 << 16, 0).unwrap();
+        assert!(matches!(
+            wide.try_into_snowflake(&layout),
+            Err(SnowflakeError::WorkerIdOverflow { .. })
+        ));
+
+        let huge_worker_id = SnowflakeLayout::builder()
+            .timestamp_bits(20)
+            .worker_id_bits(40)
+            .sequence_bits(4)
+            .build()
+            .unwrap();
+        let snowflake = Snowflake::parse_with_layout(1 << 40, &huge_worker_id);
+        assert!(Snowflake128::try_from(snowflake).is_err());
+    }
+
+    #[test]
+    fn test_generator() {
+        let clock = MockClock::new(1_000);
+        let mut generator = SnowflakeGenerator128::with_layout_and_clock(
+            u32::MAX as u64,
+            SnowflakeGenerator128::LAYOUT,
+            clock.clone(),
+        )
+        .unwrap();
+
+        let first = generator.try_next().unwrap();
+        assert_eq!(
+            (first.timestamp(), first.worker_id(), first.sequence()),
+            (1_000_000, u32::MAX as u64, 1)
+        );
+
+        // The clock going backwards borrows the last timestamp
+        clock.set(500);
+        let borrowed = generator.try_next().unwrap();
+        assert_eq!((borrowed.timestamp(), borrowed.sequence()), (1_000_000, 2));
+
+        clock.set(2_000);
+        let next = generator.next().unwrap();
+        assert_eq!((next.timestamp(), next.sequence()), (2_000_000, 0));
+        assert_eq!(generator.last_snowflake(), next);
+        assert!(SnowflakeGenerator128::try_new(1 << 32).is_err());
+    }
+}
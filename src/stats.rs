@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+/// Counters describing what a [`SnowflakeGenerator`](crate::SnowflakeGenerator) has done since
+/// it was created.
+///
+/// Compare [`GeneratorStats::max_sequence_seen_per_tick`] with
+/// [`SnowflakeLayout::max_sequence`](crate::SnowflakeLayout::max_sequence) to see how close the
+/// generator is to running out of sequence numbers, and watch
+/// [`GeneratorStats::clock_wait_time_total`] for time lost to exhaustion or a clock that went
+/// backwards.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::SnowflakeGenerator;
+///
+/// let mut generator = SnowflakeGenerator::try_new(420).unwrap();
+/// generator.next_batch(100);
+///
+/// let stats = generator.stats();
+/// assert_eq!(stats.ids_generated(), 100);
+/// assert!(stats.max_sequence_seen_per_tick() < generator.layout().max_sequence());
+/// ```
+pub struct GeneratorStats {
+    pub(crate) ids_generated: u64,
+    pub(crate) sequence_rollovers: u64,
+    pub(crate) clock_wait_time_total: Duration,
+    pub(crate) max_sequence_seen_per_tick: u64,
+}
+
+impl GeneratorStats {
+    /// The number of snowflakes generated
+    pub const fn ids_generated(&self) -> u64 {
+        self.ids_generated
+    }
+
+    /// The number of times the sequence ran out within a tick and the generator moved on to the
+    /// next tick
+    pub const fn sequence_rollovers(&self) -> u64 {
+        self.sequence_rollovers
+    }
+
+    /// The total time spent waiting for the clock, either for the next tick after the sequence
+    /// ran out or for the clock to catch up after it went backwards
+    pub const fn clock_wait_time_total(&self) -> Duration {
+        self.clock_wait_time_total
+    }
+
+    /// The highest sequence number generated under any one tick
+    pub const fn max_sequence_seen_per_tick(&self) -> u64 {
+        self.max_sequence_seen_per_tick
+    }
+}
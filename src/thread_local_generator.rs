@@ -0,0 +1,308 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::generator::generation_failed;
+use crate::layout::{mask, shl};
+use crate::{Snowflake, SnowflakeError, SnowflakeGenerator, SnowflakeLayout};
+
+/// Tells the thread-local generators of different `ThreadLocalGenerator`s apart.
+static NEXT_INSTANCE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The generator of the current thread for each `ThreadLocalGenerator` it has used.
+    static SLOTS: RefCell<HashMap<usize, Slot>> = RefCell::new(HashMap::new());
+}
+
+/// A generator that gives every OS thread its own [`SnowflakeGenerator`].
+///
+/// The worker ID field is split into a process ID in the high bits and a thread ID in the low
+/// bits. Each thread is handed a free thread ID the first time it generates a snowflake, and
+/// after that generates without touching any shared state, so throughput scales with the
+/// number of threads.
+///
+/// Thread IDs are recycled when threads exit. A recycled thread ID continues from the last
+/// snowflake of the thread that held it, so IDs stay unique.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::ThreadLocalGenerator;
+///
+/// // 6 bits of process ID leave 4 bits, or 16 threads, per process
+/// let generator = ThreadLocalGenerator::new(5, 6).unwrap();
+///
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             let snowflake = generator.next();
+///             assert_eq!(snowflake.worker_id() >> 4, 5);
+///         });
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct ThreadLocalGenerator {
+    /// Identifies this generator's entry in each thread's slots.
+    instance: usize,
+    /// The process ID, shifted into the high bits of the worker ID.
+    process_id: u64,
+    /// The number of low worker ID bits that hold the thread ID.
+    thread_bits: u8,
+    /// The bit layout of the snowflakes generated.
+    layout: SnowflakeLayout,
+    /// The thread IDs that are free to hand out.
+    pool: Arc<Mutex<ThreadIdPool>>,
+}
+
+impl ThreadLocalGenerator {
+    /// Create a new thread-local generator with [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Arguments
+    /// * `process_id` - The ID of this process, unique across the processes sharing the ID space
+    /// * `process_bits` - How many of the worker ID bits hold the process ID; the rest hold the
+    ///   thread ID
+    ///
+    /// # Returns
+    /// A new `ThreadLocalGenerator`, or `SnowflakeError::ProcessIdOverflow` if the process ID
+    /// doesn't fit in `process_bits`
+    pub fn new(process_id: u64, process_bits: u8) -> Result<ThreadLocalGenerator, SnowflakeError> {
+        ThreadLocalGenerator::with_layout(process_id, process_bits, SnowflakeLayout::DEFAULT)
+    }
+
+    /// Create a new thread-local generator with a custom bit layout
+    ///
+    /// # Arguments
+    /// * `process_id` - The ID of this process, unique across the processes sharing the ID space
+    /// * `process_bits` - How many of the worker ID bits hold the process ID, up to all of them
+    /// * `layout` - The bit layout of the snowflakes generated
+    ///
+    /// # Returns
+    /// A new `ThreadLocalGenerator`, or `SnowflakeError::ProcessIdOverflow` if the process ID
+    /// doesn't fit in `process_bits`
+    pub fn with_layout(
+        process_id: u64,
+        process_bits: u8,
+        layout: SnowflakeLayout,
+    ) -> Result<ThreadLocalGenerator, SnowflakeError> {
+        let process_bits = process_bits.min(layout.worker_id_bits());
+        let max = mask(process_bits);
+        if process_id > max {
+            return Err(SnowflakeError::ProcessIdOverflow { process_id, max });
+        }
+
+        Ok(ThreadLocalGenerator {
+            instance: NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed),
+            process_id,
+            thread_bits: layout.worker_id_bits() - process_bits,
+            layout,
+            pool: Arc::new(Mutex::new(ThreadIdPool::default())),
+        })
+    }
+
+    /// Get the bit layout of the snowflakes generated
+    pub fn layout(&self) -> &SnowflakeLayout {
+        &self.layout
+    }
+
+    /// Get the largest number of threads that can generate snowflakes at the same time
+    pub fn max_threads(&self) -> u64 {
+        mask(self.thread_bits).saturating_add(1)
+    }
+
+    /// Generates the next snowflake ID on the current thread's generator.
+    ///
+    /// # Panics
+    /// Panics if every thread ID is already held by another live thread, or in the same cases
+    /// as [`SnowflakeGenerator::next`]. Use [`ThreadLocalGenerator::try_next`] to handle that
+    /// instead.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&self) -> Snowflake {
+        match self.try_next() {
+            Ok(snowflake) => snowflake,
+            Err(err) => generation_failed(err),
+        }
+    }
+
+    /// Generates the next snowflake ID on the current thread's generator.
+    ///
+    /// # Returns
+    /// The generated snowflake, or `SnowflakeError::TooManyThreads` if every thread ID is
+    /// already held by another live thread
+    pub fn try_next(&self) -> Result<Snowflake, SnowflakeError> {
+        SLOTS.with(|slots| {
+            let mut slots = slots.borrow_mut();
+
+            let slot = match slots.get_mut(&self.instance) {
+                Some(slot) => slot,
+                None => {
+                    let slot = self.claim_slot()?;
+                    slots.entry(self.instance).or_insert(slot)
+                }
+            };
+
+            slot.generator.try_next()
+        })
+    }
+
+    /// Hand the current thread a thread ID and a generator for it
+    fn claim_slot(&self) -> Result<Slot, SnowflakeError> {
+        let mut pool = self
+            .pool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let (thread_id, last_snowflake) = match pool.free.pop() {
+            Some(free) => free,
+            None if pool.next < self.max_threads() => {
+                pool.next += 1;
+                (pool.next - 1, None)
+            }
+            None => {
+                return Err(SnowflakeError::TooManyThreads {
+                    max: self.max_threads(),
+                })
+            }
+        };
+
+        let worker_id = shl(self.process_id, self.thread_bits as u32) | thread_id;
+        let mut generator = SnowflakeGenerator::with_layout(worker_id, self.layout)?;
+        if let Some(last_snowflake) = last_snowflake {
+            generator.last_snowflake = last_snowflake;
+        }
+
+        Ok(Slot {
+            generator,
+            thread_id,
+            pool: Arc::clone(&self.pool),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+/// The thread IDs of a `ThreadLocalGenerator` that aren't held by a live thread.
+struct ThreadIdPool {
+    /// The lowest thread ID that has never been handed out.
+    next: u64,
+    /// Thread IDs returned by exited threads, with the last snowflake generated under them.
+    free: Vec<(u64, Option<Snowflake>)>,
+}
+
+/// A thread's generator for one `ThreadLocalGenerator`, which returns its thread ID to the
+/// pool when the thread exits.
+struct Slot {
+    generator: SnowflakeGenerator,
+    thread_id: u64,
+    pool: Arc<Mutex<ThreadIdPool>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut pool = self
+            .pool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        pool.free
+            .push((self.thread_id, Some(self.generator.last_snowflake)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_threads_get_distinct_worker_ids() {
+        let generator = ThreadLocalGenerator::new(3, 6).unwrap();
+
+        let barrier = std::sync::Barrier::new(4);
+        let worker_ids = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let snowflake = generator.try_next().unwrap();
+                        barrier.wait();
+                        snowflake.worker_id
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<HashSet<_>>()
+        });
+
+        assert_eq!(worker_ids.len(), 4);
+        assert!(worker_ids.iter().all(|worker_id| worker_id >> 4 == 3));
+    }
+
+    #[test]
+    fn test_same_thread_reuses_generator() {
+        let generator = ThreadLocalGenerator::new(0, 10).unwrap();
+
+        let first = generator.try_next().unwrap();
+        let second = generator.next();
+
+        assert_eq!(generator.max_threads(), 1);
+        assert_eq!(first.worker_id, second.worker_id);
+        assert!(second > first);
+    }
+
+    #[test]
+    fn test_too_many_threads() {
+        let generator = ThreadLocalGenerator::new(0, 10).unwrap();
+        generator.try_next().unwrap();
+
+        std::thread::scope(|scope| {
+            let result = scope.spawn(|| generator.try_next()).join().unwrap();
+            assert_eq!(result, Err(SnowflakeError::TooManyThreads { max: 1 }));
+        });
+    }
+
+    #[test]
+    fn test_thread_ids_are_recycled() {
+        let generator = ThreadLocalGenerator::new(0, 10).unwrap();
+
+        let first =
+            std::thread::scope(|scope| scope.spawn(|| generator.try_next()).join().unwrap());
+        let second =
+            std::thread::scope(|scope| scope.spawn(|| generator.try_next()).join().unwrap());
+
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.worker_id, second.worker_id);
+        assert!(second > first);
+    }
+
+    #[test]
+    fn test_full_width_thread_id() {
+        let layout = SnowflakeLayout::builder()
+            .timestamp_bits(0)
+            .worker_id_bits(64)
+            .sequence_bits(0)
+            .epoch(u64::MAX)
+            .build()
+            .unwrap();
+        let generator = ThreadLocalGenerator::with_layout(0, 0, layout).unwrap();
+
+        assert_eq!(generator.max_threads(), u64::MAX);
+        // Without sequence bits, the only tick of the layout has no room for another ID
+        assert!(matches!(
+            generator.try_next(),
+            Err(SnowflakeError::TimestampOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_process_id_overflow() {
+        assert_eq!(
+            ThreadLocalGenerator::new(64, 6).unwrap_err(),
+            SnowflakeError::ProcessIdOverflow {
+                process_id: 64,
+                max: 63
+            }
+        );
+    }
+}
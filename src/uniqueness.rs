@@ -0,0 +1,297 @@
+use std::collections::HashSet;
+
+use crate::{Snowflake, SnowflakeLayout};
+
+/// The number of hash functions the Bloom filter sets a bit for.
+const BLOOM_HASHES: u64 = 7;
+
+/// How many collisions are kept for [`UniquenessChecker::collisions`].
+const MAX_REPORTED: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// An ID seen more than once by a [`UniquenessChecker`].
+pub struct Collision {
+    pub(crate) id: u64,
+    pub(crate) snowflake: Snowflake,
+    pub(crate) certain: bool,
+}
+
+impl Collision {
+    /// The duplicated ID
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The duplicated ID decoded with the checker's layout
+    pub const fn snowflake(&self) -> Snowflake {
+        self.snowflake
+    }
+
+    /// Whether the ID is certainly a duplicate
+    ///
+    /// This is `false` once the checker has switched to its Bloom filter, where a collision can
+    /// be a false positive.
+    pub const fn is_certain(&self) -> bool {
+        self.certain
+    }
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ID {} (timestamp {}, worker ID {}, sequence {})",
+            if self.certain {
+                "duplicate"
+            } else {
+                "probable duplicate"
+            },
+            self.id,
+            self.snowflake.timestamp(),
+            self.snowflake.worker_id(),
+            self.snowflake.sequence()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Finds IDs that were generated more than once, for soak tests and audits.
+///
+/// IDs are remembered exactly in a hash set until it holds `max_exact` of them. After that the
+/// checker moves them into a Bloom filter of a fixed size, so memory stays bounded however many
+/// IDs are fed in, at the cost of reporting the odd unique ID as a probable duplicate.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{SnowflakeGenerator, UniquenessChecker};
+///
+/// let mut generator = SnowflakeGenerator::try_new(1).unwrap();
+/// let mut checker = UniquenessChecker::new();
+///
+/// checker.check_all(generator.next_batch(10_000).iter().map(|snowflake| snowflake.to_id()));
+///
+/// let duplicate = generator.last_snowflake.to_id();
+/// let collision = checker.check(duplicate).unwrap();
+///
+/// assert_eq!(collision.snowflake().worker_id(), 1);
+/// assert_eq!(checker.collision_count(), 1);
+/// ```
+pub struct UniquenessChecker {
+    layout: SnowflakeLayout,
+    /// How many IDs to remember exactly before switching to the Bloom filter.
+    max_exact: usize,
+    /// The number of bits in the Bloom filter.
+    bloom_bits: u64,
+    exact: HashSet<u64>,
+    /// The Bloom filter, once the exact set is full.
+    bloom: Option<Vec<u64>>,
+    checked: u64,
+    collision_count: u64,
+    /// The first collisions found.
+    collisions: Vec<Collision>,
+}
+
+impl UniquenessChecker {
+    /// Create a checker for IDs with [`SnowflakeLayout::DEFAULT`] that remembers a million IDs
+    /// exactly and then uses a 16 MiB Bloom filter
+    pub fn new() -> UniquenessChecker {
+        UniquenessChecker::with_layout(SnowflakeLayout::DEFAULT)
+    }
+
+    /// Create a checker for IDs with a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout to decode collisions with
+    pub fn with_layout(layout: SnowflakeLayout) -> UniquenessChecker {
+        UniquenessChecker {
+            layout,
+            max_exact: 1_000_000,
+            bloom_bits: 1 << 27,
+            exact: HashSet::new(),
+            bloom: None,
+            checked: 0,
+            collision_count: 0,
+            collisions: Vec::new(),
+        }
+    }
+
+    /// Set how many IDs to remember exactly before switching to the Bloom filter
+    pub fn max_exact(mut self, max_exact: usize) -> UniquenessChecker {
+        self.max_exact = max_exact;
+        self
+    }
+
+    /// Set the number of bits in the Bloom filter
+    ///
+    /// About ten bits per ID keeps false positives near one in a hundred.
+    pub fn bloom_bits(mut self, bloom_bits: u64) -> UniquenessChecker {
+        self.bloom_bits = bloom_bits.max(64);
+        self
+    }
+
+    /// Check whether an ID was seen before, and remember it
+    ///
+    /// # Arguments
+    /// * `id` - The ID to check
+    ///
+    /// # Returns
+    /// The collision if the ID was seen before
+    pub fn check(&mut self, id: u64) -> Option<Collision> {
+        self.checked += 1;
+
+        let duplicate = match &mut self.bloom {
+            Some(bloom) => !bloom_insert(bloom, self.bloom_bits, id),
+            None => !self.exact.insert(id),
+        };
+
+        if self.bloom.is_none() && self.exact.len() > self.max_exact {
+            self.switch_to_bloom();
+        }
+
+        if !duplicate {
+            return None;
+        }
+
+        let collision = Collision {
+            id,
+            snowflake: Snowflake::parse_with_layout(id, &self.layout),
+            certain: self.bloom.is_none(),
+        };
+        self.collision_count += 1;
+        if self.collisions.len() < MAX_REPORTED {
+            self.collisions.push(collision);
+        }
+        Some(collision)
+    }
+
+    /// Check every ID from an iterator
+    ///
+    /// # Returns
+    /// The number of collisions found
+    pub fn check_all(&mut self, ids: impl IntoIterator<Item = u64>) -> u64 {
+        let before = self.collision_count;
+        for id in ids {
+            self.check(id);
+        }
+        self.collision_count - before
+    }
+
+    /// Get the number of IDs checked
+    pub fn checked(&self) -> u64 {
+        self.checked
+    }
+
+    /// Get the number of collisions found
+    pub fn collision_count(&self) -> u64 {
+        self.collision_count
+    }
+
+    /// Get the first thousand collisions found
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// Check whether the checker still remembers every ID exactly, so every collision it
+    /// reports is certain
+    pub fn is_exact(&self) -> bool {
+        self.bloom.is_none()
+    }
+
+    /// Move the exact set into a new Bloom filter
+    fn switch_to_bloom(&mut self) {
+        let mut bloom = vec![0; self.bloom_bits.div_ceil(64) as usize];
+        for id in self.exact.drain() {
+            bloom_insert(&mut bloom, self.bloom_bits, id);
+        }
+
+        self.exact.shrink_to_fit();
+        self.bloom = Some(bloom);
+    }
+}
+
+impl Default for UniquenessChecker {
+    fn default() -> UniquenessChecker {
+        UniquenessChecker::new()
+    }
+}
+
+/// Set the bits for `id` in a Bloom filter of `bits` bits
+///
+/// # Returns
+/// Whether any bit was newly set, meaning the ID certainly wasn't in the filter
+fn bloom_insert(bloom: &mut [u64], bits: u64, id: u64) -> bool {
+    // Double hashing: the i-th hash is h1 + i * h2
+    let (h1, h2) = (mix(id), mix(id ^ 0x9E37_79B9_7F4A_7C15) | 1);
+
+    let mut inserted = false;
+    for i in 0..BLOOM_HASHES {
+        let bit = h1.wrapping_add(i.wrapping_mul(h2)) % bits;
+        let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+        inserted |= bloom[word] & mask == 0;
+        bloom[word] |= mask;
+    }
+    inserted
+}
+
+/// The SplitMix64 finalizer, which spreads every input bit over the whole output
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact() {
+        let mut checker = UniquenessChecker::new();
+
+        assert_eq!(checker.check_all(0..1_000), 0);
+        let collision = checker.check(42).unwrap();
+
+        assert!(collision.is_certain());
+        assert_eq!(collision.snowflake().sequence(), 42);
+        assert_eq!(checker.checked(), 1_001);
+        assert_eq!(checker.collisions(), [collision]);
+        assert!(checker.is_exact());
+    }
+
+    #[test]
+    fn test_switches_to_bloom() {
+        let mut checker = UniquenessChecker::new().max_exact(100).bloom_bits(1 << 16);
+
+        assert_eq!(checker.check_all(0..1_000), 0);
+        assert!(!checker.is_exact());
+        assert!(checker.exact.is_empty());
+
+        // IDs remembered before and after the switch are both found
+        assert!(!checker.check(5).unwrap().is_certain());
+        assert!(checker.check(999).is_some());
+        assert_eq!(checker.collision_count(), 2);
+    }
+
+    #[test]
+    fn test_bloom_false_positive_rate() {
+        let mut checker = UniquenessChecker::new().max_exact(0).bloom_bits(1 << 21);
+
+        let false_positives = checker.check_all((0..100_000).map(|id| id << 22));
+
+        assert!(false_positives < 50, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_display() {
+        let collision = Collision {
+            id: 4198401,
+            snowflake: Snowflake::parse(4198401),
+            certain: true,
+        };
+
+        assert_eq!(
+            collision.to_string(),
+            "duplicate ID 4198401 (timestamp 1, worker ID 1, sequence 1)"
+        );
+    }
+}
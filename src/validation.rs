@@ -0,0 +1,201 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+/// Rules that a snowflake ID from an untrusted source has to follow to be accepted.
+///
+/// Parsing accepts any `u64`, but an ID made up by a client usually has a creation time in the
+/// future or long before the service existed, a worker ID no server uses, or bits set that
+/// the layout doesn't use. See [`Snowflake::validate`].
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rusty_snowflake::{Snowflake, SnowflakeGenerator, ValidationRules};
+///
+/// let rules = ValidationRules::new()
+///     .allowed_worker_ids([1, 2, 3])
+///     .max_future(Duration::from_secs(5));
+///
+/// let id = SnowflakeGenerator::try_new(2).unwrap().next().to_id();
+///
+/// assert!(Snowflake::validate(id, &rules).is_ok());
+/// assert!(Snowflake::validate(u64::MAX, &rules).is_err());
+/// ```
+pub struct ValidationRules {
+    layout: SnowflakeLayout,
+    not_before_millis: u64,
+    max_future: Duration,
+    allowed_worker_ids: Option<Vec<u64>>,
+}
+
+impl ValidationRules {
+    /// Create rules that accept any ID of [`SnowflakeLayout::DEFAULT`] that isn't from the
+    /// future and has no reserved bits set
+    pub fn new() -> ValidationRules {
+        ValidationRules::default()
+    }
+
+    /// Set the layout IDs are parsed with
+    pub fn layout(mut self, layout: SnowflakeLayout) -> ValidationRules {
+        self.layout = layout;
+        self
+    }
+
+    /// Reject IDs created before a time, such as when the service first started
+    ///
+    /// # Arguments
+    /// * `millis` - The earliest creation time in milliseconds since the UNIX epoch
+    pub fn not_before_millis(mut self, millis: u64) -> ValidationRules {
+        self.not_before_millis = millis;
+        self
+    }
+
+    /// Accept IDs created up to some time in the future, to allow for clock skew between
+    /// servers
+    ///
+    /// Defaults to zero.
+    pub fn max_future(mut self, max_future: Duration) -> ValidationRules {
+        self.max_future = max_future;
+        self
+    }
+
+    /// Only accept IDs with one of these worker IDs
+    ///
+    /// By default any worker ID is accepted.
+    pub fn allowed_worker_ids(
+        mut self,
+        worker_ids: impl IntoIterator<Item = u64>,
+    ) -> ValidationRules {
+        let mut worker_ids = worker_ids.into_iter().collect::<Vec<_>>();
+        worker_ids.sort_unstable();
+        worker_ids.dedup();
+
+        self.allowed_worker_ids = Some(worker_ids);
+        self
+    }
+
+    /// Check an ID against the rules
+    ///
+    /// # Arguments
+    /// * `id` - The ID to check
+    /// * `now_millis` - The current time in milliseconds since the UNIX epoch
+    pub(crate) fn check(&self, id: u64, now_millis: u64) -> Result<Snowflake, SnowflakeError> {
+        let layout = &self.layout;
+        let snowflake = Snowflake::try_parse_with_layout(id, layout)?;
+        let created_millis = layout.millis_from_timestamp(snowflake.timestamp);
+
+        let latest = now_millis.saturating_add(self.max_future.as_millis() as u64);
+        if created_millis > latest {
+            return Err(SnowflakeError::TimestampInFuture {
+                timestamp: snowflake.timestamp,
+                max: layout.timestamp_from_millis(latest),
+            });
+        }
+
+        if created_millis < self.not_before_millis {
+            return Err(SnowflakeError::TimestampTooOld {
+                timestamp: snowflake.timestamp,
+                min: layout.timestamp_from_millis(self.not_before_millis),
+            });
+        }
+
+        if let Some(allowed) = &self.allowed_worker_ids {
+            if allowed.binary_search(&snowflake.worker_id).is_err() {
+                return Err(SnowflakeError::WorkerIdNotAllowed {
+                    worker_id: snowflake.worker_id,
+                });
+            }
+        }
+
+        Ok(snowflake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_valid() {
+        let id = Snowflake {
+            worker_id: 1,
+            sequence: 2,
+            timestamp: NOW / 1_000,
+            extra: 0,
+        }
+        .to_id();
+
+        assert_eq!(
+            ValidationRules::new().check(id, NOW),
+            Ok(Snowflake::parse(id))
+        );
+    }
+
+    #[test]
+    fn test_reserved_bits() {
+        assert_eq!(
+            ValidationRules::new().check(1 << 63, NOW),
+            Err(SnowflakeError::ReservedBitsSet { id: 1 << 63 })
+        );
+    }
+
+    #[test]
+    fn test_future() {
+        let id = ((NOW / 1_000 + 10) << 22) | 1;
+        let rules = ValidationRules::new();
+
+        assert_eq!(
+            rules.check(id, NOW),
+            Err(SnowflakeError::TimestampInFuture {
+                timestamp: NOW / 1_000 + 10,
+                max: NOW / 1_000
+            })
+        );
+
+        let rules = rules.max_future(Duration::from_secs(10));
+        assert!(rules.check(id, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_not_before() {
+        let rules = ValidationRules::new().not_before_millis(NOW);
+
+        assert_eq!(
+            rules.check(1 << 22, NOW),
+            Err(SnowflakeError::TimestampTooOld {
+                timestamp: 1,
+                min: NOW / 1_000
+            })
+        );
+        assert!(rules.check((NOW / 1_000) << 22, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_allowed_worker_ids() {
+        let rules = ValidationRules::new().allowed_worker_ids([3, 1, 3]);
+
+        assert!(rules.check(1 << 12, NOW).is_ok());
+        assert!(rules.check(3 << 12, NOW).is_ok());
+        assert_eq!(
+            rules.check(2 << 12, NOW),
+            Err(SnowflakeError::WorkerIdNotAllowed { worker_id: 2 })
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = SnowflakeLayout::DISCORD;
+        let rules = ValidationRules::new().layout(layout);
+
+        assert!(rules.check(175928847299117063, NOW).is_ok());
+        assert!(matches!(
+            rules.check(u64::MAX, NOW),
+            Err(SnowflakeError::TimestampInFuture { .. })
+        ));
+    }
+}
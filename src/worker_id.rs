@@ -0,0 +1,553 @@
+use std::net::{IpAddr, UdpSocket};
+
+use crate::{SnowflakeError, SnowflakeLayout};
+
+/// The environment variable Kubernetes deployments conventionally expose the pod IP in, through
+/// the downward API.
+const POD_IP_VAR: &str = "POD_IP";
+
+/// Addresses from the documentation ranges, connected to only to ask the kernel which local
+/// address it routes outgoing traffic from. Connecting a UDP socket sends nothing.
+const ROUTE_PROBES: [&str; 2] = ["192.0.2.1:9", "[2001:db8::1]:9"];
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+/// A strategy for picking a worker ID from the identity of the machine it runs on.
+///
+/// Replicas of a service that resolve the same source on different machines get different
+/// worker IDs without any coordination, as long as the part of the identity that fits in the
+/// worker ID field is unique across the replicas.
+///
+/// # Example
+/// ```rust
+/// use rusty_snowflake::{SnowflakeGenerator, WorkerIdSource};
+///
+/// let worker_id = WorkerIdSource::Hostname.resolve().unwrap();
+/// let mut generator = SnowflakeGenerator::try_new(worker_id).unwrap();
+///
+/// assert_eq!(generator.next().worker_id(), worker_id);
+/// ```
+pub enum WorkerIdSource {
+    /// The lowest bits of the MAC address of the first non-loopback network interface.
+    ///
+    /// Only supported on Linux, where interfaces are listed in `/sys/class/net`.
+    MacAddress,
+    /// The lowest bits of the IP address in the `POD_IP` environment variable.
+    PodIp,
+    /// A hash of the machine's hostname.
+    Hostname,
+    /// The worker ID in the given environment variable, as a decimal number.
+    Env(String),
+    /// The ordinal at the end of a Kubernetes StatefulSet pod's hostname, such as `3` for
+    /// `web-3`.
+    ///
+    /// See [`WorkerIdSource::k8s_statefulset`].
+    StatefulSetOrdinal,
+    /// The host's primary IP address, which is the one outgoing traffic is sent from, fitted
+    /// into the worker ID field with the given strategy.
+    ///
+    /// See [`WorkerIdSource::primary_ip`].
+    PrimaryIp(IpStrategy),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// How [`WorkerIdSource::PrimaryIp`] fits an IP address into the worker ID field.
+pub enum IpStrategy {
+    /// The lowest bits of the address, which are unique as long as every replica is in the
+    /// same network of `2 ^ bits` addresses.
+    ///
+    /// Sonyflake uses the lowest 16 bits, for replicas in the same `/16` IPv4 network.
+    LowerBits(u8),
+    /// A hash of the whole address, for replicas spread over several networks. Any two
+    /// addresses can hash to the same worker ID, however wide the worker ID field is.
+    Hash,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// A warning that a worker ID source can give different machines the same worker ID with a
+/// layout, because the layout's worker ID field is narrower than the source needs.
+///
+/// Returned by [`WorkerIdSource::collision_warning`].
+pub struct CollisionWarning {
+    required_bits: u8,
+    worker_id_bits: u8,
+}
+
+impl CollisionWarning {
+    /// Get the number of bits the source needs to give every machine its own worker ID
+    pub fn required_bits(&self) -> u8 {
+        self.required_bits
+    }
+
+    /// Get the number of worker ID bits in the layout
+    pub fn worker_id_bits(&self) -> u8 {
+        self.worker_id_bits
+    }
+}
+
+impl std::fmt::Display for CollisionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "worker ID source needs {} bits but the layout has {}, so machines can get the same worker ID",
+            self.required_bits, self.worker_id_bits
+        )
+    }
+}
+
+impl WorkerIdSource {
+    /// Use the ordinal of the Kubernetes StatefulSet pod the service runs in as the worker ID
+    ///
+    /// Pods in a StatefulSet are named `<statefulset>-<ordinal>` and the ordinals are unique
+    /// and stable across restarts, so every replica gets its own worker ID without any
+    /// coordination. The ordinal is parsed from `HOSTNAME`, which Kubernetes sets to the pod
+    /// name. Unlike the hashed sources, an ordinal that doesn't fit in the layout is an error
+    /// rather than being truncated into a worker ID another pod may already use.
+    ///
+    /// # Returns
+    /// [`WorkerIdSource::StatefulSetOrdinal`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{SnowflakeError, WorkerIdSource};
+    ///
+    /// match WorkerIdSource::k8s_statefulset().resolve() {
+    ///     Ok(worker_id) => assert!(worker_id <= 1023),
+    ///     Err(SnowflakeError::WorkerIdUnavailable(_)) => {} // Not running in a StatefulSet
+    ///     Err(err) => panic!("{}", err),
+    /// }
+    /// ```
+    pub fn k8s_statefulset() -> WorkerIdSource {
+        WorkerIdSource::StatefulSetOrdinal
+    }
+
+    /// Use the lowest 16 bits of the host's primary IP address as the worker ID, like Sonyflake
+    ///
+    /// The primary address is the one the kernel sends outgoing traffic from, so it's found
+    /// without sending anything. It's looked up over IPv4, falling back to IPv6. Only the
+    /// lowest bits that fit in the layout are used, so check
+    /// [`WorkerIdSource::collision_warning`] for layouts with fewer than 16 worker ID bits.
+    ///
+    /// # Returns
+    /// [`WorkerIdSource::PrimaryIp`] with [`IpStrategy::LowerBits`] of `16`
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{SnowflakeLayout, WorkerIdSource};
+    ///
+    /// let source = WorkerIdSource::primary_ip();
+    ///
+    /// assert!(source.collision_warning(&SnowflakeLayout::SONYFLAKE).is_none());
+    /// assert!(source.collision_warning(&SnowflakeLayout::DEFAULT).is_some());
+    /// ```
+    pub fn primary_ip() -> WorkerIdSource {
+        WorkerIdSource::PrimaryIp(IpStrategy::LowerBits(16))
+    }
+
+    /// Check whether the source can give different machines the same worker ID with a layout
+    ///
+    /// Sources that keep the lowest bits of an address need a worker ID field as wide as the
+    /// part of the address that differs between machines: 48 bits for
+    /// [`WorkerIdSource::MacAddress`], 32 bits for [`WorkerIdSource::PodIp`] (the width of an
+    /// IPv4 address), and the bits it slices for [`IpStrategy::LowerBits`]. Sources that hash,
+    /// and sources that fail to resolve rather than truncate, never warn.
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the worker ID has to fit in
+    ///
+    /// # Returns
+    /// A warning if the layout has fewer worker ID bits than the source needs
+    ///
+    /// # Example
+    /// ```rust
+    /// use rusty_snowflake::{IpStrategy, SnowflakeLayout, WorkerIdSource};
+    ///
+    /// let source = WorkerIdSource::PrimaryIp(IpStrategy::LowerBits(12));
+    /// let warning = source.collision_warning(&SnowflakeLayout::DEFAULT).unwrap();
+    ///
+    /// assert_eq!(warning.required_bits(), 12);
+    /// assert_eq!(warning.worker_id_bits(), 10);
+    /// ```
+    pub fn collision_warning(&self, layout: &SnowflakeLayout) -> Option<CollisionWarning> {
+        let required_bits = match self {
+            WorkerIdSource::MacAddress => 48,
+            WorkerIdSource::PodIp => 32,
+            WorkerIdSource::PrimaryIp(IpStrategy::LowerBits(bits)) => *bits,
+            _ => return None,
+        };
+        let worker_id_bits = layout.worker_id_bits();
+
+        (worker_id_bits < required_bits).then_some(CollisionWarning {
+            required_bits,
+            worker_id_bits,
+        })
+    }
+
+    /// Resolve the worker ID for [`SnowflakeLayout::DEFAULT`]
+    ///
+    /// # Returns
+    /// The worker ID, or an error if the source isn't available on this machine
+    pub fn resolve(&self) -> Result<u64, SnowflakeError> {
+        self.resolve_with_layout(&SnowflakeLayout::DEFAULT)
+    }
+
+    /// Resolve the worker ID for a custom layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout the worker ID has to fit in
+    ///
+    /// # Returns
+    /// The worker ID, or an error if the source isn't available on this machine or, for
+    /// [`WorkerIdSource::Env`] and [`WorkerIdSource::StatefulSetOrdinal`], the worker ID doesn't
+    /// fit in the layout
+    pub fn resolve_with_layout(&self, layout: &SnowflakeLayout) -> Result<u64, SnowflakeError> {
+        let max = layout.max_worker_id();
+
+        match self {
+            WorkerIdSource::MacAddress => Ok(mac_address()? & max),
+            WorkerIdSource::PodIp => {
+                let ip = env_var(POD_IP_VAR)?;
+                let ip = ip.trim().parse::<IpAddr>().map_err(|err| {
+                    SnowflakeError::WorkerIdUnavailable(format!(
+                        "{} is not an IP address: {}",
+                        POD_IP_VAR, err
+                    ))
+                })?;
+
+                Ok(ip_bits(ip) & max)
+            }
+            WorkerIdSource::Hostname => Ok(fnv1a(hostname()?.as_bytes()) & max),
+            WorkerIdSource::Env(name) => {
+                let worker_id = env_var(name)?.trim().parse::<u64>()?;
+
+                if worker_id > max {
+                    return Err(SnowflakeError::WorkerIdOverflow { worker_id, max });
+                }
+
+                Ok(worker_id)
+            }
+            WorkerIdSource::StatefulSetOrdinal => {
+                let hostname = hostname()?;
+                let worker_id = statefulset_ordinal(&hostname).ok_or_else(|| {
+                    SnowflakeError::WorkerIdUnavailable(format!(
+                        "hostname {} doesn't end in a StatefulSet ordinal",
+                        hostname
+                    ))
+                })?;
+
+                if worker_id > max {
+                    return Err(SnowflakeError::WorkerIdOverflow { worker_id, max });
+                }
+
+                Ok(worker_id)
+            }
+            WorkerIdSource::PrimaryIp(strategy) => Ok(strategy.apply(primary_ip()?) & max),
+        }
+    }
+}
+
+impl IpStrategy {
+    /// Fit an IP address into a worker ID, before masking it to the layout
+    fn apply(self, ip: IpAddr) -> u64 {
+        match self {
+            IpStrategy::LowerBits(bits) => match bits {
+                0 => 0,
+                1..=63 => ip_bits(ip) & ((1 << bits) - 1),
+                _ => ip_bits(ip),
+            },
+            IpStrategy::Hash => match ip {
+                IpAddr::V4(ip) => fnv1a(&ip.octets()),
+                IpAddr::V6(ip) => fnv1a(&ip.octets()),
+            },
+        }
+    }
+}
+
+/// Read an environment variable, treating a missing variable as an unavailable source
+fn env_var(name: &str) -> Result<String, SnowflakeError> {
+    std::env::var(name)
+        .map_err(|err| SnowflakeError::WorkerIdUnavailable(format!("{}: {}", name, err)))
+}
+
+/// Get the MAC address of the first non-loopback interface, in interface name order
+fn mac_address() -> Result<u64, SnowflakeError> {
+    let unavailable = || {
+        SnowflakeError::WorkerIdUnavailable("no network interface with a MAC address".to_string())
+    };
+
+    let mut interfaces = std::fs::read_dir("/sys/class/net")
+        .map_err(|_| unavailable())?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    interfaces.sort();
+
+    interfaces
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path.join("address")).ok())
+        .filter_map(|address| parse_mac(address.trim()))
+        .find(|&mac| mac != 0)
+        .ok_or_else(unavailable)
+}
+
+/// Parse a colon separated MAC address into its 48 bits
+fn parse_mac(address: &str) -> Option<u64> {
+    let mut mac = 0;
+    let mut octets = 0;
+
+    for octet in address.split(':') {
+        mac = (mac << 8) | u64::from(u8::from_str_radix(octet, 16).ok()?);
+        octets += 1;
+    }
+
+    (octets == 6).then_some(mac)
+}
+
+/// Get the lowest 64 bits of an IP address
+fn ip_bits(ip: IpAddr) -> u64 {
+    match ip {
+        IpAddr::V4(ip) => u64::from(u32::from(ip)),
+        IpAddr::V6(ip) => u128::from(ip) as u64,
+    }
+}
+
+/// Get the hostname from `HOSTNAME`, falling back to the kernel's hostname
+fn hostname() -> Result<String, SnowflakeError> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .ok_or_else(|| SnowflakeError::WorkerIdUnavailable("hostname is unknown".to_string()))
+}
+
+/// Get the address the kernel sends outgoing traffic from, over IPv4 or else IPv6
+fn primary_ip() -> Result<IpAddr, SnowflakeError> {
+    ROUTE_PROBES
+        .iter()
+        .filter_map(|probe| {
+            let bind = if probe.starts_with('[') {
+                "[::]:0"
+            } else {
+                "0.0.0.0:0"
+            };
+            let socket = UdpSocket::bind(bind).ok()?;
+            socket.connect(probe).ok()?;
+            socket.local_addr().ok()
+        })
+        .map(|address| address.ip())
+        .find(|ip| !ip.is_unspecified() && !ip.is_loopback())
+        .ok_or_else(|| {
+            SnowflakeError::WorkerIdUnavailable(
+                "no route to find the primary IP address".to_string(),
+            )
+        })
+}
+
+/// Parse the ordinal from a StatefulSet pod name, which is the decimal number after the last `-`
+fn statefulset_ordinal(hostname: &str) -> Option<u64> {
+    // Only the pod name, in case the hostname is fully qualified
+    let pod = hostname.split('.').next()?;
+    let (_, ordinal) = pod.rsplit_once('-')?;
+
+    if ordinal.is_empty() || !ordinal.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    ordinal.parse().ok()
+}
+
+/// Hash bytes with 64 bit FNV-1a, which unlike the standard library's hasher is stable across
+/// Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env() {
+        std::env::set_var("RUSTY_SNOWFLAKE_TEST_WORKER_ID", " 42\n");
+        std::env::set_var("RUSTY_SNOWFLAKE_TEST_WORKER_ID_OVERFLOW", "1024");
+
+        let source = WorkerIdSource::Env("RUSTY_SNOWFLAKE_TEST_WORKER_ID".to_string());
+        assert_eq!(source.resolve(), Ok(42));
+
+        let source = WorkerIdSource::Env("RUSTY_SNOWFLAKE_TEST_WORKER_ID_OVERFLOW".to_string());
+        assert_eq!(
+            source.resolve(),
+            Err(SnowflakeError::WorkerIdOverflow {
+                worker_id: 1024,
+                max: 1023
+            })
+        );
+
+        let source = WorkerIdSource::Env("RUSTY_SNOWFLAKE_TEST_WORKER_ID_MISSING".to_string());
+        assert!(matches!(
+            source.resolve(),
+            Err(SnowflakeError::WorkerIdUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_hostname() {
+        let worker_id = WorkerIdSource::Hostname.resolve_with_layout(&SnowflakeLayout::SONYFLAKE);
+
+        if let Ok(worker_id) = worker_id {
+            assert!(worker_id <= SnowflakeLayout::SONYFLAKE.max_worker_id());
+            assert_eq!(
+                WorkerIdSource::Hostname.resolve_with_layout(&SnowflakeLayout::SONYFLAKE),
+                Ok(worker_id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mac_address() {
+        match WorkerIdSource::MacAddress.resolve() {
+            Ok(worker_id) => assert!(worker_id <= 1023),
+            Err(err) => assert!(matches!(err, SnowflakeError::WorkerIdUnavailable(_))),
+        }
+    }
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(parse_mac("02:42:ac:11:00:02"), Some(0x0242_ac11_0002));
+        assert_eq!(parse_mac("00:00:00:00:00:00"), Some(0));
+        assert_eq!(parse_mac("02:42:ac:11:00"), None);
+        assert_eq!(parse_mac("not a mac"), None);
+    }
+
+    #[test]
+    fn test_ip_bits() {
+        assert_eq!(ip_bits("10.0.3.7".parse().unwrap()) & 1023, 775);
+        assert_eq!(ip_bits("fd00::1:2".parse().unwrap()), 0x0001_0002);
+    }
+
+    #[test]
+    fn test_statefulset_ordinal() {
+        assert_eq!(statefulset_ordinal("web-0"), Some(0));
+        assert_eq!(statefulset_ordinal("id-service-12"), Some(12));
+        assert_eq!(
+            statefulset_ordinal("web-3.web.default.svc.cluster.local"),
+            Some(3)
+        );
+        assert_eq!(statefulset_ordinal("web"), None);
+        assert_eq!(statefulset_ordinal("web-"), None);
+        assert_eq!(statefulset_ordinal("web-+3"), None);
+        assert_eq!(statefulset_ordinal("web-7d9f8b6c5-x2k4p"), None);
+    }
+
+    #[test]
+    fn test_k8s_statefulset() {
+        let source = WorkerIdSource::k8s_statefulset();
+
+        match source.resolve_with_layout(&SnowflakeLayout::SONYFLAKE) {
+            Ok(worker_id) => assert!(worker_id <= SnowflakeLayout::SONYFLAKE.max_worker_id()),
+            Err(err) => assert!(matches!(
+                err,
+                SnowflakeError::WorkerIdUnavailable(_) | SnowflakeError::WorkerIdOverflow { .. }
+            )),
+        }
+    }
+
+    #[test]
+    fn test_ip_strategy() {
+        let ip = "10.0.3.7".parse().unwrap();
+        assert_eq!(IpStrategy::LowerBits(16).apply(ip), 0x0307);
+        assert_eq!(IpStrategy::LowerBits(8).apply(ip), 7);
+        assert_eq!(IpStrategy::LowerBits(0).apply(ip), 0);
+        assert_eq!(IpStrategy::LowerBits(64).apply(ip), 0x0a00_0307);
+        assert_eq!(
+            IpStrategy::LowerBits(16).apply("fd00::1:2".parse().unwrap()),
+            2
+        );
+
+        assert_eq!(IpStrategy::Hash.apply(ip), fnv1a(&[10, 0, 3, 7]));
+        assert_ne!(
+            IpStrategy::Hash.apply(ip) & 1023,
+            IpStrategy::Hash.apply("10.1.3.7".parse().unwrap()) & 1023
+        );
+    }
+
+    #[test]
+    fn test_primary_ip() {
+        for source in [
+            WorkerIdSource::primary_ip(),
+            WorkerIdSource::PrimaryIp(IpStrategy::Hash),
+        ] {
+            match source.resolve() {
+                Ok(worker_id) => assert!(worker_id <= 1023),
+                Err(err) => assert!(matches!(err, SnowflakeError::WorkerIdUnavailable(_))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_collision_warning() {
+        let source = WorkerIdSource::primary_ip();
+        assert_eq!(source.collision_warning(&SnowflakeLayout::SONYFLAKE), None);
+
+        let warning = source.collision_warning(&SnowflakeLayout::DEFAULT).unwrap();
+        assert_eq!(warning.required_bits(), 16);
+        assert_eq!(warning.worker_id_bits(), 10);
+        assert_eq!(
+            warning.to_string(),
+            "worker ID source needs 16 bits but the layout has 10, so machines can get the same worker ID"
+        );
+
+        let source = WorkerIdSource::PrimaryIp(IpStrategy::LowerBits(10));
+        assert_eq!(source.collision_warning(&SnowflakeLayout::DEFAULT), None);
+        assert!(source
+            .collision_warning(&SnowflakeLayout::SINGLE_PROCESS)
+            .is_some());
+
+        assert_eq!(
+            WorkerIdSource::PrimaryIp(IpStrategy::Hash)
+                .collision_warning(&SnowflakeLayout::DEFAULT),
+            None
+        );
+        assert_eq!(
+            WorkerIdSource::k8s_statefulset().collision_warning(&SnowflakeLayout::SINGLE_PROCESS),
+            None
+        );
+        assert_eq!(
+            WorkerIdSource::Hostname.collision_warning(&SnowflakeLayout::SINGLE_PROCESS),
+            None
+        );
+    }
+
+    #[test]
+    fn test_collision_warning_truncated_addresses() {
+        let warning = WorkerIdSource::MacAddress
+            .collision_warning(&SnowflakeLayout::DEFAULT)
+            .unwrap();
+        assert_eq!(warning.required_bits(), 48);
+        assert_eq!(warning.worker_id_bits(), 10);
+
+        let warning = WorkerIdSource::PodIp
+            .collision_warning(&SnowflakeLayout::SONYFLAKE)
+            .unwrap();
+        assert_eq!(warning.required_bits(), 32);
+        assert_eq!(warning.worker_id_bits(), 16);
+
+        let wide = SnowflakeLayout::builder()
+            .timestamp_bits(8)
+            .worker_id_bits(48)
+            .sequence_bits(8)
+            .build()
+            .unwrap();
+        assert_eq!(WorkerIdSource::MacAddress.collision_warning(&wide), None);
+        assert_eq!(WorkerIdSource::PodIp.collision_warning(&wide), None);
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}